@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fmt;
+
+/// Display names for the symbols of a machine, so that a machine over `{_, a, b}` is rendered
+/// with those names instead of `0`, `1` and `2`. Symbol `i` is named by the `i`th name;
+/// symbols without a name are rendered as numbers, so the default `Alphabet` renders every symbol as a number.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let alphabet = Alphabet::new(&["_", "a", "b"]);
+/// 
+/// assert_eq!(alphabet.name(1), "a");
+/// assert_eq!(alphabet.name(7), "7");
+/// assert_eq!(alphabet.symbol("b"), Some(2));
+/// assert_eq!(Alphabet::default().name(1), "1");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Alphabet {
+    names: Vec<String>,
+    symbols: HashMap<String, u64>,
+}
+
+impl Alphabet {
+    /// Constructs a new `Alphabet` naming symbol `i` by `names[i]`.
+    /// 
+    /// # Panics
+    /// Panics if two symbols share a name or a name is empty.
+    #[inline]
+    pub fn new(names: &[&str]) -> Self {
+        let mut symbols = HashMap::new();
+        for (i, &name) in names.iter().enumerate() {
+            if name.is_empty() {
+                panic!("Symbol {} has an empty name", i);
+            }
+            if symbols.insert(name.to_string(), i as u64).is_some() {
+                panic!("Symbol name `{}` is used twice", name);
+            }
+        }
+
+        Alphabet {
+            names: names.iter().map(|x| x.to_string()).collect(),
+            symbols,
+        }
+    }
+
+    /// Constructs a new `Alphabet` naming symbol `i` by the `i`th character of `chars`,
+    /// which is how `StringTape` maps characters to symbols.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// assert_eq!(Alphabet::from_chars("_ab"), Alphabet::new(&["_", "a", "b"]));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if a character occurs twice.
+    #[inline]
+    pub fn from_chars(chars: &str) -> Self {
+        let names = chars.chars().map(String::from).collect::<Vec<_>>();
+        Alphabet::new(&names.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+
+    /// Returns the number of named symbols.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns whether no symbol is named.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Returns the names of the named symbols, indexed by symbol.
+    #[inline]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the name of `symbol`, or `symbol` as a number if it has no name.
+    #[inline]
+    pub fn name(&self, symbol: u64) -> String {
+        match self.names.get(symbol as usize) {
+            Some(name) => name.clone(),
+            None => symbol.to_string(),
+        }
+    }
+
+    /// Returns the symbol with the specified name, if any.
+    #[inline]
+    pub fn symbol(&self, name: &str) -> Option<u64> {
+        self.symbols.get(name).copied()
+    }
+}
+
+/// An error returned by `Tape::map_symbols` and `TransitionFn::map_symbols` when a symbol map is not
+/// a bijection that fixes the blank symbol, and so would change what a tape or machine means.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SymbolMapError {
+    /// The blank symbol 0 is mapped to `image`, which would change the infinitely many blank cells.
+    MovesBlank { image: u64 },
+    /// The distinct symbols `a` and `b` are both mapped to `image`.
+    NotInjective { a: u64, b: u64, image: u64 },
+}
+
+impl fmt::Display for SymbolMapError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolMapError::MovesBlank { image } => write!(f, "the blank symbol is mapped to {}", image),
+            SymbolMapError::NotInjective { a, b, image } => write!(f, "symbols {} and {} are both mapped to {}", a, b, image),
+        }
+    }
+}
+
+impl std::error::Error for SymbolMapError {}
+
+/// Applies `f` to every symbol in `symbols`, checking that it fixes the blank symbol and is injective on them.
+/// Returns the images, indexed by symbol.
+pub(crate) fn map_symbols(symbols: impl IntoIterator<Item = u64>, f: impl Fn(u64) -> u64) -> Result<HashMap<u64, u64>, SymbolMapError> {
+    let image = f(0);
+    if image != 0 {
+        return Err(SymbolMapError::MovesBlank { image });
+    }
+
+    let mut images = HashMap::from([(0, 0)]);
+    let mut preimages = HashMap::from([(0, 0)]);
+    for symbol in symbols {
+        if images.contains_key(&symbol) {
+            continue;
+        }
+
+        let image = f(symbol);
+        if let Some(&other) = preimages.get(&image) {
+            return Err(SymbolMapError::NotInjective { a: other.min(symbol), b: other.max(symbol), image });
+        }
+        images.insert(symbol, image);
+        preimages.insert(image, symbol);
+    }
+
+    Ok(images)
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_alphabet() {
+        let alphabet = Alphabet::new(&["_", "a", "b"]);
+
+        assert_eq!((0..4).map(|x| alphabet.name(x)).collect::<Vec<_>>(), ["_", "a", "b", "3"]);
+        assert_eq!(alphabet.symbol("_"), Some(0));
+        assert_eq!(alphabet.symbol("3"), None);
+        assert_eq!(alphabet.len(), 3);
+        assert!(Alphabet::default().is_empty());
+    }
+
+    #[test]
+    fn test_map_symbols() {
+        let trans_fn = TransitionFn::new(&[((0, 3), (1, 5, true))]).with_annotation(0, 3, "start");
+        let mapped = trans_fn.map_symbols(|s| s * 2).unwrap();
+        assert_eq!(mapped.run(0, 6), Some((1, 10, true)));
+        assert_eq!(mapped.annotation(0, 6), Some("start"));
+
+        // only the symbols that occur need to be mapped injectively
+        assert!(trans_fn.map_symbols(|s| if s == 4 { 3 } else { s }).is_ok());
+        assert_eq!(trans_fn.map_symbols(|s| s.min(3)), Err(SymbolMapError::NotInjective { a: 3, b: 5, image: 3 }));
+        assert_eq!(Tape::new(vec![4]).map_symbols(|s| 4 - s), Err(SymbolMapError::MovesBlank { image: 4 }));
+        assert_eq!(Tape::new(vec![0, 4]).map_symbols(|s| s % 4).unwrap_err().to_string(), "symbols 0 and 4 are both mapped to 0");
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_alphabet() {
+        Alphabet::new(&["_", "a", "_"]);
+    }
+}
@@ -0,0 +1,178 @@
+use std::thread;
+
+use crate::tape::Tape;
+use crate::transition_fn::TransitionFn;
+use crate::turing_machine::TuringMachine;
+
+/// Runs every machine enumerated by `TransitionFn::enumerate` for up to `step_limit` steps and
+/// returns a histogram whose element s is the number of machines that halted after exactly s steps.
+/// Machines that are still running after `step_limit` steps are not counted.
+/// 
+/// Machines are constructed one at a time rather than materialized up front,
+/// and the enumeration is split across all available threads.
+/// 
+/// # Panics
+/// Panics if the number of machines exceeds `usize::MAX`.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::analysis::halting_histogram;
+/// 
+/// let histogram = halting_histogram(1, 2, 10);
+/// 
+/// // a 1-state machine halts after its first step or never
+/// assert_eq!(histogram.len(), 11);
+/// assert_eq!(histogram[1], 32);
+/// assert_eq!(histogram.iter().sum::<usize>(), 32);
+/// ```
+#[inline]
+pub fn halting_histogram(num_states: usize, num_symbols: usize, step_limit: usize) -> Vec<usize> {
+    par_enumerate(
+        num_states,
+        num_symbols,
+        || vec![0; step_limit + 1],
+        |histogram, trans_fn| {
+            if let Some(steps) = halting_time(&mut TuringMachine::new(trans_fn), &mut Tape::default(), step_limit) {
+                histogram[steps] += 1;
+            }
+        },
+        |mut acc, histogram| {
+            for (total, count) in acc.iter_mut().zip(histogram) {
+                *total += count;
+            }
+
+            acc
+        },
+    )
+}
+
+/// Approximates Chaitin's constant Ω from below by summing 2^-`program_length` over every machine with
+/// 1 to `max_states` states and 1 to `max_symbols` symbols that halts on a blank tape within `step_limit` steps.
+/// Unlike `TuringMachine::chaitin_approx`, which counts every machine equally, this weights shorter
+/// programs more heavily, which is the standard definition of Ω for a prefix-free encoding.
+/// The estimate grows towards the true value as all three bounds grow.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::analysis::{omega_approx, program_length};
+/// 
+/// // with 1 symbol, a 1-state machine halts iff its only rule enters state 1
+/// let omega = omega_approx(1, 1, 10);
+/// 
+/// assert_eq!(omega, 2.0 * 2f64.powi(-(program_length(1, 1) as i32)));
+/// ```
+#[inline]
+pub fn omega_approx(max_states: usize, max_symbols: usize, step_limit: usize) -> f64 {
+    let mut omega = 0.0;
+    for num_states in 1..=max_states {
+        for num_symbols in 1..=max_symbols {
+            let halted = halting_histogram(num_states, num_symbols, step_limit).iter().sum::<usize>();
+            omega += halted as f64 * 2f64.powi(-(program_length(num_states, num_symbols) as i32));
+        }
+    }
+
+    omega
+}
+
+/// Returns the length in bits of every machine with the specified number of states and symbols
+/// under the prefix-free encoding used by `omega_approx`.
+/// 
+/// A machine is encoded as the Elias gamma codes of its number of states and its number of symbols,
+/// followed by its rules in the order of `TransitionFn::enumerate`. Each rule is encoded as its new state
+/// in ⌈log2(num_states + 1)⌉ bits, its symbol to write in ⌈log2(num_symbols)⌉ bits, and its head movement in 1 bit.
+/// Since the gamma codes determine the length of the rest, no encoding is a prefix of another.
+/// 
+/// # Panics
+/// Panics if either argument is 0.
+#[inline]
+pub fn program_length(num_states: usize, num_symbols: usize) -> u32 {
+    let gamma = |x: usize| 2 * x.ilog2() + 1;
+    let bits = |x: usize| x.next_power_of_two().ilog2();
+
+    gamma(num_states) + gamma(num_symbols) + (num_states * num_symbols) as u32 * (bits(num_states + 1) + bits(num_symbols) + 1)
+}
+
+/// Folds every machine enumerated by `TransitionFn::enumerate` into an accumulator, splitting the enumeration
+/// into one contiguous chunk per available thread. Each chunk is folded with `fold` into its own accumulator
+/// from `init`, and the accumulators are merged with `merge` in the order of enumeration.
+/// 
+/// # Panics
+/// Panics if the number of machines exceeds `usize::MAX`.
+pub(crate) fn par_enumerate<T: Send>(
+    num_states: usize,
+    num_symbols: usize,
+    init: impl Fn() -> T + Sync,
+    fold: impl Fn(&mut T, TransitionFn) + Sync,
+    merge: impl Fn(T, T) -> T,
+) -> T {
+    let num_machines = TransitionFn::enumeration_len(num_states, num_symbols);
+    let num_threads = thread::available_parallelism().map_or(1, |n| n.get()).min(num_machines.max(1));
+    let chunk_len = num_machines.div_ceil(num_threads);
+    let (init, fold) = (&init, &fold);
+
+    thread::scope(|scope| {
+        let workers = (0..num_threads)
+            .map(|t| {
+                scope.spawn(move || {
+                    let mut acc = init();
+                    for index in t * chunk_len..((t + 1) * chunk_len).min(num_machines) {
+                        fold(&mut acc, TransitionFn::enumerated(num_states, num_symbols, index));
+                    }
+
+                    acc
+                })
+            })
+            .collect::<Vec<_>>();
+
+        workers.into_iter().fold(init(), |acc, worker| merge(acc, worker.join().unwrap()))
+    })
+}
+
+/// Returns the number of steps `machine` takes to halt on `tape`, if it halts within `step_limit` steps.
+pub(crate) fn halting_time(machine: &mut TuringMachine, tape: &mut Tape, step_limit: usize) -> Option<usize> {
+    (0..=step_limit).find(|_| machine.step(tape).is_none())
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::analysis::*;
+
+    #[test]
+    fn test_halting_histogram() {
+        let histogram = halting_histogram(2, 2, 20);
+        let halted = TuringMachine::chaitin_approx(2, 2, HaltSetting::AfterSteps(20)).halted;
+
+        assert_eq!(histogram.len(), 21);
+        assert_eq!(histogram[0], 0);
+        assert_eq!(histogram[1], 4 * 12usize.pow(3));
+        assert_eq!(histogram.iter().sum::<usize>() as f64 / 12usize.pow(4) as f64, halted);
+    }
+
+    #[test]
+    fn test_omega_approx() {
+        let omega = omega_approx(2, 2, 50);
+        assert!(omega > 0.0 && omega < 1.0);
+        assert!(omega > omega_approx(2, 1, 50));
+        assert!(omega_approx(2, 2, 50) >= omega_approx(2, 2, 5));
+    }
+
+    #[test]
+    fn test_program_length() {
+        assert_eq!(program_length(1, 1), 1 + 1 + 2);
+        assert_eq!(program_length(2, 2), 3 + 3 + 4 * 4);
+        assert_eq!(program_length(3, 2), 3 + 3 + 6 * 4);
+        assert_eq!(program_length(4, 3), 5 + 3 + 12 * 6);
+
+        // Kraft's inequality holds over every machine
+        let mut kraft = 0.0;
+        for n in 1..20usize {
+            for m in 1..20usize {
+                let log2_num_machines = (n * m) as f64 * (((n + 1) * m * 2) as f64).log2();
+                kraft += 2f64.powf(log2_num_machines - program_length(n, m) as f64);
+            }
+        }
+        assert!(kraft <= 1.0);
+    }
+}
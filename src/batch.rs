@@ -0,0 +1,137 @@
+use crate::tape::Tape;
+use crate::transition_fn::{FlatTable, Move, TransitionFn};
+use crate::turing_machine::{self, Termination};
+
+/// The number of steps each machine takes before `simulate_many` moves on to the next one.
+const STRIDE: usize = 32;
+
+/// The result of running one machine with `simulate_many`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Outcome {
+    /// `Termination::Halted` if no rule matched, `Termination::NonHalting` if the budget ran out first
+    /// but `TuringMachine::runs_off_forever` proved the machine would never halt, `Termination::OutOfSpace`
+    /// if it would have written farther than `MAX_LOCATION` from location 0, or `Termination::OutOfSteps` otherwise.
+    pub termination: Termination,
+    pub steps: usize,
+    pub state: u64,
+    pub head_loc: i64,
+    pub tape: Tape,
+}
+
+/// Runs every machine from state 0 and head location 0 on a blank tape for at most `budget` steps,
+/// and returns their outcomes in the same order.
+/// 
+/// Rather than running each machine to completion in turn, the machines take turns of a few steps each,
+/// with their states and head locations kept in parallel arrays and the unfinished ones compacted after every round,
+/// so that a sweep over many short-lived machines stays in cache. Machines over at most 3 symbols
+/// are looked up in a flat table rather than their state table.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::batch::simulate_many;
+/// 
+/// let machines = TransitionFn::enumerate(2, 2);
+/// let outcomes = simulate_many(&machines, 20);
+/// 
+/// let halted = outcomes.iter().filter(|o| o.termination == Termination::Halted).count();
+/// let max_steps = outcomes.iter().filter(|o| o.termination == Termination::Halted).map(|o| o.steps).max();
+/// 
+/// assert_eq!(outcomes.len(), machines.len());
+/// assert_eq!(max_steps, Some(6));
+/// ```
+#[inline]
+pub fn simulate_many(machines: &[TransitionFn], budget: usize) -> Vec<Outcome> {
+    let tables = machines.iter().map(TransitionFn::flat_table).collect::<Vec<Option<FlatTable>>>();
+    let mut states = vec![0; machines.len()];
+    let mut head_locs = vec![0; machines.len()];
+    let mut steps = vec![0; machines.len()];
+    let mut halted = vec![false; machines.len()];
+    let mut out_of_space = vec![false; machines.len()];
+    let mut tapes = vec![Tape::default(); machines.len()];
+
+    let mut active = (0..machines.len()).collect::<Vec<usize>>();
+    while !active.is_empty() {
+        active.retain(|&i| {
+            for _ in 0..STRIDE {
+                if steps[i] == budget {
+                    return false;
+                }
+
+                let symbol = tapes[i].symbol_at_n(head_locs[i]);
+                let output = match &tables[i] {
+                    Some(table) => table.get(states[i], symbol),
+                    None => machines[i].run(states[i], symbol),
+                };
+                let Some((new_state, write, right)) = output else {
+                    halted[i] = true;
+                    return false;
+                };
+
+                if tapes[i].try_write(head_locs[i], write).is_err() {
+                    out_of_space[i] = true;
+                    return false;
+                }
+                states[i] = new_state;
+                head_locs[i] += Move::from(right).offset();
+                steps[i] += 1;
+            }
+
+            true
+        });
+    }
+
+    tapes
+        .into_iter()
+        .enumerate()
+        .map(|(i, tape)| Outcome {
+            termination: if halted[i] {
+                Termination::Halted
+            }
+            else if turing_machine::runs_off_forever(&machines[i], states[i], head_locs[i], &tape) {
+                Termination::NonHalting
+            }
+            else if out_of_space[i] {
+                Termination::OutOfSpace
+            }
+            else {
+                Termination::OutOfSteps
+            },
+            steps: steps[i],
+            state: states[i],
+            head_loc: head_locs[i],
+            tape,
+        })
+        .collect()
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::batch::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_simulate_many() {
+        // mixes machines with and without a flat table
+        let mut machines = TransitionFn::enumerate(2, 2);
+        machines.push(TransitionFn::new(&[((0, 0), (0, 7, true))]));
+        machines.push(TransitionFn::new(&[((0, 0), (1, 7, true)), ((1, 0), (2, 7, false))]));
+        let outcomes = simulate_many(&machines, 100);
+
+        for (trans_fn, outcome) in machines.iter().zip(&outcomes) {
+            let mut cursor = Cursor::new(trans_fn);
+            let mut tape = Tape::default();
+            let steps = cursor.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(100));
+
+            assert_eq!((outcome.steps, outcome.state, outcome.head_loc, &outcome.tape), (steps, cursor.state(), cursor.head_loc(), &tape));
+            let halted = trans_fn.run(cursor.state(), tape.symbol_at_n(cursor.head_loc())).is_none();
+            assert_eq!(outcome.termination == Termination::Halted, halted);
+        }
+        assert_eq!(outcomes[outcomes.len() - 1].steps, 2);
+        assert_eq!(outcomes[outcomes.len() - 2].termination, Termination::NonHalting);
+
+        assert!(simulate_many(&[], 10).is_empty());
+        assert_eq!(simulate_many(&machines[machines.len() - 1..], 0)[0].termination, Termination::OutOfSteps);
+    }
+}
@@ -0,0 +1,291 @@
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use crate::transition_fn::TransitionFn;
+
+/// The length of the header and of every machine record of a seed database.
+pub const RECORD_LEN: usize = 30;
+
+/// The number of states of every machine in a seed database, named `A` to `E` there and 0 to 4 here.
+pub const NUM_STATES: usize = 5;
+
+/// The header of a bbchallenge seed database, the first `RECORD_LEN` bytes of the file.
+/// The undecided machines follow, those exceeding the time limit first.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SeedDatabaseHeader {
+    /// The number of machines that exceeded the step limit.
+    pub undecided_time: u32,
+    /// The number of machines that exceeded the space limit.
+    pub undecided_space: u32,
+    /// The total number of machines.
+    pub total: u32,
+    /// Whether the machines are sorted lexicographically within each group.
+    pub sorted: bool,
+}
+
+impl SeedDatabaseHeader {
+    /// Parses a header from its big-endian encoding.
+    #[inline]
+    pub fn from_bytes(bytes: &[u8; RECORD_LEN]) -> Self {
+        let u32_at = |i: usize| u32::from_be_bytes(bytes[i..i + 4].try_into().unwrap());
+
+        SeedDatabaseHeader {
+            undecided_time: u32_at(0),
+            undecided_space: u32_at(4),
+            total: u32_at(8),
+            sorted: bytes[12] != 0,
+        }
+    }
+
+    /// Returns the big-endian encoding of `self`, zero-padded to `RECORD_LEN` bytes.
+    #[inline]
+    pub fn to_bytes(&self) -> [u8; RECORD_LEN] {
+        let mut bytes = [0; RECORD_LEN];
+        bytes[0..4].copy_from_slice(&self.undecided_time.to_be_bytes());
+        bytes[4..8].copy_from_slice(&self.undecided_space.to_be_bytes());
+        bytes[8..12].copy_from_slice(&self.total.to_be_bytes());
+        bytes[12] = self.sorted as u8;
+
+        bytes
+    }
+}
+
+/// Decodes a 30-byte machine record: for every state from `A` to `E` and then every symbol,
+/// the symbol to write, the move (0 for right, 1 for left), and the next state (1 for `A` to 5 for `E`).
+/// A transition whose next state is 0 is undefined, so the returned machine has no rule for it and halts there.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::bbchallenge;
+/// 
+/// let mut record = [0; bbchallenge::RECORD_LEN];
+/// // A0 -> write 1, move right, go to B
+/// record[..3].copy_from_slice(&[1, 0, 2]);
+/// 
+/// let trans_fn = bbchallenge::decode_machine(&record).unwrap();
+/// 
+/// assert_eq!(trans_fn.state_table(), [((0, 0), (1, 1, true))]);
+/// ```
+/// 
+/// # Errors
+/// Returns an error of kind `InvalidData` if a field is out of range.
+#[inline]
+pub fn decode_machine(record: &[u8; RECORD_LEN]) -> io::Result<TransitionFn> {
+    let mut state_table = Vec::new();
+    for (i, transition) in record.chunks_exact(3).enumerate() {
+        let (write, head_move, next) = (transition[0], transition[1], transition[2]);
+        if write > 1 || head_move > 1 || next as usize > NUM_STATES {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("invalid transition {:?}", transition)));
+        }
+        if next == 0 {
+            continue;
+        }
+
+        let key = ((i / 2) as u64, (i % 2) as u64);
+        state_table.push((key, (next as u64 - 1, write as u64, head_move == 0)));
+    }
+
+    Ok(TransitionFn::new(&state_table))
+}
+
+/// Encodes `trans_fn` as a 30-byte machine record; see `decode_machine`.
+/// Returns `None` if it has a rule outside of states 0 to 4 and symbols 0 and 1,
+/// or a rule going to another state, since only undefined transitions can halt in this format.
+#[inline]
+pub fn encode_machine(trans_fn: &TransitionFn) -> Option<[u8; RECORD_LEN]> {
+    let mut record = [0; RECORD_LEN];
+    for ((state, symbol), (next, write, right)) in trans_fn.state_table() {
+        if state as usize >= NUM_STATES || symbol > 1 || next as usize >= NUM_STATES || write > 1 {
+            return None;
+        }
+
+        let i = 3 * (2 * state + symbol) as usize;
+        record[i..i + 3].copy_from_slice(&[write as u8, !right as u8, next as u8 + 1]);
+    }
+
+    Some(record)
+}
+
+/// A reader over the machines of a bbchallenge seed database, which yields them in order.
+/// With a seekable reader, any machine can also be read directly by its index with `get`.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::bbchallenge::*;
+/// use turing_machine::prelude::*;
+/// 
+/// let machines = vec![TransitionFn::new(&[((0, 0), (1, 1, true))]), TransitionFn::new(&[((0, 1), (4, 0, false))])];
+/// let mut file = Vec::new();
+/// write_database(&mut file, &SeedDatabaseHeader { undecided_time: 2, total: 2, ..Default::default() }, &machines).unwrap();
+/// 
+/// let database = SeedDatabase::new(file.as_slice()).unwrap();
+/// assert_eq!(database.header().total, 2);
+/// assert_eq!(database.collect::<std::io::Result<Vec<_>>>().unwrap(), machines);
+/// ```
+#[derive(Debug)]
+pub struct SeedDatabase<R> {
+    reader: R,
+    header: SeedDatabaseHeader,
+    next_index: u32,
+}
+
+impl<R: Read> SeedDatabase<R> {
+    /// Reads the header from `reader`, which must be at the start of the database.
+    /// 
+    /// # Errors
+    /// Passes on any error from `reader`.
+    #[inline]
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut bytes = [0; RECORD_LEN];
+        reader.read_exact(&mut bytes)?;
+
+        Ok(SeedDatabase {
+            reader,
+            header: SeedDatabaseHeader::from_bytes(&bytes),
+            next_index: 0,
+        })
+    }
+
+    /// Returns the header of the database.
+    #[inline]
+    pub fn header(&self) -> &SeedDatabaseHeader {
+        &self.header
+    }
+}
+
+impl<R: Read + Seek> SeedDatabase<R> {
+    /// Reads the machine at position `index`, after which iteration continues from the next one.
+    /// 
+    /// # Errors
+    /// Returns an error of kind `InvalidInput` if `index` is out of bounds, an error of kind `InvalidData`
+    /// if the record is malformed, and passes on any error from the reader.
+    #[inline]
+    pub fn get(&mut self, index: u32) -> io::Result<TransitionFn> {
+        if index >= self.header.total {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("machine {} out of bounds", index)));
+        }
+
+        self.reader.seek(SeekFrom::Start(RECORD_LEN as u64 * (index as u64 + 1)))?;
+        self.next_index = index;
+        self.next().unwrap()
+    }
+}
+
+impl<R: Read> Iterator for SeedDatabase<R> {
+    type Item = io::Result<TransitionFn>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next_index >= self.header.total {
+            return None;
+        }
+        self.next_index += 1;
+
+        let mut record = [0; RECORD_LEN];
+        Some(self.reader.read_exact(&mut record).and_then(|_| decode_machine(&record)))
+    }
+}
+
+/// Writes a seed database holding `machines` with the specified header, whose `total` should match.
+/// 
+/// # Errors
+/// Returns an error of kind `InvalidInput` if a machine can not be encoded; see `encode_machine`.
+/// Passes on any error from `writer`.
+#[inline]
+pub fn write_database(writer: &mut impl Write, header: &SeedDatabaseHeader, machines: &[TransitionFn]) -> io::Result<()> {
+    writer.write_all(&header.to_bytes())?;
+    for (i, machine) in machines.iter().enumerate() {
+        let record = encode_machine(machine)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("machine {} can not be encoded", i)))?;
+        writer.write_all(&record)?;
+    }
+
+    Ok(())
+}
+
+/// Reads an index file: a list of big-endian `u32` machine indices into a seed database,
+/// as published for the machines decided by each decider.
+/// 
+/// # Errors
+/// Returns an error of kind `InvalidData` if the length is not a multiple of 4, and passes on any error from `reader`.
+#[inline]
+pub fn read_index(reader: &mut impl Read) -> io::Result<Vec<u32>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    if bytes.len() % 4 != 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "index length is not a multiple of 4"));
+    }
+
+    Ok(bytes.chunks_exact(4).map(|x| u32::from_be_bytes(x.try_into().unwrap())).collect())
+}
+
+/// Writes `indices` as an index file; see `read_index`.
+/// 
+/// # Errors
+/// Passes on any error from `writer`.
+#[inline]
+pub fn write_index(writer: &mut impl Write, indices: &[u32]) -> io::Result<()> {
+    for index in indices {
+        writer.write_all(&index.to_be_bytes())?;
+    }
+
+    Ok(())
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::bbchallenge::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_decode_machine() {
+        // the 5-state champion, 1RB1LC_1RC1RB_1RD0LE_1LA1LD_---0LA
+        let record = [
+            1, 0, 2, 1, 1, 3,
+            1, 0, 3, 1, 0, 2,
+            1, 0, 4, 0, 1, 5,
+            1, 1, 1, 1, 1, 4,
+            0, 0, 0, 0, 1, 1,
+        ];
+        let trans_fn = decode_machine(&record).unwrap();
+
+        assert_eq!(trans_fn.state_table().len(), 9);
+        assert_eq!(trans_fn.run(2, 1), Some((4, 0, false)));
+        assert_eq!(trans_fn.run(4, 0), None);
+        assert_eq!(encode_machine(&trans_fn), Some(record));
+
+        // runs for 47,176,870 steps before halting
+        assert_eq!(Cursor::new(&trans_fn).run_with_halt_setting(&mut Tape::default(), HaltSetting::AfterSteps(1000)), 1000);
+
+        let mut bad = record;
+        bad[2] = 6;
+        assert_eq!(decode_machine(&bad).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(encode_machine(&TransitionFn::new(&[((0, 0), (5, 1, true))])), None);
+        assert_eq!(encode_machine(&TransitionFn::new(&[((0, 2), (1, 1, true))])), None);
+    }
+
+    #[test]
+    fn test_database_and_index() {
+        let machines = (0..4)
+            .map(|i| TransitionFn::new(&[((0, 0), (i, 1, true)), ((i, 1), (0, 0, false))]))
+            .collect::<Vec<_>>();
+        let header = SeedDatabaseHeader { undecided_time: 3, undecided_space: 1, total: 4, sorted: true };
+
+        let mut file = Vec::new();
+        write_database(&mut file, &header, &machines).unwrap();
+        assert_eq!(file.len(), 5 * RECORD_LEN);
+
+        let mut database = SeedDatabase::new(std::io::Cursor::new(file)).unwrap();
+        assert_eq!(*database.header(), header);
+        assert_eq!(database.get(2).unwrap(), machines[2]);
+        assert_eq!(database.next().unwrap().unwrap(), machines[3]);
+        assert!(database.next().is_none());
+        assert_eq!(database.get(4).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+        let mut index = Vec::new();
+        write_index(&mut index, &[3, 0, 70000]).unwrap();
+        assert_eq!(index[..4], [0, 0, 0, 3]);
+        assert_eq!(read_index(&mut index.as_slice()).unwrap(), [3, 0, 70000]);
+        assert!(read_index(&mut &index[1..]).is_err());
+    }
+}
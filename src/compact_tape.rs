@@ -0,0 +1,273 @@
+use crate::tape::{checked_i64_to_idx, idx_to_i64, Tape, TapeError};
+
+/// The number of bytes used to store each cell of a `CompactTape`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SymbolWidth {
+    #[default]
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl SymbolWidth {
+    /// Returns the narrowest width that can store `symbol`.
+    #[inline]
+    pub fn for_symbol(symbol: u64) -> Self {
+        if symbol <= u8::MAX as u64 {
+            SymbolWidth::U8
+        }
+        else if symbol <= u16::MAX as u64 {
+            SymbolWidth::U16
+        }
+        else if symbol <= u32::MAX as u64 {
+            SymbolWidth::U32
+        }
+        else {
+            SymbolWidth::U64
+        }
+    }
+
+    /// Returns the number of bytes per cell.
+    #[inline]
+    pub fn bytes(self) -> usize {
+        match self {
+            SymbolWidth::U8 => 1,
+            SymbolWidth::U16 => 2,
+            SymbolWidth::U32 => 4,
+            SymbolWidth::U64 => 8,
+        }
+    }
+}
+
+/// A tape like `Tape` that stores its cells as `u8`, `u16`, `u32` or `u64`, using as little as an eighth
+/// of the memory for the common case of small alphabets. It starts at a chosen width and widens itself
+/// the first time a symbol that does not fit is written, so it never loses information.
+/// 
+/// Run a machine on it with `Cursor::run_compact`, and convert with `from_tape` and `to_tape`.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let mut tape = CompactTape::new(SymbolWidth::U8);
+/// tape.write(-3, 7);
+/// assert_eq!(tape.width(), SymbolWidth::U8);
+/// 
+/// tape.write(2, 1000);
+/// assert_eq!(tape.width(), SymbolWidth::U16);
+/// assert_eq!((tape.symbol_at_n(-3), tape.symbol_at_n(2), tape.symbol_at_n(50)), (7, 1000, 0));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactTape {
+    cells: Cells,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Cells {
+    U8(Vec<u8>),
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+    U64(Vec<u64>),
+}
+
+impl Default for CompactTape {
+    #[inline]
+    fn default() -> Self {
+        CompactTape::new(SymbolWidth::default())
+    }
+}
+
+impl CompactTape {
+    /// Constructs a new blank `CompactTape` whose cells start out `width` wide.
+    #[inline]
+    pub fn new(width: SymbolWidth) -> Self {
+        let cells = match width {
+            SymbolWidth::U8 => Cells::U8(Vec::new()),
+            SymbolWidth::U16 => Cells::U16(Vec::new()),
+            SymbolWidth::U32 => Cells::U32(Vec::new()),
+            SymbolWidth::U64 => Cells::U64(Vec::new()),
+        };
+
+        CompactTape { cells }
+    }
+
+    /// Constructs a `CompactTape` with the same contents as `tape`, using the narrowest width that fits them.
+    #[inline]
+    pub fn from_tape(tape: &Tape) -> Self {
+        let width = SymbolWidth::for_symbol(tape.raw_symbols().iter().copied().max().unwrap_or(0));
+        let mut compact = CompactTape::new(width);
+        for (idx, &symbol) in tape.raw_symbols().iter().enumerate().rev() {
+            compact.write_idx(idx, symbol);
+        }
+
+        compact
+    }
+
+    /// Returns a `Tape` with the same contents as `self`.
+    #[inline]
+    pub fn to_tape(&self) -> Tape {
+        let mut tape = Tape::default();
+        for idx in (0..self.len()).rev() {
+            let n = idx_to_i64(idx);
+            tape.write(n, self.symbol_at_n(n));
+        }
+
+        tape
+    }
+
+    /// Returns the current width of the cells of `self`.
+    #[inline]
+    pub fn width(&self) -> SymbolWidth {
+        match self.cells {
+            Cells::U8(_) => SymbolWidth::U8,
+            Cells::U16(_) => SymbolWidth::U16,
+            Cells::U32(_) => SymbolWidth::U32,
+            Cells::U64(_) => SymbolWidth::U64,
+        }
+    }
+
+    /// Returns the number of bytes used by the cells of `self`, not counting spare capacity.
+    #[inline]
+    pub fn memory_usage(&self) -> usize {
+        self.len() * self.width().bytes()
+    }
+
+    /// Returns the symbol at location n.
+    #[inline]
+    pub fn symbol_at_n(&self, n: i64) -> u64 {
+        let idx = match checked_i64_to_idx(n) {
+            Some(idx) => idx,
+            None => return 0,
+        };
+        match &self.cells {
+            Cells::U8(v) => v.get(idx).map_or(0, |&x| x as u64),
+            Cells::U16(v) => v.get(idx).map_or(0, |&x| x as u64),
+            Cells::U32(v) => v.get(idx).map_or(0, |&x| x as u64),
+            Cells::U64(v) => v.get(idx).copied().unwrap_or(0),
+        }
+    }
+
+    /// Writes the specified symbol into `self` at location n, widening the cells if it does not fit.
+    /// 
+    /// # Panics
+    /// Panics if a nonblank symbol is written farther than `MAX_LOCATION` from location 0, like `Tape::write`; see `try_write`.
+    #[inline]
+    pub fn write(&mut self, n: i64, symbol: u64) {
+        if let Err(e) = self.try_write(n, symbol) {
+            panic!("{}", e);
+        }
+    }
+
+    /// Equivalent to `write`, but returns an error instead of panicking. Writing a blank always succeeds.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut tape = CompactTape::default();
+    /// 
+    /// assert_eq!(tape.try_write(MAX_LOCATION + 1, 1), Err(TapeError::LocationOutOfRange { location: MAX_LOCATION + 1 }));
+    /// assert_eq!(tape.try_write(MAX_LOCATION + 1, 0), Ok(()));
+    /// assert_eq!(tape.try_write(-5, 1), Ok(()));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `TapeError::LocationOutOfRange` if a nonblank symbol is written farther than `MAX_LOCATION` from location 0.
+    #[inline]
+    pub fn try_write(&mut self, n: i64, symbol: u64) -> Result<(), TapeError> {
+        match checked_i64_to_idx(n) {
+            Some(idx) => self.write_idx(idx, symbol),
+            None if symbol == 0 => {}
+            None => return Err(TapeError::LocationOutOfRange { location: n }),
+        }
+
+        Ok(())
+    }
+
+    fn write_idx(&mut self, idx: usize, symbol: u64) {
+        if SymbolWidth::for_symbol(symbol) > self.width() {
+            self.widen(SymbolWidth::for_symbol(symbol));
+        }
+
+        match &mut self.cells {
+            Cells::U8(v) => set(v, idx, symbol as u8),
+            Cells::U16(v) => set(v, idx, symbol as u16),
+            Cells::U32(v) => set(v, idx, symbol as u32),
+            Cells::U64(v) => set(v, idx, symbol),
+        }
+    }
+
+    fn len(&self) -> usize {
+        match &self.cells {
+            Cells::U8(v) => v.len(),
+            Cells::U16(v) => v.len(),
+            Cells::U32(v) => v.len(),
+            Cells::U64(v) => v.len(),
+        }
+    }
+
+    /// Converts the cells of `self` to `width`, which must be wider than the current width.
+    fn widen(&mut self, width: SymbolWidth) {
+        let symbols = match &self.cells {
+            Cells::U8(v) => v.iter().map(|&x| x as u64).collect::<Vec<u64>>(),
+            Cells::U16(v) => v.iter().map(|&x| x as u64).collect(),
+            Cells::U32(v) => v.iter().map(|&x| x as u64).collect(),
+            Cells::U64(v) => v.clone(),
+        };
+
+        self.cells = match width {
+            SymbolWidth::U8 => Cells::U8(symbols.into_iter().map(|x| x as u8).collect()),
+            SymbolWidth::U16 => Cells::U16(symbols.into_iter().map(|x| x as u16).collect()),
+            SymbolWidth::U32 => Cells::U32(symbols.into_iter().map(|x| x as u32).collect()),
+            SymbolWidth::U64 => Cells::U64(symbols),
+        };
+    }
+}
+
+/// Sets `vec[idx]`, growing `vec` with blanks if needed.
+fn set<T: Copy + Default>(vec: &mut Vec<T>, idx: usize, value: T) {
+    if idx >= vec.len() {
+        vec.resize(idx + 1, T::default());
+    }
+    vec[idx] = value;
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_compact_tape() {
+        let tape = Tape::new(vec![3, 0, 200, 1]);
+        let mut compact = CompactTape::from_tape(&tape);
+        assert_eq!(compact.width(), SymbolWidth::U8);
+        assert_eq!(compact.to_tape(), tape);
+
+        compact.write(-2, 70000);
+        assert_eq!(compact.width(), SymbolWidth::U32);
+        assert_eq!(compact.memory_usage(), 4 * 7);
+        assert_eq!(compact.symbol_at_n(2), 200);
+        assert_eq!(compact.symbol_at_n(-2), 70000);
+
+        compact.write(0, u64::MAX);
+        assert_eq!(compact.width(), SymbolWidth::U64);
+        assert_eq!(compact.to_tape().symbols(), vec![70000, 0, u64::MAX, 0, 200, 1]);
+    }
+
+    #[test]
+    fn test_run_compact() {
+        let (mut machine, _) = crate::examples::busy_beaver_4();
+        machine.reset();
+
+        let mut tape = Tape::default();
+        let mut compact = CompactTape::default();
+        let steps = machine.cursor().run_compact(&mut compact, HaltSetting::NoForcedHalt);
+        machine.run(&mut tape);
+
+        assert_eq!(steps, 107);
+        assert_eq!(compact.to_tape(), tape);
+        assert_eq!(compact.width(), SymbolWidth::U8);
+    }
+}
@@ -0,0 +1,183 @@
+use std::io::{self, Write};
+use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::{cursor, execute, terminal};
+
+use crate::alphabet::Alphabet;
+use crate::recording::Frame;
+use crate::tape::Tape;
+use crate::transition_fn::Move;
+use crate::turing_machine::{StepEvent, TuringMachine};
+
+/// A live terminal view of a running machine, showing the tape around the head, the state,
+/// the step count, and the steps per second. Pressing `q`, `Esc`, or `Ctrl-C` aborts the run.
+/// 
+/// The terminal is put into raw mode while the dashboard exists and restored when it is dropped.
+/// Redrawing and checking for keys happen at most once per `refresh_interval`,
+/// so the dashboard barely slows down an unthrottled run.
+#[derive(Debug)]
+pub struct Dashboard {
+    alphabet: Alphabet,
+    refresh_interval: Duration,
+    start: Instant,
+    last_draw: Option<Instant>,
+    last_event: Option<StepEvent>,
+    error: Option<io::Error>,
+}
+
+impl Dashboard {
+    /// Takes over the terminal and constructs a dashboard that names symbols with `alphabet`.
+    /// 
+    /// # Errors
+    /// Returns an error if the terminal cannot be put into raw mode, e.g. if stdout is not a terminal.
+    #[inline]
+    pub fn new(alphabet: &Alphabet) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+
+        Ok(Dashboard {
+            alphabet: alphabet.clone(),
+            refresh_interval: Duration::from_millis(50),
+            start: Instant::now(),
+            last_draw: None,
+            last_event: None,
+            error: None,
+        })
+    }
+
+    /// Sets how often the view is redrawn and the keyboard checked. The default is 50 milliseconds.
+    #[inline]
+    pub fn with_refresh_interval(mut self, refresh_interval: Duration) -> Self {
+        self.refresh_interval = refresh_interval;
+        self
+    }
+
+    /// An observer for `TuringMachine::run_throttled` that updates the view and
+    /// returns `ControlFlow::Break` once the user aborts or drawing fails.
+    #[inline]
+    pub fn observe(&mut self, tape: &Tape, event: &StepEvent) -> ControlFlow<()> {
+        self.last_event = Some(*event);
+        if self.last_draw.is_some_and(|t| t.elapsed() < self.refresh_interval) {
+            return ControlFlow::Continue(());
+        }
+        self.last_draw = Some(Instant::now());
+
+        match self.draw(tape, "running, press q to abort").and_then(|_| abort_requested()) {
+            Ok(false) => ControlFlow::Continue(()),
+            Ok(true) => ControlFlow::Break(()),
+            Err(e) => {
+                self.error = Some(e);
+                ControlFlow::Break(())
+            }
+        }
+    }
+
+    /// Returns the text of the view after `event`, `steps_per_second` having been measured so far.
+    #[inline]
+    pub fn render(&self, tape: &Tape, event: &StepEvent, steps_per_second: f64, status: &str) -> String {
+        let head_loc = event.head_loc + Move::from(event.output.2).offset();
+        let frame = Frame {
+            state: event.output.0,
+            head_loc,
+            head_move: Move::Stay,
+            window_start: head_loc - 5,
+            window: (head_loc - 5..=head_loc + 5).map(|n| tape.symbol_at_n(n)).collect(),
+        };
+
+        format!("{}\n\nstep: {}    steps/sec: {:.1}    {}", frame.render(&self.alphabet), event.step_num, steps_per_second, status)
+    }
+
+    /// Draws the final state of the run and returns the first error encountered while drawing, if any.
+    /// The terminal is restored when `self` is dropped.
+    /// 
+    /// # Errors
+    /// Passes on any error from drawing.
+    #[inline]
+    pub fn finish(mut self, tape: &Tape) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+
+        self.draw(tape, "finished")?;
+        print!("\r\n");
+        io::stdout().flush()
+    }
+
+    fn draw(&self, tape: &Tape, status: &str) -> io::Result<()> {
+        let Some(event) = self.last_event else { return Ok(()) };
+        let steps_per_second = event.step_num as f64 / self.start.elapsed().as_secs_f64().max(f64::EPSILON);
+        let text = self.render(tape, &event, steps_per_second, status);
+
+        let mut stdout = io::stdout();
+        execute!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+        // raw mode does not turn line feeds into carriage returns
+        write!(stdout, "{}", text.replace('\n', "\r\n"))?;
+        stdout.flush()
+    }
+}
+
+impl Drop for Dashboard {
+    #[inline]
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Runs `machine` on `tape` like `TuringMachine::run_throttled` while showing it on a `Dashboard`,
+/// until it halts, is forcibly halted by its `HaltSetting`, or the user aborts.
+/// Returns the number of steps taken.
+/// 
+/// # Errors
+/// Returns an error if the terminal cannot be used, in which case the machine may have run partially.
+/// 
+/// # Panics
+/// Panics if `steps_per_second` is not positive. Pass `f64::INFINITY` to run at full speed.
+#[inline]
+pub fn run_live(machine: &mut TuringMachine, tape: &mut Tape, steps_per_second: f64, alphabet: &Alphabet) -> io::Result<usize> {
+    let mut dashboard = Dashboard::new(alphabet)?;
+    let steps = machine.run_throttled(tape, steps_per_second, |tape, event| dashboard.observe(tape, event));
+    dashboard.finish(tape)?;
+
+    Ok(steps)
+}
+
+/// Drains pending terminal events and returns whether any of them asks to abort.
+fn abort_requested() -> io::Result<bool> {
+    let mut abort = false;
+    while event::poll(Duration::ZERO)? {
+        if let Event::Key(key) = event::read()? {
+            let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            abort |= key.kind == KeyEventKind::Press && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c);
+        }
+    }
+
+    Ok(abort)
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::dashboard::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_render() {
+        // constructed directly so that the test does not need a terminal
+        let dashboard = Dashboard {
+            alphabet: Alphabet::new(&["_", "x"]),
+            refresh_interval: Duration::from_millis(50),
+            start: Instant::now(),
+            last_draw: None,
+            last_event: None,
+            error: None,
+        };
+        let tape = Tape::new(vec![1, 1, 0, 1]);
+        let event = StepEvent { step_num: 42, state: 0, head_loc: 1, symbol: 1, output: (3, 1, true) };
+
+        let text = dashboard.render(&tape, &event, 1234.5, "running");
+        assert!(text.contains("H(3)"));
+        assert!(text.ends_with("step: 42    steps/sec: 1234.5    running"));
+        assert!(text.contains("x         _         x"));
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use crate::explore::render_tape;
+use crate::machine::{Machine, Project};
+use crate::tape::Tape;
+use crate::transition_fn::Move;
+use crate::turing_machine::{HaltSetting, Termination};
+use crate::verify::Configuration;
+
+/// The most steps a sample input is run for by `machine_report` if the project has no forced halt.
+pub const MAX_SAMPLE_STEPS: usize = 10_000;
+
+/// Generates a Markdown document describing the machine of `project`, with its description and author,
+/// the transition table from `transition_table`, the state diagram from `state_diagram`, the rule annotations,
+/// statistics about the machine, a run on each sample input, and the notes of `project`.
+/// 
+/// Sample inputs are written from location 0 and run with the halt setting of `project`,
+/// or for at most `MAX_SAMPLE_STEPS` steps if it has no forced halt, so that generating a report always finishes.
+/// The report renders as is on sites that support Mermaid diagrams in Markdown, such as GitHub,
+/// and otherwise shows the diagram source.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::docgen;
+/// use turing_machine::prelude::*;
+/// 
+/// let mut builder = SmartBuilder::new(2, "start");
+/// builder.accept_state("yes").rule("start", 1, "yes", 1, true);
+/// let mut machine = builder.build_bundle().0;
+/// machine.alphabet = Alphabet::new(&["_", "x"]);
+/// machine.description = "Accepts tapes starting with x.".to_string();
+/// let mut project = Project::new(machine);
+/// project.sample_inputs = vec![vec![1], vec![0, 1]];
+/// 
+/// let report = docgen::machine_report(&project);
+/// assert!(report.starts_with("# Machine "));
+/// assert!(report.contains("Accepts tapes starting with x."));
+/// assert!(report.contains("| start |  | x,R,yes |"));
+/// assert!(report.contains("```mermaid\nstateDiagram-v2\n"));
+/// assert!(report.contains("| `x` | accept | 1 | `x [_]` |"));
+/// assert!(report.contains("| `_ x` | halt | 0 | `[_] x` |"));
+/// ```
+#[inline]
+pub fn machine_report(project: &Project) -> String {
+    let machine = &project.machine;
+    let mut report = String::new();
+
+    writeln!(report, "# Machine `{:016x}`\n", machine.fingerprint()).unwrap();
+    if !machine.description.is_empty() {
+        writeln!(report, "{}\n", machine.description.trim()).unwrap();
+    }
+    if !machine.author.is_empty() {
+        writeln!(report, "By {}.\n", machine.author.trim()).unwrap();
+    }
+
+    writeln!(report, "## Transition table\n\n{}", transition_table(machine)).unwrap();
+    writeln!(report, "## State diagram\n\n```mermaid\n{}```\n", state_diagram(machine)).unwrap();
+
+    let mut state_table = machine.transition_fn.state_table();
+    state_table.sort_unstable_by_key(|x| x.0);
+    let notes = state_table
+        .iter()
+        .filter_map(|&((state, symbol), _)| Some((state, symbol, machine.transition_fn.annotation(state, symbol)?)))
+        .collect::<Vec<_>>();
+    if !notes.is_empty() {
+        writeln!(report, "## Rule notes\n").unwrap();
+        for (state, symbol, note) in &notes {
+            writeln!(report, "- In state {} reading `{}`: {}", label(machine, *state), machine.alphabet.name(*symbol), note).unwrap();
+        }
+        report.push('\n');
+    }
+
+    writeln!(report, "## Statistics\n").unwrap();
+    writeln!(report, "- States: {}", states(machine).len()).unwrap();
+    writeln!(report, "- Symbols: {}", machine.num_symbols()).unwrap();
+    writeln!(report, "- Rules: {}, of which {} annotated", state_table.len(), notes.len()).unwrap();
+    for (kind, states) in [("Accepting", &machine.halting_states.accept), ("Rejecting", &machine.halting_states.reject), ("Halting", &machine.halting_states.halt)] {
+        if !states.is_empty() {
+            let labels = states.iter().copied().collect::<BTreeSet<u64>>().into_iter().map(|s| label(machine, s)).collect::<Vec<_>>();
+            writeln!(report, "- {} states: {}", kind, labels.join(", ")).unwrap();
+        }
+    }
+    report.push('\n');
+
+    if !project.sample_inputs.is_empty() {
+        let halt_setting = match project.halt_setting {
+            HaltSetting::NoForcedHalt => HaltSetting::AfterSteps(MAX_SAMPLE_STEPS),
+            halt_setting => halt_setting,
+        };
+        writeln!(report, "## Example runs\n\n| Input | Result | Steps | Output |\n| --- | --- | --- | --- |").unwrap();
+        for input in &project.sample_inputs {
+            let mut turing_machine = machine.turing_machine().with_halt_setting(halt_setting);
+            let mut tape = Tape::new(input.clone());
+            let outcome = turing_machine.run_with_outcome(&mut tape);
+
+            let state = turing_machine.state();
+            let result = match outcome.termination {
+                Termination::Halted if machine.halting_states.accept.contains(&state) => "accept",
+                Termination::Halted if machine.halting_states.reject.contains(&state) => "reject",
+                Termination::Halted => "halt",
+                termination => termination.name(),
+            };
+            let input = match input.is_empty() {
+                true => "(blank)".to_string(),
+                false => format!("`{}`", input.iter().map(|&x| machine.alphabet.name(x)).collect::<Vec<_>>().join(" ")),
+            };
+            let output = render_tape(&Configuration { step_num: outcome.steps, state, head_loc: turing_machine.head_loc(), tape }, &machine.alphabet);
+            writeln!(report, "| {} | {} | {} | `{}` |", escape_cell(&input), result, outcome.steps, escape_cell(&output)).unwrap();
+        }
+        report.push('\n');
+    }
+
+    if !project.notes.is_empty() {
+        writeln!(report, "## Notes\n\n{}", project.notes.trim()).unwrap();
+    }
+
+    report
+}
+
+/// Renders the transition function of `machine` as a Markdown table with a row per state that has rules
+/// and a column per symbol, naming states by their labels and symbols by the alphabet of `machine`.
+/// Each cell holds the symbol written, the move, and the next state of a rule, or nothing if there is no rule.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::docgen;
+/// use turing_machine::prelude::*;
+/// 
+/// let mut machine = Machine::new("[0,0 -> 1,1,R; 1,0 -> 0,1,L; 1,1 -> 2,1,R]".parse().unwrap());
+/// machine.alphabet = Alphabet::new(&["_", "x"]);
+/// 
+/// assert_eq!(docgen::transition_table(&machine), "\
+/// | State | _ | x |
+/// | --- | --- | --- |
+/// | 0 | x,R,1 |  |
+/// | 1 | x,L,0 | x,R,2 |
+/// ");
+/// ```
+#[inline]
+pub fn transition_table(machine: &Machine) -> String {
+    let num_symbols = machine.num_symbols();
+    let mut table = String::from("| State |");
+    for symbol in 0..num_symbols {
+        write!(table, " {} |", escape_cell(&machine.alphabet.name(symbol))).unwrap();
+    }
+    table.push_str("\n| --- |");
+    table.push_str(&" --- |".repeat(num_symbols as usize));
+    table.push('\n');
+
+    let sources = machine.transition_fn.state_table().iter().map(|&((state, _), _)| state).collect::<BTreeSet<u64>>();
+    for state in sources {
+        write!(table, "| {} |", escape_cell(&label(machine, state))).unwrap();
+        for symbol in 0..num_symbols {
+            match machine.transition_fn.run(state, symbol) {
+                Some((next, write, right)) => {
+                    let cell = format!("{},{},{}", machine.alphabet.name(write), Move::from(right), label(machine, next));
+                    write!(table, " {} |", escape_cell(&cell)).unwrap();
+                }
+                None => table.push_str("  |"),
+            }
+        }
+        table.push('\n');
+    }
+
+    table
+}
+
+/// Renders the transition function of `machine` as a Mermaid state diagram, with an edge per rule labeled
+/// `read/write,move` like `ConfigGraph::to_dot`, an edge into state 0, where runs start,
+/// and an edge out of every state without rules, where runs halt.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::docgen;
+/// use turing_machine::prelude::*;
+/// 
+/// let mut builder = SmartBuilder::new(2, "start");
+/// builder.accept_state("yes").rule("start", 1, "yes", 1, true);
+/// 
+/// assert_eq!(docgen::state_diagram(&builder.build_bundle().0), "\
+/// stateDiagram-v2
+///     state \"start\" as s0
+///     state \"yes\" as s1
+///     [*] --> s0
+///     s0 --> s1: 1/1,R
+///     s1 --> [*]
+/// ");
+/// ```
+#[inline]
+pub fn state_diagram(machine: &Machine) -> String {
+    let states = states(machine);
+    let mut state_table = machine.transition_fn.state_table();
+    state_table.sort_unstable_by_key(|x| x.0);
+
+    let mut diagram = String::from("stateDiagram-v2\n");
+    for &state in &states {
+        writeln!(diagram, "    state \"{}\" as s{}", label(machine, state).replace('"', "'"), state).unwrap();
+    }
+    diagram.push_str("    [*] --> s0\n");
+    for ((state, read), (next, write, right)) in &state_table {
+        writeln!(diagram, "    s{} --> s{}: {}/{},{}", state, next, machine.alphabet.name(*read), machine.alphabet.name(*write), Move::from(*right)).unwrap();
+    }
+    for state in states.into_iter().filter(|&s| state_table.iter().all(|((state, _), _)| *state != s)) {
+        writeln!(diagram, "    s{} --> [*]", state).unwrap();
+    }
+
+    diagram
+}
+
+/// Returns every state of `machine`: state 0, and every state that a rule reads in or goes to.
+fn states(machine: &Machine) -> BTreeSet<u64> {
+    let mut states = BTreeSet::from([0]);
+    for ((state, _), (next, _, _)) in machine.transition_fn.state_table() {
+        states.insert(state);
+        states.insert(next);
+    }
+
+    states
+}
+
+/// Returns the label of `state` in `machine`, or its number if it has none.
+fn label(machine: &Machine, state: u64) -> String {
+    machine.state_labels.get(state as usize).cloned().unwrap_or_else(|| state.to_string())
+}
+
+/// Escapes the pipes in `s` so that it fits in a cell of a Markdown table.
+fn escape_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::docgen::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_machine_report() {
+        // runs right over ones until it halts on a blank, or writes ones forever after a bar
+        let transition_fn = TransitionFn::new(&[((0, 1), (0, 1, true)), ((0, 2), (1, 2, true)), ((1, 0), (1, 1, true))])
+            .with_annotation(0, 2, "start | skip");
+        let mut machine = Machine::new(transition_fn);
+        machine.alphabet = Alphabet::new(&["_", "1", "|"]);
+        machine.author = "me".to_string();
+        let mut project = Project::new(machine);
+        project.sample_inputs = vec![vec![1, 1], vec![2]];
+        project.notes = "Never halts after a bar.".to_string();
+
+        let report = machine_report(&project);
+        assert!(report.contains("\n\nBy me.\n\n## Transition table\n\n| State | _ | 1 | \\| |\n"));
+        assert!(report.contains("| 0 |  | 1,R,0 | \\|,R,1 |\n| 1 | 1,R,1 |  |  |\n"));
+        assert!(report.contains("    s0 --> s1: |/|,R\n"));
+        assert!(!report.contains("--> [*]\n"));
+        assert!(report.contains("## Rule notes\n\n- In state 0 reading `|`: start | skip\n"));
+        assert!(report.contains("- States: 2\n- Symbols: 3\n- Rules: 3, of which 1 annotated\n\n"));
+        assert!(report.contains("| `1 1` | halt | 2 | `1 1 [_]` |\n"));
+        assert!(report.contains(&format!("| `\\|` | non_halting | {} |", MAX_SAMPLE_STEPS)));
+        assert!(report.ends_with("## Notes\n\nNever halts after a bar.\n"));
+
+        // sections without content are left out
+        let report = machine_report(&Project::new(Machine::new(TransitionFn::default())));
+        assert_eq!(report.matches("## ").count(), 3);
+        assert!(report.contains("stateDiagram-v2\n    state \"0\" as s0\n    [*] --> s0\n    s0 --> [*]\n"));
+    }
+}
@@ -0,0 +1,434 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::experiment::{JsonlSink, SweepLine};
+use crate::tape::Tape;
+use crate::transition_fn::{Rule, TransitionFn};
+use crate::turing_machine::{HaltCheck, HaltSetting, Termination, TuringMachine};
+
+/// The number of steps between checks of the deciders of `Simulations` by default.
+const DEFAULT_CHECK_INTERVAL: usize = 64;
+
+/// Builds a lazy iterator over the machines of `TransitionFn::enumerate`, in the same order,
+/// that skips classes of machines known to be irrelevant to a sweep. Filters are checked on the
+/// rules of each machine before its `TransitionFn` is built, so skipped machines are cheap.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::enumeration::EnumerationBuilder;
+/// use turing_machine::transition_fn::TransitionFn;
+/// 
+/// let all = EnumerationBuilder::new(2, 2);
+/// assert!(all.iter().eq(TransitionFn::enumerate(2, 2)));
+/// 
+/// // machines that can enter the halting state, up to mirroring and renaming states
+/// let machines = EnumerationBuilder::new(2, 2).only_with_halting_rule().canonical_only().iter().collect::<Vec<_>>();
+/// assert_eq!(machines.len(), 8_320);
+/// assert!(machines.iter().all(|x| x.has_halting_rule(2) && x.run(0, 0).unwrap().2));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnumerationBuilder {
+    num_states: usize,
+    num_symbols: usize,
+    range: Range<u128>,
+    only_with_halting_rule: bool,
+    canonical_only: bool,
+    max_write_symbol: Option<u64>,
+}
+
+impl EnumerationBuilder {
+    /// Constructs a builder that enumerates every machine with the specified number of states and symbols.
+    /// 
+    /// # Panics
+    /// Panics if the number of machines exceeds `u128::MAX`; see `TransitionFn::enumeration_size`.
+    #[inline]
+    pub fn new(num_states: usize, num_symbols: usize) -> Self {
+        EnumerationBuilder {
+            num_states,
+            num_symbols,
+            range: 0..TransitionFn::enumeration_size(num_states, num_symbols),
+            only_with_halting_rule: false,
+            canonical_only: false,
+            max_write_symbol: None,
+        }
+    }
+
+    /// Only enumerates the machines at the indices of `range` in the output of `TransitionFn::enumerate`,
+    /// such as a range of `TransitionFn::enumeration_shards`.
+    #[inline]
+    pub fn shard(mut self, range: Range<u128>) -> Self {
+        self.range = range.start.min(self.range.end)..range.end.min(self.range.end);
+        self
+    }
+
+    /// Skips machines with no rule that enters the halting state, which can only halt on a tape
+    /// containing symbols they have no rule for, and so never halt on a blank tape.
+    #[inline]
+    pub fn only_with_halting_rule(mut self) -> Self {
+        self.only_with_halting_rule = true;
+        self
+    }
+
+    /// Skips all but one machine of each class of machines that behave the same on a blank tape
+    /// up to mirroring the tape and renaming states other than 0 and the halting state.
+    /// A machine is kept if its rule for state 0 and symbol 0 moves right, and if, reading its rules
+    /// in order of state and symbol, each state is entered for the first time in increasing order.
+    #[inline]
+    pub fn canonical_only(mut self) -> Self {
+        self.canonical_only = true;
+        self
+    }
+
+    /// Skips machines that write any symbol above `max`, such as 1 to only enumerate machines
+    /// that write ones over more symbols.
+    #[inline]
+    pub fn max_write_symbol(mut self, max: u64) -> Self {
+        self.max_write_symbol = Some(max);
+        self
+    }
+
+    /// Returns a lazy iterator over the machines that pass every filter of `self`.
+    #[inline]
+    pub fn iter(&self) -> Enumeration {
+        Enumeration { builder: self.clone(), next: self.range.start }
+    }
+
+    /// Returns a lazy iterator that runs each machine of `self` on a blank tape as it is enumerated,
+    /// until it halts, runs out of the budget of `halt_setting`, or is proven never to halt by a decider,
+    /// and yields it along with the outcome. Only the machine being run is held in memory.
+    /// 
+    /// A machine has halted once it enters the halting state, which is the number of states.
+    /// Machines that run out of budget are classified like `TuringMachine::chaitin_approx` does.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::enumeration::{EnumerationBuilder, Simulations};
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let simulations = EnumerationBuilder::new(2, 2).simulate(HaltSetting::AfterSteps(50));
+    /// let max_steps = simulations
+    ///     .filter(|(_, outcome)| outcome.termination == Termination::Halted)
+    ///     .map(|(_, outcome)| outcome.steps)
+    ///     .max();
+    /// assert_eq!(max_steps, Some(6));
+    /// 
+    /// // checking a decider every 8 steps abandons machines that run off early, without changing any termination
+    /// let steps = |simulations: Simulations| simulations.map(|(_, outcome)| outcome.steps).sum::<usize>();
+    /// let quick = EnumerationBuilder::new(2, 2).simulate(HaltSetting::AfterSteps(50)).with_decider(TuringMachine::runs_off_forever).check_every(8);
+    /// assert!(steps(quick) < steps(EnumerationBuilder::new(2, 2).simulate(HaltSetting::AfterSteps(50))));
+    /// ```
+    #[inline]
+    pub fn simulate(&self, halt_setting: HaltSetting) -> Simulations {
+        Simulations {
+            machines: self.iter(),
+            halt_setting,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            deciders: Vec::new(),
+        }
+    }
+
+    /// Returns whether the machine with the sorted `rules` passes every filter of `self`.
+    fn accepts(&self, rules: &[Rule]) -> bool {
+        let num_states = self.num_states as u64;
+        (!self.only_with_halting_rule || rules.iter().any(|&(_, (next, _, _))| next >= num_states))
+            && self.max_write_symbol.is_none_or(|max| rules.iter().all(|&(_, (_, write, _))| write <= max))
+            && (!self.canonical_only || is_canonical(rules, num_states))
+    }
+}
+
+impl IntoIterator for &EnumerationBuilder {
+    type Item = TransitionFn;
+    type IntoIter = Enumeration;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A lazy iterator over enumerated machines, created by `EnumerationBuilder::iter`.
+#[derive(Clone, Debug)]
+pub struct Enumeration {
+    builder: EnumerationBuilder,
+    next: u128,
+}
+
+impl Enumeration {
+    /// Returns the next machine along with its index in the output of `TransitionFn::enumerate`.
+    fn next_indexed(&mut self) -> Option<(u128, TransitionFn)> {
+        let builder = &self.builder;
+        while self.next < builder.range.end {
+            let index = self.next;
+            let rules = TransitionFn::enumerated_rules(builder.num_states, builder.num_symbols, index);
+            self.next += 1;
+            if builder.accepts(&rules) {
+                return Some((index, TransitionFn::new(&rules)));
+            }
+        }
+
+        None
+    }
+}
+
+impl Iterator for Enumeration {
+    type Item = TransitionFn;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|(_, trans_fn)| trans_fn)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, usize::try_from(self.builder.range.end - self.next).ok())
+    }
+}
+
+/// A check of whether a machine provably never halts from its current configuration, such as `TuringMachine::runs_off_forever`.
+pub type NonHaltingDecider = Arc<dyn Fn(&TuringMachine, &Tape) -> bool + Send + Sync>;
+
+/// How the run of an enumerated machine ended, yielded by `Simulations`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimulationOutcome {
+    /// The index of the machine in the output of `TransitionFn::enumerate`.
+    pub index: u128,
+    pub termination: Termination,
+    /// The number of steps taken before the machine halted, ran out of budget, or was proven never to halt.
+    pub steps: usize,
+}
+
+/// A lazy iterator that runs enumerated machines, created by `EnumerationBuilder::simulate`.
+#[derive(Clone)]
+pub struct Simulations {
+    machines: Enumeration,
+    halt_setting: HaltSetting,
+    check_interval: usize,
+    deciders: Vec<NonHaltingDecider>,
+}
+
+impl Simulations {
+    /// Abandons a machine as `Termination::NonHalting` as soon as `decider` proves it never halts,
+    /// checking every `check_every` steps. Deciders are not checked when a machine runs out of budget,
+    /// so they can only make runs shorter.
+    #[inline]
+    pub fn with_decider(mut self, decider: impl Fn(&TuringMachine, &Tape) -> bool + Send + Sync + 'static) -> Self {
+        self.deciders.push(Arc::new(decider));
+        self
+    }
+
+    /// Checks the deciders every `steps` steps instead of every 64.
+    /// 
+    /// # Panics
+    /// Panics if `steps` is 0.
+    #[inline]
+    pub fn check_every(mut self, steps: usize) -> Self {
+        if steps == 0 {
+            panic!("Deciders must be checked at an interval of at least one step");
+        }
+
+        self.check_interval = steps;
+        self
+    }
+
+    /// Runs every remaining machine like iterating over `self`, but writes a `SweepLine` for each to `sink`
+    /// instead of yielding it.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::enumeration::EnumerationBuilder;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut sink = JsonlSink::new(Vec::new());
+    /// EnumerationBuilder::new(1, 2).shard(0..10).simulate(HaltSetting::AfterSteps(10)).run_logged(&mut sink).unwrap();
+    /// 
+    /// let log = String::from_utf8(sink.into_inner().unwrap()).unwrap();
+    /// assert_eq!(log.lines().count(), 10);
+    /// assert!(log.starts_with(r#"{"index":0,"fingerprint":""#));
+    /// ```
+    /// 
+    /// # Errors
+    /// Passes on any error from `sink`.
+    /// 
+    /// # Panics
+    /// Panics if the index of a machine does not fit in a `usize`.
+    #[inline]
+    pub fn run_logged(mut self, sink: &mut JsonlSink<impl Write>) -> io::Result<()> {
+        while let Some((index, trans_fn)) = self.machines.next_indexed() {
+            let fingerprint = trans_fn.fingerprint();
+            let mut machine = TuringMachine::new(trans_fn);
+            let mut tape = Tape::default();
+            let (termination, steps) = self.run(&mut machine, &mut tape);
+            crate::turing_machine::record_run(steps, || termination);
+
+            sink.write(&SweepLine {
+                index: usize::try_from(index).expect("the index of a logged machine fits in a usize"),
+                fingerprint,
+                termination,
+                steps,
+                nonblank: tape.symbols().iter().filter(|&&s| s != 0).count(),
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs `machine` on `tape` until it halts, runs out of budget, or a decider proves it never halts.
+    fn run(&self, machine: &mut TuringMachine, tape: &mut Tape) -> (Termination, usize) {
+        let mut halt_check = HaltCheck::new(self.halt_setting);
+        let mut steps = 0;
+        while !halt_check.should_halt() {
+            if machine.step(tape).is_none() {
+                return (Termination::Halted, steps);
+            }
+            steps += 1;
+
+            if steps.is_multiple_of(self.check_interval) && self.deciders.iter().any(|decider| decider(machine, tape)) {
+                return (Termination::NonHalting, steps);
+            }
+        }
+
+        let termination = if machine.state() == self.machines.builder.num_states as u64 {
+            Termination::Halted
+        }
+        else if machine.runs_off_forever(tape) {
+            Termination::NonHalting
+        }
+        else {
+            Termination::forced_by(self.halt_setting)
+        };
+        (termination, steps)
+    }
+}
+
+impl fmt::Debug for Simulations {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Simulations")
+            .field("machines", &self.machines)
+            .field("halt_setting", &self.halt_setting)
+            .field("check_interval", &self.check_interval)
+            .field("deciders", &self.deciders.len())
+            .finish()
+    }
+}
+
+impl Iterator for Simulations {
+    type Item = (TransitionFn, SimulationOutcome);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (index, trans_fn) = self.machines.next_indexed()?;
+        let mut machine = TuringMachine::new(trans_fn);
+        let (termination, steps) = self.run(&mut machine, &mut Tape::default());
+        crate::turing_machine::record_run(steps, || termination);
+
+        Some((machine.transition_fn().clone(), SimulationOutcome { index, termination, steps }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.machines.size_hint()
+    }
+}
+
+/// Returns whether the machine with the `rules` sorted by key is the representative of its class
+/// described by `EnumerationBuilder::canonical_only`.
+pub(crate) fn is_canonical(rules: &[Rule], num_states: u64) -> bool {
+    if rules.first().is_some_and(|&(key, (_, _, right))| key == (0, 0) && !right) {
+        return false;
+    }
+
+    // the lowest state that has neither been entered nor had its rules read yet
+    let mut next_unseen = 1;
+    for &((state, _), (next, _, _)) in rules {
+        next_unseen = next_unseen.max(state + 1);
+        if next >= next_unseen && next < num_states {
+            if next != next_unseen {
+                return false;
+            }
+            next_unseen += 1;
+        }
+    }
+
+    true
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::enumeration::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_filters() {
+        let all = EnumerationBuilder::new(2, 3);
+        let shard = TransitionFn::enumeration_shards(2, 3, 1000)[500].clone();
+        let expected = shard.clone().map(|i| TransitionFn::enumerated(2, 3, i as usize)).collect::<Vec<_>>();
+        assert!(all.clone().shard(shard.clone()).iter().eq(expected.iter().cloned()));
+
+        let filtered = all.clone().shard(shard).only_with_halting_rule().max_write_symbol(1).iter().collect::<Vec<_>>();
+        let expected = expected
+            .into_iter()
+            .filter(|x| x.has_halting_rule(2) && x.state_table().iter().all(|r| r.1.1 <= 1))
+            .collect::<Vec<_>>();
+        assert!(!filtered.is_empty());
+        assert_eq!(filtered, expected);
+    }
+
+    #[test]
+    fn test_canonical_only() {
+        // the canonical machines include a champion, but only half the machines of 2 states, which only differ by mirroring
+        let canonical = EnumerationBuilder::new(2, 2).canonical_only().iter().collect::<Vec<_>>();
+        assert_eq!(canonical.len() * 2, TransitionFn::enumeration_size(2, 2) as usize);
+        let steps = canonical.iter().filter_map(|x| {
+            let mut machine = TuringMachine::new(x.clone());
+            let mut tape = Tape::default();
+            (0..=20).find(|_| machine.step(&mut tape).is_none())
+        });
+        assert_eq!(steps.max(), Some(6));
+
+        // some way of mirroring and renaming states 1 and 2 makes any machine of 3 states canonical
+        for seed in 0..1000 {
+            let rules = TransitionFn::random(3, 2, Seed(seed)).state_table();
+            let variants = [[0, 1, 2, 3], [0, 2, 1, 3]].into_iter().flat_map(|names| {
+                [false, true].map(|mirror| {
+                    let mut variant = rules.iter().map(|&((s, y), (n, w, r))| ((names[s as usize], y), (names[n as usize], w, r != mirror))).collect::<Vec<_>>();
+                    variant.sort_unstable_by_key(|x| x.0);
+                    variant
+                })
+            });
+            assert!(variants.into_iter().any(|x| super::is_canonical(&x, 3)), "{:?}", rules);
+        }
+    }
+
+    #[test]
+    fn test_simulate() {
+        let halt_setting = HaltSetting::AfterSteps(100);
+        let outcomes = EnumerationBuilder::new(2, 2).shard(1000..3000).simulate(halt_setting).collect::<Vec<_>>();
+        assert_eq!(outcomes.len(), 2000);
+        for (trans_fn, outcome) in &outcomes {
+            assert_eq!(*trans_fn, TransitionFn::enumerated(2, 2, outcome.index as usize));
+            let mut machine = TuringMachine::new(trans_fn.clone());
+            assert_eq!(machine.run_counted(&mut Tape::default(), halt_setting), outcome.steps);
+        }
+
+        // runs abandoned by a decider end early with the same terminations
+        let decided = EnumerationBuilder::new(2, 2)
+            .shard(1000..3000)
+            .simulate(halt_setting)
+            .with_decider(TuringMachine::runs_off_forever)
+            .check_every(10)
+            .collect::<Vec<_>>();
+        assert!(outcomes.iter().zip(&decided).all(|(x, y)| x.1.termination == y.1.termination && x.1.steps >= y.1.steps));
+        assert!(decided.iter().any(|(_, outcome)| outcome.termination == Termination::NonHalting && outcome.steps == 10));
+
+        let mut sink = JsonlSink::with_flush_every(Vec::new(), 100);
+        EnumerationBuilder::new(2, 2).shard(1000..3000).simulate(halt_setting).run_logged(&mut sink).unwrap();
+        let log = String::from_utf8(sink.into_inner().unwrap()).unwrap();
+        let lines = log.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), outcomes.len());
+        let (trans_fn, outcome) = &outcomes[1999];
+        assert!(lines[1999].starts_with(&format!(r#"{{"index":2999,"fingerprint":"{:016x}","#, trans_fn.fingerprint())));
+        assert!(lines[1999].contains(&format!(r#""termination":"{}","steps":{},"#, outcome.termination.name(), outcome.steps)));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::recording::Recording;
+use crate::tape::Tape;
+use crate::transition_fn::{Rule, TransitionFn};
+use crate::turing_machine::TuringMachine;
+
+/// The 2-state, 2-symbol busy beaver, which writes 4 ones in 6 steps.
+/// Returns the machine in its halted configuration and the recording of its run.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::examples;
+/// 
+/// let (machine, recording) = examples::busy_beaver_2();
+/// 
+/// assert_eq!(machine.state(), 2);
+/// assert_eq!(recording.len(), 6);
+/// // recording.play_in_console(std::time::Duration::from_millis(500), true);
+/// ```
+#[inline]
+pub fn busy_beaver_2() -> (TuringMachine, Recording) {
+    run_on_blank(&[
+        ((0, 0), (1, 1, true)), ((0, 1), (1, 1, false)),
+        ((1, 0), (0, 1, false)), ((1, 1), (2, 1, true)),
+    ])
+}
+
+/// The 3-state, 2-symbol busy beaver that writes the most ones, 6 in 14 steps.
+/// Returns the machine in its halted configuration and the recording of its run.
+#[inline]
+pub fn busy_beaver_3() -> (TuringMachine, Recording) {
+    run_on_blank(&[
+        ((0, 0), (1, 1, true)), ((0, 1), (3, 1, true)),
+        ((1, 0), (2, 0, true)), ((1, 1), (1, 1, true)),
+        ((2, 0), (2, 1, false)), ((2, 1), (0, 1, false)),
+    ])
+}
+
+/// The 4-state, 2-symbol busy beaver, which writes 13 ones in 107 steps.
+/// Returns the machine in its halted configuration and the recording of its run.
+#[inline]
+pub fn busy_beaver_4() -> (TuringMachine, Recording) {
+    run_on_blank(&[
+        ((0, 0), (1, 1, true)), ((0, 1), (1, 1, false)),
+        ((1, 0), (0, 1, false)), ((1, 1), (2, 0, false)),
+        ((2, 0), (4, 1, true)), ((2, 1), (3, 1, false)),
+        ((3, 0), (3, 1, true)), ((3, 1), (0, 0, true)),
+    ])
+}
+
+/// Runs the machine with the specified state table on a blank tape until it halts.
+fn run_on_blank(state_table: &[Rule]) -> (TuringMachine, Recording) {
+    let mut machine = TuringMachine::new(TransitionFn::new(state_table));
+    let recording = machine.run_and_record(&mut Tape::default());
+
+    (machine, recording)
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::examples::*;
+
+    #[test]
+    fn test_examples() {
+        for ((machine, recording), (states, steps, ones)) in [busy_beaver_2(), busy_beaver_3(), busy_beaver_4()].into_iter().zip([(2, 6, 4), (3, 14, 6), (4, 107, 13)]) {
+            assert_eq!(machine.state(), states);
+            assert_eq!(recording.len(), steps);
+
+            let tape = recording.seek(steps).unwrap().tape;
+            assert_eq!(tape.raw_symbols().iter().filter(|&&x| x != 0).count(), ones);
+        }
+    }
+}
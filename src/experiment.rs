@@ -0,0 +1,891 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufWriter, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use crate::rng::Seed;
+use crate::tape::Tape;
+use crate::transition_fn::TransitionFn;
+use crate::turing_machine::{Decision, HaltSetting, HaltingStates, Termination, TuringMachine};
+
+/// The progress of a `chaitin_approx`-style sweep over every machine with the specified number of
+/// states and symbols, which can be advanced in batches, saved, and resumed later.
+/// Machines are visited in the order of `TransitionFn::enumerate`, but constructed one at a time.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let mut state = ExperimentState::new(2, 2, HaltSetting::AfterSteps(20));
+/// state.run_for(5000);
+/// 
+/// // save, e.g. before a reboot...
+/// let mut saved = Vec::new();
+/// state.save(&mut saved).unwrap();
+/// 
+/// // ...and pick up where the sweep left off
+/// let mut resumed = ExperimentState::load(&mut saved.as_slice()).unwrap();
+/// while !resumed.is_finished() {
+///     resumed.run_for(5000);
+/// }
+/// 
+/// assert_eq!(resumed.ratios(), TuringMachine::chaitin_approx(2, 2, HaltSetting::AfterSteps(20)).ratios());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExperimentState {
+    pub num_states: usize,
+    pub num_symbols: usize,
+    pub halt_setting: HaltSetting,
+    /// The index of the next machine to run.
+    pub next_index: usize,
+    pub halted: usize,
+    pub undecided: usize,
+    /// The number of machines proven never to halt by `TuringMachine::runs_off_forever`.
+    pub non_halting: usize,
+}
+
+impl ExperimentState {
+    /// Constructs the state of a sweep that has not run any machines yet.
+    #[inline]
+    pub fn new(num_states: usize, num_symbols: usize, halt_setting: HaltSetting) -> Self {
+        ExperimentState {
+            num_states,
+            num_symbols,
+            halt_setting,
+            next_index: 0,
+            halted: 0,
+            undecided: 0,
+            non_halting: 0,
+        }
+    }
+
+    /// Returns the total number of machines in the sweep.
+    /// 
+    /// # Panics
+    /// Panics if the number exceeds `usize::MAX`.
+    #[inline]
+    pub fn num_machines(&self) -> usize {
+        TransitionFn::enumeration_len(self.num_states, self.num_symbols)
+    }
+
+    /// Returns whether every machine has been run.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.next_index >= self.num_machines()
+    }
+
+    /// Runs up to `max_machines` more machines, updating the tallies.
+    #[inline]
+    pub fn run_for(&mut self, max_machines: usize) {
+        let _ = self.run_for_with(max_machines, |_| Ok(()));
+    }
+
+    /// Equivalent to `run_for`, but also writes a `SweepLine` for every machine to `sink`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut state = ExperimentState::new(1, 2, HaltSetting::AfterSteps(10));
+    /// let mut sink = JsonlSink::new(Vec::new());
+    /// state.run_for_logged(2, &mut sink).unwrap();
+    /// 
+    /// let log = String::from_utf8(sink.into_inner().unwrap()).unwrap();
+    /// assert_eq!(log.lines().count(), 2);
+    /// assert!(log.starts_with(r#"{"index":0,"fingerprint":""#));
+    /// ```
+    /// 
+    /// # Errors
+    /// Passes on any error from `sink`. The machines run before the error are still tallied.
+    #[inline]
+    pub fn run_for_logged(&mut self, max_machines: usize, sink: &mut JsonlSink<impl Write>) -> io::Result<()> {
+        self.run_for_with(max_machines, |line| sink.write(&line))
+    }
+
+    fn run_for_with(&mut self, max_machines: usize, mut callback: impl FnMut(SweepLine) -> io::Result<()>) -> io::Result<()> {
+        let end = self.num_machines().min(self.next_index.saturating_add(max_machines));
+        while self.next_index < end {
+            let index = self.next_index;
+            let trans_fn = TransitionFn::enumerated(self.num_states, self.num_symbols, index);
+            let fingerprint = trans_fn.fingerprint();
+            let mut tm = TuringMachine::new(trans_fn);
+            let mut tape = Tape::default();
+            let steps = tm.run_counted(&mut tape, self.halt_setting);
+
+            let termination = if tm.state() == self.num_states as u64 {
+                self.halted += 1;
+                Termination::Halted
+            }
+            else if tm.runs_off_forever(&tape) {
+                self.non_halting += 1;
+                Termination::NonHalting
+            }
+            else {
+                self.undecided += 1;
+                Termination::forced_by(self.halt_setting)
+            };
+            self.next_index += 1;
+            crate::turing_machine::record_run(steps, || termination);
+
+            let nonblank = tape.symbols().iter().filter(|&&s| s != 0).count();
+            callback(SweepLine { index, fingerprint, termination, steps, nonblank })?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the fractions of halted and undecided machines among those run so far,
+    /// which equal the ratios of `TuringMachine::chaitin_approx` once the sweep is finished.
+    #[inline]
+    pub fn ratios(&self) -> (f64, f64) {
+        let total = (self.halted + self.undecided + self.non_halting) as f64;
+
+        (self.halted as f64 / total, self.undecided as f64 / total)
+    }
+
+    /// Writes `self` to `writer` as human-readable `key=value` lines.
+    #[inline]
+    pub fn save(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "num_states={}", self.num_states)?;
+        writeln!(writer, "num_symbols={}", self.num_symbols)?;
+        writeln!(writer, "halt_setting={}", self.halt_setting.token())?;
+        writeln!(writer, "next_index={}", self.next_index)?;
+        writeln!(writer, "halted={}", self.halted)?;
+        writeln!(writer, "undecided={}", self.undecided)?;
+        writeln!(writer, "non_halting={}", self.non_halting)
+    }
+
+    /// Reads a state written by `save`. A missing `non_halting` key, as written before it was tallied, counts as 0.
+    /// 
+    /// # Errors
+    /// Returns an error of kind `InvalidData` if a key is missing or a value is malformed,
+    /// and passes on any error from `reader`.
+    #[inline]
+    pub fn load(reader: &mut impl BufRead) -> io::Result<Self> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+        let mut state = ExperimentState::new(0, 0, HaltSetting::NoForcedHalt);
+        let mut found = 0;
+        for line in reader.lines() {
+            let line = line?;
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let number = || value.parse::<usize>().map_err(|_| invalid("malformed number"));
+
+            match key {
+                "num_states" => state.num_states = number()?,
+                "num_symbols" => state.num_symbols = number()?,
+                "next_index" => state.next_index = number()?,
+                "halted" => state.halted = number()?,
+                "undecided" => state.undecided = number()?,
+                "non_halting" => {
+                    state.non_halting = number()?;
+                    continue;
+                }
+                "halt_setting" => state.halt_setting = HaltSetting::from_token(value).ok_or_else(|| invalid("malformed halt setting"))?,
+                _ => continue,
+            }
+            found += 1;
+        }
+
+        if found < 6 {
+            return Err(invalid("missing keys"));
+        }
+
+        Ok(state)
+    }
+}
+
+/// The result of running one machine in a sweep, written by `JsonlSink` as a line of JSON such as
+/// `{"index":7,"fingerprint":"00c4a2f31b9d6e05","termination":"out_of_steps","steps":1000,"nonblank":3}`.
+/// The fingerprint is written in hexadecimal, since most JSON tools lose precision on large integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SweepLine {
+    /// The index of the machine in the output of `TransitionFn::enumerate`.
+    pub index: usize,
+    pub fingerprint: u64,
+    pub termination: Termination,
+    pub steps: usize,
+    /// The number of nonblank cells on the tape after the run.
+    pub nonblank: usize,
+}
+
+impl fmt::Display for SweepLine {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            r#"{{"index":{},"fingerprint":"{:016x}","termination":"{}","steps":{},"nonblank":{}}}"#,
+            self.index, self.fingerprint, self.termination.name(), self.steps, self.nonblank,
+        )
+    }
+}
+
+/// A sink that writes one `SweepLine` or `ExperimentRecord` per line of JSON,
+/// for analyzing huge sweeps incrementally with standard tools.
+/// 
+/// Lines are buffered and written to the underlying writer every `flush_every` lines, on `flush`, and on drop.
+/// If a write fails partway through a line, the lines that were written in full are dropped from the buffer,
+/// and the next `flush` continues the torn line where it left off instead of writing any of it twice.
+/// A `JsonlSink<File>` can instead be cut back to its last whole line with `truncate_torn_line`,
+/// so that the file never ends in a partial line of JSON.
+#[derive(Debug)]
+pub struct JsonlSink<W: Write> {
+    writer: Option<W>,
+    /// Whole lines, the first of which may have been written in part.
+    buffer: Vec<u8>,
+    buffered_lines: usize,
+    flush_every: usize,
+    /// The number of bytes of the first buffered line that were written before a write failed.
+    torn: usize,
+}
+
+impl<W: Write> JsonlSink<W> {
+    /// Constructs a new `JsonlSink` writing to `writer` every 1024 lines.
+    #[inline]
+    pub fn new(writer: W) -> Self {
+        JsonlSink::with_flush_every(writer, 1024)
+    }
+
+    /// Constructs a new `JsonlSink` writing to `writer` every `flush_every` lines, or every line if it is 0.
+    #[inline]
+    pub fn with_flush_every(writer: W, flush_every: usize) -> Self {
+        JsonlSink {
+            writer: Some(writer),
+            buffer: Vec::new(),
+            buffered_lines: 0,
+            flush_every: flush_every.max(1),
+            torn: 0,
+        }
+    }
+
+    /// Buffers `line`, writing the buffer out if it holds `flush_every` lines.
+    /// 
+    /// # Errors
+    /// Passes on any error from writing the buffer out.
+    #[inline]
+    pub fn write(&mut self, line: &SweepLine) -> io::Result<()> {
+        self.push(line)
+    }
+
+    /// Buffers `record` as a line of JSON such as
+    /// `{"machine":3,"input":0,"halt_setting":1,"decider":0,"decision":"Halt"}`, like `write`.
+    /// 
+    /// # Errors
+    /// Passes on any error from writing the buffer out.
+    #[inline]
+    pub fn write_record(&mut self, record: &ExperimentRecord) -> io::Result<()> {
+        self.push(format_args!(
+            r#"{{"machine":{},"input":{},"halt_setting":{},"decider":{},"decision":"{:?}"}}"#,
+            record.machine, record.input, record.halt_setting, record.decider, record.decision,
+        ))
+    }
+
+    fn push(&mut self, line: impl fmt::Display) -> io::Result<()> {
+        writeln!(self.buffer, "{}", line)?;
+        self.buffered_lines += 1;
+        if self.buffered_lines >= self.flush_every {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes every buffered line to the underlying writer and flushes it.
+    /// 
+    /// # Errors
+    /// Passes on any error from the underlying writer, after dropping the lines it took in full from the buffer.
+    #[inline]
+    pub fn flush(&mut self) -> io::Result<()> {
+        let writer = self.writer.as_mut().expect("the writer is only taken by into_inner");
+        let mut written = self.torn;
+        while written < self.buffer.len() {
+            let error = match writer.write(&self.buffer[written..]) {
+                Ok(0) => io::ErrorKind::WriteZero.into(),
+                Ok(n) => {
+                    written += n;
+                    continue;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => e,
+            };
+            let whole = self.buffer[..written].iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+            self.buffered_lines -= self.buffer[..whole].iter().filter(|&&b| b == b'\n').count();
+            self.buffer.drain(..whole);
+            self.torn = written - whole;
+            return Err(error);
+        }
+        self.buffer.clear();
+        self.buffered_lines = 0;
+        self.torn = 0;
+
+        writer.flush()
+    }
+
+    /// Flushes `self` and returns the underlying writer.
+    /// 
+    /// # Errors
+    /// Passes on any error from flushing.
+    #[inline]
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.writer.take().expect("the writer is only taken by into_inner"))
+    }
+}
+
+impl JsonlSink<File> {
+    /// Cuts the underlying file back to the end of its last whole line if a write failed partway through a line,
+    /// and makes the next `flush` write that line again in full.
+    /// 
+    /// # Errors
+    /// Passes on any error from seeking in or truncating the file.
+    #[inline]
+    pub fn truncate_torn_line(&mut self) -> io::Result<()> {
+        if self.torn > 0 {
+            let file = self.writer.as_mut().expect("the writer is only taken by into_inner");
+            let end = file.stream_position()? - self.torn as u64;
+            file.set_len(end)?;
+            file.seek(SeekFrom::Start(end))?;
+            self.torn = 0;
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for JsonlSink<W> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.writer.is_some() {
+            let _ = self.flush();
+        }
+    }
+}
+
+/// A declarative description of a sweep: every machine from `machines` is run on every input,
+/// under every halt setting, and classified by every decider, and each result is written to every sink.
+/// Empty lists fall back to a blank tape, `HaltSetting::AfterSteps(1000)`,
+/// and `Decider::HaltingStates` with no declared halting states, respectively; without sinks,
+/// the results are only returned by `run`.
+/// 
+/// With the `serde` feature, configs can be read from JSON or TOML files.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let mut config = ExperimentConfig::new(MachineSource::Enumerate { num_states: 1, num_symbols: 2 });
+/// config.halt_settings = vec![HaltSetting::AfterSteps(10)];
+/// 
+/// let records = run(&config).unwrap();
+/// 
+/// assert_eq!(records.len(), 64);
+/// assert_eq!(records.iter().filter(|r| r.decision == Decision::Halt).count(), 32);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExperimentConfig {
+    pub machines: MachineSource,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub inputs: Vec<InputSource>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub halt_settings: Vec<HaltSetting>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deciders: Vec<Decider>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sinks: Vec<OutputSink>,
+}
+
+impl ExperimentConfig {
+    /// Constructs a config that runs the specified machines with every other setting at its fallback.
+    #[inline]
+    pub fn new(machines: MachineSource) -> Self {
+        ExperimentConfig {
+            machines,
+            inputs: Vec::new(),
+            halt_settings: Vec::new(),
+            deciders: Vec::new(),
+            sinks: Vec::new(),
+        }
+    }
+
+    /// Parses a config from JSON.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let config = ExperimentConfig::from_json(r#"{
+    ///     "machines": { "kind": "listed", "machines": ["[0,0 -> 1,1,R]"] },
+    ///     "inputs": [{ "kind": "symbols", "symbols": [0, 1] }],
+    ///     "halt_settings": [{ "AfterSteps": 100 }]
+    /// }"#).unwrap();
+    /// 
+    /// assert_eq!(config.inputs, [InputSource::Symbols { symbols: vec![0, 1] }]);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if `s` is not valid JSON or does not describe a config.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_json(s: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(s)
+    }
+
+    /// Parses a config from TOML.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let config = ExperimentConfig::from_toml(r#"
+    ///     halt_settings = [{ AfterSteps = 20 }]
+    /// 
+    ///     [machines]
+    ///     kind = "enumerate"
+    ///     num_states = 2
+    ///     num_symbols = 2
+    /// 
+    ///     [[deciders]]
+    ///     kind = "pattern"
+    ///     pattern = [1, 1, 1]
+    /// "#).unwrap();
+    /// 
+    /// assert_eq!(config.machines, MachineSource::Enumerate { num_states: 2, num_symbols: 2 });
+    /// assert_eq!(config.deciders, [Decider::Pattern { pattern: vec![1, 1, 1] }]);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if `s` is not valid TOML or does not describe a config.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_toml(s: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(s)
+    }
+}
+
+/// Where the machines of an `ExperimentConfig` come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum MachineSource {
+    /// Every machine in the order of `TransitionFn::enumerate`.
+    Enumerate { num_states: usize, num_symbols: usize },
+    /// `count` machines from `TransitionFn::random`, the `i`th seeded with `seed.derive(i)`.
+    Random { num_states: usize, num_symbols: usize, count: usize, seed: Seed },
+    /// Transition functions in the format of their `Display` implementation, e.g. `[0,0 -> 1,1,R]`.
+    Listed { machines: Vec<String> },
+}
+
+impl MachineSource {
+    /// Returns the number of machines.
+    /// 
+    /// # Panics
+    /// Panics if an enumeration has more than `usize::MAX` machines.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            MachineSource::Enumerate { num_states, num_symbols } => TransitionFn::enumeration_len(*num_states, *num_symbols),
+            MachineSource::Random { count, .. } => *count,
+            MachineSource::Listed { machines } => machines.len(),
+        }
+    }
+
+    /// Returns whether there are no machines.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Constructs the machine at position `index`.
+    /// 
+    /// # Errors
+    /// Returns an error of kind `InvalidData` if a listed machine is malformed.
+    /// 
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    #[inline]
+    pub fn get(&self, index: usize) -> io::Result<TransitionFn> {
+        assert!(index < self.len(), "machine index {} out of bounds", index);
+
+        match self {
+            MachineSource::Enumerate { num_states, num_symbols } => Ok(TransitionFn::enumerated(*num_states, *num_symbols, index)),
+            MachineSource::Random { num_states, num_symbols, seed, .. } => Ok(TransitionFn::random(*num_states, *num_symbols, seed.derive(index as u64))),
+            MachineSource::Listed { machines } => machines[index]
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("machine {}: {:?}", index, e))),
+        }
+    }
+}
+
+/// An input tape of an `ExperimentConfig`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum InputSource {
+    Blank,
+    /// The symbols starting at location 0, as in `Tape::new`.
+    Symbols { symbols: Vec<u64> },
+    /// A tape from `Tape::random_seeded`.
+    Random { len: usize, alphabet_size: u64, seed: Seed },
+}
+
+impl InputSource {
+    /// Constructs the tape.
+    #[inline]
+    pub fn tape(&self) -> Tape {
+        match self {
+            InputSource::Blank => Tape::default(),
+            InputSource::Symbols { symbols } => Tape::new(symbols.clone()),
+            InputSource::Random { len, alphabet_size, seed } => Tape::random_seeded(*len, *alphabet_size, *seed),
+        }
+    }
+}
+
+/// How an `ExperimentConfig` classifies each run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Decider {
+    /// Classifies runs with `TuringMachine::decide` using the specified halting states.
+    HaltingStates(HaltingStates),
+    /// Accepts runs that write `pattern` to the tape, found with `TuringMachine::run_until_pattern`,
+    /// and rejects runs that halt without doing so.
+    Pattern { pattern: Vec<u64> },
+}
+
+impl Decider {
+    /// Runs the specified transition function on `tape` from state 0 and head location 0 and classifies the run.
+    #[inline]
+    pub fn decide(&self, trans_fn: &TransitionFn, tape: &mut Tape, halt_setting: HaltSetting) -> Decision {
+        match self {
+            Decider::HaltingStates(halting_states) => TuringMachine::new(trans_fn.clone())
+                .with_halt_setting(halt_setting)
+                .with_halting_states(halting_states.clone())
+                .decide(tape),
+            Decider::Pattern { pattern } => {
+                let mut machine = TuringMachine::new(trans_fn.clone());
+                if machine.run_until_pattern(tape, pattern, halt_setting).is_some() {
+                    Decision::Accept
+                }
+                else if trans_fn.run(machine.state(), tape.symbol_at_n(machine.head_loc())).is_none() {
+                    Decision::Reject
+                }
+                else {
+                    Decision::Undecided
+                }
+            }
+        }
+    }
+}
+
+/// Where `run` writes its results, one `ExperimentRecord` per line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum OutputSink {
+    Stdout,
+    /// A file, which is created or truncated.
+    File { path: PathBuf },
+    /// A file, which is created or truncated, written through a `JsonlSink` with one line of JSON per record.
+    Jsonl { path: PathBuf },
+}
+
+/// An opened `OutputSink`.
+enum OpenSink {
+    Lines(Box<dyn Write>),
+    Jsonl(JsonlSink<File>),
+}
+
+impl OpenSink {
+    /// Writes `record` as a line, cutting a JSONL file back to its last whole line if the write fails.
+    fn write(&mut self, record: &ExperimentRecord) -> io::Result<()> {
+        match self {
+            OpenSink::Lines(writer) => writeln!(writer, "{}", record),
+            OpenSink::Jsonl(sink) => sink.write_record(record).inspect_err(|_| {
+                let _ = sink.truncate_torn_line();
+            }),
+        }
+    }
+
+    /// Flushes `self` like `write`.
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            OpenSink::Lines(writer) => writer.flush(),
+            OpenSink::Jsonl(sink) => sink.flush().inspect_err(|_| {
+                let _ = sink.truncate_torn_line();
+            }),
+        }
+    }
+}
+
+/// The result of a single run of `run`, identified by its position in each list of the `ExperimentConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExperimentRecord {
+    pub machine: usize,
+    pub input: usize,
+    pub halt_setting: usize,
+    pub decider: usize,
+    pub decision: Decision,
+}
+
+impl fmt::Display for ExperimentRecord {
+    /// Formats `self` as tab-separated fields in declaration order, e.g. `3\t0\t1\t0\tHalt`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}\t{}\t{}\t{}\t{:?}", self.machine, self.input, self.halt_setting, self.decider, self.decision)
+    }
+}
+
+/// Runs the sweep described by `config`, writing each result to its sinks as it is produced.
+/// Returns every result, ordered by machine, then input, then halt setting, then decider.
+/// 
+/// # Errors
+/// Returns an error of kind `InvalidData` if a listed machine is malformed,
+/// and passes on any error from creating or writing to a sink.
+#[inline]
+pub fn run(config: &ExperimentConfig) -> io::Result<Vec<ExperimentRecord>> {
+    let default_inputs = [InputSource::Blank];
+    let default_halt_settings = [HaltSetting::AfterSteps(1000)];
+    let default_deciders = [Decider::HaltingStates(HaltingStates::default())];
+    let inputs = if config.inputs.is_empty() { &default_inputs[..] } else { &config.inputs };
+    let halt_settings = if config.halt_settings.is_empty() { &default_halt_settings[..] } else { &config.halt_settings };
+    let deciders = if config.deciders.is_empty() { &default_deciders[..] } else { &config.deciders };
+
+    let mut sinks = config.sinks
+        .iter()
+        .map(|sink| -> io::Result<OpenSink> {
+            match sink {
+                OutputSink::Stdout => Ok(OpenSink::Lines(Box::new(io::stdout()))),
+                OutputSink::File { path } => Ok(OpenSink::Lines(Box::new(BufWriter::new(File::create(path)?)))),
+                OutputSink::Jsonl { path } => Ok(OpenSink::Jsonl(JsonlSink::new(File::create(path)?))),
+            }
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let mut records = Vec::new();
+    for machine in 0..config.machines.len() {
+        let trans_fn = config.machines.get(machine)?;
+        for (input, source) in inputs.iter().enumerate() {
+            for (halt_index, &halt_setting) in halt_settings.iter().enumerate() {
+                for (decider_index, decider) in deciders.iter().enumerate() {
+                    let record = ExperimentRecord {
+                        machine,
+                        input,
+                        halt_setting: halt_index,
+                        decider: decider_index,
+                        decision: decider.decide(&trans_fn, &mut source.tape(), halt_setting),
+                    };
+                    for sink in &mut sinks {
+                        sink.write(&record)?;
+                    }
+                    records.push(record);
+                }
+            }
+        }
+    }
+
+    for sink in &mut sinks {
+        sink.flush()?;
+    }
+
+    Ok(records)
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_run_for() {
+        let mut state = ExperimentState::new(1, 2, HaltSetting::AfterSteps(10));
+        assert_eq!(state.num_machines(), 64);
+
+        state.run_for(10);
+        assert_eq!((state.next_index, state.halted + state.undecided + state.non_halting), (10, 10));
+        state.run_for(usize::MAX);
+        assert!(state.is_finished());
+        // a 1-state machine that does not halt immediately runs off forever
+        assert_eq!(state.ratios(), (0.5, 0.0));
+        assert_eq!(state.non_halting, 32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_num_machines_overflow() {
+        // 36^15 machines fit in a u128 but not a usize
+        ExperimentState::new(5, 3, HaltSetting::AfterSteps(10)).num_machines();
+    }
+
+    #[test]
+    fn test_run_for_logged() {
+        let mut state = ExperimentState::new(2, 2, HaltSetting::AfterSteps(20));
+        let mut sink = JsonlSink::with_flush_every(Vec::new(), 100);
+        state.run_for_logged(250, &mut sink).unwrap();
+        state.run_for_logged(50, &mut sink).unwrap();
+        let log = String::from_utf8(sink.into_inner().unwrap()).unwrap();
+
+        let lines = log.lines().collect::<Vec<&str>>();
+        assert_eq!(lines.len(), 300);
+        assert_eq!(state.halted + state.undecided + state.non_halting, 300);
+        assert_eq!(lines.iter().filter(|line| line.contains(r#""termination":"halted""#)).count(), state.halted);
+
+        let trans_fn = TransitionFn::enumerated(2, 2, 299);
+        assert!(lines[299].starts_with(&format!(r#"{{"index":299,"fingerprint":"{:016x}","#, trans_fn.fingerprint())));
+    }
+
+    #[test]
+    fn test_jsonl_sink_writes_whole_lines() {
+        let line = SweepLine { index: 1, fingerprint: 0xab, termination: Termination::NonHalting, steps: 20, nonblank: 0 };
+        let mut sink = JsonlSink::with_flush_every(Vec::new(), 2);
+        sink.write(&line).unwrap();
+        assert!(sink.writer.as_ref().unwrap().is_empty());
+        sink.write(&line).unwrap();
+        assert_eq!(sink.writer.as_ref().unwrap().len(), 2 * (line.to_string().len() + 1));
+
+        assert_eq!(line.to_string(), r#"{"index":1,"fingerprint":"00000000000000ab","termination":"non_halting","steps":20,"nonblank":0}"#);
+    }
+
+    /// A writer that takes at most `limit` bytes in total, failing once it has taken them all.
+    struct FailingWriter {
+        written: Vec<u8>,
+        limit: usize,
+    }
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            let n = buf.len().min(self.limit - self.written.len()).min(7);
+            if n == 0 {
+                return Err(std::io::ErrorKind::Other.into());
+            }
+            self.written.extend_from_slice(&buf[..n]);
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_jsonl_sink_partial_write() {
+        let line = SweepLine { index: 1, fingerprint: 0xab, termination: Termination::Halted, steps: 2, nonblank: 1 };
+        let mut sink = JsonlSink::with_flush_every(FailingWriter { written: Vec::new(), limit: 150 }, 3);
+        sink.write(&line).unwrap();
+        sink.write(&line).unwrap();
+        assert!(sink.write(&line).is_err());
+
+        // the write failed partway through the second line, so only the first one was dropped from the buffer
+        let line_len = line.to_string().len() + 1;
+        assert_eq!((sink.buffered_lines, sink.torn), (2, 150 - line_len));
+
+        // the bytes written before the failure are not written again
+        sink.writer.as_mut().unwrap().limit = usize::MAX;
+        let written = sink.into_inner().unwrap().written;
+        assert_eq!(String::from_utf8(written).unwrap(), format!("{}\n", line).repeat(3));
+    }
+
+    #[test]
+    fn test_jsonl_sink_truncate_torn_line() {
+        let path = std::env::temp_dir().join(format!("tm_experiment_{}.jsonl", std::process::id()));
+        let line = format!("{}\n", SweepLine { index: 4, fingerprint: 0xcd, termination: Termination::Halted, steps: 3, nonblank: 2 });
+        let mut sink = JsonlSink::new(std::fs::File::create(&path).unwrap());
+
+        // the state of the sink after writing two lines failed 10 bytes into the second one
+        std::io::Write::write_all(sink.writer.as_mut().unwrap(), &line.repeat(2).as_bytes()[..line.len() + 10]).unwrap();
+        sink.buffer.extend_from_slice(line.as_bytes());
+        sink.buffered_lines = 1;
+        sink.torn = 10;
+
+        sink.truncate_torn_line().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), line);
+        sink.flush().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), line.repeat(2));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut state = ExperimentState::new(2, 3, HaltSetting::AfterDuration(Duration::from_millis(3)));
+        state.next_index = 123;
+        state.halted = 100;
+        state.undecided = 20;
+        state.non_halting = 3;
+
+        let mut saved = Vec::new();
+        state.save(&mut saved).unwrap();
+        assert_eq!(ExperimentState::load(&mut saved.as_slice()).unwrap(), state);
+
+        let mut virtual_state = state.clone();
+        virtual_state.halt_setting = HaltSetting::AfterVirtualDuration { duration: Duration::from_millis(3), steps_per_second: 1_000_000 };
+        let mut virtual_saved = Vec::new();
+        virtual_state.save(&mut virtual_saved).unwrap();
+        assert_eq!(ExperimentState::load(&mut virtual_saved.as_slice()).unwrap(), virtual_state);
+
+        assert!(ExperimentState::load(&mut "num_states=2\n".as_bytes()).is_err());
+        let old = String::from_utf8(saved.clone()).unwrap().replace("non_halting=3\n", "");
+        assert_eq!(ExperimentState::load(&mut old.as_bytes()).unwrap().non_halting, 0);
+        assert!(ExperimentState::load(&mut String::from_utf8(saved).unwrap().replace("123", "x").as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let mut config = ExperimentConfig::new(MachineSource::Listed { machines: vec![
+            "[0,0 -> 1,1,R; 1,0 -> 0,1,L; 0,1 -> 1,1,L; 1,1 -> 2,1,R]".to_string(),
+            "[0,0 -> 0,1,R]".to_string(),
+        ] });
+        config.inputs = vec![InputSource::Blank, InputSource::Symbols { symbols: vec![1] }];
+        config.halt_settings = vec![HaltSetting::AfterSteps(3), HaltSetting::AfterSteps(50)];
+        config.deciders = vec![
+            Decider::HaltingStates(HaltingStates::default()),
+            Decider::Pattern { pattern: vec![1, 1, 1] },
+        ];
+
+        let path = std::env::temp_dir().join(format!("tm_experiment_{}.tsv", std::process::id()));
+        let jsonl_path = path.with_extension("jsonl");
+        config.sinks = vec![OutputSink::File { path: path.clone() }, OutputSink::Jsonl { path: jsonl_path.clone() }];
+        let records = run(&config).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        let jsonl = std::fs::read_to_string(&jsonl_path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&jsonl_path).unwrap();
+
+        assert_eq!(records.len(), 16);
+        assert_eq!(written.lines().collect::<Vec<_>>(), records.iter().map(|r| r.to_string()).collect::<Vec<_>>());
+        assert_eq!(jsonl.lines().count(), 16);
+        assert_eq!(jsonl.lines().last(), Some(r#"{"machine":1,"input":1,"halt_setting":1,"decider":1,"decision":"Reject"}"#));
+        let decisions = |machine, input| records
+            .iter()
+            .filter(|r| r.machine == machine && r.input == input)
+            .map(|r| r.decision)
+            .collect::<Vec<_>>();
+        // the BB-2 champion halts within 50 steps but not 3, and writes 1 1 1 along the way;
+        // the second machine halts immediately on a 1 but runs forever on a blank tape
+        assert_eq!(decisions(0, 0), [Decision::Undecided, Decision::Undecided, Decision::Halt, Decision::Accept]);
+        assert_eq!(decisions(1, 0), [Decision::Undecided, Decision::Accept, Decision::Undecided, Decision::Accept]);
+        assert_eq!(decisions(1, 1), [Decision::Halt, Decision::Reject, Decision::Halt, Decision::Reject]);
+
+        config.machines = MachineSource::Listed { machines: vec!["[0,0 -> 1,1,X]".to_string()] };
+        assert_eq!(run(&config).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_config_round_trip() {
+        let mut config = ExperimentConfig::new(MachineSource::Random { num_states: 3, num_symbols: 2, count: 5, seed: Seed(1) });
+        config.inputs = vec![InputSource::Random { len: 8, alphabet_size: 2, seed: Seed(2) }];
+        config.halt_settings = vec![HaltSetting::NoForcedHalt, HaltSetting::AfterDuration(Duration::from_millis(5))];
+        config.deciders = vec![Decider::HaltingStates(HaltingStates { accept: [3].into(), ..Default::default() })];
+        config.sinks = vec![OutputSink::Stdout];
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(ExperimentConfig::from_json(&json).unwrap(), config);
+        let toml = toml::to_string(&config).unwrap();
+        assert_eq!(ExperimentConfig::from_toml(&toml).unwrap(), config);
+
+        assert!(ExperimentConfig::from_json(r#"{ "machines": { "kind": "unknown" } }"#).is_err());
+    }
+}
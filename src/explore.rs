@@ -0,0 +1,239 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write;
+
+use crate::alphabet::Alphabet;
+use crate::tape::Tape;
+use crate::transition_fn::{Move, TransitionFn};
+use crate::turing_machine::TuringMachine;
+use crate::verify::Configuration;
+
+/// Bounds on the search performed by `explore`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExploreLimits {
+    /// The maximum number of distinct configurations to discover.
+    pub max_configurations: usize,
+    /// Configurations whose head is farther than this from its initial location are discovered but not expanded.
+    pub max_excursion: u64,
+}
+
+impl Default for ExploreLimits {
+    #[inline]
+    fn default() -> Self {
+        ExploreLimits {
+            max_configurations: 1000,
+            max_excursion: u64::MAX,
+        }
+    }
+}
+
+/// The graph of configurations reachable from an initial configuration, built by `explore`.
+/// Node 0 is the initial configuration, and each node's `step_num` is its distance from it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConfigGraph {
+    /// The distinct configurations discovered, in breadth-first order.
+    pub nodes: Vec<Configuration>,
+    /// The transitions between nodes as `(from, to, (new state, symbol written, head movement))`.
+    pub edges: Vec<(usize, usize, (u64, u64, bool))>,
+    /// Whether a limit stopped the search before every reachable configuration was discovered.
+    pub truncated: bool,
+    expanded: Vec<bool>,
+    transition_fn: TransitionFn,
+}
+
+impl ConfigGraph {
+    /// Returns the indices of the nodes reached from `node` in one step.
+    #[inline]
+    pub fn successors(&self, node: usize) -> Vec<usize> {
+        self.edges.iter().filter(|e| e.0 == node).map(|e| e.1).collect()
+    }
+
+    /// Returns the indices of the expanded nodes without successors, i.e. the halting configurations.
+    #[inline]
+    pub fn halting_nodes(&self) -> Vec<usize> {
+        (0..self.nodes.len()).filter(|&i| self.expanded[i] && self.successors(i).is_empty()).collect()
+    }
+
+    /// Returns whether some edge leads back to an already discovered configuration,
+    /// in which case a deterministic machine never halts.
+    #[inline]
+    pub fn has_cycle(&self) -> bool {
+        self.edges.iter().any(|e| self.nodes[e.1].step_num <= self.nodes[e.0].step_num)
+    }
+
+    /// Returns the index of the first node, in breadth-first order, whose configuration satisfies `predicate`.
+    #[inline]
+    pub fn find(&self, predicate: impl Fn(&Configuration) -> bool) -> Option<usize> {
+        self.nodes.iter().position(predicate)
+    }
+
+    /// Renders `self` in the DOT language of Graphviz, labeling each node with its state and its tape,
+    /// with the cell under the head in brackets and symbols named by `alphabet`.
+    /// Edges taken by annotated rules are also labeled with the annotation.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// use turing_machine::explore::{explore, ExploreLimits};
+    /// 
+    /// let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+    /// let graph = explore(&machine, &Tape::default(), ExploreLimits::default());
+    /// 
+    /// assert_eq!(graph.to_dot(&Alphabet::new(&["_", "a"])), "digraph {\n    0 [label=\"0: [_]\"];\n    1 [label=\"1: a [_]\"];\n    0 -> 1 [label=\"_/a,R\"];\n}\n");
+    /// ```
+    #[inline]
+    pub fn to_dot(&self, alphabet: &Alphabet) -> String {
+        let mut dot = String::from("digraph {\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            writeln!(dot, "    {} [label=\"{}: {}\"];", i, node.state, render_tape(node, alphabet)).unwrap();
+        }
+        for &(from, to, (_, write, right)) in &self.edges {
+            let read = self.nodes[from].tape.symbol_at_n(self.nodes[from].head_loc);
+            let note = self.transition_fn
+                .annotation(self.nodes[from].state, read)
+                .map(|x| format!("\\n{}", x.replace('"', "\\\"")))
+                .unwrap_or_default();
+            writeln!(dot, "    {} -> {} [label=\"{}/{},{}{}\"];", from, to, alphabet.name(read), alphabet.name(write), Move::from(right), note).unwrap();
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+}
+
+/// Explores the configurations reachable by `machine` from its current configuration on `input`
+/// breadth-first, within `limits`. A deterministic machine has at most one successor per configuration,
+/// so its graph is its trace, ending either in a halting configuration or in an edge back to an earlier one.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::explore::{explore, ExploreLimits};
+/// 
+/// // moves back and forth between two cells forever
+/// let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 0, true)), ((1, 0), (0, 0, false))]));
+/// let graph = explore(&machine, &Tape::default(), ExploreLimits::default());
+/// 
+/// assert_eq!(graph.nodes.len(), 2);
+/// assert!(graph.has_cycle());
+/// assert!(!graph.truncated);
+/// ```
+#[inline]
+pub fn explore(machine: &TuringMachine, input: &Tape, limits: ExploreLimits) -> ConfigGraph {
+    let mut graph = ConfigGraph {
+        transition_fn: machine.transition_fn().clone(),
+        ..Default::default()
+    };
+    let mut indices = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    let initial = Configuration {
+        step_num: 0,
+        state: machine.state(),
+        head_loc: machine.head_loc(),
+        tape: input.clone(),
+    };
+    indices.insert(key(&initial), 0);
+    graph.nodes.push(initial);
+    graph.expanded.push(false);
+    queue.push_back(0);
+
+    while let Some(i) = queue.pop_front() {
+        if graph.nodes[i].head_loc.abs_diff(machine.head_loc()) > limits.max_excursion {
+            graph.truncated = true;
+            continue;
+        }
+        graph.expanded[i] = true;
+
+        let mut next = graph.nodes[i].clone();
+        let symbol = next.tape.symbol_at_n(next.head_loc);
+        let output = match machine.transition_fn().run(next.state, symbol) {
+            Some(output) => output,
+            None => continue,
+        };
+        next.tape.write(next.head_loc, output.1);
+        next.state = output.0;
+        next.head_loc += Move::from(output.2).offset();
+        next.step_num += 1;
+
+        let j = match indices.get(&key(&next)) {
+            Some(&j) => j,
+            None => {
+                if graph.nodes.len() == limits.max_configurations {
+                    graph.truncated = true;
+                    break;
+                }
+                indices.insert(key(&next), graph.nodes.len());
+                graph.nodes.push(next);
+                graph.expanded.push(false);
+                queue.push_back(graph.nodes.len() - 1);
+
+                graph.nodes.len() - 1
+            }
+        };
+        graph.edges.push((i, j, output));
+    }
+
+    graph
+}
+
+/// Returns a key identifying a configuration regardless of its step number or of how its tape is stored.
+fn key(configuration: &Configuration) -> (u64, i64, Option<i64>, Vec<u64>) {
+    let tape = &configuration.tape;
+
+    (configuration.state, configuration.head_loc, tape.nonblank_range().map(|r| *r.start()), tape.symbols())
+}
+
+/// Renders the nonblank region of a configuration's tape together with the head, e.g. `a [_] b`.
+pub(crate) fn render_tape(configuration: &Configuration, alphabet: &Alphabet) -> String {
+    let head_loc = configuration.head_loc;
+    let (start, end) = match configuration.tape.nonblank_range() {
+        Some(r) => (*r.start().min(&head_loc), *r.end().max(&head_loc)),
+        None => (head_loc, head_loc),
+    };
+
+    (start..=end)
+        .map(|n| {
+            let name = alphabet.name(configuration.tape.symbol_at_n(n));
+            if n == head_loc { format!("[{}]", name) } else { name }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::explore::*;
+
+    #[test]
+    fn test_explore() {
+        let (mut machine, recording) = crate::examples::busy_beaver_2();
+        machine.reset();
+
+        let graph = explore(&machine, &Tape::default(), ExploreLimits::default());
+        assert_eq!(graph.nodes.len(), recording.len() + 1);
+        assert_eq!(graph.halting_nodes(), vec![6]);
+        assert!(!graph.has_cycle() && !graph.truncated);
+        assert_eq!(graph.find(|c| c.head_loc == -2), Some(4));
+
+        let graph = explore(&machine, &Tape::default(), ExploreLimits { max_configurations: 3, ..Default::default() });
+        assert_eq!((graph.nodes.len(), graph.edges.len()), (3, 2));
+        assert!(graph.truncated);
+
+        let graph = explore(&machine, &Tape::default(), ExploreLimits { max_excursion: 1, ..Default::default() });
+        assert!(graph.truncated);
+        assert!(graph.halting_nodes().is_empty());
+    }
+
+    #[test]
+    fn test_to_dot_with_annotations() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 0, false))])
+            .with_annotation(1, 0, "turn \"back\"");
+        let graph = explore(&TuringMachine::new(trans_fn), &Tape::default(), ExploreLimits::default());
+
+        let dot = graph.to_dot(&Alphabet::default());
+        assert!(dot.contains("0 -> 1 [label=\"0/1,R\"];"));
+        assert!(dot.contains("1 -> 2 [label=\"0/0,L\\nturn \\\"back\\\"\"];"));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::transition_fn::{Rule, TransitionFn};
+
+/// A machine of `n` states that writes `n` ones moving right from location 0 and halts in state `n` after `n` steps.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::families;
+/// use turing_machine::prelude::*;
+/// 
+/// let mut machine = TuringMachine::new(families::write_ones(5));
+/// let mut tape = Tape::default();
+/// machine.run(&mut tape);
+/// 
+/// assert_eq!(tape.symbols(), [1; 5]);
+/// assert_eq!((machine.state(), machine.head_loc()), (5, 5));
+/// ```
+#[inline]
+pub fn write_ones(n: usize) -> TransitionFn {
+    TransitionFn::from_rules((0..n as u64).map(|state| ((state, 0), (state + 1, 1, true)))).unwrap()
+}
+
+/// A machine of `2 * bits + 2` states that counts in binary modulo `2^bits` forever, keeping the count
+/// least significant bit first in locations 0 to `bits - 1` of a blank tape.
+/// Each increment starts in state 0 with the head at location 0 and returns there,
+/// taking `2 * c + 2` steps for a carry of length `c`, or `2 * bits + 2` steps when the count wraps around to 0.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::families;
+/// use turing_machine::prelude::*;
+/// 
+/// let mut machine = TuringMachine::new(families::binary_counter(3));
+/// let mut tape = Tape::default();
+/// 
+/// // incrementing 0 takes 2 steps, 1 takes 4, and 2 takes 2
+/// machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(8));
+/// assert_eq!(tape.symbols(), [1, 1]);
+/// assert_eq!((machine.state(), machine.head_loc()), (0, 0));
+/// ```
+/// 
+/// # Panics
+/// Panics if `bits` is 0.
+#[inline]
+pub fn binary_counter(bits: usize) -> TransitionFn {
+    if bits == 0 {
+        panic!("A counter must have at least one bit");
+    }
+
+    // state i carries into location i, state bits + 1 + j returns left from location j,
+    // and the last state steps back onto location 0 from location -1
+    let bits = bits as u64;
+    let carry = |i: u64| i;
+    let back = |j: u64| bits + 1 + j;
+    let start = 2 * bits + 1;
+    let back_from = |i: u64| if i == 0 { start } else { back(i - 1) };
+
+    let mut rules = Vec::<Rule>::new();
+    for i in 0..bits {
+        rules.push(((carry(i), 0), (back_from(i), 1, false)));
+        rules.push(((carry(i), 1), (carry(i + 1), 0, true)));
+        for symbol in 0..2 {
+            rules.push(((back(i), symbol), (back_from(i), symbol, false)));
+        }
+    }
+    // the count wrapped around, leaving every bit 0
+    rules.push(((carry(bits), 0), (back(bits - 1), 0, false)));
+    rules.push(((start, 0), (carry(0), 0, true)));
+
+    TransitionFn::new(&rules)
+}
+
+/// A machine of `2 * k` states over the symbols 0 and 1 that walks back and forth between locations 0 and `k` forever,
+/// writing 1 to every cell it leaves, so that it is in state `t % (2 * k)` after `t` steps.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::families;
+/// use turing_machine::prelude::*;
+/// 
+/// let mut machine = TuringMachine::new(families::zigzag(3));
+/// let mut tape = Tape::default();
+/// machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(10));
+/// 
+/// assert_eq!(tape.symbols(), [1; 4]);
+/// assert_eq!((machine.state(), machine.head_loc()), (4, 2));
+/// ```
+/// 
+/// # Panics
+/// Panics if `k` is 0.
+#[inline]
+pub fn zigzag(k: usize) -> TransitionFn {
+    if k == 0 {
+        panic!("A zigzag must span at least one cell");
+    }
+
+    let period = 2 * k as u64;
+    TransitionFn::from_rules((0..period).flat_map(|t| [0, 1].map(|symbol| ((t, symbol), ((t + 1) % period, 1, t < k as u64))))).unwrap()
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::families::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_binary_counter() {
+        for bits in 1..6 {
+            let mut machine = TuringMachine::new(binary_counter(bits));
+            let mut tape = Tape::default();
+
+            // count past the wraparound, checking the count every time the head returns
+            for count in 1..(1 << bits) + 3 {
+                machine.step(&mut tape).unwrap();
+                while (machine.state(), machine.head_loc()) != (0, 0) {
+                    machine.step(&mut tape).unwrap();
+                }
+
+                let value = (0..bits as i64).map(|n| tape.symbol_at_n(n) << n).sum::<u64>();
+                assert_eq!(value, count % (1 << bits));
+                assert!(tape.nonblank_range().is_none_or(|r| *r.start() >= 0 && *r.end() < bits as i64));
+            }
+        }
+    }
+
+    #[test]
+    fn test_zigzag() {
+        let mut machine = TuringMachine::new(zigzag(5));
+        let mut tape = Tape::default();
+        let mut head_locs = Vec::new();
+        for _ in 0..20 {
+            machine.step(&mut tape).unwrap();
+            head_locs.push(machine.head_loc());
+        }
+
+        assert_eq!(head_locs, [1, 2, 3, 4, 5, 4, 3, 2, 1, 0, 1, 2, 3, 4, 5, 4, 3, 2, 1, 0]);
+        assert_eq!(machine.state(), 0);
+        assert_eq!(tape.symbols(), [1; 6]);
+    }
+}
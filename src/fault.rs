@@ -0,0 +1,71 @@
+use crate::rng::{Seed, SplitMix64};
+
+/// A description of the faults to inject into a run with `TuringMachine::run_with_faults_and_record`.
+/// Faults are drawn from a pseudorandom generator seeded with `seed`, so a run with the same
+/// machine, tape, and `FaultModel` always suffers the same faults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FaultModel {
+    /// The number of symbols in the alphabet; corrupted symbols are drawn from `0..num_symbols`.
+    pub num_symbols: u64,
+    /// The probability per step that the cell just written is flipped to a different symbol.
+    pub flip_probability: f64,
+    /// The probability per step that the head reads a different symbol than the one on the tape.
+    pub misread_probability: f64,
+    /// Locations whose symbols can not be changed by the machine.
+    pub stuck_cells: Vec<i64>,
+    pub seed: Seed,
+}
+
+impl FaultModel {
+    /// Constructs a `FaultModel` over `num_symbols` symbols seeded with `seed`, which injects no faults
+    /// until its probabilities or stuck cells are set.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let fault_model = FaultModel { flip_probability: 0.1, ..FaultModel::new(2, Seed(7)) };
+    /// 
+    /// assert_eq!((fault_model.num_symbols, fault_model.seed), (2, Seed(7)));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `num_symbols` is 0, since even a blank tape holds a symbol.
+    #[inline]
+    pub fn new(num_symbols: u64, seed: Seed) -> Self {
+        assert!(num_symbols > 0, "A fault model needs at least one symbol");
+
+        FaultModel { num_symbols, seed, ..Default::default() }
+    }
+
+    /// Draws a symbol from `0..num_symbols` other than `symbol` with `rng`, treating a symbol beyond the alphabet
+    /// as its last one, or returns `None` if there are fewer than 2 symbols to choose from.
+    pub(crate) fn other_symbol(&self, rng: &mut SplitMix64, symbol: u64) -> Option<u64> {
+        let last = self.num_symbols.checked_sub(1).filter(|&last| last > 0)?;
+        let symbol = symbol.min(last);
+        let r = rng.below(last);
+
+        Some(if r >= symbol { r + 1 } else { r })
+    }
+}
+
+/// A single fault injected into a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fault {
+    /// The head read `read` at `location` although the tape held `actual`.
+    Misread { step_num: usize, location: i64, actual: u64, read: u64 },
+    /// The machine tried to write `intended` to the stuck cell at `location`, which kept its symbol.
+    StuckWrite { step_num: usize, location: i64, intended: u64 },
+    /// The symbol at `location` was flipped from `old` to `new` after being written.
+    Flip { step_num: usize, location: i64, old: u64, new: u64 },
+}
+
+impl Fault {
+    /// Returns the number of steps taken, including the faulty one, when the fault occurred.
+    #[inline]
+    pub fn step_num(&self) -> usize {
+        match *self {
+            Fault::Misread { step_num, .. } | Fault::StuckWrite { step_num, .. } | Fault::Flip { step_num, .. } => step_num,
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::transition_fn::TransitionFn;
+
+/// A transition function laid out as a flat, padded array of `u32`s, for shipping to GPU kernels
+/// or SIMD batch simulators. `run` is a reference interpreter that such simulators should agree with.
+/// 
+/// The layout is as follows, where `stride` is `num_symbols` rounded up to a power of two:
+/// - The rule for `(state, symbol)` is at index `state * stride + symbol`, and there are `num_states * stride` entries.
+/// - An entry of 0 means there is no rule, so the machine halts.
+/// - Otherwise, bit 0 is set, bit 1 is set if the head moves right, bits 2 to 15 hold the symbol to write,
+///   and bits 16 to 31 hold the new state.
+/// 
+/// New states at or above `num_states` have no rules, so entering one halts the machine.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let trans_fn = TransitionFn::new(&[((0, 0), (1, 2, true)), ((1, 0), (0, 1, false))]);
+/// let flat = FlatMachine::new(&trans_fn).unwrap();
+/// 
+/// assert_eq!((flat.num_states(), flat.num_symbols(), flat.stride()), (2, 3, 4));
+/// assert_eq!(flat.entries()[0], 1 << 16 | 2 << 2 | 1 << 1 | 1);
+/// assert_eq!(flat.to_transition_fn(), trans_fn);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FlatMachine {
+    num_states: u32,
+    num_symbols: u32,
+    entries: Vec<u32>,
+}
+
+/// How a run of `FlatMachine::run` ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FlatStop {
+    /// No rule matched.
+    Halted,
+    /// The step budget ran out.
+    Budget,
+    /// The head was about to move off either end of the cells.
+    OutOfBounds,
+}
+
+/// The result of `FlatMachine::run`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FlatRun {
+    pub state: u32,
+    pub head: usize,
+    pub steps: usize,
+    pub stop: FlatStop,
+}
+
+impl FlatMachine {
+    /// The largest state and symbol the layout can hold.
+    pub const MAX_STATE: u64 = (1 << 16) - 1;
+    pub const MAX_SYMBOL: u64 = (1 << 14) - 1;
+
+    /// Lays out `trans_fn`, or returns `None` if a state above `MAX_STATE` or a symbol above `MAX_SYMBOL` appears in it.
+    #[inline]
+    pub fn new(trans_fn: &TransitionFn) -> Option<Self> {
+        let state_table = trans_fn.state_table();
+
+        let mut num_states = 0;
+        let mut num_symbols = 0;
+        for &((state, symbol), (new_state, write, _)) in &state_table {
+            if state.max(new_state) > Self::MAX_STATE || symbol.max(write) > Self::MAX_SYMBOL {
+                return None;
+            }
+            num_states = num_states.max(state as u32 + 1);
+            num_symbols = num_symbols.max(symbol.max(write) as u32 + 1);
+        }
+
+        let mut flat = FlatMachine {
+            num_states,
+            num_symbols,
+            entries: Vec::new(),
+        };
+        let stride = flat.stride();
+        flat.entries = vec![0; num_states as usize * stride];
+        for ((state, symbol), (new_state, write, right)) in state_table {
+            flat.entries[state as usize * stride + symbol as usize] = (new_state as u32) << 16 | (write as u32) << 2 | (right as u32) << 1 | 1;
+        }
+
+        Some(flat)
+    }
+
+    /// Returns the number of states with rows in the table, i.e. one more than the largest state with a rule.
+    #[inline]
+    pub fn num_states(&self) -> u32 {
+        self.num_states
+    }
+
+    /// Returns one more than the largest symbol read or written.
+    #[inline]
+    pub fn num_symbols(&self) -> u32 {
+        self.num_symbols
+    }
+
+    /// Returns the distance between consecutive states' rows: `num_symbols` rounded up to a power of two.
+    #[inline]
+    pub fn stride(&self) -> usize {
+        (self.num_symbols as usize).next_power_of_two()
+    }
+
+    /// Returns the entries in the layout described on `FlatMachine`.
+    #[inline]
+    pub fn entries(&self) -> &[u32] {
+        &self.entries
+    }
+
+    /// Converts `self` back into a `TransitionFn`.
+    #[inline]
+    pub fn to_transition_fn(&self) -> TransitionFn {
+        let state_table = self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, &entry)| entry != 0)
+            .map(|(i, &entry)| {
+                let key = ((i / self.stride()) as u64, (i % self.stride()) as u64);
+                (key, ((entry >> 16) as u64, (entry >> 2 & 0x3FFF) as u64, entry & 2 != 0))
+            })
+            .collect::<Vec<_>>();
+
+        TransitionFn::new(&state_table)
+    }
+
+    /// Runs `self` from state 0 with the head on `cells[head]` for at most `max_steps` steps,
+    /// stopping when no rule matches, including on a symbol at or above `num_symbols`,
+    /// or when the head would leave `cells`, in which case the last step is not taken.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let (machine, _) = turing_machine::examples::busy_beaver_2();
+    /// let flat = FlatMachine::new(machine.transition_fn()).unwrap();
+    /// 
+    /// let mut cells = [0; 8];
+    /// let run = flat.run(&mut cells, 4, 100);
+    /// 
+    /// assert_eq!((run.steps, run.stop), (6, FlatStop::Halted));
+    /// assert_eq!(cells, [0, 0, 1, 1, 1, 1, 0, 0]);
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `head` is out of bounds.
+    #[inline]
+    pub fn run(&self, cells: &mut [u32], mut head: usize, max_steps: usize) -> FlatRun {
+        assert!(head < cells.len(), "head {} out of bounds", head);
+
+        let stride = self.stride();
+        let mut state = 0;
+        let mut steps = 0;
+        let stop = loop {
+            if steps == max_steps {
+                break FlatStop::Budget;
+            }
+
+            let symbol = cells[head];
+            let entry = if state < self.num_states && symbol < self.num_symbols {
+                self.entries[state as usize * stride + symbol as usize]
+            }
+            else {
+                0
+            };
+            if entry == 0 {
+                break FlatStop::Halted;
+            }
+
+            let next_head = if entry & 2 != 0 { head.checked_add(1).filter(|&h| h < cells.len()) } else { head.checked_sub(1) };
+            let Some(next_head) = next_head else { break FlatStop::OutOfBounds };
+
+            cells[head] = entry >> 2 & 0x3FFF;
+            state = entry >> 16;
+            head = next_head;
+            steps += 1;
+        };
+
+        FlatRun { state, head, steps, stop }
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_run_agrees_with_cursor() {
+        for trans_fn in TransitionFn::enumerate(2, 2).iter().step_by(7).chain([&TransitionFn::random(5, 6, Seed(3))]) {
+            let flat = FlatMachine::new(trans_fn).unwrap();
+            assert_eq!(flat.to_transition_fn(), *trans_fn);
+
+            let mut cells = [0; 64];
+            let run = flat.run(&mut cells, 32, 50);
+
+            let mut cursor = Cursor::new(trans_fn);
+            let mut tape = Tape::default();
+            let steps = cursor.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(run.steps));
+            assert_eq!((steps, cursor.state(), cursor.head_loc()), (run.steps, run.state as u64, run.head as i64 - 32));
+            assert!((0..64).all(|i| tape.symbol_at_n(i - 32) == cells[i as usize] as u64));
+            if run.stop == FlatStop::Halted {
+                assert!(trans_fn.run(cursor.state(), 0).is_none() || trans_fn.run(cursor.state(), tape.symbol_at_n(cursor.head_loc())).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn test_bounds() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (0, 1, false))]);
+        let run = FlatMachine::new(&trans_fn).unwrap().run(&mut [0; 3], 2, 10);
+        assert_eq!((run.head, run.steps, run.stop), (0, 2, FlatStop::OutOfBounds));
+
+        assert!(FlatMachine::new(&TransitionFn::new(&[((0, 0), (1 << 16, 1, true))])).is_none());
+        assert!(FlatMachine::new(&TransitionFn::new(&[((0, 1 << 14), (0, 1, true))])).is_none());
+        assert_eq!(FlatMachine::new(&TransitionFn::default()).unwrap().run(&mut [5], 0, 10).stop, FlatStop::Halted);
+    }
+}
@@ -0,0 +1,201 @@
+use std::iter;
+
+use crate::tape::Tape;
+use crate::turing_machine::{Decision, HaltSetting, TuringMachine};
+
+/// Runs `machine` with `TuringMachine::decide` on every string over `alphabet` of length at most `max_len`,
+/// each written from location 0 and run from the initial configuration for at most `budget` steps,
+/// and returns the strings in order of length, then lexicographically by position in `alphabet`, with their decisions.
+/// Comparing the accepted strings against an expected language definition is a quick empirical check of a recognizer.
+/// 
+/// Strings that end in blanks are indistinguishable on the tape from the strings without them,
+/// so `alphabet` should usually leave out the blank symbol 0.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::language;
+/// use turing_machine::prelude::*;
+/// 
+/// // accepts strings of 1s and 2s with an even number of 1s
+/// let mut builder = SmartBuilder::new(3, "even");
+/// builder
+///     .accept_state("yes")
+///     .reject_state("no")
+///     .rule("even", 1, "odd", 1, true)
+///     .rule("even", 2, "even", 2, true)
+///     .rule("even", 0, "yes", 0, true)
+///     .rule("odd", 1, "even", 1, true)
+///     .rule("odd", 2, "odd", 2, true)
+///     .rule("odd", 0, "no", 0, true);
+/// let machine = builder.build_machine().0;
+/// 
+/// let samples = language::sample(&machine, &[1, 2], 4, 100);
+/// assert_eq!(samples.len(), 31);
+/// assert_eq!(samples[..4], [(vec![], Decision::Accept), (vec![1], Decision::Reject), (vec![2], Decision::Accept), (vec![1, 1], Decision::Accept)]);
+/// assert!(samples.iter().all(|(s, d)| (*d == Decision::Accept) == s.iter().filter(|&&x| x == 1).count().is_multiple_of(2)));
+/// ```
+#[inline]
+pub fn sample(machine: &TuringMachine, alphabet: &[u64], max_len: usize, budget: usize) -> Vec<(Vec<u64>, Decision)> {
+    let mut machine = machine.clone().with_halt_setting(HaltSetting::AfterSteps(budget));
+
+    strings(alphabet, max_len)
+        .map(|input| {
+            let decision = decide(&mut machine, &input);
+            (input, decision)
+        })
+        .collect()
+}
+
+/// Returns the strings `sample` finds that `machine` accepts, in the same order.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::language;
+/// use turing_machine::prelude::*;
+/// 
+/// // accepts strings starting with a 1
+/// let mut builder = SmartBuilder::new(3, "start");
+/// builder.accept_state("yes").rule("start", 1, "yes", 1, true);
+/// let machine = builder.build_machine().0;
+/// 
+/// assert_eq!(language::accepted(&machine, &[1, 2], 2, 10), [vec![1], vec![1, 1], vec![1, 2]]);
+/// ```
+#[inline]
+pub fn accepted(machine: &TuringMachine, alphabet: &[u64], max_len: usize, budget: usize) -> Vec<Vec<u64>> {
+    sample(machine, alphabet, max_len, budget)
+        .into_iter()
+        .filter(|(_, decision)| *decision == Decision::Accept)
+        .map(|(input, _)| input)
+        .collect()
+}
+
+/// Checks whether `m1` and `m2` accept the same strings over `alphabet` of length at most `max_len`,
+/// running each on every such string like `sample` does, and returns the shortest string accepted by exactly one of them
+/// if there is any. A run that does not halt within `budget` steps does not accept,
+/// so a budget too small for either machine can make equivalent machines look different.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::language;
+/// use turing_machine::prelude::*;
+/// 
+/// // both accept strings of 1s and 2s ending in a 2, one by scanning right to the end, the other by stepping back from it
+/// let mut builder = SmartBuilder::new(3, "scan");
+/// builder
+///     .accept_state("yes")
+///     .rule("scan", 1, "scan1", 1, true)
+///     .rule("scan", 2, "scan2", 2, true)
+///     .rule("scan1", 1, "scan1", 1, true)
+///     .rule("scan1", 2, "scan2", 2, true)
+///     .rule("scan2", 1, "scan1", 1, true)
+///     .rule("scan2", 2, "scan2", 2, true)
+///     .rule("scan2", 0, "yes", 0, true);
+/// let scanner = builder.build_machine().0;
+/// 
+/// let mut builder = SmartBuilder::new(3, "right");
+/// builder
+///     .accept_state("yes")
+///     .rule("right", 1, "right", 1, true)
+///     .rule("right", 2, "right", 2, true)
+///     .rule("right", 0, "back", 0, false)
+///     .rule("back", 2, "yes", 2, true);
+/// let stepper = builder.build_machine().0;
+/// assert_eq!(language::equivalent_up_to(&scanner, &stepper, &[1, 2], 6, 100), Ok(()));
+/// 
+/// // one that also accepts a lone 1 is told apart by it
+/// let mut builder = SmartBuilder::new(3, "right");
+/// builder
+///     .accept_state("yes")
+///     .rule("right", 1, "right", 1, true)
+///     .rule("right", 2, "right", 2, true)
+///     .rule("right", 0, "back", 0, false)
+///     .rule("back", 2, "yes", 2, true)
+///     .rule("back", 1, "lone", 1, false)
+///     .rule("lone", 0, "yes", 0, true);
+/// let lenient = builder.build_machine().0;
+/// assert_eq!(language::equivalent_up_to(&scanner, &lenient, &[1, 2], 6, 100), Err(vec![1]));
+/// ```
+/// 
+/// # Errors
+/// Returns the first string in the order of `sample` that exactly one of `m1` and `m2` accepts.
+#[inline]
+pub fn equivalent_up_to(m1: &TuringMachine, m2: &TuringMachine, alphabet: &[u64], max_len: usize, budget: usize) -> Result<(), Vec<u64>> {
+    let mut m1 = m1.clone().with_halt_setting(HaltSetting::AfterSteps(budget));
+    let mut m2 = m2.clone().with_halt_setting(HaltSetting::AfterSteps(budget));
+
+    match strings(alphabet, max_len).find(|input| (decide(&mut m1, input) == Decision::Accept) != (decide(&mut m2, input) == Decision::Accept)) {
+        Some(input) => Err(input),
+        None => Ok(()),
+    }
+}
+
+/// Returns every string over `alphabet` of length at most `max_len`, in order of length,
+/// then lexicographically by position in `alphabet`.
+fn strings(alphabet: &[u64], max_len: usize) -> impl Iterator<Item = Vec<u64>> + '_ {
+    (0..=max_len)
+        .take_while(move |&len| len == 0 || !alphabet.is_empty())
+        .flat_map(move |len| {
+            // the digits of each string in base `alphabet.len()`, most significant first
+            iter::successors(Some(vec![0; len]), move |digits: &Vec<usize>| {
+                let i = digits.iter().rposition(|&i| i + 1 < alphabet.len())?;
+                let mut next = digits.clone();
+                next[i] += 1;
+                next[i + 1..].fill(0);
+                Some(next)
+            })
+        })
+        .map(|digits| digits.iter().map(|&i| alphabet[i]).collect())
+}
+
+/// Runs `machine` from its initial configuration on `input` and returns its decision.
+fn decide(machine: &mut TuringMachine, input: &[u64]) -> Decision {
+    machine.reset();
+    machine.decide(&mut Tape::new(input.to_vec()))
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::language::*;
+    use crate::prelude::*;
+
+    #[test]
+    fn test_sample() {
+        // runs off to the right forever once it reads a 2, so strings containing one are undecided
+        let mut builder = SmartBuilder::new(3, "start");
+        builder
+            .accept_state("yes")
+            .rule("start", 1, "start", 1, true)
+            .rule("start", 2, "run", 2, true)
+            .rule("start", 0, "yes", 0, true);
+        for symbol in 0..3 {
+            builder.rule("run", symbol, "run", symbol, true);
+        }
+        let machine = builder.build_machine().0;
+
+        let samples = sample(&machine, &[1, 2], 3, 50);
+        assert_eq!(samples.len(), 1 + 2 + 4 + 8);
+        assert!(samples.windows(2).all(|w| w[0].0.len() < w[1].0.len() || w[0].0 < w[1].0));
+        assert_eq!(accepted(&machine, &[1, 2], 3, 50), [vec![], vec![1], vec![1, 1], vec![1, 1, 1]]);
+        assert!(samples.iter().all(|(s, d)| s.contains(&2) == (*d == Decision::Undecided)));
+
+        assert_eq!(sample(&machine, &[], 3, 50), [(vec![], Decision::Accept)]);
+        assert_eq!(sample(&machine, &[1], 0, 50), [(vec![], Decision::Accept)]);
+    }
+
+    #[test]
+    fn test_equivalent_up_to() {
+        // accepts strings of 1s, scanning to their end
+        let mut builder = SmartBuilder::new(2, "start");
+        builder.accept_state("yes").rule("start", 1, "start", 1, true).rule("start", 0, "yes", 0, true);
+        let machine = builder.build_machine().0;
+        assert_eq!(equivalent_up_to(&machine, &machine, &[1], 10, 100), Ok(()));
+
+        // a string too long to scan within the budget is not accepted, unlike by a machine that accepts at once
+        let mut builder = SmartBuilder::new(2, "start");
+        builder.accept_state("yes").rule("start", 1, "yes", 1, true).rule("start", 0, "yes", 0, true);
+        let eager = builder.build_machine().0;
+        assert_eq!(equivalent_up_to(&machine, &eager, &[1], 10, 100), Ok(()));
+        assert_eq!(equivalent_up_to(&machine, &eager, &[1], 10, 5), Err(vec![1; 5]));
+    }
+}
@@ -1,12 +1,54 @@
+pub mod alphabet;
+pub mod analysis;
+pub mod batch;
+pub mod bbchallenge;
+pub mod compact_tape;
+#[cfg(feature = "dashboard")]
+pub mod dashboard;
+pub mod docgen;
+pub mod enumeration;
+pub mod examples;
+pub mod experiment;
+pub mod explore;
+pub mod families;
+pub mod fault;
+pub mod flat;
+pub mod language;
+pub mod machine;
+pub mod multi_head;
+pub mod profile;
 pub mod recording;
+pub mod rng;
+pub mod sandbox;
+pub mod search;
+pub mod simulator;
+pub mod smart_builder;
+pub mod string_tape;
 pub mod tape;
-pub mod tests;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+pub mod testing;
+pub mod transforms;
 pub mod transition_fn;
 pub mod turing_machine;
+pub mod validate;
+pub mod verify;
 
 pub mod prelude {
+    pub use crate::alphabet::*;
+    pub use crate::compact_tape::*;
+    pub use crate::experiment::*;
+    pub use crate::fault::*;
+    pub use crate::flat::*;
+    pub use crate::machine::*;
+    pub use crate::multi_head::*;
     pub use crate::recording::*;
+    pub use crate::rng::*;
+    pub use crate::smart_builder::*;
+    pub use crate::string_tape::*;
     pub use crate::tape::*;
+    pub use crate::testing::*;
     pub use crate::transition_fn::*;
     pub use crate::turing_machine::*;
+    pub use crate::validate::*;
 }
\ No newline at end of file
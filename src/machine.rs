@@ -0,0 +1,436 @@
+use std::io::{self, Write};
+
+use crate::alphabet::Alphabet;
+use crate::smart_builder::{BuildWarning, SmartBuilder};
+use crate::transition_fn::TransitionFn;
+use crate::tape::Tape;
+use crate::turing_machine::{HaltSetting, HaltingStates, TuringMachine};
+
+/// A transition function bundled with everything that describes it: state labels, symbol names,
+/// declared halting states, and who wrote it and why. With the `serde` feature, a `Machine`
+/// is serialized as a single JSON or TOML document.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let mut builder = SmartBuilder::new(2, "start");
+/// builder.accept_state("yes").rule("start", 1, "yes", 1, true);
+/// let mut machine = builder.build_bundle().0;
+/// machine.alphabet = Alphabet::new(&["_", "x"]);
+/// machine.description = "accepts tapes starting with x".to_string();
+/// 
+/// assert_eq!(machine.explain(), "\
+/// State start:
+///   In state start reading x: write x, move right, go to state yes and halt
+/// ");
+/// assert_eq!(machine.turing_machine().decide(&mut Tape::new(vec![1])), Decision::Accept);
+/// assert_eq!(machine.fingerprint(), machine.transition_fn.fingerprint());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "MachineDocument", into = "MachineDocument"))]
+pub struct Machine {
+    pub transition_fn: TransitionFn,
+    /// The names of the states, indexed by state, such as `SmartBuilder::labels`.
+    pub state_labels: Vec<String>,
+    pub alphabet: Alphabet,
+    pub halting_states: HaltingStates,
+    pub author: String,
+    pub description: String,
+}
+
+impl Machine {
+    /// Constructs a new `Machine` from the specified transition function, with no labels, names, or metadata.
+    #[inline]
+    pub fn new(transition_fn: TransitionFn) -> Self {
+        Machine {
+            transition_fn,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the fingerprint of the transition function of `self`; see `TransitionFn::fingerprint`.
+    #[inline]
+    pub fn fingerprint(&self) -> u64 {
+        self.transition_fn.fingerprint()
+    }
+
+    /// Returns the number of symbols `self` uses: one more than the largest symbol read or written by a rule,
+    /// or the number of named symbols if that is larger.
+    #[inline]
+    pub fn num_symbols(&self) -> u64 {
+        self.transition_fn
+            .state_table()
+            .iter()
+            .map(|((_, read), (_, write, _))| read.max(write) + 1)
+            .max()
+            .unwrap_or(0)
+            .max(self.alphabet.len() as u64)
+    }
+
+    /// Returns a `TuringMachine` in its initial configuration that runs the transition function of `self`
+    /// and classifies runs with its declared halting states.
+    #[inline]
+    pub fn turing_machine(&self) -> TuringMachine {
+        TuringMachine::new(self.transition_fn.clone()).with_halting_states(self.halting_states.clone())
+    }
+
+    /// Describes every rule of `self` in English with its state labels and symbol names; see `TransitionFn::explain_with`.
+    #[inline]
+    pub fn explain(&self) -> String {
+        self.transition_fn.explain_with(&self.alphabet, &self.state_labels)
+    }
+
+    /// Renders the transition function of `self` with its symbol names; see `TransitionFn::render`.
+    #[inline]
+    pub fn render(&self) -> String {
+        self.transition_fn.render(&self.alphabet)
+    }
+
+    /// Writes the transition function of `self` as CSV with its state labels and symbol names; see `TransitionFn::to_csv`.
+    /// 
+    /// # Errors
+    /// Passes on any error from `writer`.
+    #[inline]
+    pub fn to_csv(&self, writer: &mut impl Write) -> io::Result<()> {
+        self.transition_fn.to_csv(writer, &self.alphabet, &self.state_labels)
+    }
+
+    /// Parses a machine from JSON.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let machine = Machine::from_json(r#"{
+    ///     "transition_fn": "[0,0 -> 1,1,R]",
+    ///     "alphabet": ["_", "x"],
+    ///     "author": "me"
+    /// }"#).unwrap();
+    /// 
+    /// assert_eq!(machine.render(), "[0,_ -> 1,x,R]");
+    /// assert_eq!(Machine::from_json(&machine.to_json()), Ok(machine));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if `s` is not valid JSON or does not describe a machine,
+    /// or if its fingerprint does not match its transition function.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// Formats `self` as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a machine is always representable as JSON")
+    }
+
+    /// Parses a machine from TOML.
+    /// 
+    /// # Errors
+    /// Returns an error if `s` is not valid TOML or does not describe a machine,
+    /// or if its fingerprint does not match its transition function.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_toml(s: &str) -> Result<Self, String> {
+        toml::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// Formats `self` as TOML.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("a machine is always representable as TOML")
+    }
+}
+
+impl From<TransitionFn> for Machine {
+    #[inline]
+    fn from(transition_fn: TransitionFn) -> Self {
+        Machine::new(transition_fn)
+    }
+}
+
+impl From<&Machine> for TuringMachine {
+    #[inline]
+    fn from(machine: &Machine) -> Self {
+        machine.turing_machine()
+    }
+}
+
+impl SmartBuilder {
+    /// Builds a `Machine` with the state labels and declared halting states of `self`,
+    /// along with the warnings returned by `build`.
+    /// 
+    /// # Panics
+    /// Panics if two rules share a key; see `TransitionFn::new`.
+    #[inline]
+    pub fn build_bundle(&self) -> (Machine, Vec<BuildWarning>) {
+        let (transition_fn, warnings) = self.build();
+        let machine = Machine {
+            transition_fn,
+            state_labels: self.labels(),
+            halting_states: self.halting_states().clone(),
+            ..Default::default()
+        };
+
+        (machine, warnings)
+    }
+}
+
+/// A `Machine` along with everything needed to present it: the halt setting it is run with by default,
+/// sample inputs, and notes. With the `serde` feature, a whole teaching example lives in one JSON or TOML file,
+/// unlike the table formats of `TransitionFn`, which only keep the rules.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let mut project = Project::new(Machine::new("[0,0 -> 1,1,R; 1,0 -> 0,1,R]".parse().unwrap()));
+/// project.halt_setting = HaltSetting::AfterSteps(3);
+/// project.sample_inputs = vec![vec![], vec![0, 0, 0, 0, 1]];
+/// 
+/// let mut tapes = project.sample_tapes();
+/// let mut machine = project.turing_machine();
+/// machine.run_with_halt_setting(&mut tapes[1], project.halt_setting);
+/// assert_eq!(tapes[1].symbols(), [1, 1, 1, 0, 1]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Project {
+    pub machine: Machine,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub halt_setting: HaltSetting,
+    /// The tapes the machine is meant to be run on, each starting at location 0.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub sample_inputs: Vec<Vec<u64>>,
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "String::is_empty"))]
+    pub notes: String,
+}
+
+impl Project {
+    /// Constructs a new `Project` for `machine`, with no forced halt, sample inputs, or notes.
+    #[inline]
+    pub fn new(machine: Machine) -> Self {
+        Project {
+            machine,
+            ..Default::default()
+        }
+    }
+
+    /// Returns a `TuringMachine` in its initial configuration that runs the machine of `self`
+    /// with its declared halting states and the halt setting of `self`.
+    #[inline]
+    pub fn turing_machine(&self) -> TuringMachine {
+        self.machine.turing_machine().with_halt_setting(self.halt_setting)
+    }
+
+    /// Returns a tape for each of the sample inputs of `self`, in order.
+    #[inline]
+    pub fn sample_tapes(&self) -> Vec<Tape> {
+        self.sample_inputs.iter().map(|input| Tape::new(input.clone())).collect()
+    }
+
+    /// Parses a project from JSON.
+    /// 
+    /// # Errors
+    /// Returns an error if `s` is not valid JSON or does not describe a project, or if the machine is invalid;
+    /// see `Machine::from_json`.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_json(s: &str) -> Result<Self, String> {
+        serde_json::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// Formats `self` as pretty-printed JSON.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a project is always representable as JSON")
+    }
+
+    /// Parses a project from TOML.
+    /// 
+    /// # Errors
+    /// Returns an error if `s` is not valid TOML or does not describe a project, or if the machine is invalid;
+    /// see `Machine::from_toml`.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn from_toml(s: &str) -> Result<Self, String> {
+        toml::from_str(s).map_err(|e| e.to_string())
+    }
+
+    /// Formats `self` as TOML.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("a project is always representable as TOML")
+    }
+
+    /// Reads a project from the file at `path`, as TOML if its extension is `toml` and as JSON otherwise.
+    /// 
+    /// # Errors
+    /// Passes on any error from reading the file, and returns an error of kind `InvalidData` if it does not parse.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn load(path: impl AsRef<std::path::Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+        let s = std::fs::read_to_string(path)?;
+        let project = if is_toml(path) { Project::from_toml(&s) } else { Project::from_json(&s) };
+        project.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Writes `self` to the file at `path`, as TOML if its extension is `toml` and as JSON otherwise.
+    /// 
+    /// # Errors
+    /// Passes on any error from writing the file.
+    #[cfg(feature = "serde")]
+    #[inline]
+    pub fn save(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let s = if is_toml(path) { self.to_toml() } else { self.to_json() };
+        std::fs::write(path, s)
+    }
+}
+
+impl From<&Project> for TuringMachine {
+    #[inline]
+    fn from(project: &Project) -> Self {
+        project.turing_machine()
+    }
+}
+
+/// Returns whether `path` has the extension `toml`.
+#[cfg(feature = "serde")]
+fn is_toml(path: &std::path::Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "toml")
+}
+
+/// The serialized form of a `Machine`, with the transition function in the format of its `Display` implementation,
+/// the alphabet as its list of names, and the fingerprint in hexadecimal, which is checked when present.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MachineDocument {
+    transition_fn: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    state_labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    alphabet: Vec<String>,
+    #[serde(default)]
+    halting_states: HaltingStates,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    author: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    description: String,
+    #[serde(default)]
+    fingerprint: Option<String>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Machine> for MachineDocument {
+    #[inline]
+    fn from(machine: Machine) -> Self {
+        MachineDocument {
+            fingerprint: Some(format!("{:016x}", machine.fingerprint())),
+            transition_fn: machine.transition_fn.to_string(),
+            state_labels: machine.state_labels,
+            alphabet: machine.alphabet.names().to_vec(),
+            halting_states: machine.halting_states,
+            author: machine.author,
+            description: machine.description,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<MachineDocument> for Machine {
+    type Error = crate::transition_fn::ParseMachineError;
+
+    #[inline]
+    fn try_from(document: MachineDocument) -> Result<Self, Self::Error> {
+        use crate::transition_fn::ParseMachineError;
+
+        let transition_fn = document.transition_fn.parse::<TransitionFn>()?;
+        if let Some(fingerprint) = document.fingerprint {
+            if fingerprint != format!("{:016x}", transition_fn.fingerprint()) {
+                return Err(ParseMachineError::new(format!("fingerprint {} does not match the transition function", fingerprint)));
+            }
+        }
+
+        let names = document.alphabet.iter().map(String::as_str).collect::<Vec<&str>>();
+        if names.iter().any(|name| name.is_empty()) || (1..names.len()).any(|i| names[..i].contains(&names[i])) {
+            return Err(ParseMachineError::new("alphabet names must be nonempty and distinct"));
+        }
+
+        Ok(Machine {
+            transition_fn,
+            state_labels: document.state_labels,
+            alphabet: Alphabet::new(&names),
+            halting_states: document.halting_states,
+            author: document.author,
+            description: document.description,
+        })
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_num_symbols() {
+        let mut machine = Machine::new(TransitionFn::new(&[((0, 0), (1, 2, true)), ((1, 1), (0, 0, false))]));
+        assert_eq!(machine.num_symbols(), 3);
+
+        machine.alphabet = Alphabet::new(&["_", "a", "b", "c"]);
+        assert_eq!(machine.num_symbols(), 4);
+        assert_eq!(Machine::default().num_symbols(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_documents() {
+        let mut machine = Machine::new(crate::examples::busy_beaver_2().0.transition_fn().clone());
+        machine.state_labels = vec!["A".to_string(), "B".to_string()];
+        machine.halting_states.halt.insert(2);
+        machine.author = "Radó".to_string();
+
+        assert_eq!(Machine::from_json(&machine.to_json()), Ok(machine.clone()));
+        assert_eq!(Machine::from_toml(&machine.to_toml()), Ok(machine.clone()));
+
+        let tampered = machine.to_json().replace("1,1,R", "1,1,L");
+        assert!(Machine::from_json(&tampered).unwrap_err().contains("fingerprint"));
+        assert!(Machine::from_json(r#"{ "transition_fn": "[]", "alphabet": ["a", "a"] }"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_project_files() {
+        let mut project = Project::new(Machine::new(crate::examples::busy_beaver_2().0.transition_fn().clone()));
+        project.machine.alphabet = Alphabet::new(&["_", "1"]);
+        project.halt_setting = HaltSetting::AfterVirtualDuration { duration: std::time::Duration::from_secs(2), steps_per_second: 10 };
+        project.sample_inputs = vec![vec![], vec![1, 0, 1]];
+        project.notes = "The champion of 2 states.\nTry it on the second input.".to_string();
+
+        let dir = std::env::temp_dir().join(format!("turing_machine_project_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["project.json", "project.toml"] {
+            project.save(dir.join(name)).unwrap();
+            assert_eq!(Project::load(dir.join(name)).unwrap(), project);
+        }
+        assert!(std::fs::read_to_string(dir.join("project.toml")).unwrap().contains("notes = "));
+
+        std::fs::write(dir.join("broken.json"), "{}").unwrap();
+        assert_eq!(Project::load(dir.join("broken.json")).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // everything but the machine may be left out
+        let minimal = Project::from_toml("[machine]\ntransition_fn = \"[0,0 -> 1,1,R]\"").unwrap();
+        assert_eq!(minimal, Project::new(Machine::new("[0,0 -> 1,1,R]".parse().unwrap())));
+    }
+}
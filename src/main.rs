@@ -1,5 +1,31 @@
-use turing_machine::{prelude::*, tests::*};
+use std::env;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 
+use turing_machine::prelude::*;
+
+/// Without arguments, prints an approximation of Chaitin's constant.
+/// With a machine in the format of `TuringMachine`'s `Display` implementation, e.g. `0 0 [0,0 -> 0,1,R]`,
+/// runs it on a blank tape until it halts or Ctrl-C is pressed, and prints the outcome and the final machine.
 fn main() {
-    println!("{:?}", TuringMachine::chaitin_approx(3, 2, HaltSetting::AfterSteps(100)));
-}
\ No newline at end of file
+    let Some(machine) = env::args().nth(1) else {
+        println!("{}", TuringMachine::chaitin_approx(3, 2, HaltSetting::AfterSteps(100)));
+        return;
+    };
+    let mut machine = machine.parse::<TuringMachine>().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(2);
+    });
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = Arc::clone(&stop);
+    if let Err(e) = ctrlc::set_handler(move || handler_stop.store(true, Ordering::Relaxed)) {
+        eprintln!("failed to install the Ctrl-C handler: {}", e);
+    }
+
+    let halt_setting = machine.halt_setting();
+    let outcome = machine.run_with_stop_flag(&mut Tape::default(), halt_setting, &stop);
+    println!("{}", outcome);
+    println!("{}", machine);
+}
@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::tape::Tape;
+use crate::transition_fn::Move;
+use crate::turing_machine::{HaltCheck, HaltSetting};
+
+/// A single entry of a multi-head state table in the form
+/// `((state, symbols read by each head), (new state, (symbol to write, head movement) for each head))`.
+pub type MultiHeadRule = ((u64, Vec<u64>), MultiHeadOutput);
+
+/// The output of a multi-head transition function in the form
+/// `(new state, (symbol to write, head movement) for each head)`.
+pub type MultiHeadOutput = (u64, Vec<(u64, bool)>);
+
+/// A transition function for a Turing machine with several heads on a single tape.
+/// It takes a state and the symbol under each head and returns a new state
+/// and, for each head, a symbol to write and whether to move left or right.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MultiHeadTransitionFn {
+    num_heads: usize,
+    map: HashMap<(u64, Vec<u64>), MultiHeadOutput>,
+}
+
+impl MultiHeadTransitionFn {
+    /// Constructs a new `MultiHeadTransitionFn` for `num_heads` heads from the specified state table.
+    /// 
+    /// # Panics
+    /// Panics if the state table contains identical keys, or if any rule does not read or write
+    /// exactly one symbol per head.
+    #[inline]
+    pub fn new(num_heads: usize, state_table: &[MultiHeadRule]) -> Self {
+        let mut keys = HashSet::new();
+        for ((state, read), (_, write)) in state_table {
+            if read.len() != num_heads || write.len() != num_heads {
+                panic!("Every rule must read and write exactly one symbol per head");
+            }
+            if !keys.insert((*state, read)) {
+                panic!("State table contains identical keys");
+            }
+        }
+
+        MultiHeadTransitionFn {
+            num_heads,
+            map: state_table.iter().cloned().collect(),
+        }
+    }
+
+    /// Returns the number of heads `self` is defined for.
+    #[inline]
+    pub fn num_heads(&self) -> usize {
+        self.num_heads
+    }
+
+    /// Returns the state table of `self` in no particular order.
+    #[inline]
+    pub fn state_table(&self) -> Vec<MultiHeadRule> {
+        self.map.iter().map(|x| (x.0.clone(), x.1.clone())).collect()
+    }
+
+    /// Runs `self` with the specified state and symbols and returns the matching output, if any.
+    #[inline]
+    pub fn run(&self, state: u64, symbols: &[u64]) -> Option<&MultiHeadOutput> {
+        self.map.get(&(state, symbols.to_vec()))
+    }
+}
+
+/// A Turing machine with several heads moving over a single tape.
+/// Each step, every head reads its symbol, then the heads write in order,
+/// so if two heads share a location, the write of the later head wins.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// // accepts (halts in state 1) if the words on either side of a blank at location 3 are equal
+/// let mut state_table = vec![((0, vec![0, 0]), (1, vec![(0, true), (0, true)]))];
+/// for s in 1..3 {
+///     state_table.push(((0, vec![s, s]), (0, vec![(s, true), (s, true)])));
+/// }
+/// 
+/// let trans_fn = MultiHeadTransitionFn::new(2, &state_table);
+/// let mut machine = MultiHeadTuringMachine::new(trans_fn, vec![0, 4]);
+/// let mut tape = Tape::new(vec![1, 2, 1, 0, 1, 2, 1]);
+/// 
+/// machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(100));
+/// 
+/// assert_eq!(machine.state(), 1);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MultiHeadTuringMachine {
+    transition_fn: MultiHeadTransitionFn,
+    state: u64,
+    head_locs: Vec<i64>,
+}
+
+impl MultiHeadTuringMachine {
+    /// Constructs a new multi-head Turing machine with its heads at the specified locations.
+    /// Initial state is always 0.
+    /// 
+    /// # Panics
+    /// Panics if the number of head locations does not match the number of heads of `transition_fn`.
+    #[inline]
+    pub fn new(transition_fn: MultiHeadTransitionFn, head_locs: Vec<i64>) -> Self {
+        if head_locs.len() != transition_fn.num_heads {
+            panic!("There must be exactly one initial location per head");
+        }
+
+        MultiHeadTuringMachine {
+            transition_fn,
+            state: 0,
+            head_locs,
+        }
+    }
+
+    /// Returns the transition function of `self`.
+    #[inline]
+    pub fn transition_fn(&self) -> &MultiHeadTransitionFn {
+        &self.transition_fn
+    }
+
+    /// Returns the current state of `self`.
+    #[inline]
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Returns the current location of each head of `self`.
+    #[inline]
+    pub fn head_locs(&self) -> &[i64] {
+        &self.head_locs
+    }
+
+    /// Performs a single step of `self` on the specified tape.
+    /// Returns `false` if no rule matched, in which case `self` has halted and nothing is changed.
+    #[inline]
+    pub fn step(&mut self, tape: &mut Tape) -> bool {
+        let symbols = self.head_locs.iter().map(|&n| tape.symbol_at_n(n)).collect::<Vec<u64>>();
+        let Some((new_state, outputs)) = self.transition_fn.run(self.state, &symbols) else { return false };
+
+        self.state = *new_state;
+        for (head_loc, &(symbol, right)) in self.head_locs.iter_mut().zip(outputs) {
+            tape.write(*head_loc, symbol);
+            *head_loc += Move::from(right).offset();
+        }
+
+        true
+    }
+
+    /// Runs `self`, changing its state and moving its heads while writing to the specified tape.
+    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
+    #[inline]
+    pub fn run_with_halt_setting(&mut self, tape: &mut Tape, halt_setting: HaltSetting) {
+        let mut halt_check = HaltCheck::new(halt_setting);
+        while !halt_check.should_halt() && self.step(tape) {}
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    #[should_panic]
+    fn panic_test_new() {
+        MultiHeadTransitionFn::new(2, &[((0, vec![0]), (1, vec![(0, true), (0, true)]))]);
+    }
+
+    #[test]
+    fn test_step() {
+        let trans_fn = MultiHeadTransitionFn::new(
+            2,
+            &[
+                ((0, vec![0, 0]), (1, vec![(1, false), (2, true)])),
+                ((1, vec![0, 0]), (2, vec![(3, true), (4, false)])),
+            ]
+        );
+        let mut machine = MultiHeadTuringMachine::new(trans_fn, vec![0, 2]);
+        let mut tape = Tape::default();
+
+        assert!(machine.step(&mut tape));
+        assert_eq!(machine.head_locs(), [-1, 3]);
+        assert!(machine.step(&mut tape));
+        assert_eq!(machine.head_locs(), [0, 2]);
+        assert!(!machine.step(&mut tape));
+        assert_eq!(machine.state(), 2);
+        assert_eq!(tape.symbols(), [3, 1, 0, 2, 4]);
+    }
+
+    #[test]
+    fn test_run_with_halt_setting() {
+        // both heads at the same location, the second head's write wins
+        let trans_fn = MultiHeadTransitionFn::new(3, &[((0, vec![0, 0, 0]), (0, vec![(1, true), (2, true), (0, false)]))]);
+        let mut machine = MultiHeadTuringMachine::new(trans_fn, vec![0, 0, -10]);
+        let mut tape = Tape::default();
+
+        machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(3));
+        assert_eq!(machine.head_locs(), [3, 3, -13]);
+        assert_eq!(tape.symbols(), [2, 2, 2]);
+    }
+}
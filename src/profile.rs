@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use crate::tape::Tape;
+use crate::turing_machine::TuringMachine;
+
+/// How often each rule of a machine fired during a run, and how long it stayed in each state; see `profile`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransitionProfile {
+    /// The number of steps taken.
+    pub steps: usize,
+    /// The number of times each rule fired, keyed by `(state, symbol)`. Rules that never fired are absent.
+    pub rule_counts: HashMap<(u64, u64), usize>,
+    /// Statistics about the contiguous stretches of steps taken in each state.
+    pub state_runs: HashMap<u64, StateRuns>,
+    /// The keys of the rules that never fired, sorted.
+    pub unused_rules: Vec<(u64, u64)>,
+}
+
+/// Statistics about the maximal stretches of consecutive steps a machine took in a single state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StateRuns {
+    /// The number of stretches.
+    pub runs: usize,
+    /// The total number of steps taken in the state.
+    pub total_steps: usize,
+    /// The length of the longest stretch.
+    pub longest: usize,
+}
+
+impl TransitionProfile {
+    /// Returns the keys of the rules that fired along with their counts, most frequent first,
+    /// with ties broken by key.
+    #[inline]
+    pub fn hot_rules(&self) -> Vec<((u64, u64), usize)> {
+        let mut rules = self.rule_counts.iter().map(|(&k, &v)| (k, v)).collect::<Vec<_>>();
+        rules.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        rules
+    }
+}
+
+/// Runs `machine` from its current configuration on a copy of `tape` for up to `budget` steps,
+/// counting how many times each rule fired and how long the contiguous runs in each state were.
+/// Neither `machine` nor `tape` is changed.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::profile::profile;
+/// 
+/// // scans right over a block of 1s, then halts
+/// let machine = TuringMachine::new(TransitionFn::new(&[
+///     ((0, 1), (0, 1, true)),
+///     ((0, 0), (1, 0, false)),
+///     ((0, 2), (1, 2, false)),
+/// ]));
+/// 
+/// let profile = profile(&machine, &Tape::new(vec![1; 5]), 100);
+/// 
+/// assert_eq!(profile.steps, 6);
+/// assert_eq!(profile.hot_rules(), vec![((0, 1), 5), ((0, 0), 1)]);
+/// assert_eq!(profile.state_runs[&0].longest, 6);
+/// assert_eq!(profile.unused_rules, vec![(0, 2)]);
+/// ```
+#[inline]
+pub fn profile(machine: &TuringMachine, tape: &Tape, budget: usize) -> TransitionProfile {
+    let mut cursor = machine.cursor();
+    let mut tape = tape.clone();
+    let mut profile = TransitionProfile::default();
+
+    let mut run_len = 0;
+    while profile.steps < budget {
+        let state = cursor.state();
+        let key = (state, tape.symbol_at_n(cursor.head_loc()));
+        let output = match cursor.step(&mut tape) {
+            Some(output) => output,
+            None => break,
+        };
+
+        profile.steps += 1;
+        *profile.rule_counts.entry(key).or_default() += 1;
+
+        let runs = profile.state_runs.entry(state).or_default();
+        runs.total_steps += 1;
+        run_len += 1;
+        if run_len == 1 {
+            runs.runs += 1;
+        }
+        runs.longest = runs.longest.max(run_len);
+        if output.0 != state {
+            run_len = 0;
+        }
+    }
+
+    profile.unused_rules = machine.transition_fn()
+        .state_table()
+        .into_iter()
+        .map(|x| x.0)
+        .filter(|k| !profile.rule_counts.contains_key(k))
+        .collect();
+    profile.unused_rules.sort_unstable();
+
+    profile
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::profile::{profile, StateRuns};
+
+    #[test]
+    fn test_profile() {
+        let machine = TuringMachine::new(TransitionFn::new(&[
+            ((0, 0), (1, 1, true)),
+            ((0, 1), (1, 1, false)),
+            ((1, 0), (0, 1, false)),
+            ((1, 1), (2, 1, true)),
+            ((2, 0), (2, 0, true)),
+        ]));
+
+        let report = profile(&machine, &Tape::default(), 1000);
+        assert_eq!(report.steps, 6);
+        assert_eq!(report.rule_counts.values().sum::<usize>(), 6);
+        assert_eq!(report.state_runs[&0], StateRuns { runs: 3, total_steps: 3, longest: 1 });
+        assert_eq!(report.unused_rules, vec![(2, 0)]);
+        assert_eq!((machine.state(), machine.head_loc()), (0, 0));
+
+        // the budget cuts the run short
+        let report = profile(&machine, &Tape::default(), 4);
+        assert_eq!(report.steps, 4);
+        assert_eq!(report.rule_counts.values().sum::<usize>(), 4);
+    }
+}
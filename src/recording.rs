@@ -1,71 +1,1924 @@
-use std::{thread::sleep, time::Duration};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::{thread::sleep, time::{Duration, Instant}};
 
-use crate::tape::Tape;
+use crate::alphabet::Alphabet;
+use crate::fault::Fault;
+use crate::rng::Seed;
+use crate::tape::{Tape, MAX_LOCATION};
+use crate::transition_fn::Move;
 
 /// A log of the movements and operations of a specific `TuringMachine`.
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug)]
 pub struct Recording {
     pub(crate) input: Tape,
     pub(crate) init_state: u64,
     pub(crate) init_head_loc: i64,
-    pub(crate) steps: Vec<(u64, u64, bool)>,
+    /// The steps held in memory, which follow the `spilled_len` steps in `spill`.
+    pub(crate) steps: Vec<(u64, u64, Move)>,
+    pub(crate) spill: Option<Arc<SpillFile>>,
+    pub(crate) spilled_len: usize,
+    /// The number of bytes of steps and snapshots `push_step` holds in memory before moving the steps to `spill`,
+    /// or `None` to never spill.
+    pub(crate) max_memory_bytes: Option<usize>,
+    /// The number of bytes taken by `snapshots`, as counted by `push_snapshot`.
+    pub(crate) snapshot_bytes: usize,
+    pub(crate) perturbations: Vec<Perturbation>,
+    pub(crate) faults: Vec<Fault>,
+    pub(crate) machine_fingerprint: u64,
+    pub(crate) snapshot_every: usize,
+    /// The interval between the snapshots of a sampled recording, which holds no steps, or 0 if every step is held.
+    pub(crate) sample_rate: usize,
+    pub(crate) snapshots: Vec<Snapshot>,
+    pub(crate) input_detached: bool,
+    pub(crate) input_fingerprint: u64,
+    pub(crate) seed: Option<Seed>,
+    pub(crate) summary: RecordingSummary,
+    /// The symbol each step held in `steps` overwrote, which makes the steps invertible;
+    /// those of spilled steps are stored along with them in `spill`. Empty if `overwritten_unknown`.
+    pub(crate) overwritten: Vec<u64>,
+    /// Whether the symbols the steps overwrote are unknown, such as while the input is detached.
+    pub(crate) overwritten_unknown: bool,
+}
+
+/// Returns the output of a transition as a step to record.
+#[inline]
+pub(crate) fn recorded_step((new_state, write, right): (u64, u64, bool)) -> (u64, u64, Move) {
+    (new_state, write, Move::from(right))
+}
+
+/// The number of bytes a step held in memory takes along with the symbol it overwrote.
+const IN_MEMORY_STEP_LEN: usize = mem::size_of::<(u64, u64, Move)>() + mem::size_of::<u64>();
+
+/// The magic bytes that begin every recording written by `Recording::write_to`.
+pub const RECORDING_MAGIC: [u8; 4] = *b"TMRC";
+
+/// The version of the binary format written by `Recording::write_to`.
+/// Version 2 added the snapshot interval, version 3 detached inputs, version 4 the seed, version 5 the summary,
+/// version 6 sampled recordings, and version 7 overwritten symbols; older recordings are still read.
+pub const RECORDING_FORMAT_VERSION: u16 = 7;
+
+/// Options for `TuringMachine::run_and_record_with_config`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RecordingConfig {
+    /// Embed a full snapshot of the configuration every `snapshot_every` steps, or never if 0.
+    pub snapshot_every: usize,
+    /// Store only the `Tape::fingerprint` of the input rather than the input itself,
+    /// for inputs that are persisted elsewhere; see `Recording::attach_input`.
+    pub detach_input: bool,
+    /// Hold at most this many bytes of steps and embedded snapshots in memory, moving older steps to a temporary file
+    /// that is deleted along with the recording, or hold every step in memory if `None`.
+    /// Snapshots are never moved, so once they take the whole budget every further step is moved on its own.
+    /// Spilled steps are read back transparently, except by `Recording::steps`, which panics,
+    /// and reading them back panics if the file cannot be read, except with `Recording::try_raw_steps`.
+    /// If the file cannot be created or written, the remaining steps are held in memory.
+    pub max_memory_bytes: Option<usize>,
+    /// Record only a snapshot of the configuration every `sample_rate` steps and after the last step,
+    /// instead of every step, or every step if 0. A sampled recording holds no steps and ignores `snapshot_every`;
+    /// if the input is detached, its snapshots only show the cells written during the run and keep doing so
+    /// after `Recording::attach_input`, since there are no steps to replay them from.
+    pub sample_rate: usize,
+}
+
+/// An error returned by `Recording::attach_input` when the tape is not the recorded input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InputMismatchError {
+    /// The fingerprint of the recorded input.
+    pub expected: u64,
+    /// The fingerprint of the tape that was given.
+    pub found: u64,
+}
+
+impl fmt::Display for InputMismatchError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "input fingerprint {:#018x} does not match the recorded {:#018x}", self.found, self.expected)
+    }
+}
+
+impl std::error::Error for InputMismatchError {}
+
+/// A single recorded step with named fields, yielded by `Recording::steps_iter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Step {
+    /// The index of the step, starting at 0.
+    pub index: usize,
+    /// The state before the step.
+    pub state: u64,
+    /// The state after the step.
+    pub new_state: u64,
+    /// The symbol written at `head_loc_before`.
+    pub write: u64,
+    pub head_move: Move,
+    pub head_loc_before: i64,
+    pub head_loc_after: i64,
+}
+
+/// The full configuration of a recorded run after some number of steps.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    pub step_num: usize,
+    pub state: u64,
+    pub head_loc: i64,
+    pub tape: Tape,
+}
+
+/// Statistics about a recorded run, computed when it is recorded and stored at the start of the binary format,
+/// so that they can be shown without replaying the steps; see `Recording::read_summary`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordingSummary {
+    pub steps: usize,
+    /// The number of nonblank cells on the tape after the run, the "ones" of a busy beaver.
+    pub nonblank: usize,
+    /// The leftmost and rightmost locations of the head during the run.
+    pub head_range: RangeInclusive<i64>,
+    /// The number of steps taken from each state, for the states that took any.
+    pub state_counts: BTreeMap<u64, usize>,
+}
+
+impl RecordingSummary {
+    /// Counts a step taken from `state` that left the head at `head_loc`.
+    #[inline]
+    pub(crate) fn count_step(&mut self, state: u64, head_loc: i64) {
+        *self.state_counts.entry(state).or_default() += 1;
+        self.head_range = *self.head_range.start().min(&head_loc)..=*self.head_range.end().max(&head_loc);
+    }
+}
+
+impl Default for RecordingSummary {
+    #[inline]
+    fn default() -> Self {
+        RecordingSummary {
+            steps: 0,
+            nonblank: 0,
+            head_range: 0..=0,
+            state_counts: BTreeMap::new(),
+        }
+    }
+}
+
+/// The head location and the number of nonblank cells over the course of a run, as `(step, value)` points,
+/// returned by `Recording::series`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunSeries {
+    pub head_loc: Vec<(usize, i64)>,
+    pub nonblank: Vec<(usize, usize)>,
+}
+
+impl RunSeries {
+    /// Plots the head location above the number of nonblank cells against the step, as an SVG image at `path`.
+    /// 
+    /// # Errors
+    /// Returns an error if the image cannot be drawn or written.
+    #[cfg(feature = "plot")]
+    #[inline]
+    pub fn plot_to_svg(&self, path: impl AsRef<std::path::Path>) -> io::Result<()> {
+        use plotters::prelude::*;
+
+        let root = SVGBackend::new(path.as_ref(), (800, 600)).into_drawing_area();
+        root.fill(&WHITE).map_err(plot_error)?;
+        let (upper, lower) = root.split_vertically(300);
+
+        let nonblank = self.nonblank.iter().map(|&(step, n)| (step, n as i64)).collect::<Vec<(usize, i64)>>();
+        plot_series(&upper, "head location", &self.head_loc, &BLUE).map_err(plot_error)?;
+        plot_series(&lower, "nonblank cells", &nonblank, &RED).map_err(plot_error)?;
+
+        root.present().map_err(plot_error)
+    }
+}
+
+/// Plots `points` as a line on `area`, with axes fitted to them.
+#[cfg(feature = "plot")]
+fn plot_series<DB: plotters::prelude::DrawingBackend>(
+    area: &plotters::prelude::DrawingArea<DB, plotters::coord::Shift>,
+    caption: &str,
+    points: &[(usize, i64)],
+    color: &plotters::style::RGBColor,
+) -> Result<(), plotters::prelude::DrawingAreaErrorKind<DB::ErrorType>> {
+    use plotters::prelude::*;
+
+    let last_step = points.last().map_or(0, |x| x.0).max(1);
+    let min = points.iter().map(|x| x.1).min().unwrap_or(0);
+    let max = points.iter().map(|x| x.1).max().unwrap_or(0);
+
+    let mut chart = ChartBuilder::on(area)
+        .caption(caption, ("sans-serif", 20))
+        .margin(10)
+        .x_label_area_size(30)
+        .y_label_area_size(50)
+        .build_cartesian_2d(0..last_step, min..max + 1)?;
+    chart.configure_mesh().x_desc("step").draw()?;
+    chart.draw_series(LineSeries::new(points.iter().copied(), color))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "plot")]
+fn plot_error(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::other(e)
+}
+
+/// A change made to the tape by something other than the machine, such as a step hook.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Perturbation {
+    /// The number of steps taken when the change was made.
+    pub step_num: usize,
+    pub location: i64,
+    pub old: u64,
+    pub new: u64,
 }
 
 impl Recording {
-    /// Plays back a "movie" of the Turing machine in the terminal.
+    /// Plays back a "movie" of the Turing machine in the terminal, drawing every frame in place
+    /// by rewriting only the cells that changed, so that playback stays smooth at high speeds.
+    /// If `cls` is set, the screen is cleared once before the first frame.
+    /// This blocks the current thread until playback is finished; see `frames` for a non-blocking alternative.
+    #[inline]
+    pub fn play_in_console(&self, step_delay: Duration, cls: bool) {
+        self.play_in_console_with(step_delay, cls, &Alphabet::default());
+    }
+
+    /// Equivalent to `play_in_console`, but renders symbols with their names in `alphabet`.
+    #[inline]
+    pub fn play_in_console_with(&self, step_delay: Duration, cls: bool, alphabet: &Alphabet) {
+        self.play_in_console_with_view(step_delay, cls, alphabet, ViewPolicy::Centered);
+    }
+
+    /// Equivalent to `play_in_console_with`, but shows the part of the tape chosen by `view`.
+    #[inline]
+    pub fn play_in_console_with_view(&self, step_delay: Duration, cls: bool, alphabet: &Alphabet, view: ViewPolicy) {
+        let mut stdout = io::stdout().lock();
+        if cls {
+            let _ = write!(stdout, "\x1b[2J\x1b[H");
+        }
+
+        let mut renderer = ConsoleRenderer::default();
+        let start = Instant::now();
+        for (offset, frame) in self.frames(step_delay).with_view(view) {
+            if let Some(remaining) = offset.checked_sub(start.elapsed()) {
+                sleep(remaining);
+            }
+
+            let _ = stdout.write_all(renderer.update(&frame.render(alphabet)).as_bytes());
+            let _ = stdout.flush();
+        }
+    }
+
+    /// Returns every change made to the tape during the run by something other than the machine, in order.
+    #[inline]
+    pub fn perturbations(&self) -> &[Perturbation] {
+        &self.perturbations
+    }
+
+    /// Returns every fault injected during the run, in order.
+    #[inline]
+    pub fn faults(&self) -> &[Fault] {
+        &self.faults
+    }
+
+    /// Returns the `TransitionFn::fingerprint` of the machine that produced `self`.
+    #[inline]
+    pub fn machine_fingerprint(&self) -> u64 {
+        self.machine_fingerprint
+    }
+
+    /// Returns the seed of the randomness used during the run, if any, such as `FaultModel::seed`.
+    /// Rerunning with the same machine, input, and seed reproduces `self` exactly.
+    #[inline]
+    pub fn seed(&self) -> Option<Seed> {
+        self.seed
+    }
+
+    /// Returns whether `self` stores only the fingerprint of its input; see `RecordingConfig::detach_input`.
+    /// Until the input is attached, `frames`, `seek` and the snapshots show only the cells written
+    /// during the run, over a blank tape.
+    #[inline]
+    pub fn is_input_detached(&self) -> bool {
+        self.input_detached
+    }
+
+    /// Returns the `Tape::fingerprint` of the input of `self`.
+    #[inline]
+    pub fn input_fingerprint(&self) -> u64 {
+        self.input_fingerprint
+    }
+
+    /// Supplies the input of a recording made with `RecordingConfig::detach_input`, after which `self`
+    /// behaves as if the input had been stored in it. Does nothing if the input is already attached.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let input = Tape::new(vec![1, 1, 1]);
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 1), (0, 2, true))]));
+    /// let config = RecordingConfig { detach_input: true, ..Default::default() };
+    /// let mut recording = machine.run_and_record_with_config(&mut input.clone(), HaltSetting::NoForcedHalt, &config);
+    /// 
+    /// assert!(recording.attach_input(Tape::new(vec![1, 1])).is_err());
+    /// recording.attach_input(input).unwrap();
+    /// assert_eq!(recording.seek(1).unwrap().tape, Tape::new(vec![2, 1, 1]));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error if the fingerprint of `input` does not match the recorded one.
+    #[inline]
+    pub fn attach_input(&mut self, input: Tape) -> Result<(), InputMismatchError> {
+        if !self.input_detached {
+            return Ok(());
+        }
+        if input.fingerprint() != self.input_fingerprint {
+            return Err(InputMismatchError { expected: self.input_fingerprint, found: input.fingerprint() });
+        }
+
+        self.input = input;
+        self.input_detached = false;
+        self.replay_overwritten();
+        self.rebuild_snapshots();
+
+        Ok(())
+    }
+
+    /// Returns the raw steps of `self` as `(new state, symbol written, head movement)` tuples;
+    /// see `steps_iter` for a richer view.
+    /// 
+    /// # Panics
+    /// Panics if steps were spilled to disk, which only `raw_steps` and the other iterators read back;
+    /// see `RecordingConfig::max_memory_bytes`.
+    #[inline]
+    pub fn steps(&self) -> &[(u64, u64, Move)] {
+        assert!(self.spilled_len == 0, "The steps of a recording that spilled them to disk must be read with raw_steps");
+        &self.steps
+    }
+
+    /// Returns an iterator over the raw steps of `self` like `steps`, reading those spilled to disk back in chunks.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let config = RecordingConfig { max_memory_bytes: Some(1000), ..Default::default() };
+    /// let recording = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(100), &config);
+    /// 
+    /// assert_eq!(recording.raw_steps().collect::<Vec<_>>(), [(0, 1, Move::Right); 100]);
+    /// ```
+    /// 
+    /// # Panics
+    /// The iterator panics if spilled steps cannot be read back; see `try_raw_steps`.
+    #[inline]
+    pub fn raw_steps(&self) -> impl Iterator<Item = (u64, u64, Move)> + '_ {
+        self.raw_steps_from(0)
+    }
+
+    /// Equivalent to `raw_steps`, but yields the error instead of panicking if spilled steps cannot be read back,
+    /// and then ends.
+    #[inline]
+    pub fn try_raw_steps(&self) -> impl Iterator<Item = io::Result<(u64, u64, Move)>> + '_ {
+        let mut steps = self.raw_steps_from(0);
+        std::iter::from_fn(move || steps.try_next_record().map(|record| record.map(|(step, _)| step)))
+    }
+
+    /// Returns an iterator over the steps of `self` that also tracks the state and head location before and after each.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 1, false))]));
+    /// let recording = machine.run_and_record(&mut Tape::default());
+    /// let steps = recording.steps_iter().collect::<Vec<Step>>();
+    /// 
+    /// assert_eq!(steps[1], Step { index: 1, state: 1, new_state: 2, write: 1, head_move: Move::Left, head_loc_before: 1, head_loc_after: 0 });
+    /// ```
+    #[inline]
+    pub fn steps_iter(&self) -> impl Iterator<Item = Step> + '_ {
+        self.raw_steps_from(0).enumerate().scan((self.init_state, self.init_head_loc), |(state, head_loc), (index, (new_state, write, head_move))| {
+            let step = Step {
+                index,
+                state: *state,
+                new_state,
+                write,
+                head_move,
+                head_loc_before: *head_loc,
+                head_loc_after: *head_loc + head_move.offset(),
+            };
+            (*state, *head_loc) = (new_state, step.head_loc_after);
+
+            Some(step)
+        })
+    }
+
+    /// Returns the head location after each step of `self`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::examples;
+    /// 
+    /// let (_, recording) = examples::busy_beaver_2();
+    /// 
+    /// assert_eq!(recording.head_trajectory(), vec![1, 0, -1, -2, -1, 0]);
+    /// assert_eq!(recording.max_excursion(), 2);
+    /// ```
+    #[inline]
+    pub fn head_trajectory(&self) -> Vec<i64> {
+        self.steps_iter().map(|x| x.head_loc_after).collect()
+    }
+
+    /// Returns the farthest distance the head got from its initial location during `self`.
+    #[inline]
+    pub fn max_excursion(&self) -> u64 {
+        self.steps_iter()
+            .map(|x| x.head_loc_after.abs_diff(self.init_head_loc))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the index of the first step at which `self` and `other` differ in new state, symbol written,
+    /// or head movement, or `None` if they are identical. If one recording is a prefix of the other,
+    /// they diverge at the length of the shorter one.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine1 = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 1, true))]));
+    /// let mut machine2 = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 1, false))]));
+    /// let recording1 = machine1.run_and_record(&mut Tape::default());
+    /// let recording2 = machine2.run_and_record(&mut Tape::default());
+    /// 
+    /// assert_eq!(recording1.first_divergence(&recording2), Some(1));
+    /// assert_eq!(recording1.first_divergence(&recording1), None);
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if the recordings do not start from the same input, state, and head location,
+    /// since their steps could not be meaningfully compared. Inputs are compared by `input_fingerprint`,
+    /// so a recording with a detached input can be compared with one that stores it.
+    #[inline]
+    pub fn first_divergence(&self, other: &Recording) -> Option<usize> {
+        if self.input_fingerprint != other.input_fingerprint || (self.init_state, self.init_head_loc) != (other.init_state, other.init_head_loc) {
+            panic!("Recordings must start from the same configuration to be compared");
+        }
+
+        match self.raw_steps_from(0).zip(other.raw_steps_from(0)).position(|(a, b)| a != b) {
+            Some(index) => Some(index),
+            None if self.raw_len() != other.raw_len() => Some(self.raw_len().min(other.raw_len())),
+            None => None,
+        }
+    }
+
+    /// Returns the snapshots embedded in `self`, in order of step; see `RecordingConfig`.
+    #[inline]
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Returns the number of steps between the snapshots of `self` if it is a sampled recording,
+    /// which holds snapshots instead of steps, or 0 if it holds every step; see `RecordingConfig::sample_rate`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let config = RecordingConfig { sample_rate: 40, ..Default::default() };
+    /// let recording = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(100), &config);
+    /// 
+    /// assert_eq!(recording.sample_rate(), 40);
+    /// assert_eq!(recording.len(), 100);
+    /// assert_eq!(recording.summary().steps, 100);
+    /// 
+    /// // a snapshot every 40 steps and one after the last
+    /// let steps = recording.snapshots().iter().map(|x| x.step_num).collect::<Vec<usize>>();
+    /// assert_eq!(steps, [40, 80, 100]);
+    /// assert_eq!(recording.seek(80).unwrap().tape, Tape::new(vec![1; 80]));
+    /// assert_eq!(recording.seek(50), None);
+    /// ```
+    #[inline]
+    pub fn sample_rate(&self) -> usize {
+        self.sample_rate
+    }
+
+    /// Returns a coarser, sampled copy of `self` with a snapshot every `factor` steps and one after the last step,
+    /// for rendering an overview of a long run while `self` is kept for the details.
+    /// A recording that is already sampled keeps every `factor`th of its snapshots and its last,
+    /// so its sample rate is multiplied by `factor`. The copy has the same summary and metadata as `self`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let recording = machine.run_with_halt_setting_and_record(&mut Tape::default(), HaltSetting::AfterSteps(100));
+    /// 
+    /// let overview = recording.downsample(30);
+    /// assert_eq!(overview.snapshots().iter().map(|x| x.step_num).collect::<Vec<_>>(), [30, 60, 90, 100]);
+    /// assert_eq!(overview.seek(60), recording.seek(60));
+    /// assert_eq!(overview.summary(), recording.summary());
+    /// 
+    /// let coarser = overview.downsample(2);
+    /// assert_eq!((coarser.sample_rate(), coarser.snapshots().len()), (60, 2));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `factor` is 0.
+    #[inline]
+    pub fn downsample(&self, factor: usize) -> Recording {
+        if factor == 0 {
+            panic!("A recording can only be downsampled by a positive factor");
+        }
+
+        let (sample_rate, snapshots) = if self.sample_rate != 0 {
+            let sample_rate = self.sample_rate * factor;
+            let last = self.snapshots.len().saturating_sub(1);
+            let snapshots = self.snapshots
+                .iter()
+                .enumerate()
+                .filter(|&(i, x)| x.step_num.is_multiple_of(sample_rate) || i == last)
+                .map(|(_, x)| x.clone())
+                .collect();
+            (sample_rate, snapshots)
+        }
+        else {
+            let mut snapshot = self.initial_snapshot();
+            let mut snapshots = Vec::with_capacity(self.raw_len() / factor + 1);
+            for step_num in (factor..=self.raw_len()).step_by(factor).chain((!self.raw_len().is_multiple_of(factor)).then_some(self.raw_len())) {
+                snapshot = self.replay(snapshot, step_num);
+                snapshots.push(snapshot.clone());
+            }
+            (factor, snapshots)
+        };
+
+        Recording {
+            input: self.input.clone(),
+            init_state: self.init_state,
+            init_head_loc: self.init_head_loc,
+            steps: Vec::new(),
+            spill: None,
+            spilled_len: 0,
+            max_memory_bytes: None,
+            snapshot_bytes: 0,
+            perturbations: self.perturbations.clone(),
+            faults: self.faults.clone(),
+            machine_fingerprint: self.machine_fingerprint,
+            snapshot_every: self.snapshot_every,
+            sample_rate,
+            snapshots,
+            input_detached: self.input_detached,
+            input_fingerprint: self.input_fingerprint,
+            seed: self.seed,
+            summary: self.summary.clone(),
+            overwritten: Vec::new(),
+            overwritten_unknown: false,
+        }
+    }
+
+    /// Returns the statistics of the run recorded by `self`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::examples;
+    /// 
+    /// let (_, recording) = examples::busy_beaver_2();
+    /// let summary = recording.summary();
+    /// 
+    /// assert_eq!((summary.steps, summary.nonblank, summary.head_range.clone()), (6, 4, -2..=1));
+    /// assert_eq!(summary.state_counts.values().collect::<Vec<_>>(), [&3, &3]);
+    /// ```
+    #[inline]
+    pub fn summary(&self) -> &RecordingSummary {
+        &self.summary
+    }
+
+    /// Computes the summary of `self` from its steps, with `tape` as the tape after the run.
+    pub(crate) fn summarize(&mut self, tape: &Tape) {
+        let mut summary = RecordingSummary {
+            steps: self.raw_len(),
+            nonblank: nonblank_count(tape),
+            head_range: self.init_head_loc..=self.init_head_loc,
+            state_counts: BTreeMap::new(),
+        };
+        for step in self.steps_iter() {
+            summary.count_step(step.state, step.head_loc_after);
+        }
+
+        self.summary = summary;
+    }
+
+    /// Returns the head location and the number of nonblank cells over the course of `self`,
+    /// including perturbations, from the initial configuration at step 0 to the final one.
+    /// A sampled recording only has points for its initial configuration and its snapshots.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::examples;
+    /// 
+    /// let (_, recording) = examples::busy_beaver_2();
+    /// let series = recording.series();
+    /// 
+    /// assert_eq!(series.head_loc, [(0, 0), (1, 1), (2, 0), (3, -1), (4, -2), (5, -1), (6, 0)]);
+    /// assert_eq!(series.nonblank, [(0, 0), (1, 1), (2, 2), (3, 2), (4, 3), (5, 4), (6, 4)]);
+    /// ```
+    #[inline]
+    pub fn series(&self) -> RunSeries {
+        let mut snapshot = self.initial_snapshot();
+        let mut nonblank = nonblank_count(&snapshot.tape);
+        let mut series = RunSeries {
+            head_loc: vec![(0, snapshot.head_loc)],
+            nonblank: vec![(0, nonblank)],
+        };
+        if self.sample_rate != 0 {
+            for x in &self.snapshots {
+                series.head_loc.push((x.step_num, x.head_loc));
+                series.nonblank.push((x.step_num, nonblank_count(&x.tape)));
+            }
+            return series;
+        }
+
+        // keeps the count up to date with each write instead of recounting the whole tape
+        let mut write = |tape: &mut Tape, location, symbol| {
+            nonblank = nonblank + (symbol != 0) as usize - (tape.symbol_at_n(location) != 0) as usize;
+            tape.write(location, symbol);
+            nonblank
+        };
+
+        let mut perturbation_num = self.perturbations.partition_point(|p| p.step_num == 0);
+        for (step_num, (state, symbol, head_move)) in (1..).zip(self.raw_steps_from(0)) {
+            snapshot.state = state;
+            let mut count = write(&mut snapshot.tape, snapshot.head_loc, symbol);
+            snapshot.head_loc += head_move.offset();
+
+            while let Some(p) = self.perturbations.get(perturbation_num).filter(|p| p.step_num == step_num) {
+                count = write(&mut snapshot.tape, p.location, p.new);
+                perturbation_num += 1;
+            }
+
+            series.head_loc.push((step_num, snapshot.head_loc));
+            series.nonblank.push((step_num, count));
+        }
+
+        series
+    }
+
+    /// Returns the number of steps in `self`. A sampled recording holds no steps,
+    /// so this is the number of steps it counted in its summary instead; see `sample_rate`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self.sample_rate {
+            0 => self.raw_len(),
+            _ => self.summary.steps,
+        }
+    }
+
+    /// Returns the number of steps held in memory or spilled to disk, which is 0 for a sampled recording.
+    fn raw_len(&self) -> usize {
+        self.spilled_len + self.steps.len()
+    }
+
+    /// Returns whether `self` has no steps, i.e. `len` is 0.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Appends a step and the symbol it overwrote, unless those are unknown, first moving the steps held in memory
+    /// to the spill file if holding one more would take more than `max_memory_bytes` along with the snapshots.
+    /// If the spill file cannot be created or written, spilling is given up and the steps stay in memory.
+    pub(crate) fn push_step(&mut self, step: (u64, u64, Move), overwritten: u64) {
+        let over_budget = |max| (self.steps.len() + 1) * IN_MEMORY_STEP_LEN + self.snapshot_bytes > max;
+        if !self.steps.is_empty() && self.max_memory_bytes.is_some_and(over_budget) && self.spill_steps().is_err() {
+            self.max_memory_bytes = None;
+        }
+
+        self.steps.push(step);
+        if !self.overwritten_unknown {
+            self.overwritten.push(overwritten);
+        }
+    }
+
+    /// Appends an embedded snapshot, counting its bytes against `max_memory_bytes`.
+    pub(crate) fn push_snapshot(&mut self, snapshot: Snapshot) {
+        self.snapshot_bytes += mem::size_of::<Snapshot>() + snapshot.tape.raw_symbols().len() * mem::size_of::<u64>();
+        self.snapshots.push(snapshot);
+    }
+
+    /// Moves the steps held in memory and the symbols they overwrote to the end of the spill file, creating it if needed.
+    fn spill_steps(&mut self) -> io::Result<()> {
+        let spill = match &self.spill {
+            Some(spill) => spill,
+            None => self.spill.insert(Arc::new(SpillFile::create()?)),
+        };
+        spill.append(&self.steps, &self.overwritten)?;
+        self.spilled_len += self.steps.len();
+        self.steps.clear();
+        self.overwritten.clear();
+
+        Ok(())
+    }
+
+    /// Returns an iterator over the raw steps of `self` starting at index `start`, reading spilled steps in chunks.
+    fn raw_steps_from(&self, start: usize) -> RawSteps<'_> {
+        RawSteps { recording: self, next: start, chunk: Vec::new(), chunk_start: 0 }
+    }
+
+    /// Returns an iterator over the raw steps of `self` starting at index `start`,
+    /// each with the symbol it overwrote, or `None` if those are unknown.
+    fn raw_records_from(&self, start: usize) -> impl Iterator<Item = StepRecord> + '_ {
+        let mut steps = self.raw_steps_from(start);
+        std::iter::from_fn(move || steps.next_record())
+    }
+
+    /// Returns the step at `index` and the symbol it overwrote, reading only that step back if it was spilled.
+    /// 
+    /// # Panics
+    /// Panics if `self` has no step at `index` or the symbols the steps overwrote are unknown.
+    fn step_at(&self, index: usize) -> ((u64, u64, Move), u64) {
+        assert!(!self.overwritten_unknown, "the overwritten symbols are known");
+        match index.checked_sub(self.spilled_len) {
+            Some(i) => (self.steps[i], self.overwritten[i]),
+            None => {
+                let mut record = Vec::with_capacity(1);
+                let spill = self.spill.as_ref().expect("spilled steps have a spill file");
+                spill.read(index, 1, &mut record).unwrap_or_else(|e| panic!("failed to read spilled steps: {}", e));
+                record[0]
+            }
+        }
+    }
+
+    /// Returns the configuration after `step_num` steps, including any perturbations made at that step,
+    /// or `None` if `self` has fewer steps. Replays from the latest embedded snapshot at or before `step_num`,
+    /// so seeking takes O(`snapshot_every`) steps when snapshots were recorded.
+    /// A sampled recording can only seek to its initial configuration and its snapshots.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let config = RecordingConfig { snapshot_every: 10, ..Default::default() };
+    /// let recording = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(100), &config);
+    /// 
+    /// assert_eq!(recording.snapshots().len(), 10);
+    /// 
+    /// let snapshot = recording.seek(42).unwrap();
+    /// assert_eq!(snapshot.head_loc, 42);
+    /// assert_eq!(snapshot.tape, Tape::new(vec![1; 42]));
+    /// assert_eq!(recording.seek(101), None);
+    /// ```
+    #[inline]
+    pub fn seek(&self, step_num: usize) -> Option<Snapshot> {
+        if self.sample_rate != 0 {
+            return match step_num {
+                0 => Some(self.initial_snapshot()),
+                _ => self.snapshots.iter().find(|x| x.step_num == step_num).cloned(),
+            };
+        }
+        if step_num > self.raw_len() {
+            return None;
+        }
+
+        let start = match self.snapshots.partition_point(|x| x.step_num <= step_num) {
+            0 => self.initial_snapshot(),
+            i => self.snapshots[i - 1].clone(),
+        };
+
+        Some(self.replay(start, step_num))
+    }
+
+    /// Moves `snapshot` of `self` back by one step, undoing the perturbations made at its step and then the step itself,
+    /// and returns whether it could: not at step 0, past the end of `self`, or in a sampled recording.
+    /// Recordings made by `TuringMachine` know the symbol each step overwrote, so this takes constant time
+    /// without any embedded snapshots, reading at most two steps back if they were spilled.
+    /// While the input is detached, or if the recording was read from an older format that did not store those symbols
+    /// and has a detached input, it falls back to `seek` instead, which shows blanks for the cells of the input.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::examples;
+    /// 
+    /// let (_, recording) = examples::busy_beaver_3();
+    /// let mut snapshot = recording.seek(recording.len()).unwrap();
+    /// 
+    /// while recording.step_back(&mut snapshot) {
+    ///     assert_eq!(Some(&snapshot), recording.seek(snapshot.step_num).as_ref());
+    /// }
+    /// assert_eq!(snapshot.step_num, 0);
+    /// ```
+    #[inline]
+    pub fn step_back(&self, snapshot: &mut Snapshot) -> bool {
+        if snapshot.step_num == 0 || snapshot.step_num > self.raw_len() || self.sample_rate != 0 {
+            return false;
+        }
+        let index = snapshot.step_num - 1;
+        if self.overwritten_unknown {
+            *snapshot = self.seek(index).expect("a recording can seek to any of its steps");
+            return true;
+        }
+
+        let start = self.perturbations.partition_point(|p| p.step_num < snapshot.step_num);
+        let end = self.perturbations.partition_point(|p| p.step_num <= snapshot.step_num);
+        for p in self.perturbations[start..end].iter().rev() {
+            snapshot.tape.write(p.location, p.old);
+        }
+
+        let ((_, _, head_move), overwritten) = self.step_at(index);
+        snapshot.head_loc -= head_move.offset();
+        snapshot.tape.write(snapshot.head_loc, overwritten);
+        snapshot.state = match index {
+            0 => self.init_state,
+            _ => self.step_at(index - 1).0.0,
+        };
+        snapshot.step_num = index;
+
+        true
+    }
+
+    /// Recomputes the symbol each step of `self` overwrote by replaying it, e.g. after reading it from an older format
+    /// or attaching its input, storing those of spilled steps in the spill file a chunk at a time.
+    /// Leaves them unknown if the input is detached, `self` is sampled, or the spill file cannot be written.
+    fn replay_overwritten(&mut self) {
+        self.overwritten.clear();
+        self.overwritten_unknown = true;
+        if self.input_detached || self.sample_rate != 0 {
+            return;
+        }
+
+        let mut overwritten = Vec::new();
+        let mut snapshot = self.initial_snapshot();
+        let mut perturbation_num = self.perturbations.partition_point(|p| p.step_num == 0);
+        for (step_num, (state, symbol, head_move)) in (1..).zip(self.raw_steps_from(0)) {
+            overwritten.push(snapshot.tape.symbol_at_n(snapshot.head_loc));
+            snapshot.state = state;
+            snapshot.tape.write(snapshot.head_loc, symbol);
+            snapshot.head_loc += head_move.offset();
+
+            while let Some(p) = self.perturbations.get(perturbation_num).filter(|p| p.step_num == step_num) {
+                snapshot.tape.write(p.location, p.new);
+                perturbation_num += 1;
+            }
+
+            if step_num <= self.spilled_len && (overwritten.len() == SPILL_CHUNK_LEN || step_num == self.spilled_len) {
+                let spill = self.spill.as_ref().expect("spilled steps have a spill file");
+                if spill.write_overwritten(step_num - overwritten.len(), &overwritten).is_err() {
+                    return;
+                }
+                overwritten.clear();
+            }
+        }
+
+        self.overwritten = overwritten;
+        self.overwritten_unknown = false;
+    }
+
+    /// Returns the configuration before the first step, including any perturbations made before it.
+    fn initial_snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot {
+            step_num: 0,
+            state: self.init_state,
+            head_loc: self.init_head_loc,
+            tape: self.input.clone(),
+        };
+        for p in self.perturbations.iter().take_while(|p| p.step_num == 0) {
+            snapshot.tape.write(p.location, p.new);
+        }
+
+        snapshot
+    }
+
+    /// Advances `snapshot` to `step_num` by replaying steps and perturbations.
+    fn replay(&self, mut snapshot: Snapshot, step_num: usize) -> Snapshot {
+        let mut perturbation_num = self.perturbations.partition_point(|p| p.step_num <= snapshot.step_num);
+        for (state, symbol, head_move) in self.raw_steps_from(snapshot.step_num).take(step_num - snapshot.step_num) {
+            snapshot.state = state;
+            snapshot.tape.write(snapshot.head_loc, symbol);
+            snapshot.head_loc += head_move.offset();
+            snapshot.step_num += 1;
+
+            while let Some(p) = self.perturbations.get(perturbation_num).filter(|p| p.step_num == snapshot.step_num) {
+                snapshot.tape.write(p.location, p.new);
+                perturbation_num += 1;
+            }
+        }
+
+        snapshot
+    }
+
+    /// Recomputes the embedded snapshots of `self` by replaying it, e.g. after reading it back.
+    /// The snapshots of a sampled recording are left alone, since it has no steps to replay.
+    pub(crate) fn rebuild_snapshots(&mut self) {
+        if self.sample_rate != 0 {
+            return;
+        }
+        self.snapshots.clear();
+        self.snapshot_bytes = 0;
+        if self.snapshot_every == 0 {
+            return;
+        }
+
+        let mut snapshot = self.initial_snapshot();
+        for step_num in (self.snapshot_every..=self.raw_len()).step_by(self.snapshot_every) {
+            snapshot = self.replay(snapshot, step_num);
+            self.push_snapshot(snapshot.clone());
+        }
+    }
+
+    /// Writes `self` to `writer` in a compact, versioned binary format that `read_from` reads back.
+    /// 
+    /// The format begins with `RECORDING_MAGIC`, then `RECORDING_FORMAT_VERSION` and the machine fingerprint
+    /// as little-endian integers. The summary follows: the number of steps and nonblank cells, the head range,
+    /// and the number of states with their step counts. Then the initial configuration, the nonblank cells of the input tape,
+    /// the steps, the perturbations, and the faults follow, each list prefixed by its length,
+    /// and then the snapshot interval, whether the input is detached, the input fingerprint,
+    /// the seed, if any, prefixed by a 1 or else just a 0, and the sample rate.
+    /// Snapshots themselves are not written but rebuilt by `read_from`, except those of a sampled recording,
+    /// which follow as a list of the step number, the configuration, and the nonblank cells of the tape.
+    /// Last is the list of symbols the steps overwrote, which is empty if they are unknown,
+    /// as they are while the input is detached, since they would reveal it.
+    /// All further integers are LEB128 varints, with signed integers zigzag-encoded, and each step
+    /// stores its state as the difference from the previous state, so a typical step takes 2 or 3 bytes.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]));
+    /// let recording = machine.run_with_halt_setting_and_record(&mut Tape::default(), HaltSetting::AfterSteps(100));
+    /// 
+    /// let mut bytes = Vec::new();
+    /// recording.write_to(&mut bytes).unwrap();
+    /// 
+    /// assert_eq!(Recording::read_from(&mut bytes.as_slice()).unwrap(), recording);
+    /// ```
+    #[inline]
+    pub fn write_to(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(32 + self.steps.len() * 3);
+        buf.extend(RECORDING_MAGIC);
+        buf.extend(RECORDING_FORMAT_VERSION.to_le_bytes());
+        buf.extend(self.machine_fingerprint.to_le_bytes());
+        write_varint(&mut buf, self.summary.steps as u64);
+        write_varint(&mut buf, self.summary.nonblank as u64);
+        write_varint(&mut buf, zigzag(*self.summary.head_range.start()));
+        write_varint(&mut buf, zigzag(*self.summary.head_range.end()));
+        write_varint(&mut buf, self.summary.state_counts.len() as u64);
+        for (&state, &count) in &self.summary.state_counts {
+            write_varint(&mut buf, state);
+            write_varint(&mut buf, count as u64);
+        }
+        write_varint(&mut buf, self.init_state);
+        write_varint(&mut buf, zigzag(self.init_head_loc));
+
+        write_tape(&mut buf, &self.input);
+
+        write_varint(&mut buf, self.raw_len() as u64);
+        let mut prev_state = self.init_state;
+        for (state, symbol, head_move) in self.raw_steps_from(0) {
+            // recorded steps never stay, since a transition always moves the head
+            write_varint(&mut buf, (zigzag(state.wrapping_sub(prev_state) as i64) << 1) | (head_move == Move::Right) as u64);
+            write_varint(&mut buf, symbol);
+            prev_state = state;
+
+            // keeps the buffer small when the steps were spilled to disk
+            if buf.len() >= 1 << 16 {
+                writer.write_all(&buf)?;
+                buf.clear();
+            }
+        }
+
+        write_varint(&mut buf, self.perturbations.len() as u64);
+        for p in &self.perturbations {
+            for x in [p.step_num as u64, zigzag(p.location), p.old, p.new] {
+                write_varint(&mut buf, x);
+            }
+        }
+
+        write_varint(&mut buf, self.faults.len() as u64);
+        for fault in &self.faults {
+            let fields = match *fault {
+                Fault::Misread { step_num, location, actual, read } => [0, step_num as u64, zigzag(location), actual, read],
+                Fault::StuckWrite { step_num, location, intended } => [1, step_num as u64, zigzag(location), intended, 0],
+                Fault::Flip { step_num, location, old, new } => [2, step_num as u64, zigzag(location), old, new],
+            };
+            for x in fields {
+                write_varint(&mut buf, x);
+            }
+        }
+
+        write_varint(&mut buf, self.snapshot_every as u64);
+        write_varint(&mut buf, self.input_detached as u64);
+        buf.extend(self.input_fingerprint.to_le_bytes());
+        match self.seed {
+            Some(seed) => {
+                write_varint(&mut buf, 1);
+                buf.extend(seed.0.to_le_bytes());
+            }
+            None => write_varint(&mut buf, 0),
+        }
+
+        write_varint(&mut buf, self.sample_rate as u64);
+        if self.sample_rate != 0 {
+            write_varint(&mut buf, self.snapshots.len() as u64);
+            for snapshot in &self.snapshots {
+                write_varint(&mut buf, snapshot.step_num as u64);
+                write_varint(&mut buf, snapshot.state);
+                write_varint(&mut buf, zigzag(snapshot.head_loc));
+                write_tape(&mut buf, &snapshot.tape);
+            }
+        }
+
+        match self.overwritten_unknown || self.input_detached {
+            true => write_varint(&mut buf, 0),
+            false => {
+                write_varint(&mut buf, self.raw_len() as u64);
+                for (_, symbol) in self.raw_records_from(0) {
+                    write_varint(&mut buf, symbol.expect("the overwritten symbols are known"));
+                    if buf.len() >= 1 << 16 {
+                        writer.write_all(&buf)?;
+                        buf.clear();
+                    }
+                }
+            }
+        }
+
+        writer.write_all(&buf)
+    }
+
+    /// Reads a recording written by `write_to`.
+    /// 
+    /// # Errors
+    /// Returns an error of kind `InvalidData` if the data does not begin with `RECORDING_MAGIC`,
+    /// has an unsupported version, or is malformed, such as by writing farther than `MAX_LOCATION` from location 0,
+    /// and passes on any error from `reader`. Nothing is allocated up front for the lengths of lists,
+    /// so a corrupt length fails at the end of the data instead of exhausting memory.
+    #[inline]
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let (version, machine_fingerprint) = read_header(reader)?;
+        let summary = if version >= 5 { Some(read_summary_fields(reader)?) } else { None };
+
+        let mut recording = Recording {
+            machine_fingerprint,
+            init_state: read_varint(reader)?,
+            init_head_loc: unzigzag(read_varint(reader)?),
+            ..Default::default()
+        };
+
+        recording.input = read_tape(reader)?;
+
+        let mut state = recording.init_state;
+        for _ in 0..read_varint(reader)? {
+            let x = read_varint(reader)?;
+            state = state.wrapping_add(unzigzag(x >> 1) as u64);
+            recording.steps.push((state, read_varint(reader)?, Move::from(x & 1 == 1)));
+        }
+
+        for _ in 0..read_varint(reader)? {
+            recording.perturbations.push(Perturbation {
+                step_num: read_varint(reader)? as usize,
+                location: unzigzag(read_varint(reader)?),
+                old: read_varint(reader)?,
+                new: read_varint(reader)?,
+            });
+        }
+
+        for _ in 0..read_varint(reader)? {
+            let mut fields = [0; 5];
+            for x in fields.iter_mut() {
+                *x = read_varint(reader)?;
+            }
+
+            let (step_num, location) = (fields[1] as usize, unzigzag(fields[2]));
+            recording.faults.push(match fields[0] {
+                0 => Fault::Misread { step_num, location, actual: fields[3], read: fields[4] },
+                1 => Fault::StuckWrite { step_num, location, intended: fields[3] },
+                2 => Fault::Flip { step_num, location, old: fields[3], new: fields[4] },
+                _ => return Err(invalid_data("unknown fault kind")),
+            });
+        }
+
+        if version >= 2 {
+            recording.snapshot_every = read_varint(reader)? as usize;
+        }
+        if version >= 3 {
+            recording.input_detached = read_varint(reader)? == 1;
+            let mut fingerprint = [0; 8];
+            reader.read_exact(&mut fingerprint)?;
+            recording.input_fingerprint = u64::from_le_bytes(fingerprint);
+        }
+        else {
+            recording.input_fingerprint = recording.input.fingerprint();
+        }
+        if version >= 4 && read_varint(reader)? == 1 {
+            let mut seed = [0; 8];
+            reader.read_exact(&mut seed)?;
+            recording.seed = Some(Seed(u64::from_le_bytes(seed)));
+        }
+        if version >= 6 {
+            recording.sample_rate = read_varint(reader)? as usize;
+        }
+        if recording.sample_rate != 0 {
+            for _ in 0..read_varint(reader)? {
+                recording.snapshots.push(Snapshot {
+                    step_num: read_varint(reader)? as usize,
+                    state: read_varint(reader)?,
+                    head_loc: unzigzag(read_varint(reader)?),
+                    tape: read_tape(reader)?,
+                });
+            }
+        }
+        if version >= 7 {
+            let len = read_varint(reader)?;
+            if len != 0 && len != recording.raw_len() as u64 {
+                return Err(invalid_data("the number of overwritten symbols does not match the number of steps"));
+            }
+            for _ in 0..len {
+                recording.overwritten.push(read_varint(reader)?);
+            }
+            recording.overwritten_unknown = recording.overwritten.len() != recording.raw_len() || recording.input_detached;
+            if recording.overwritten_unknown {
+                recording.overwritten.clear();
+            }
+        }
+        else {
+            recording.replay_overwritten();
+        }
+        recording.validate()?;
+        recording.rebuild_snapshots();
+        match summary {
+            Some(summary) => recording.summary = summary,
+            None => {
+                let tape = recording.seek(recording.raw_len()).ok_or_else(|| invalid_data("the recording cannot be replayed"))?.tape;
+                recording.summarize(&tape);
+            }
+        }
+
+        Ok(recording)
+    }
+
+    /// Checks that a recording read by `read_from` can be replayed without panicking:
+    /// its perturbations are in order and within its steps, and it never writes farther than `MAX_LOCATION` from location 0.
+    fn validate(&self) -> io::Result<()> {
+        let out_of_range = |location: i64| location.unsigned_abs() > MAX_LOCATION as u64;
+        if out_of_range(self.init_head_loc) {
+            return Err(invalid_data("the initial head location is out of range"));
+        }
+        if self.perturbations.windows(2).any(|w| w[0].step_num > w[1].step_num) {
+            return Err(invalid_data("perturbations are out of order"));
+        }
+        if self.perturbations.iter().any(|p| p.step_num > self.raw_len() || out_of_range(p.location)) {
+            return Err(invalid_data("a perturbation is out of range"));
+        }
+
+        let mut head_loc = self.init_head_loc;
+        for ((_, symbol, head_move), overwritten) in self.raw_records_from(0) {
+            if out_of_range(head_loc) && (symbol != 0 || overwritten.is_some_and(|x| x != 0)) {
+                return Err(invalid_data("a step writes out of range"));
+            }
+            head_loc += head_move.offset();
+        }
+
+        Ok(())
+    }
+
+    /// Reads only the summary of a recording written by `write_to`, without reading its steps.
+    /// Recordings written before version 5 of the format have no stored summary,
+    /// so they are read in full and replayed instead, counting only the nonblank cells written during the run
+    /// if the input was detached.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let recording = machine.run_with_halt_setting_and_record(&mut Tape::default(), HaltSetting::AfterSteps(100));
+    /// 
+    /// let mut bytes = Vec::new();
+    /// recording.write_to(&mut bytes).unwrap();
+    /// 
+    /// let summary = Recording::read_summary(&mut bytes.as_slice()).unwrap();
+    /// assert_eq!((summary.steps, summary.nonblank, summary.head_range), (100, 100, 0..=100));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error like `read_from`.
+    #[inline]
+    pub fn read_summary(reader: &mut impl Read) -> io::Result<RecordingSummary> {
+        let mut header = [0; 14];
+        reader.read_exact(&mut header)?;
+        let (version, _) = read_header(&mut header.as_slice())?;
+        if version >= 5 {
+            read_summary_fields(reader)
+        }
+        else {
+            Recording::read_from(&mut header.as_slice().chain(reader)).map(|recording| recording.summary)
+        }
+    }
+
+    /// Plays back `self` and `other` next to each other in the terminal, showing the same step of both at once,
+    /// such as to compare a machine before and after an optimization. The shorter recording stays on its last frame
+    /// until the longer one finishes. Each side shows the part of the tape chosen by `view`.
+    /// This blocks the current thread until playback is finished; see `frames_side_by_side` for a non-blocking alternative.
+    #[inline]
+    pub fn play_side_by_side(&self, other: &Recording, step_delay: Duration, cls: bool, alphabet: &Alphabet, view: ViewPolicy) {
+        let mut stdout = io::stdout().lock();
+        if cls {
+            let _ = write!(stdout, "\x1b[2J\x1b[H");
+        }
+
+        let mut renderer = ConsoleRenderer::default();
+        let start = Instant::now();
+        for (offset, left, right) in self.frames_side_by_side(other, step_delay).with_view(view) {
+            if let Some(remaining) = offset.checked_sub(start.elapsed()) {
+                sleep(remaining);
+            }
+
+            let _ = stdout.write_all(renderer.update(&left.render_beside(&right, alphabet)).as_bytes());
+            let _ = stdout.flush();
+        }
+    }
+
+    /// Returns an iterator over the frames of `self` and `other` played by `play_side_by_side`,
+    /// each pair showing the same step of both and paired with the offset at which it should be shown.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine1 = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+    /// let mut machine2 = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 1, true))]));
+    /// let recording1 = machine1.run_and_record(&mut Tape::default());
+    /// let recording2 = machine2.run_and_record(&mut Tape::default());
+    /// 
+    /// let frames = recording1.frames_side_by_side(&recording2, Duration::from_millis(100)).collect::<Vec<_>>();
+    /// 
+    /// // the first recording stays on its last frame while the second takes its second step
+    /// assert_eq!(frames.len(), 5);
+    /// assert_eq!(frames[4].0, Duration::from_millis(400));
+    /// assert_eq!((frames[4].1.head_loc, frames[4].2.head_loc), (1, 2));
+    /// ```
+    #[inline]
+    pub fn frames_side_by_side<'a>(&'a self, other: &'a Recording, step_delay: Duration) -> PairedFrames<'a> {
+        PairedFrames {
+            left: self.frames(step_delay),
+            right: other.frames(step_delay),
+            last: None,
+        }
+    }
+
+    /// Returns an iterator over the frames of the "movie" played by `play_in_console`,
+    /// each paired with the offset from the start of playback at which it should be shown.
+    /// Frames are computed lazily and nothing is printed, leaving scheduling to the caller.
+    /// A sampled recording shows a frame per snapshot after the initial one.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+    /// let recording = machine.run_and_record(&mut Tape::default());
+    /// 
+    /// let frames = recording.frames(Duration::from_millis(100)).collect::<Vec<_>>();
+    /// 
+    /// // the initial frame, then a moving and a resting frame per step
+    /// assert_eq!(frames.len(), 3);
+    /// assert_eq!(frames[2].0, Duration::from_millis(200));
+    /// assert_eq!(frames[2].1.head_loc, 1);
+    /// ```
+    #[inline]
+    pub fn frames(&self, step_delay: Duration) -> Frames<'_> {
+        Frames {
+            recording: self,
+            steps: self.raw_steps_from(0),
+            tape: self.input.clone(),
+            state: self.init_state,
+            head_loc: self.init_head_loc,
+            head_move: Move::Stay,
+            step_delay,
+            frame_num: 0,
+            perturbation_num: 0,
+            view: ViewPolicy::Centered,
+            window: None,
+        }
+    }
+}
+
+impl PartialEq for Recording {
+    /// Compares every recorded field, regardless of which steps were spilled to disk.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.input == other.input
+            && (self.init_state, self.init_head_loc) == (other.init_state, other.init_head_loc)
+            && self.raw_len() == other.raw_len()
+            && self.perturbations == other.perturbations
+            && self.faults == other.faults
+            && self.machine_fingerprint == other.machine_fingerprint
+            && self.snapshot_every == other.snapshot_every
+            && self.sample_rate == other.sample_rate
+            && self.snapshots == other.snapshots
+            && self.input_detached == other.input_detached
+            && self.input_fingerprint == other.input_fingerprint
+            && self.seed == other.seed
+            && self.summary == other.summary
+            && self.raw_records_from(0).eq(other.raw_records_from(0))
+    }
+}
+
+/// The size of a step in a spill file: the new state, the symbol written, and the symbol overwritten,
+/// or 0 if unknown, as little-endian integers, then the movement as 0 for left, 1 for right, or 2 for stay.
+const SPILLED_STEP_LEN: usize = 25;
+
+/// The number of spilled steps `RawSteps` reads at a time.
+const SPILL_CHUNK_LEN: usize = 4096;
+
+/// A temporary file holding the oldest steps of a `Recording`, shared by its clones and deleted along with the last of them.
+#[derive(Debug)]
+pub(crate) struct SpillFile {
+    file: Mutex<File>,
+    path: PathBuf,
+}
+
+impl SpillFile {
+    fn create() -> io::Result<Self> {
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("turing_machine-{}-{}.steps", process::id(), id));
+        let file = File::options().read(true).write(true).create_new(true).open(&path)?;
+
+        Ok(SpillFile { file: Mutex::new(file), path })
+    }
+
+    /// Appends `steps` along with the symbols they overwrote, which may be empty if those are unknown.
+    fn append(&self, steps: &[(u64, u64, Move)], overwritten: &[u64]) -> io::Result<()> {
+        let mut buf = Vec::with_capacity(steps.len() * SPILLED_STEP_LEN);
+        for (i, &(state, symbol, head_move)) in steps.iter().enumerate() {
+            buf.extend(state.to_le_bytes());
+            buf.extend(symbol.to_le_bytes());
+            buf.extend(overwritten.get(i).copied().unwrap_or(0).to_le_bytes());
+            buf.push(match head_move {
+                Move::Left => 0,
+                Move::Right => 1,
+                Move::Stay => 2,
+            });
+        }
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.seek(SeekFrom::End(0))?;
+        file.write_all(&buf)
+    }
+
+    /// Reads `len` steps and the symbols they overwrote starting at index `start` into `steps`, replacing its contents.
+    fn read(&self, start: usize, len: usize, steps: &mut Vec<((u64, u64, Move), u64)>) -> io::Result<()> {
+        let mut buf = vec![0; len * SPILLED_STEP_LEN];
+        {
+            let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+            file.seek(SeekFrom::Start((start * SPILLED_STEP_LEN) as u64))?;
+            file.read_exact(&mut buf)?;
+        }
+
+        steps.clear();
+        steps.extend(buf.chunks_exact(SPILLED_STEP_LEN).map(|record| {
+            let state = u64::from_le_bytes(record[..8].try_into().unwrap());
+            let symbol = u64::from_le_bytes(record[8..16].try_into().unwrap());
+            let overwritten = u64::from_le_bytes(record[16..24].try_into().unwrap());
+            let head_move = match record[24] {
+                0 => Move::Left,
+                1 => Move::Right,
+                _ => Move::Stay,
+            };
+            ((state, symbol, head_move), overwritten)
+        }));
+
+        Ok(())
+    }
+
+    /// Replaces the overwritten symbols of the steps starting at index `start` with `overwritten`.
+    fn write_overwritten(&self, start: usize, overwritten: &[u64]) -> io::Result<()> {
+        let mut buf = vec![0; overwritten.len() * SPILLED_STEP_LEN];
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.seek(SeekFrom::Start((start * SPILLED_STEP_LEN) as u64))?;
+        file.read_exact(&mut buf)?;
+
+        for (record, symbol) in buf.chunks_exact_mut(SPILLED_STEP_LEN).zip(overwritten) {
+            record[16..24].copy_from_slice(&symbol.to_le_bytes());
+        }
+        file.seek(SeekFrom::Start((start * SPILLED_STEP_LEN) as u64))?;
+        file.write_all(&buf)
+    }
+}
+
+impl Drop for SpillFile {
     #[inline]
-    pub fn play_in_console(&self, step_delay: Duration, cls: bool) {
-        let mut tape = self.input.clone();
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
 
-        let mut head_loc = self.init_head_loc;
-        let mut state = self.init_state;
+/// A raw step with the symbol it overwrote, or `None` if those are unknown.
+type StepRecord = ((u64, u64, Move), Option<u64>);
+
+/// An iterator over the raw steps of a `Recording` from some index on, whether spilled or held in memory.
+#[derive(Clone, Debug)]
+struct RawSteps<'a> {
+    recording: &'a Recording,
+    next: usize,
+    /// The spilled steps last read with the symbols they overwrote, starting at index `chunk_start`.
+    chunk: Vec<((u64, u64, Move), u64)>,
+    chunk_start: usize,
+}
+
+impl RawSteps<'_> {
+    /// Returns the next step with the symbol it overwrote, or `None` if those are unknown.
+    /// 
+    /// # Panics
+    /// Panics if the step was spilled and cannot be read back.
+    fn next_record(&mut self) -> Option<StepRecord> {
+        // the file is private to the recording, so failing to read it back is as unexpected as running out of memory
+        self.try_next_record().map(|record| record.unwrap_or_else(|e| panic!("failed to read spilled steps: {}", e)))
+    }
+
+    /// Equivalent to `next_record`, but returns the error if the step was spilled and cannot be read back,
+    /// after which the iterator ends.
+    fn try_next_record(&mut self) -> Option<io::Result<StepRecord>> {
+        let recording = self.recording;
+        let (step, overwritten) = if self.next < recording.spilled_len {
+            if !(self.chunk_start..self.chunk_start + self.chunk.len()).contains(&self.next) {
+                let len = SPILL_CHUNK_LEN.min(recording.spilled_len - self.next);
+                let spill = recording.spill.as_ref().expect("spilled steps have a spill file");
+                if let Err(e) = spill.read(self.next, len, &mut self.chunk) {
+                    self.next = recording.raw_len();
+                    return Some(Err(e));
+                }
+                self.chunk_start = self.next;
+            }
+            self.chunk[self.next - self.chunk_start]
+        }
+        else {
+            let i = self.next - recording.spilled_len;
+            (*recording.steps.get(i)?, recording.overwritten.get(i).copied().unwrap_or(0))
+        };
+        self.next += 1;
+
+        Some(Ok((step, (!recording.overwritten_unknown).then_some(overwritten))))
+    }
+}
+
+impl Iterator for RawSteps<'_> {
+    type Item = (u64, u64, Move);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().map(|(step, _)| step)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.recording.raw_len().saturating_sub(self.next);
+        (len, Some(len))
+    }
+}
+
+/// How the window of tape cells shown during playback follows the head.
+/// Every policy but `Region` shows 11 cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ViewPolicy {
+    /// The window is always centered on the head.
+    #[default]
+    Centered,
+    /// The window starts centered on the head and only scrolls, as little as possible,
+    /// to keep at least `margin` cells (at most 5) between the head and either edge.
+    Scroll { margin: usize },
+    /// The window stays on the cells centered on the initial location of the head, even once the head leaves them.
+    Fixed,
+    /// The window shows the cells from location `start` to `end` inclusive, wherever the head is.
+    Region { start: i64, end: i64 },
+}
+
+impl ViewPolicy {
+    /// Returns the first and last location to show with the head at `head_loc`, given the window of the previous frame.
+    #[inline]
+    fn window(self, previous: Option<(i64, i64)>, head_loc: i64) -> (i64, i64) {
+        let centered = (head_loc - 5, head_loc + 5);
+        match (self, previous) {
+            (ViewPolicy::Region { start, end }, _) => (start, end),
+            (ViewPolicy::Fixed, Some(previous)) => previous,
+            (ViewPolicy::Scroll { margin }, Some((start, _))) => {
+                let margin = margin.min(5) as i64;
+                let start = start.clamp(head_loc + margin - 10, head_loc - margin);
+                (start, start + 10)
+            }
+            _ => centered,
+        }
+    }
+}
+
+/// A single frame of console playback: the state, the head, and a window of tape cells,
+/// by default the 11 centered on the head.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pub state: u64,
+    pub head_loc: i64,
+    /// The direction the head is about to move, or `Move::Stay` if it is resting.
+    pub head_move: Move,
+    /// The location of the first symbol in `window`.
+    pub window_start: i64,
+    /// The symbols from location `window_start` onwards.
+    pub window: Vec<u64>,
+}
+
+impl Frame {
+    #[inline]
+    fn new(tape: &Tape, state: u64, head_loc: i64, head_move: Move, (start, end): (i64, i64)) -> Self {
+        Frame {
+            state,
+            head_loc,
+            head_move,
+            window_start: start,
+            window: (start..=end).map(|n| tape.symbol_at_n(n)).collect(),
+        }
+    }
+
+    /// Renders `self` like its `Display` implementation, but with symbols named by `alphabet`.
+    /// The head is drawn above its cell, or above the nearest edge of the window along with its location if it is outside.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 2, true))]));
+    /// let recording = machine.run_and_record(&mut Tape::default());
+    /// let frame = recording.frames(Duration::ZERO).last().unwrap().1;
+    /// 
+    /// assert!(frame.render(&Alphabet::new(&["_", "a", "b"])).contains("_         _         _         _         b         _"));
+    /// ```
+    #[inline]
+    pub fn render(&self, alphabet: &Alphabet) -> String {
+        let mut move_left = "    ";
+        let mut move_right = "";
+        if self.head_move == Move::Left {
+            move_left = "<-- ";
+        }
+        else if self.head_move == Move::Right {
+            move_right = " -->";
+        }
+
+        let window_end = self.window_start + self.window.len() as i64 - 1;
+        let column = self.head_loc.clamp(self.window_start, window_end.max(self.window_start)) - self.window_start;
+        let off_screen = if column == self.head_loc - self.window_start { String::new() } else { format!(" at {}", self.head_loc) };
+
+        // every symbol takes up a column of 10 characters unless its name is longer
+        let symbols = self.window.iter().map(|&x| format!("{: <9}", alphabet.name(x))).collect::<Vec<String>>();
+        let mut out = format!(
+            "\n{: >width$}{}H({}){}{}\nTape:      {}",
+            "",
+            move_left,
+            self.state,
+            move_right,
+            off_screen,
+            symbols.join(" ").trim_end(),
+            width = 5 + 10 * column as usize,
+        );
+
+        out.push_str("\n\nindex:");
+        for n in self.window_start..=window_end {
+            out.push_str(&format!(" {: ^9}", n));
+        }
+
+        out
+    }
+}
+
+impl Frame {
+    /// Renders `self` and `other` with symbols named by `alphabet`, with the lines of `other`
+    /// to the right of those of `self`, separated by a vertical bar.
+    #[inline]
+    pub fn render_beside(&self, other: &Frame, alphabet: &Alphabet) -> String {
+        let left = self.render(alphabet);
+        let right = other.render(alphabet);
+        let width = left.lines().map(|line| line.chars().count()).max().unwrap_or(0);
+
+        let mut left_lines = left.lines();
+        let mut right_lines = right.lines();
+        let mut lines = Vec::new();
+        loop {
+            match (left_lines.next(), right_lines.next()) {
+                (None, None) => break,
+                (l, r) => lines.push(format!("{: <width$} | {}", l.unwrap_or(""), r.unwrap_or(""), width = width).trim_end().to_string()),
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+impl fmt::Display for Frame {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&Alphabet::default()))
+    }
+}
+
+/// Turns a sequence of multi-line texts, such as rendered `Frame`s, into terminal output that draws each one
+/// over the last by moving the cursor with ANSI escape codes and rewriting only the characters that changed.
+/// Every character is assumed to take up one column.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::recording::ConsoleRenderer;
+/// 
+/// let mut renderer = ConsoleRenderer::default();
+/// 
+/// // the first text is written as is, leaving the cursor below it
+/// assert_eq!(renderer.update("H(0)\n0 1 1"), "H(0)\n0 1 1\n");
+/// // later texts move the cursor up and rewrite the changed characters in place
+/// assert_eq!(renderer.update("H(1)\n0 1 0"), "\x1b[2A\x1b[3G1\n\x1b[5G0\n");
+/// assert_eq!(renderer.update("H(1)\n0"), "\x1b[2A\n\x1b[2G\x1b[K\n");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsoleRenderer {
+    /// The lines on screen, as many as the tallest text so far.
+    lines: Vec<Vec<char>>,
+}
 
-        recording_print(&tape, head_loc, state, 0);
-        sleep(step_delay);
-        if cls { print!("{}[2J", 27 as char) };
+impl ConsoleRenderer {
+    /// Returns the output that replaces the last text written through `self` with `text`.
+    #[inline]
+    pub fn update(&mut self, text: &str) -> String {
+        let new_lines = text.lines().map(|line| line.chars().collect()).collect::<Vec<Vec<char>>>();
+        if self.lines.is_empty() {
+            self.lines = new_lines;
+            return text.lines().map(|line| format!("{}\n", line)).collect();
+        }
+
+        let mut out = format!("\x1b[{}A", self.lines.len());
+        self.lines.resize(self.lines.len().max(new_lines.len()), Vec::new());
+        for (i, old) in self.lines.iter_mut().enumerate() {
+            let new = new_lines.get(i).map_or(&[][..], Vec::as_slice);
+
+            // rewrite every run of changed characters, starting at its 1-based column
+            let mut col = 0;
+            while col < new.len() {
+                if old.get(col) == Some(&new[col]) {
+                    col += 1;
+                    continue;
+                }
+                let run_start = col;
+                while col < new.len() && old.get(col) != Some(&new[col]) {
+                    col += 1;
+                }
+                out.push_str(&format!("\x1b[{}G", run_start + 1));
+                out.extend(&new[run_start..col]);
+            }
+            if new.len() < old.len() {
+                out.push_str(&format!("\x1b[{}G\x1b[K", new.len() + 1));
+            }
+            out.push('\n');
+
+            *old = new.to_vec();
+        }
+
+        out
+    }
+}
+
+/// A lazy iterator over the timed frames of a `Recording`, created by `Recording::frames`.
+#[derive(Clone, Debug)]
+pub struct Frames<'a> {
+    recording: &'a Recording,
+    steps: RawSteps<'a>,
+    tape: Tape,
+    state: u64,
+    head_loc: i64,
+    /// The movement of the step being shown.
+    head_move: Move,
+    step_delay: Duration,
+    frame_num: usize,
+    perturbation_num: usize,
+    view: ViewPolicy,
+    /// The first and last location shown in the previous frame.
+    window: Option<(i64, i64)>,
+}
+
+impl Frames<'_> {
+    /// Shows the part of the tape chosen by `view` in every frame, instead of the 11 cells centered on the head.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let recording = machine.run_with_halt_setting_and_record(&mut Tape::default(), HaltSetting::AfterSteps(8));
+    /// 
+    /// let starts = |view| recording.frames(Duration::ZERO).with_view(view).map(|(_, frame)| frame.window_start).collect::<Vec<_>>();
+    /// 
+    /// // the head keeps 3 cells away from the right edge, so the window only scrolls once it moves past location 2
+    /// let scroll = starts(ViewPolicy::Scroll { margin: 3 });
+    /// assert_eq!((scroll[5], scroll[6], scroll[16]), (-5, -4, 1));
+    /// 
+    /// assert!(starts(ViewPolicy::Fixed).iter().all(|&start| start == -5));
+    /// assert!(starts(ViewPolicy::Region { start: 0, end: 3 }).iter().all(|&start| start == 0));
+    /// ```
+    #[inline]
+    pub fn with_view(mut self, view: ViewPolicy) -> Self {
+        self.view = view;
+        self
+    }
+
+    #[inline]
+    fn frame(&mut self, head_move: Move) -> Frame {
+        let window = self.view.window(self.window, self.head_loc);
+        self.window = Some(window);
+        Frame::new(&self.tape, self.state, self.head_loc, head_move, window)
+    }
+}
+
+/// A lazy iterator over pairs of timed frames of two `Recording`s, created by `Recording::frames_side_by_side`.
+#[derive(Clone, Debug)]
+pub struct PairedFrames<'a> {
+    left: Frames<'a>,
+    right: Frames<'a>,
+    /// The frames of the previous pair, repeated for whichever recording has finished.
+    last: Option<(Frame, Frame)>,
+}
+
+impl PairedFrames<'_> {
+    /// Shows the part of the tape chosen by `view` in the frames of both recordings; see `Frames::with_view`.
+    #[inline]
+    pub fn with_view(mut self, view: ViewPolicy) -> Self {
+        self.left = self.left.with_view(view);
+        self.right = self.right.with_view(view);
+        self
+    }
+}
+
+impl Iterator for PairedFrames<'_> {
+    type Item = (Duration, Frame, Frame);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let (offset, left, right) = match (self.left.next(), self.right.next(), self.last.take()) {
+            (Some((offset, left)), Some((_, right)), _) => (offset, left, right),
+            (Some((offset, left)), None, Some((_, right))) => (offset, left, right),
+            (None, Some((offset, right)), Some((left, _))) => (offset, left, right),
+            // every recording has an initial frame, so the last pair is only missing if both have finished
+            _ => return None,
+        };
+
+        self.last = Some((left.clone(), right.clone()));
+        Some((offset, left, right))
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = (Duration, Frame);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.step_delay * self.frame_num as u32;
+
+        // odd frames show a step being taken, even frames show the result
+        let frame = if self.frame_num == 0 {
+            self.frame(Move::Stay)
+        }
+        else if self.recording.sample_rate != 0 {
+            // a sampled recording has no steps, so every later frame shows the next snapshot
+            let snapshot = self.recording.snapshots.get(self.frame_num - 1)?;
+            (self.tape, self.state, self.head_loc) = (snapshot.tape.clone(), snapshot.state, snapshot.head_loc);
+            self.frame(Move::Stay)
+        }
+        else if self.frame_num % 2 == 1 {
+            let (state, symbol, head_move) = self.steps.next()?;
+            self.head_move = head_move;
+            self.state = state;
+            self.tape.write(self.head_loc, symbol);
+            self.frame(self.head_move)
+        }
+        else {
+            self.head_loc += self.head_move.offset();
+
+            let step_num = self.frame_num / 2;
+            while let Some(p) = self.recording.perturbations.get(self.perturbation_num).filter(|p| p.step_num == step_num) {
+                self.tape.write(p.location, p.new);
+                self.perturbation_num += 1;
+            }
 
-        for s in self.steps.clone() {
-            state = s.0;
-            tape.write(head_loc, s.1);
+            self.frame(Move::Stay)
+        };
+
+        self.frame_num += 1;
+        Some((offset, frame))
+    }
+}
 
-            let head_move = s.2 as i64 * 2 - 1;
+#[inline]
+fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
 
-            if cls { print!("{}[2J", 27 as char) };
-            recording_print(&tape, head_loc, state, head_move);
-            sleep(step_delay);
+#[inline]
+fn unzigzag(n: u64) -> i64 {
+    (n >> 1) as i64 ^ -((n & 1) as i64)
+}
 
-            head_loc += head_move;
+#[inline]
+fn write_varint(buf: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        buf.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
 
-            if cls { print!("{}[2J", 27 as char) };
-            recording_print(&tape, head_loc, state, 0);
-            sleep(step_delay);
+#[inline]
+fn read_varint(reader: &mut impl Read) -> io::Result<u64> {
+    let mut n = 0;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte)?;
+        n |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] < 0x80 {
+            return Ok(n);
         }
     }
+
+    Err(invalid_data("varint is too long"))
 }
 
 #[inline]
-fn recording_print(tape: &Tape, head_loc: i64, state: u64, head_move: i64) {
-    let mut move_left = "    ";
-    let mut move_right = "";
-    if head_move == -1 {
-        move_left = "<-- ";
+/// Writes the nonblank cells of `tape` as their number, then each location, as the difference from the previous one,
+/// and symbol.
+fn write_tape(buf: &mut Vec<u8>, tape: &Tape) {
+    let cells = tape.nonblank_range().map_or(vec![], |r| r.filter(|&n| tape.symbol_at_n(n) != 0).collect::<Vec<i64>>());
+    write_varint(buf, cells.len() as u64);
+    let mut prev_loc = 0;
+    for n in cells {
+        write_varint(buf, zigzag(n - prev_loc));
+        write_varint(buf, tape.symbol_at_n(n));
+        prev_loc = n;
+    }
+}
+
+/// Reads a tape written by `write_tape`.
+fn read_tape(reader: &mut impl Read) -> io::Result<Tape> {
+    let len = read_varint(reader)?;
+    if len > 2 * MAX_LOCATION as u64 + 1 {
+        return Err(invalid_data("tape has more cells than fit on a tape"));
+    }
+
+    let mut tape = Tape::default();
+    let mut loc: i64 = 0;
+    for _ in 0..len {
+        loc = loc.checked_add(unzigzag(read_varint(reader)?)).ok_or_else(|| invalid_data("tape location overflows"))?;
+        tape.try_write(loc, read_varint(reader)?).map_err(|e| invalid_data(e.to_string()))?;
+    }
+
+    Ok(tape)
+}
+
+/// Reads the magic bytes, the format version and the machine fingerprint that begin every recording.
+fn read_header(reader: &mut impl Read) -> io::Result<(u16, u64)> {
+    let mut header = [0; 14];
+    reader.read_exact(&mut header)?;
+    if header[..4] != RECORDING_MAGIC {
+        return Err(invalid_data("not a recording"));
+    }
+    let version = u16::from_le_bytes([header[4], header[5]]);
+    if version == 0 || version > RECORDING_FORMAT_VERSION {
+        return Err(invalid_data(format!("unsupported recording format version {}", version)));
     }
-    else if head_move == 1 {
-        move_right = " -->";
+
+    Ok((version, u64::from_le_bytes(header[6..].try_into().unwrap())))
+}
+
+/// Reads a summary in the format written by `Recording::write_to`.
+fn read_summary_fields(reader: &mut impl Read) -> io::Result<RecordingSummary> {
+    let steps = read_varint(reader)? as usize;
+    let nonblank = read_varint(reader)? as usize;
+    let head_range = unzigzag(read_varint(reader)?)..=unzigzag(read_varint(reader)?);
+    let mut state_counts = BTreeMap::new();
+    for _ in 0..read_varint(reader)? {
+        state_counts.insert(read_varint(reader)?, read_varint(reader)? as usize);
     }
 
-    println!("
-                                                       {}H({}){}
-Tape:      {}         {}         {}         {}         {}         {}         {}         {}         {}         {}         {}
+    Ok(RecordingSummary { steps, nonblank, head_range, state_counts })
+}
+
+/// Returns the number of nonblank cells on `tape`.
+fn nonblank_count(tape: &Tape) -> usize {
+    tape.nonblank_range().map_or(0, |r| r.filter(|&n| tape.symbol_at_n(n) != 0).count())
+}
 
-index: {: ^9} {: ^9} {: ^9} {: ^9} {: ^9} {: ^9} {: ^9} {: ^9} {: ^9} {: ^9} {: ^9}", 
-    move_left, state, move_right, tape.symbol_at_n(head_loc - 5), tape.symbol_at_n(head_loc - 4), tape.symbol_at_n(head_loc - 3), 
-    tape.symbol_at_n(head_loc - 2), tape.symbol_at_n(head_loc - 1), tape.symbol_at_n(head_loc), 
-    tape.symbol_at_n(head_loc + 1), tape.symbol_at_n(head_loc + 2), tape.symbol_at_n(head_loc + 3), 
-    tape.symbol_at_n(head_loc + 4), tape.symbol_at_n(head_loc + 5),
-    head_loc - 5, head_loc - 4, head_loc - 3, head_loc - 2, head_loc - 1, head_loc,
-    head_loc + 1, head_loc + 2, head_loc + 3, head_loc + 4, head_loc + 5
-    );
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
 }
 
 #[cfg(test)]
@@ -75,7 +1928,7 @@ mod tests {
     #[test]
     fn recording_test() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((0, 0), (1, 1, true)),
                 ((1, 0), (0, 1, false)),
                 ((0, 1), (1, 2, true)),
@@ -94,4 +1947,491 @@ mod tests {
     
         record.play_in_console(std::time::Duration::from_micros(1), false);
     }
+
+    #[test]
+    fn test_frames() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 7, false)), ((1, 0), (2, 3, true))]));
+        let record = machine.run_and_record(&mut Tape::default());
+        let frames = record.frames(std::time::Duration::from_secs(1)).collect::<Vec<_>>();
+
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[1].1, Frame { state: 1, head_loc: 0, head_move: Move::Left, window_start: -5, window: vec![0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0] });
+        assert_eq!(frames[4].0, std::time::Duration::from_secs(4));
+        assert_eq!(frames[4].1, Frame { state: 2, head_loc: 0, head_move: Move::Stay, window_start: -5, window: vec![0, 0, 0, 0, 3, 7, 0, 0, 0, 0, 0] });
+        assert!(frames[1].1.to_string().ends_with("<-- H(1)\nTape:      0         0         0         0         0         7         0         0         0         0         0\n\nindex:    -5        -4        -3        -2        -1         0         1         2         3         4         5    "));
+
+        let frames = record.frames(std::time::Duration::ZERO).with_view(ViewPolicy::Region { start: 1, end: 2 }).collect::<Vec<_>>();
+        assert_eq!(frames[1].1, Frame { state: 1, head_loc: 0, head_move: Move::Left, window_start: 1, window: vec![0, 0] });
+        assert_eq!(frames[1].1.to_string(), "\n     <-- H(1) at 0\nTape:      0         0\n\nindex:     1         2    ");
+    }
+
+    #[test]
+    fn test_series() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true)), ((0, 1), (0, 0, true))]));
+        let record = machine.run_with_hook_and_record(&mut Tape::new(vec![0, 1]), HaltSetting::AfterSteps(4), |tape, event| {
+            if event.step_num == 3 {
+                tape.write(-4, 2);
+            }
+        });
+
+        let series = record.series();
+        assert_eq!(series.head_loc, [(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+        assert_eq!(series.nonblank, [(0, 1), (1, 2), (2, 1), (3, 3), (4, 4)]);
+        assert_eq!(series.nonblank.last().unwrap().1, record.summary().nonblank);
+
+        let config = RecordingConfig { sample_rate: 3, ..Default::default() };
+        machine.reset();
+        let sampled = machine.run_and_record_with_config(&mut Tape::new(vec![0, 1]), HaltSetting::AfterSteps(4), &config);
+        assert_eq!(sampled.series().nonblank, [(0, 1), (3, 2), (4, 3)]);
+    }
+
+    #[cfg(feature = "plot")]
+    #[test]
+    fn test_plot_to_svg() {
+        let path = std::env::temp_dir().join(format!("turing_machine-{}-series.svg", std::process::id()));
+        crate::examples::busy_beaver_2().1.series().plot_to_svg(&path).unwrap();
+
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.starts_with("<svg") && svg.contains("nonblank cells"));
+    }
+
+    #[test]
+    fn test_frames_side_by_side() {
+        let mut machine1 = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, false))]));
+        let mut machine2 = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 2, true)), ((1, 0), (2, 1, true))]));
+        let record1 = machine1.run_and_record(&mut Tape::default());
+        let record2 = machine2.run_and_record(&mut Tape::default());
+
+        let view = ViewPolicy::Region { start: 0, end: 1 };
+        let frames = record2.frames_side_by_side(&record1, std::time::Duration::ZERO).with_view(view).collect::<Vec<_>>();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[4].1.window, vec![2, 1]);
+        assert_eq!(frames[4].2, record1.frames(std::time::Duration::ZERO).with_view(view).last().unwrap().1);
+        assert_eq!(
+            frames[1].1.render_beside(&frames[1].2, &Alphabet::default()),
+            "                           |\n         H(1) -->          |      <-- H(1)\nTape:      2         0     | Tape:      1         0\n                           |\nindex:     0         1     | index:     0         1"
+        );
+    }
+
+    #[test]
+    fn test_write_to_and_read_from() {
+        let trans_fn = TransitionFn::new(
+            &[
+                ((0, 0), (1000, 1, true)),
+                ((1000, 0), (0, 1, false)),
+                ((0, 1), (1000, 0, false)),
+                ((1000, 1), (0, 2, true)),
+                ((0, 2), (1000, 1, true)),
+                ((1000, 2), (0, 0, false)),
+            ]
+        );
+        let fault_model = FaultModel { num_symbols: 3, flip_probability: 0.1, misread_probability: 0.1, stuck_cells: vec![-1], seed: Seed(9) };
+
+        let mut machine = TuringMachine::new(trans_fn);
+        let mut tape = Tape::new(vec![0, 2, 0, 0, 1]);
+        tape.write(-300, 1);
+        let record = machine.run_with_faults_and_record(&mut tape, HaltSetting::AfterSteps(500), &fault_model);
+        assert!(!record.faults().is_empty());
+        assert_eq!(record.seed(), Some(Seed(9)));
+
+        let mut bytes = Vec::new();
+        record.write_to(&mut bytes).unwrap();
+        assert_eq!(&bytes[..4], b"TMRC");
+        assert!(bytes.len() < 14 + 20 + record.steps.len() * 4 + record.faults.len() * 10 + record.perturbations.len() * 10);
+        let read = Recording::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, record);
+        assert_eq!(read.seed(), Some(Seed(9)));
+
+        assert_eq!(Recording::read_summary(&mut bytes.as_slice()).unwrap(), *record.summary());
+        assert_eq!(record.summary().nonblank, tape.nonblank_range().unwrap().filter(|&n| tape.symbol_at_n(n) != 0).count());
+
+        // corrupted or truncated input
+        bytes[4] = 99;
+        assert_eq!(Recording::read_from(&mut bytes.as_slice()).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        assert!(Recording::read_from(&mut &b"TMRC"[..]).is_err());
+    }
+
+    #[test]
+    fn test_read_summary_of_version_4() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, false)), ((1, 0), (0, 2, false))]));
+        let record = machine.run_with_halt_setting_and_record(&mut Tape::new(vec![0, 3]), HaltSetting::AfterSteps(5));
+
+        // version 4 is version 5 without the summary after the header
+        let mut bytes = Vec::new();
+        record.write_to(&mut bytes).unwrap();
+        let mut old = bytes[..14].to_vec();
+        old[4] = 4;
+        let mut rest = &bytes[14..];
+        super::read_summary_fields(&mut rest).unwrap();
+        old.extend(rest);
+
+        let summary = Recording::read_summary(&mut old.as_slice()).unwrap();
+        assert_eq!(summary, RecordingSummary { steps: 5, nonblank: 6, head_range: -5..=0, state_counts: [(0, 3), (1, 2)].into() });
+        assert_eq!(Recording::read_from(&mut old.as_slice()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_read_corrupted() {
+        // a cell far beyond `MAX_LOCATION`
+        let mut buf = Vec::new();
+        super::write_varint(&mut buf, 1);
+        super::write_varint(&mut buf, super::zigzag(1 << 40));
+        super::write_varint(&mut buf, 1);
+        assert_eq!(super::read_tape(&mut buf.as_slice()).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        // locations whose sum overflows
+        let mut buf = Vec::new();
+        super::write_varint(&mut buf, 2);
+        for _ in 0..2 {
+            super::write_varint(&mut buf, super::zigzag(i64::MAX));
+            super::write_varint(&mut buf, 0);
+        }
+        assert_eq!(super::read_tape(&mut buf.as_slice()).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 1, true)), ((1, 0), (0, 1, false))]);
+        let mut machine = TuringMachine::new(trans_fn);
+        let recording = machine.run_with_hook_and_record(&mut Tape::new(vec![2, 0, 3]), HaltSetting::AfterSteps(20), |tape, event| {
+            if event.step_num % 5 == 0 {
+                tape.write(-2, 1);
+            }
+        });
+        let mut bytes = Vec::new();
+        recording.write_to(&mut bytes).unwrap();
+
+        // every truncation and every single corrupted byte is either read or rejected, but never panics
+        for len in 0..bytes.len() {
+            assert!(Recording::read_from(&mut &bytes[..len]).is_err());
+        }
+        for i in 0..bytes.len() {
+            for byte in [0x00, 0x01, 0x7f, 0x80, 0xff] {
+                let mut corrupted = bytes.clone();
+                corrupted[i] = byte;
+                if let Err(e) = Recording::read_from(&mut corrupted.as_slice()) {
+                    assert!(matches!(e.kind(), std::io::ErrorKind::InvalidData | std::io::ErrorKind::UnexpectedEof));
+                }
+            }
+        }
+
+        // a huge initial head location, which would only fail once the recording is replayed
+        let mut far = recording.clone();
+        far.init_head_loc = 1 << 40;
+        bytes.clear();
+        far.write_to(&mut bytes).unwrap();
+        assert_eq!(Recording::read_from(&mut bytes.as_slice()).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_step_back() {
+        // fills the tape with ones to the right, stepping back after every second one
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 1, true)), ((1, 0), (0, 1, false))]);
+        let mut machine = TuringMachine::new(trans_fn);
+
+        // flips the cell just written every third step
+        let recording = machine.run_with_hook_and_record(&mut Tape::default(), HaltSetting::AfterSteps(40), |tape, event| {
+            if event.step_num % 3 == 0 {
+                tape.write(event.head_loc, 1 - tape.symbol_at_n(event.head_loc));
+            }
+        });
+        assert_eq!(recording.len(), 40);
+        assert!(!recording.perturbations().is_empty());
+        assert_eq!(recording.overwritten.len(), recording.len());
+
+        let mut snapshot = recording.seek(recording.len()).unwrap();
+        while recording.step_back(&mut snapshot) {
+            assert_eq!(snapshot, recording.seek(snapshot.step_num).unwrap());
+        }
+        assert_eq!(snapshot.step_num, 0);
+        assert!(!recording.step_back(&mut snapshot));
+
+        // version 6 did not store the overwritten symbols, so they are replayed
+        let mut stripped = recording.clone();
+        stripped.overwritten.clear();
+        stripped.overwritten_unknown = true;
+        let mut bytes = Vec::new();
+        stripped.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.pop(), Some(0));
+        bytes[4] = 6;
+        assert_eq!(Recording::read_from(&mut bytes.as_slice()).unwrap(), recording);
+    }
+
+    #[test]
+    fn test_sampled_recording() {
+        // fills the tape with ones to the right, stepping back after every second one
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 1, true)), ((1, 0), (0, 1, false))]);
+        let mut input = Tape::default();
+        input.write(-3, 5);
+
+        let config = RecordingConfig { sample_rate: 3, snapshot_every: 2, detach_input: true, ..Default::default() };
+        let record = TuringMachine::new(trans_fn.clone()).run_and_record_with_config(&mut input.clone(), HaltSetting::AfterSteps(10), &config);
+        let full = TuringMachine::new(trans_fn.clone()).run_with_halt_setting_and_record(&mut input.clone(), HaltSetting::AfterSteps(10));
+        let detached_config = RecordingConfig { detach_input: true, ..Default::default() };
+        let detached = TuringMachine::new(trans_fn.clone()).run_and_record_with_config(&mut input.clone(), HaltSetting::AfterSteps(10), &detached_config);
+        assert_eq!(record.summary(), full.summary());
+        assert_eq!((record.len(), record.is_empty()), (10, false));
+        assert_eq!(record.snapshots().iter().map(|x| x.step_num).collect::<Vec<_>>(), [3, 6, 9, 10]);
+        // the snapshots only show the cells written during the run, like those of a detached recording
+        assert_eq!(record.snapshots()[3], detached.seek(10).unwrap());
+        assert_ne!(record.snapshots()[3], full.seek(10).unwrap());
+
+        let mut bytes = Vec::new();
+        record.write_to(&mut bytes).unwrap();
+        let read = Recording::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, record);
+
+        let frames = read.frames(std::time::Duration::ZERO).map(|(_, frame)| (frame.head_loc, frame.window)).collect::<Vec<_>>();
+        let expected = [0, 3, 6, 9, 10].map(|n| detached.seek(n).unwrap()).map(|x| (x.head_loc, (x.head_loc - 5..=x.head_loc + 5).map(|n| x.tape.symbol_at_n(n)).collect()));
+        assert_eq!(frames, expected);
+
+        // downsampling the full recording gives the same recording as sampling the run
+        let sample = |sample_rate| {
+            let config = RecordingConfig { sample_rate, ..Default::default() };
+            TuringMachine::new(trans_fn.clone()).run_and_record_with_config(&mut input.clone(), HaltSetting::AfterSteps(10), &config)
+        };
+        assert_eq!(full.downsample(3), sample(3));
+        assert_eq!(full.downsample(3).downsample(2), sample(6));
+        assert_eq!(full.downsample(20).snapshots(), [full.seek(10).unwrap()]);
+    }
+
+    #[test]
+    fn test_snapshots_and_seek() {
+        let trans_fn = TransitionFn::new(&[
+            ((0, 0), (1, 1, true)),
+            ((0, 1), (1, 1, false)),
+            ((1, 0), (0, 1, false)),
+            ((1, 1), (0, 0, true)),
+        ]);
+        let config = RecordingConfig { snapshot_every: 7, ..Default::default() };
+
+        let mut machine = TuringMachine::new(trans_fn.clone());
+        let record = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(50), &config);
+        assert_eq!(record.len(), 50);
+        assert_eq!(record.snapshots().iter().map(|x| x.step_num).collect::<Vec<_>>(), vec![7, 14, 21, 28, 35, 42, 49]);
+
+        for step_num in 0..=50 {
+            let mut machine = TuringMachine::new(trans_fn.clone());
+            let mut tape = Tape::default();
+            machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(step_num));
+
+            let snapshot = record.seek(step_num).unwrap();
+            assert_eq!((snapshot.step_num, snapshot.state, snapshot.head_loc), (step_num, machine.state(), machine.head_loc()));
+            assert_eq!(snapshot.tape.diff(&tape), vec![]);
+        }
+
+        let mut bytes = Vec::new();
+        record.write_to(&mut bytes).unwrap();
+        assert_eq!(Recording::read_from(&mut bytes.as_slice()).unwrap(), record);
+    }
+
+    #[test]
+    fn test_steps_iter() {
+        let (_, record) = crate::examples::busy_beaver_3();
+
+        let steps = record.steps_iter().collect::<Vec<Step>>();
+        assert_eq!(steps.len(), record.steps().len());
+        for step in steps {
+            let before = record.seek(step.index).unwrap();
+            let after = record.seek(step.index + 1).unwrap();
+            assert_eq!((step.state, step.head_loc_before), (before.state, before.head_loc));
+            assert_eq!((step.new_state, step.head_loc_after), (after.state, after.head_loc));
+            assert_eq!(after.tape.symbol_at_n(step.head_loc_before), step.write);
+        }
+    }
+
+    #[test]
+    fn test_head_trajectory() {
+        let mut machine = "0 10 [0,0 -> 1,1,L; 1,0 -> 2,1,L; 2,0 -> 3,1,R]".parse::<TuringMachine>().unwrap();
+        let record = machine.run_and_record(&mut Tape::default());
+
+        assert_eq!(record.head_trajectory(), vec![9, 8, 9]);
+        assert_eq!(record.max_excursion(), 2);
+        assert_eq!(Recording::default().max_excursion(), 0);
+    }
+
+    #[test]
+    fn test_first_divergence() {
+        let (_, record) = crate::examples::busy_beaver_4();
+        // takes the same first step as BB-4, then halts
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+        let prefix = machine.run_and_record(&mut Tape::default());
+
+        assert_eq!(prefix.first_divergence(&record), Some(1));
+        assert_eq!(record.first_divergence(&prefix), Some(1));
+        assert_eq!(record.first_divergence(&record.clone()), None);
+
+        let input = Tape::new(vec![1, 0, 1]);
+        let config = RecordingConfig { detach_input: true, ..Default::default() };
+        machine.reset();
+        let detached = machine.run_and_record_with_config(&mut input.clone(), HaltSetting::NoForcedHalt, &config);
+        machine.reset();
+        let attached = machine.run_and_record(&mut input.clone());
+        assert_eq!(detached.first_divergence(&attached), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_first_divergence_detached() {
+        // both detached recordings store a blank input, but their inputs differ
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+        let config = RecordingConfig { detach_input: true, ..Default::default() };
+        let record1 = machine.run_and_record_with_config(&mut Tape::new(vec![0, 1]), HaltSetting::NoForcedHalt, &config);
+        machine.reset();
+        let record2 = machine.run_and_record_with_config(&mut Tape::new(vec![0, 2]), HaltSetting::NoForcedHalt, &config);
+
+        record1.first_divergence(&record2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_first_divergence() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+        let record1 = machine.run_and_record(&mut Tape::default());
+        machine.reset();
+        let record2 = machine.run_and_record(&mut Tape::new(vec![1]));
+
+        record1.first_divergence(&record2);
+    }
+
+    #[test]
+    fn test_detached_input() {
+        let (mut machine, _) = crate::examples::busy_beaver_3();
+        let input = Tape::new(vec![0, 1, 0, 1]);
+
+        machine.reset();
+        let attached = machine.run_and_record_with_config(&mut input.clone(), HaltSetting::AfterSteps(30), &RecordingConfig { snapshot_every: 4, ..Default::default() });
+        machine.reset();
+        let mut detached = machine.run_and_record_with_config(&mut input.clone(), HaltSetting::AfterSteps(30), &RecordingConfig { snapshot_every: 4, detach_input: true, ..Default::default() });
+
+        assert!(detached.is_input_detached());
+        assert_eq!(detached.input_fingerprint(), input.fingerprint());
+        assert_eq!(detached.steps(), attached.steps());
+        assert_eq!(detached.snapshots().len(), attached.snapshots().len());
+        assert_eq!(detached.seek(0).unwrap().tape, Tape::default());
+
+        let mut bytes = Vec::new();
+        detached.write_to(&mut bytes).unwrap();
+        assert_eq!(Recording::read_from(&mut bytes.as_slice()).unwrap(), detached);
+
+        detached.attach_input(input).unwrap();
+        assert_eq!(detached, attached);
+
+        // until the input is attached, stepping back shows blanks for its cells like seeking does
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 1), (0, 2, true))]));
+        let input = Tape::new(vec![1, 1, 1]);
+        let config = RecordingConfig { detach_input: true, ..Default::default() };
+        let mut detached = machine.run_and_record_with_config(&mut input.clone(), HaltSetting::NoForcedHalt, &config);
+        let mut snapshot = detached.seek(3).unwrap();
+        assert!(detached.step_back(&mut snapshot));
+        assert_eq!(snapshot.tape, Tape::new(vec![2, 2]));
+        assert_eq!(Some(snapshot), detached.seek(2));
+
+        let mut bytes = Vec::new();
+        detached.write_to(&mut bytes).unwrap();
+        assert_eq!(bytes.last(), Some(&0));
+
+        detached.attach_input(input).unwrap();
+        let mut snapshot = detached.seek(3).unwrap();
+        assert!(detached.step_back(&mut snapshot));
+        assert_eq!(snapshot.tape, Tape::new(vec![2, 2, 1]));
+    }
+
+    #[test]
+    fn test_console_renderer() {
+        let mut renderer = ConsoleRenderer::default();
+        assert_eq!(renderer.update("ab"), "ab\n");
+        assert_eq!(renderer.update("ab"), "\x1b[1A\n");
+        // a new line is written in full, and a line that disappears is cleared
+        assert_eq!(renderer.update("xb\ncd"), "\x1b[1A\x1b[1Gx\n\x1b[1Gcd\n");
+        assert_eq!(renderer.update("xbyz"), "\x1b[2A\x1b[3Gyz\n\x1b[1G\x1b[K\n");
+    }
+
+    #[test]
+    fn test_spill_to_disk() {
+        let (mut machine, _) = crate::examples::busy_beaver_4();
+        machine.reset();
+        let in_memory = machine.run_and_record(&mut Tape::default());
+        machine.reset();
+        // 3 steps and the symbols they overwrote fit in 120 bytes
+        let config = RecordingConfig { max_memory_bytes: Some(120), ..Default::default() };
+        let spilled = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::NoForcedHalt, &config);
+
+        assert_eq!((spilled.steps.len(), spilled.overwritten.len()), (2, 2));
+        assert_eq!(spilled.spilled_len, 105);
+        let path = spilled.spill.as_ref().unwrap().path.clone();
+        assert!(path.exists());
+
+        assert_eq!(spilled.len(), in_memory.len());
+        assert!(spilled.raw_steps().eq(in_memory.steps().iter().copied()));
+        assert!(spilled.try_raw_steps().map(Result::unwrap).eq(spilled.raw_steps()));
+        assert!(spilled.steps_iter().eq(in_memory.steps_iter()));
+        assert_eq!(spilled.seek(57).map(|s| s.tape), in_memory.seek(57).map(|s| s.tape));
+        assert!(spilled.frames(std::time::Duration::ZERO).eq(in_memory.frames(std::time::Duration::ZERO)));
+        assert_eq!(spilled.first_divergence(&in_memory), None);
+
+        let mut snapshot = spilled.seek(spilled.len()).unwrap();
+        while spilled.step_back(&mut snapshot) {
+            assert_eq!(Some(&snapshot), in_memory.seek(snapshot.step_num).as_ref());
+        }
+        assert_eq!(snapshot.step_num, 0);
+
+        // snapshots count against the budget, so once they take it all every step is spilled on its own
+        machine.reset();
+        let with_snapshots = RecordingConfig { snapshot_every: 10, ..config };
+        let recording = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::NoForcedHalt, &with_snapshots);
+        assert_eq!((recording.snapshots().len(), recording.steps.len()), (10, 1));
+        assert!(recording.raw_steps().eq(in_memory.raw_steps()));
+        assert_eq!(recording.seek(57), in_memory.seek(57));
+
+        // attaching the input stores the symbols the spilled steps overwrote in the spill file
+        machine.reset();
+        let config = RecordingConfig { detach_input: true, ..config };
+        let mut detached = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::NoForcedHalt, &config);
+        detached.attach_input(Tape::default()).unwrap();
+        assert_eq!(detached, in_memory);
+
+        let mut bytes = Vec::new();
+        spilled.write_to(&mut bytes).unwrap();
+        let read = Recording::read_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(read, spilled);
+        assert!(read.spill.is_none());
+
+        // the file lives as long as any clone
+        let clone = spilled.clone();
+        drop(spilled);
+        assert!(path.exists());
+        assert_eq!(clone, in_memory);
+        drop(clone);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_spill_read_error() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+        let config = RecordingConfig { max_memory_bytes: Some(100), ..Default::default() };
+        let recording = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(10), &config);
+        std::fs::File::options().write(true).open(&recording.spill.as_ref().unwrap().path).unwrap().set_len(0).unwrap();
+
+        let steps = recording.try_raw_steps().collect::<Vec<_>>();
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].as_ref().unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_spill_read_error() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+        let config = RecordingConfig { max_memory_bytes: Some(100), ..Default::default() };
+        let recording = machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(10), &config);
+        std::fs::File::options().write(true).open(&recording.spill.as_ref().unwrap().path).unwrap().set_len(0).unwrap();
+
+        recording.raw_steps().for_each(drop);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_spilled_steps() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+        let config = RecordingConfig { max_memory_bytes: Some(100), ..Default::default() };
+        machine.run_and_record_with_config(&mut Tape::default(), HaltSetting::AfterSteps(10), &config).steps();
+    }
 }
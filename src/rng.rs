@@ -0,0 +1,154 @@
+use std::fmt;
+
+/// The seed of every randomized feature of this crate, such as random tapes and machines and fault injection.
+/// Randomness is always drawn from a `SplitMix64` seeded with it, and the seed is stored in the artifacts
+/// it produces, so every experiment can be replayed bit-for-bit.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let seed = Seed(42);
+/// 
+/// assert_eq!(seed.rng().next_u64(), seed.rng().next_u64());
+/// assert_ne!(seed.derive(0), seed.derive(1));
+/// assert_eq!(seed.to_string(), "0x000000000000002a");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Seed(pub u64);
+
+impl Seed {
+    /// Returns a new generator seeded with `self`.
+    #[inline]
+    pub fn rng(self) -> SplitMix64 {
+        SplitMix64::new(self.0)
+    }
+
+    /// Returns an independent seed for the `stream`th sub-task of a task seeded with `self`,
+    /// e.g. one per machine or per thread.
+    #[inline]
+    pub fn derive(self, stream: u64) -> Seed {
+        Seed(SplitMix64::new(self.0 ^ stream.wrapping_mul(0xD1B54A32D192ED03)).next_u64())
+    }
+}
+
+impl From<u64> for Seed {
+    #[inline]
+    fn from(seed: u64) -> Self {
+        Seed(seed)
+    }
+}
+
+impl fmt::Display for Seed {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#018x}", self.0)
+    }
+}
+
+/// A small, fast pseudorandom number generator (SplitMix64) used wherever the crate needs
+/// randomness. Its output depends only on its seed, so runs are reproducible
+/// bit-for-bit across platforms and dependency versions. With the `rand` feature it implements
+/// `rand::RngCore`, so it can be passed to the functions that take an `impl rand::Rng`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    #[inline]
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+
+    #[inline]
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed float in `0.0..1.0`.
+    #[inline]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns `true` with the specified probability.
+    #[inline]
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+
+    /// Returns a uniformly distributed integer in `0..n`.
+    /// 
+    /// # Panics
+    /// Panics if `n` is 0.
+    #[inline]
+    pub fn below(&mut self, n: u64) -> u64 {
+        // rejection sampling avoids modulo bias
+        let zone = u64::MAX - u64::MAX % n;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "rand")]
+impl rand::RngCore for SplitMix64 {
+    #[inline]
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    #[inline]
+    fn next_u64(&mut self) -> u64 {
+        SplitMix64::next_u64(self)
+    }
+
+    #[inline]
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(8) {
+            chunk.copy_from_slice(&SplitMix64::next_u64(self).to_le_bytes()[..chunk.len()]);
+        }
+    }
+
+    #[inline]
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_split_mix_64() {
+        // reference values of SplitMix64 seeded with 0
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xE220A8397B1DCDAF);
+        assert_eq!(rng.next_u64(), 0x6E789E6AA1B965F4);
+
+        let mut rng = SplitMix64::new(5);
+        assert!((0..1000).map(|_| rng.below(3)).all(|x| x < 3));
+        assert!((0..1000).map(|_| rng.next_f64()).all(|x| (0.0..1.0).contains(&x)));
+        assert!(!(0..1000).any(|_| rng.chance(0.0)));
+    }
+
+    #[test]
+    fn test_seed() {
+        assert_eq!(Seed(3).rng(), SplitMix64::new(3));
+        assert_eq!(Seed::from(3).derive(7), Seed(3).derive(7));
+        assert_ne!(Seed(3).derive(7), Seed(4).derive(7));
+    }
+}
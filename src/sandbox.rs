@@ -0,0 +1,212 @@
+use std::any::Any;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+use crate::tape::Tape;
+use crate::transition_fn::{ParseMachineError, TransitionFn};
+use crate::turing_machine::{Limits, Termination, TuringMachine};
+
+/// The number of steps between checks of the time budget, since reading the clock every step is relatively slow.
+const TIME_CHECK_INTERVAL: usize = 1024;
+
+/// The budgets a sandboxed run must stay within.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    /// The longest table source, in bytes, that is parsed at all.
+    pub max_source_len: usize,
+    pub max_steps: usize,
+    /// The longest the run may take, checked every 1024 steps.
+    pub max_duration: Duration,
+    pub limits: Limits,
+}
+
+impl Default for SandboxPolicy {
+    /// Returns a policy of 64 KiB of source, 10,000,000 steps, one second, and 1,000,000 cells or 64 MiB of tape.
+    #[inline]
+    fn default() -> Self {
+        SandboxPolicy {
+            max_source_len: 64 * 1024,
+            max_steps: 10_000_000,
+            max_duration: Duration::from_secs(1),
+            limits: Limits {
+                max_tape_cells: Some(1_000_000),
+                max_memory_bytes: Some(64 * 1024 * 1024),
+            },
+        }
+    }
+}
+
+/// An error that prevented a sandboxed run from finishing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SandboxError {
+    /// The table source was longer than `SandboxPolicy::max_source_len`.
+    SourceTooLong { len: usize, max: usize },
+    /// The table source was not a valid `TransitionFn`.
+    Parse(ParseMachineError),
+    /// The simulation panicked with the given message.
+    Panicked(String),
+}
+
+impl fmt::Display for SandboxError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxError::SourceTooLong { len, max } => write!(f, "table source of {} bytes is longer than the maximum of {}", len, max),
+            SandboxError::Parse(e) => write!(f, "{}", e),
+            SandboxError::Panicked(message) => write!(f, "simulation panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SandboxError {}
+
+/// The result of `run`, with the configuration the machine was left in even if the run failed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SandboxReport {
+    /// How the run ended: `Termination::OutOfSteps`, `OutOfTime` or `OutOfSpace` if it exceeded a budget of the policy,
+    /// unless `TuringMachine::runs_off_forever` proved that it never halts.
+    pub result: Result<Termination, SandboxError>,
+    pub steps: usize,
+    pub elapsed: Duration,
+    pub state: u64,
+    pub head_loc: i64,
+    pub tape: Tape,
+}
+
+/// Parses a transition function from `table_source` in the format of its `Display` implementation
+/// and runs it on `input` from state 0 at location 0, within the step, time and space budgets of `policy`,
+/// catching any panic. Intended for services that simulate user-submitted machines, which should never
+/// be able to crash or exhaust the host.
+/// 
+/// Caught panics are still reported by the panic hook, which prints them to stderr by default.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::sandbox::{self, SandboxError, SandboxPolicy};
+/// 
+/// let policy = SandboxPolicy { max_steps: 1000, ..Default::default() };
+/// 
+/// let report = sandbox::run("[0,0 -> 1,1,R; 1,0 -> 0,1,L; 0,1 -> 2,1,L]", &[], &policy);
+/// assert_eq!((report.result, report.steps), (Ok(Termination::Halted), 3));
+/// assert_eq!(report.tape.symbols(), [1, 1]);
+/// 
+/// // a machine that bounces back and forth never halts, but can't be proven not to
+/// let report = sandbox::run("[0,0 -> 1,0,R; 1,0 -> 0,0,L]", &[], &policy);
+/// assert_eq!(report.result, Ok(Termination::OutOfSteps));
+/// 
+/// let report = sandbox::run("[0,0 -> 1,1]", &[], &policy);
+/// assert!(matches!(report.result, Err(SandboxError::Parse(_))));
+/// ```
+#[inline]
+pub fn run(table_source: &str, input: &[u64], policy: &SandboxPolicy) -> SandboxReport {
+    let mut report = SandboxReport {
+        result: Ok(Termination::Halted),
+        steps: 0,
+        elapsed: Duration::ZERO,
+        state: 0,
+        head_loc: 0,
+        tape: Tape::new(input.to_vec()),
+    };
+    if table_source.len() > policy.max_source_len {
+        report.result = Err(SandboxError::SourceTooLong { len: table_source.len(), max: policy.max_source_len });
+        return report;
+    }
+
+    let start = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let transition_fn = table_source.parse::<TransitionFn>().map_err(SandboxError::Parse)?;
+        let mut machine = TuringMachine::new(transition_fn).with_limits(policy.limits);
+        let result = run_machine(&mut machine, &mut report, policy, start);
+        #[cfg(feature = "metrics")]
+        crate::telemetry::record_run(report.steps, result);
+        report.state = machine.state();
+        report.head_loc = machine.head_loc();
+
+        Ok(result)
+    }));
+    report.elapsed = start.elapsed();
+    report.result = result.unwrap_or_else(|payload| Err(SandboxError::Panicked(panic_message(payload.as_ref()))));
+
+    report
+}
+
+/// Runs `machine` on the tape of `report` within the budgets of `policy`, counting steps in `report`.
+fn run_machine(machine: &mut TuringMachine, report: &mut SandboxReport, policy: &SandboxPolicy, start: Instant) -> Termination {
+    let forced = loop {
+        if report.steps >= policy.max_steps {
+            break Termination::OutOfSteps;
+        }
+        if report.steps.is_multiple_of(TIME_CHECK_INTERVAL) && start.elapsed() >= policy.max_duration {
+            break Termination::OutOfTime;
+        }
+        if machine.next_step_exceeds_limits(&report.tape) {
+            break Termination::OutOfSpace;
+        }
+        if machine.step(&mut report.tape).is_none() {
+            return Termination::Halted;
+        }
+        report.steps += 1;
+    };
+
+    if machine.runs_off_forever(&report.tape) {
+        Termination::NonHalting
+    }
+    else {
+        forced
+    }
+}
+
+/// Extracts the message from the payload of a panic, which is a `&str` or `String` unless `panic_any` was used.
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::sandbox::{self, *};
+
+    #[test]
+    fn test_budgets() {
+        let policy = SandboxPolicy {
+            max_source_len: 100,
+            max_steps: usize::MAX,
+            max_duration: Duration::from_millis(20),
+            limits: Limits { max_tape_cells: Some(100), max_memory_bytes: None },
+        };
+
+        // fills the tape with ones to the right, stepping back after every second one
+        let filler = "[0,0 -> 1,1,R; 0,1 -> 0,1,R; 1,0 -> 0,1,L]";
+        let report = sandbox::run(filler, &[], &policy);
+        assert_eq!(report.result, Ok(Termination::OutOfSpace));
+        assert_eq!(report.tape.symbols(), vec![1; 50]);
+
+        let bouncer = "[0,0 -> 1,0,R; 1,0 -> 0,0,L]";
+        let report = sandbox::run(bouncer, &[], &policy);
+        assert_eq!(report.result, Ok(Termination::OutOfTime));
+        assert!(report.elapsed >= policy.max_duration);
+        assert_eq!((report.state, report.head_loc), ((report.steps % 2) as u64, (report.steps % 2) as i64));
+
+        let runner = "[0,0 -> 0,1,R]";
+        assert_eq!(sandbox::run(runner, &[], &policy).result, Ok(Termination::NonHalting));
+
+        let report = sandbox::run(&" ".repeat(101), &[], &policy);
+        assert_eq!(report.result, Err(SandboxError::SourceTooLong { len: 101, max: 100 }));
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let payload = std::panic::catch_unwind(|| panic!("at step {}", 3)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "at step 3");
+
+        let payload = std::panic::catch_unwind(|| std::panic::panic_any(3)).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "unknown panic payload");
+    }
+}
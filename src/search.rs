@@ -0,0 +1,146 @@
+use crate::analysis::{halting_time, par_enumerate};
+use crate::tape::Tape;
+use crate::transition_fn::TransitionFn;
+use crate::turing_machine::TuringMachine;
+
+/// The busy beaver champions among a class of enumerated machines; see `champions`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Champions {
+    /// The number of machines that halted within the budget.
+    pub halted: usize,
+    /// The number of machines that were still running after the budget.
+    pub undecided: usize,
+    /// The most nonblank cells left on the tape by a halting machine (the sigma function).
+    pub max_ones: usize,
+    /// The halting machines that left `max_ones` nonblank cells, in order of enumeration.
+    pub sigma_champions: Vec<TransitionFn>,
+    /// The most steps taken by a halting machine (the shift function).
+    pub max_steps: usize,
+    /// The halting machines that took `max_steps` steps, in order of enumeration.
+    pub shift_champions: Vec<TransitionFn>,
+}
+
+impl Champions {
+    /// Folds the run of a single machine into `self`.
+    fn update(&mut self, trans_fn: &TransitionFn, result: Option<(usize, usize)>) {
+        let (steps, ones) = match result {
+            Some(result) => result,
+            None => {
+                self.undecided += 1;
+                return;
+            }
+        };
+        self.halted += 1;
+
+        if self.sigma_champions.is_empty() || ones > self.max_ones {
+            self.max_ones = ones;
+            self.sigma_champions.clear();
+        }
+        if ones == self.max_ones {
+            self.sigma_champions.push(trans_fn.clone());
+        }
+
+        if self.shift_champions.is_empty() || steps > self.max_steps {
+            self.max_steps = steps;
+            self.shift_champions.clear();
+        }
+        if steps == self.max_steps {
+            self.shift_champions.push(trans_fn.clone());
+        }
+    }
+
+    /// Merges the champions of a later part of the enumeration into `self`.
+    fn merge(mut self, other: Champions) -> Self {
+        self.halted += other.halted;
+        self.undecided += other.undecided;
+
+        if other.max_ones > self.max_ones || self.sigma_champions.is_empty() {
+            self.max_ones = other.max_ones;
+            self.sigma_champions = other.sigma_champions;
+        }
+        else if other.max_ones == self.max_ones {
+            self.sigma_champions.extend(other.sigma_champions);
+        }
+
+        if other.max_steps > self.max_steps || self.shift_champions.is_empty() {
+            self.max_steps = other.max_steps;
+            self.shift_champions = other.shift_champions;
+        }
+        else if other.max_steps == self.max_steps {
+            self.shift_champions.extend(other.shift_champions);
+        }
+
+        self
+    }
+}
+
+/// Runs every machine enumerated by `TransitionFn::enumerate` on a blank tape for up to `budget` steps
+/// and returns the halting machines that write the most nonblank symbols and that take the most steps,
+/// along with how many machines halted. Machines that are still running after `budget` steps are
+/// counted as undecided, so the champions are only lower bounds unless the budget exceeds the true shift value.
+/// 
+/// Like `analysis::halting_histogram`, the enumeration is split across all available threads.
+/// 
+/// # Panics
+/// Panics if the number of machines exceeds `usize::MAX`.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::search::champions;
+/// 
+/// let champions = champions(2, 2, 100);
+/// 
+/// // the 2-state, 2-symbol busy beaver writes 4 ones in 6 steps
+/// assert_eq!(champions.max_ones, 4);
+/// assert_eq!(champions.max_steps, 6);
+/// assert_eq!(champions.halted + champions.undecided, 12usize.pow(4));
+/// ```
+#[inline]
+pub fn champions(num_states: usize, num_symbols: usize, budget: usize) -> Champions {
+    par_enumerate(
+        num_states,
+        num_symbols,
+        Champions::default,
+        |champions, trans_fn| {
+            let result = run_blank(&trans_fn, budget);
+            champions.update(&trans_fn, result);
+        },
+        Champions::merge,
+    )
+}
+
+/// Returns the number of steps taken and nonblank cells left by `trans_fn` on a blank tape,
+/// if it halts within `budget` steps.
+fn run_blank(trans_fn: &TransitionFn, budget: usize) -> Option<(usize, usize)> {
+    let mut machine = TuringMachine::new(trans_fn.clone());
+    let mut tape = Tape::default();
+
+    let steps = halting_time(&mut machine, &mut tape, budget)?;
+    let ones = tape.raw_symbols().iter().filter(|&&x| x != 0).count();
+
+    Some((steps, ones))
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::search::champions;
+
+    #[test]
+    fn test_champions() {
+        let result = champions(1, 2, 10);
+        assert_eq!((result.max_ones, result.max_steps), (1, 1));
+        assert_eq!(result.halted, 32);
+
+        let result = champions(2, 2, 100);
+        assert!(!result.sigma_champions.is_empty());
+        for trans_fn in &result.shift_champions {
+            let mut machine = TuringMachine::new(trans_fn.clone());
+            let mut tape = Tape::default();
+            machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(6));
+            assert_eq!(machine.step(&mut tape), None);
+        }
+        assert_eq!(result, champions(2, 2, 100));
+    }
+}
@@ -0,0 +1,437 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{self, AtomicBool};
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use crate::machine::Machine;
+use crate::sandbox;
+use crate::tape::Tape;
+use crate::turing_machine::{HaltSetting, RunOutcome, Termination};
+
+/// A unit of work for a `Simulator`: a machine to run on a tape until it halts or is forcibly halted.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Job {
+    pub machine: Machine,
+    pub tape: Tape,
+    pub halt_setting: HaltSetting,
+    pub priority: Priority,
+}
+
+/// How urgently a `Simulator` should run a job. Queued jobs are started in order of priority,
+/// and in order of submission among jobs of the same priority.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    /// Work nobody is waiting on, such as a long sweep.
+    Background,
+    #[default]
+    Normal,
+    /// Work a user is waiting on, such as a quick run requested from a UI.
+    Interactive,
+}
+
+/// The identifier `Simulator::submit` assigns to a job, unique within its simulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JobId(pub u64);
+
+/// The result of a `Job`, sent over the result channel of its `Simulator`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobResult {
+    pub id: JobId,
+    /// The outcome of `TuringMachine::run_with_outcome`, or why there is none.
+    pub outcome: Result<RunOutcome, JobError>,
+    /// The tape after the run.
+    pub tape: Tape,
+}
+
+/// Why a `Job` has no `RunOutcome`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobError {
+    /// The job was cancelled with `Simulator::cancel`.
+    Cancelled,
+    /// The run panicked with the given message.
+    Panicked(String),
+}
+
+impl fmt::Display for JobError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JobError::Cancelled => write!(f, "job was cancelled"),
+            JobError::Panicked(message) => write!(f, "job panicked: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for JobError {}
+
+/// A long-lived service that runs jobs on a pool of threads and sends their results over a channel,
+/// for servers and GUIs that continuously submit simulation work.
+/// 
+/// At most `max_in_flight` jobs are queued or running at once: `submit` blocks until one finishes,
+/// and `try_submit` hands the job back, so that producers faster than the pool are slowed down
+/// instead of queuing without bound. Every job yields exactly one result, and results arrive in order of completion.
+/// Dropping a `Simulator` discards the queued jobs and cancels the running ones, whose results could no longer
+/// be received, and waits for the workers to notice, which takes at most 1024 steps of each running job.
+/// 
+/// Jobs are started in order of their `Priority`, but a running job is never preempted by another:
+/// to make room for an interactive job behind long background jobs, `cancel` them.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::simulator::{Job, Simulator};
+/// 
+/// let simulator = Simulator::new(2, 4);
+/// let ids = TransitionFn::enumerate(2, 2)
+///     .into_iter()
+///     .take(10)
+///     .map(|trans_fn| simulator.submit(Job { machine: trans_fn.into(), halt_setting: HaltSetting::AfterSteps(50), ..Default::default() }))
+///     .collect::<Vec<_>>();
+/// 
+/// let mut results = (0..ids.len()).map(|_| simulator.results().recv().unwrap()).collect::<Vec<_>>();
+/// results.sort_by_key(|result| result.id);
+/// 
+/// assert_eq!(results.iter().map(|result| result.id).collect::<Vec<_>>(), ids);
+/// assert!(results.iter().all(|result| result.outcome.is_ok()));
+/// ```
+#[derive(Debug)]
+pub struct Simulator {
+    shared: Arc<Shared>,
+    results: mpsc::Receiver<JobResult>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Simulator {
+    /// Starts a simulator with `num_threads` worker threads that keeps at most `max_in_flight` jobs queued or running.
+    /// 
+    /// # Panics
+    /// Panics if `num_threads` or `max_in_flight` is 0, or if a thread cannot be spawned.
+    #[inline]
+    pub fn new(num_threads: usize, max_in_flight: usize) -> Self {
+        assert!(num_threads > 0, "a simulator needs at least one thread");
+        assert!(max_in_flight > 0, "a simulator needs room for at least one job");
+
+        let (sender, results) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(Queue {
+                jobs: BinaryHeap::new(),
+                running: HashMap::new(),
+                in_flight: 0,
+                next_id: 0,
+                shutdown: false,
+            }),
+            job_available: Condvar::new(),
+            job_finished: Condvar::new(),
+            max_in_flight,
+            sender,
+        });
+        let workers = (0..num_threads)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                thread::Builder::new()
+                    .name(format!("simulator-{}", i))
+                    .spawn(move || work(&shared))
+                    .expect("failed to spawn a simulator thread")
+            })
+            .collect();
+
+        Simulator { shared, results, workers }
+    }
+
+    /// Queues `job`, first waiting for a job to finish if `max_in_flight` jobs are queued or running,
+    /// and returns the id its result will be sent with.
+    #[inline]
+    pub fn submit(&self, job: Job) -> JobId {
+        let mut queue = self.shared.lock();
+        while queue.in_flight >= self.shared.max_in_flight {
+            queue = self.shared.job_finished.wait(queue).unwrap_or_else(|e| e.into_inner());
+        }
+
+        self.shared.enqueue(queue, job)
+    }
+
+    /// Queues `job` and returns the id its result will be sent with,
+    /// unless `max_in_flight` jobs are queued or running, in which case `job` is handed back.
+    /// 
+    /// # Errors
+    /// Returns `job`, boxed, if the simulator is full.
+    #[inline]
+    pub fn try_submit(&self, job: Job) -> Result<JobId, Box<Job>> {
+        let queue = self.shared.lock();
+        if queue.in_flight >= self.shared.max_in_flight {
+            return Err(Box::new(job));
+        }
+
+        Ok(self.shared.enqueue(queue, job))
+    }
+
+    /// Returns the channel results are sent over. It never disconnects while `self` is alive,
+    /// so only receive as many results as jobs were submitted, or use a timeout.
+    #[inline]
+    pub fn results(&self) -> &mpsc::Receiver<JobResult> {
+        &self.results
+    }
+
+    /// Returns the number of jobs that are queued or running.
+    #[inline]
+    pub fn in_flight(&self) -> usize {
+        self.shared.lock().in_flight
+    }
+
+    /// Cancels the job with the specified id, which then yields a result with `JobError::Cancelled`:
+    /// immediately if it is queued, or within 1024 steps if it is running.
+    /// Returns whether the job was queued or running.
+    #[inline]
+    pub fn cancel(&self, id: JobId) -> bool {
+        let mut queue = self.shared.lock();
+        if let Some(cancelled) = queue.running.get(&id) {
+            cancelled.store(true, atomic::Ordering::Relaxed);
+            return true;
+        }
+
+        let jobs = std::mem::take(&mut queue.jobs).into_vec();
+        let (cancelled, jobs) = jobs.into_iter().partition::<Vec<Queued>, _>(|queued| queued.id == id);
+        queue.jobs = jobs.into();
+        let Some(queued) = cancelled.into_iter().next() else { return false };
+
+        self.shared.finish(queue, JobResult { id, outcome: Err(JobError::Cancelled), tape: queued.job.tape });
+        true
+    }
+}
+
+impl Drop for Simulator {
+    #[inline]
+    fn drop(&mut self) {
+        let mut queue = self.shared.lock();
+        queue.shutdown = true;
+        for cancelled in queue.running.values() {
+            cancelled.store(true, atomic::Ordering::Relaxed);
+        }
+        queue.in_flight -= queue.jobs.len();
+        queue.jobs.clear();
+        drop(queue);
+
+        self.shared.job_available.notify_all();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The state shared between a `Simulator` and its workers.
+#[derive(Debug)]
+struct Shared {
+    queue: Mutex<Queue>,
+    job_available: Condvar,
+    job_finished: Condvar,
+    max_in_flight: usize,
+    sender: mpsc::Sender<JobResult>,
+}
+
+impl Shared {
+    /// Locks the queue, ignoring poisoning since jobs run outside the lock.
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queues `job` under an id, waking a worker.
+    fn enqueue(&self, mut queue: MutexGuard<'_, Queue>, job: Job) -> JobId {
+        let id = JobId(queue.next_id);
+        queue.next_id += 1;
+        queue.jobs.push(Queued { id, job });
+        queue.in_flight += 1;
+        self.job_available.notify_one();
+
+        id
+    }
+
+    /// Sends the result of a job that is no longer queued or running, and makes room for another.
+    fn finish(&self, mut queue: MutexGuard<'_, Queue>, result: JobResult) {
+        // sending under the lock means a result has always arrived by the time its job stops counting as in flight;
+        // the receiver is only gone once the simulator is dropped, when results are no longer wanted
+        let _ = self.sender.send(result);
+        queue.in_flight -= 1;
+        drop(queue);
+        self.job_finished.notify_one();
+    }
+}
+
+#[derive(Debug)]
+struct Queue {
+    jobs: BinaryHeap<Queued>,
+    /// The cancellation flags of the running jobs.
+    running: HashMap<JobId, Arc<AtomicBool>>,
+    /// The number of jobs queued or running.
+    in_flight: usize,
+    next_id: u64,
+    shutdown: bool,
+}
+
+/// A queued job, ordered so that a `BinaryHeap` pops the highest priority first, then the earliest submitted.
+#[derive(Debug)]
+struct Queued {
+    id: JobId,
+    job: Job,
+}
+
+impl Queued {
+    fn key(&self) -> (Priority, Reverse<JobId>) {
+        (self.job.priority, Reverse(self.id))
+    }
+}
+
+impl PartialEq for Queued {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl Eq for Queued {}
+
+impl PartialOrd for Queued {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Queued {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// The loop of a worker thread: runs queued jobs until the simulator shuts down.
+fn work(shared: &Shared) {
+    loop {
+        let mut queue = shared.lock();
+        let Queued { id, job } = loop {
+            if let Some(queued) = queue.jobs.pop() {
+                break queued;
+            }
+            if queue.shutdown {
+                return;
+            }
+            queue = shared.job_available.wait(queue).unwrap_or_else(|e| e.into_inner());
+        };
+        let cancelled = Arc::new(AtomicBool::new(false));
+        queue.running.insert(id, Arc::clone(&cancelled));
+        drop(queue);
+
+        let mut tape = job.tape;
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| {
+            job.machine
+                .turing_machine()
+                .run_with_stop_flag(&mut tape, job.halt_setting, &cancelled)
+        }))
+        .map_err(|payload| JobError::Panicked(sandbox::panic_message(payload.as_ref())))
+        .and_then(|outcome| if outcome.termination == Termination::Stopped { Err(JobError::Cancelled) } else { Ok(outcome) });
+
+        let mut queue = shared.lock();
+        queue.running.remove(&id);
+        shared.finish(queue, JobResult { id, outcome, tape });
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::prelude::*;
+    use crate::simulator::*;
+
+    fn job(trans_fn: TransitionFn, halt_setting: HaltSetting) -> Job {
+        Job { machine: trans_fn.into(), halt_setting, ..Default::default() }
+    }
+
+    #[test]
+    fn test_backpressure() {
+        // never halts, and can't be proven not to
+        let bouncer = TransitionFn::new(&[((0, 0), (1, 0, true)), ((1, 0), (0, 0, false))]);
+        let slow = job(bouncer, HaltSetting::AfterDuration(Duration::from_millis(50)));
+
+        let simulator = Simulator::new(1, 2);
+        let first = simulator.submit(slow.clone());
+        let second = simulator.submit(slow.clone());
+        assert_eq!(simulator.in_flight(), 2);
+        assert!(simulator.try_submit(slow.clone()).is_err());
+
+        // blocks until the first job is done
+        let third = simulator.submit(slow);
+        assert_eq!(simulator.results().try_recv().map(|result| result.id), Ok(first));
+
+        for id in [second, third] {
+            let result = simulator.results().recv().unwrap();
+            assert_eq!(result.id, id);
+            assert_eq!(result.outcome.unwrap().termination, Termination::OutOfTime);
+        }
+        assert_eq!(simulator.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_results() {
+        let (machine, _) = crate::examples::busy_beaver_3();
+        let simulator = Simulator::new(3, 8);
+        let ids = (0..20).map(|_| simulator.submit(job(machine.transition_fn().clone(), HaltSetting::NoForcedHalt))).collect::<Vec<_>>();
+
+        let mut results = (0..ids.len()).map(|_| simulator.results().recv().unwrap()).collect::<Vec<_>>();
+        results.sort_by_key(|result| result.id);
+        for (id, result) in ids.into_iter().zip(results) {
+            assert_eq!(result.id, id);
+            assert_eq!(result.outcome.unwrap().steps, 14);
+            assert_eq!(result.tape.symbols().iter().sum::<u64>(), 6);
+        }
+        assert_eq!(simulator.results().recv_timeout(Duration::from_millis(10)), Err(mpsc::RecvTimeoutError::Timeout));
+    }
+
+    #[test]
+    fn test_priorities_and_cancellation() {
+        // never halts, and can't be proven not to
+        let bouncer = TransitionFn::new(&[((0, 0), (1, 0, true)), ((1, 0), (0, 0, false))]);
+        let quick = job(bouncer.clone(), HaltSetting::AfterSteps(10));
+        let endless = Job { priority: Priority::Background, ..job(bouncer, HaltSetting::NoForcedHalt) };
+
+        let simulator = Simulator::new(1, 8);
+        let sweep = simulator.submit(endless.clone());
+        while !simulator.shared.lock().jobs.is_empty() {
+            thread::yield_now();
+        }
+        let queued_sweep = simulator.submit(endless);
+        let normal = simulator.submit(quick.clone());
+        let interactive = simulator.submit(Job { priority: Priority::Interactive, ..quick });
+
+        assert!(simulator.cancel(queued_sweep));
+        assert!(simulator.cancel(sweep));
+        assert!(!simulator.cancel(JobId(100)));
+
+        let results = (0..4).map(|_| simulator.results().recv().unwrap()).collect::<Vec<_>>();
+        assert_eq!(results.iter().map(|result| result.id).collect::<Vec<_>>(), [queued_sweep, sweep, interactive, normal]);
+        assert_eq!(results[0].outcome, Err(JobError::Cancelled));
+        assert_eq!(results[1].outcome, Err(JobError::Cancelled));
+        assert_eq!(results[2].outcome.as_ref().unwrap().steps, 10);
+
+        assert!(!simulator.cancel(sweep));
+        assert_eq!(simulator.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_drop() {
+        // never halts, and can't be proven not to
+        let bouncer = TransitionFn::new(&[((0, 0), (1, 0, true)), ((1, 0), (0, 0, false))]);
+        let endless = job(bouncer, HaltSetting::NoForcedHalt);
+
+        let simulator = Simulator::new(2, 8);
+        for _ in 0..5 {
+            simulator.submit(endless.clone());
+        }
+        while simulator.shared.lock().running.len() < 2 {
+            thread::yield_now();
+        }
+
+        // returns rather than waiting for the endless jobs
+        drop(simulator);
+    }
+}
@@ -0,0 +1,709 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::transition_fn::{Move, Rule, TransitionFn};
+use crate::turing_machine::{HaltingStates, TuringMachine};
+
+/// A rule as added to a `SmartBuilder`, whose head movement may still be `Move::Stay`.
+type MoveRule = ((u64, u64), (u64, u64, Move));
+
+/// A builder for `TransitionFn`s that refers to states by name and can instantiate
+/// parameterized `Template`s, allocating fresh intermediate states for them.
+/// 
+/// The state named in `new` is the initial state 0; every other state is numbered in order of first mention.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// // appends a 1 to a block of 1s
+/// let mut builder = SmartBuilder::new(2, "start");
+/// builder
+///     .template("start", &Template::ScanUntil { symbol: 0, right: true }, "end")
+///     .rule("end", 0, "done", 1, true);
+/// 
+/// let (trans_fn, warnings) = builder.build();
+/// // `done` has no rules, so it halts implicitly
+/// assert_eq!(warnings, [BuildWarning::ImplicitHalt { state: "done".to_string() }]);
+/// 
+/// let mut machine = TuringMachine::new(trans_fn);
+/// let mut tape = Tape::new(vec![1, 1, 1]);
+/// machine.run(&mut tape);
+/// 
+/// assert_eq!(tape.symbols(), [1, 1, 1, 1]);
+/// assert_eq!(machine.state(), builder.state_id("done").unwrap());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SmartBuilder {
+    num_symbols: u64,
+    names: HashMap<String, u64>,
+    labels: Vec<String>,
+    rules: Vec<MoveRule>,
+    wildcard_rules: Vec<MoveRule>,
+    halting_states: HaltingStates,
+    annotations: HashMap<(u64, u64), String>,
+}
+
+/// A set of symbols that a wildcard rule added by `SmartBuilder::rule_class` reads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SymbolClass {
+    /// Every symbol of the builder's alphabet.
+    Any,
+    /// The listed symbols.
+    AnyOf(Vec<u64>),
+    /// Every symbol of the builder's alphabet except the listed ones.
+    AnyExcept(Vec<u64>),
+}
+
+impl SymbolClass {
+    /// Returns whether `self` contains `symbol`.
+    #[inline]
+    pub fn contains(&self, symbol: u64) -> bool {
+        match self {
+            SymbolClass::Any => true,
+            SymbolClass::AnyOf(symbols) => symbols.contains(&symbol),
+            SymbolClass::AnyExcept(symbols) => !symbols.contains(&symbol),
+        }
+    }
+}
+
+/// What a wildcard rule added by `SmartBuilder::rule_class` writes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteAction {
+    /// Writes the given symbol.
+    Symbol(u64),
+    /// Writes back the symbol that was read.
+    Same,
+}
+
+/// A parameterized sub-machine that `SmartBuilder::template` can instantiate between two states.
+/// Every template is entered in its `from` state with the head on some cell, and ends in its `to` state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Template {
+    /// Moves in the given direction until the head is on a cell holding `symbol`, leaving the tape unchanged.
+    /// The `from` state loops on itself, so it can not be used by any other rule.
+    ScanUntil { symbol: u64, right: bool },
+    /// Moves `cells` cells in the given direction, leaving the tape unchanged.
+    Move { cells: usize, right: bool },
+    /// Writes `symbols` one after another, moving in the given direction after each.
+    Write { symbols: Vec<u64>, right: bool },
+}
+
+/// A likely mistake in a machine, found by `SmartBuilder::build`. States are referred to by their labels.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BuildWarning {
+    /// No sequence of rules leads from the initial state to `state`.
+    UnreachableState { state: String },
+    /// `state` is reachable but has no rules, so the machine halts whenever it enters it.
+    ImplicitHalt { state: String },
+    /// `symbol` is written by some rule, but no rule reads it.
+    UnhandledSymbol { symbol: u64 },
+    /// The rule for `(state, symbol)` leads to a state without rules, but `state` is unreachable.
+    UnreachableHaltingRule { state: String, symbol: u64 },
+    /// Several wildcard rules, or a wildcard rule and an explicit rule, match `(state, symbol)` with different outcomes.
+    /// `outcomes` lists each distinct `(new state, symbol to write, head movement)` in priority order;
+    /// the first is the one that was built.
+    ConflictingRules { state: String, symbol: u64, outcomes: Vec<(String, u64, Move)> },
+}
+
+impl SmartBuilder {
+    /// Constructs a new builder for machines over `num_symbols` symbols, with `start` as the initial state.
+    #[inline]
+    pub fn new(num_symbols: u64, start: &str) -> Self {
+        let mut builder = SmartBuilder {
+            num_symbols,
+            ..Default::default()
+        };
+        builder.state(start);
+
+        builder
+    }
+
+    /// Returns the id of the state with the specified name, if it has been mentioned.
+    #[inline]
+    pub fn state_id(&self, name: &str) -> Option<u64> {
+        self.names.get(name).copied()
+    }
+
+    /// Returns a label for every state of the built machine, indexed by id. Fresh states get a unique generated label
+    /// derived from the state and template that allocated them and their id, e.g. `start.scan#3`,
+    /// and come after the named states mentioned before them; those `build` allocates for `Move::Stay` rules come last.
+    #[inline]
+    pub fn labels(&self) -> Vec<String> {
+        self.compile(&self.expanded_rules()).1
+    }
+
+    /// Declares `name` as an accepting state; see `TuringMachine::decide`.
+    /// 
+    /// # Panics
+    /// Panics if the state has rules; halting states can not have any.
+    #[inline]
+    pub fn accept_state(&mut self, name: &str) -> &mut Self {
+        let id = self.halting_state(name);
+        self.halting_states.accept.insert(id);
+
+        self
+    }
+
+    /// Declares `name` as a rejecting state; see `TuringMachine::decide`.
+    /// 
+    /// # Panics
+    /// Panics if the state has rules; halting states can not have any.
+    #[inline]
+    pub fn reject_state(&mut self, name: &str) -> &mut Self {
+        let id = self.halting_state(name);
+        self.halting_states.reject.insert(id);
+
+        self
+    }
+
+    /// Declares `name` as a halting state that neither accepts nor rejects.
+    /// 
+    /// # Panics
+    /// Panics if the state has rules; halting states can not have any.
+    #[inline]
+    pub fn halt_state(&mut self, name: &str) -> &mut Self {
+        let id = self.halting_state(name);
+        self.halting_states.halt.insert(id);
+
+        self
+    }
+
+    /// Returns the declared halting states.
+    #[inline]
+    pub fn halting_states(&self) -> &HaltingStates {
+        &self.halting_states
+    }
+
+    /// Adds the rule `(state, symbol) -> (new_state, write, head_move)`, where `head_move` is a `Move` or a `bool`
+    /// (`true` for right). When the machine is built, a `Move::Stay` is compiled into a step right followed by a step left
+    /// through a fresh state, which every such rule into `new_state` shares.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut builder = SmartBuilder::new(2, "start");
+    /// builder.rule("start", 0, "done", 1, Move::Stay);
+    /// 
+    /// let mut machine = TuringMachine::new(builder.build().0);
+    /// let mut tape = Tape::default();
+    /// machine.run(&mut tape);
+    /// 
+    /// assert_eq!((tape.symbol_at_n(0), machine.head_loc()), (1, 0));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `state` is a declared halting state.
+    #[inline]
+    pub fn rule(&mut self, state: &str, symbol: u64, new_state: &str, write: u64, head_move: impl Into<Move>) -> &mut Self {
+        self.add_rule(state, symbol, new_state, write, head_move.into(), false);
+
+        self
+    }
+
+    /// Adds a wildcard rule `(state, any symbol in class) -> (new_state, write, head_move)`,
+    /// which is expanded into one rule per symbol of the alphabet in `class` when the machine is built.
+    /// 
+    /// Rules added by `rule` and `template` always take priority over wildcard rules,
+    /// and among wildcard rules the first one added that matches a symbol wins,
+    /// so a wildcard can be used as a default for every symbol not handled explicitly.
+    /// Every key matched by rules with different outcomes is reported as a `BuildWarning::ConflictingRules`.
+    /// A `Move::Stay` is only compiled for the symbols the wildcard wins, so an overridden one leaves no fresh state behind.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // replaces every 1, 2 and 3 by a 1 until a blank is reached
+    /// let mut builder = SmartBuilder::new(4, "start");
+    /// builder
+    ///     .rule("start", 0, "done", 0, true)
+    ///     .rule_class("start", &SymbolClass::AnyOf(vec![1, 2]), "start", WriteAction::Symbol(1), true)
+    ///     .rule_class("start", &SymbolClass::Any, "start", WriteAction::Same, true);
+    /// 
+    /// let mut machine = TuringMachine::new(builder.build().0);
+    /// let mut tape = Tape::new(vec![2, 3, 1, 2]);
+    /// machine.run(&mut tape);
+    /// 
+    /// assert_eq!(tape.symbols(), [1, 3, 1, 1]);
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `state` is a declared halting state.
+    #[inline]
+    pub fn rule_class(&mut self, state: &str, class: &SymbolClass, new_state: &str, write: WriteAction, head_move: impl Into<Move>) -> &mut Self {
+        let head_move = head_move.into();
+        for y in (0..self.num_symbols).filter(|&y| class.contains(y)) {
+            let write = match write {
+                WriteAction::Symbol(symbol) => symbol,
+                WriteAction::Same => y,
+            };
+            self.add_rule(state, y, new_state, write, head_move, true);
+        }
+
+        self
+    }
+
+    /// Attaches `note` to the rule for `(state, symbol)` in the built `TransitionFn`; see `TransitionFn::with_annotation`.
+    /// The rule may be added before or after the annotation, and annotations of keys without a rule are dropped.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut builder = SmartBuilder::new(2, "start");
+    /// builder
+    ///     .rule("start", 0, "done", 1, true)
+    ///     .annotate("start", 0, "mark the first cell");
+    /// 
+    /// assert_eq!(builder.build().0.annotation(0, 0), Some("mark the first cell"));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `note` contains `;`, `]`, or a line break.
+    #[inline]
+    pub fn annotate(&mut self, state: &str, symbol: u64, note: &str) -> &mut Self {
+        if note.contains([';', ']', '\n', '\r']) {
+            panic!("Annotation `{}` contains `;`, `]`, or a line break", note);
+        }
+        let state = self.state(state);
+        self.annotations.insert((state, symbol), note.to_string());
+
+        self
+    }
+
+    /// Instantiates `template` starting in `from` and ending in `to`.
+    /// 
+    /// # Panics
+    /// Panics if `from` is a declared halting state, or if `template` is a `Template::Move` of 0 cells or a `Template::Write` of no symbols,
+    /// since every step moves the head.
+    #[inline]
+    pub fn template(&mut self, from: &str, template: &Template, to: &str) -> &mut Self {
+        let (from_id, to_id) = (self.state(from), self.state(to));
+        self.assert_not_halting(from_id);
+
+        match template {
+            Template::ScanUntil { symbol, right } => {
+                let back = self.fresh_state(from, "scan");
+                let (forward, backward) = (Move::from(*right), Move::from(!right));
+                for y in 0..self.num_symbols {
+                    if y == *symbol {
+                        self.rules.push(((from_id, y), (back, y, backward)));
+                    }
+                    else {
+                        self.rules.push(((from_id, y), (from_id, y, forward)));
+                    }
+                    self.rules.push(((back, y), (to_id, y, forward)));
+                }
+            }
+            Template::Move { cells, right } => {
+                if *cells == 0 {
+                    panic!("A move template must move at least one cell");
+                }
+                self.chain(from_id, to_id, from, "move", &vec![None; *cells], *right);
+            }
+            Template::Write { symbols, right } => {
+                if symbols.is_empty() {
+                    panic!("A write template must write at least one symbol");
+                }
+                let writes = symbols.iter().map(|&s| Some(s)).collect::<Vec<Option<u64>>>();
+                self.chain(from_id, to_id, from, "write", &writes, *right);
+            }
+        }
+
+        self
+    }
+
+    /// Builds the `TransitionFn`, along with warnings about likely mistakes in it,
+    /// sorted by kind and then by state id.
+    /// 
+    /// # Panics
+    /// Panics if two rules share a key; see `TransitionFn::new`.
+    #[inline]
+    pub fn build(&self) -> (TransitionFn, Vec<BuildWarning>) {
+        let (rules, labels) = self.compile(&self.expanded_rules());
+        let trans_fn = self.annotations
+            .iter()
+            .filter(|(key, _)| rules.iter().any(|r| r.0 == **key))
+            .fold(TransitionFn::new(&rules), |trans_fn, (&(state, symbol), note)| trans_fn.with_annotation(state, symbol, note));
+
+        (trans_fn, self.diagnose(&rules, &labels))
+    }
+
+    /// Builds a `TuringMachine` with the declared halting states, along with the warnings returned by `build`.
+    /// 
+    /// # Panics
+    /// Panics if two rules share a key; see `TransitionFn::new`.
+    #[inline]
+    pub fn build_machine(&self) -> (TuringMachine, Vec<BuildWarning>) {
+        let (trans_fn, warnings) = self.build();
+
+        (TuringMachine::new(trans_fn).with_halting_states(self.halting_states.clone()), warnings)
+    }
+
+    /// Returns the explicit rules followed by the wildcard rules whose keys are not already taken.
+    fn expanded_rules(&self) -> Vec<MoveRule> {
+        let mut keys = self.rules.iter().map(|r| r.0).collect::<HashSet<(u64, u64)>>();
+        let mut rules = self.rules.clone();
+        rules.extend(self.wildcard_rules.iter().filter(|r| keys.insert(r.0)));
+
+        rules
+    }
+
+    /// Compiles every `Move::Stay` of the expanded `rules` away through a fresh state per target state,
+    /// returning the compiled rules and the labels of all states, including the fresh ones.
+    fn compile(&self, rules: &[MoveRule]) -> (Vec<Rule>, Vec<String>) {
+        let mut labels = self.labels.clone();
+        let mut detours = Vec::<(u64, u64)>::new();
+        let mut compiled = Vec::with_capacity(rules.len());
+        for &((state, symbol), (new_state, write, head_move)) in rules {
+            let (next, right) = match head_move.is_right() {
+                Some(right) => (new_state, right),
+                None => match detours.iter().find(|d| d.0 == new_state) {
+                    Some(&(_, back)) => (back, true),
+                    None => {
+                        let back = labels.len() as u64;
+                        labels.push(self.fresh_label(&self.labels[new_state as usize], "stay", back));
+                        detours.push((new_state, back));
+                        (back, true)
+                    }
+                },
+            };
+            compiled.push(((state, symbol), (next, write, right)));
+        }
+        for (new_state, back) in detours {
+            compiled.extend((0..self.num_symbols).map(|y| ((back, y), (new_state, y, false))));
+        }
+
+        (compiled, labels)
+    }
+
+    /// Returns the warnings returned by `build` for the compiled `rules` over the states named by `labels`.
+    /// Conflicts are found among the rules as added, before `Move::Stay` is compiled away.
+    fn diagnose(&self, rules: &[Rule], labels: &[String]) -> Vec<BuildWarning> {
+        let num_states = labels.len() as u64;
+        let has_rules = rules.iter().map(|r| r.0.0).collect::<HashSet<u64>>();
+
+        // depth-first search from the initial state
+        let mut reachable = HashSet::from([0]);
+        let mut stack = vec![0];
+        while let Some(state) = stack.pop() {
+            for rule in rules.iter().filter(|r| r.0.0 == state) {
+                if reachable.insert(rule.1.0) {
+                    stack.push(rule.1.0);
+                }
+            }
+        }
+
+        let mut warnings = Vec::default();
+        let label = |state: u64| labels[state as usize].clone();
+
+        for state in (0..num_states).filter(|s| !reachable.contains(s)) {
+            warnings.push(BuildWarning::UnreachableState { state: label(state) });
+        }
+        for state in (0..num_states).filter(|&s| reachable.contains(&s) && !has_rules.contains(&s) && !self.halting_states.contains(s)) {
+            warnings.push(BuildWarning::ImplicitHalt { state: label(state) });
+        }
+
+        let read = rules.iter().map(|r| r.0.1).collect::<HashSet<u64>>();
+        let mut unhandled = rules.iter().map(|r| r.1.1).filter(|s| !read.contains(s)).collect::<Vec<u64>>();
+        unhandled.sort_unstable();
+        unhandled.dedup();
+        warnings.extend(unhandled.into_iter().map(|symbol| BuildWarning::UnhandledSymbol { symbol }));
+
+        let mut halting_rules = rules.iter()
+            .filter(|r| !reachable.contains(&r.0.0) && !has_rules.contains(&r.1.0))
+            .map(|r| r.0)
+            .collect::<Vec<(u64, u64)>>();
+        halting_rules.sort_unstable();
+        warnings.extend(halting_rules.into_iter().map(|(state, symbol)| BuildWarning::UnreachableHaltingRule { state: label(state), symbol }));
+
+        let mut outcomes = HashMap::<(u64, u64), Vec<(u64, u64, Move)>>::new();
+        for rule in self.rules.iter().chain(&self.wildcard_rules) {
+            let key_outcomes = outcomes.entry(rule.0).or_default();
+            if !key_outcomes.contains(&rule.1) {
+                key_outcomes.push(rule.1);
+            }
+        }
+        let mut conflicts = outcomes.into_iter().filter(|x| x.1.len() > 1).collect::<Vec<_>>();
+        conflicts.sort_unstable_by_key(|x| x.0);
+        warnings.extend(conflicts.into_iter().map(|((state, symbol), outcomes)| BuildWarning::ConflictingRules {
+            state: label(state),
+            symbol,
+            outcomes: outcomes.into_iter().map(|(n, w, m)| (label(n), w, m)).collect(),
+        }));
+
+        warnings
+    }
+
+    /// Adds a rule to the explicit or wildcard rules.
+    fn add_rule(&mut self, state: &str, symbol: u64, new_state: &str, write: u64, head_move: Move, wildcard: bool) {
+        let (state_id, new_state_id) = (self.state(state), self.state(new_state));
+        self.assert_not_halting(state_id);
+
+        let rule = ((state_id, symbol), (new_state_id, write, head_move));
+        if wildcard {
+            self.wildcard_rules.push(rule);
+        }
+        else {
+            self.rules.push(rule);
+        }
+    }
+
+    /// Adds a chain of states from `from` to `to`, one step per element of `writes`,
+    /// writing the given symbol or, if `None`, the symbol read.
+    fn chain(&mut self, from: u64, to: u64, from_name: &str, kind: &str, writes: &[Option<u64>], right: bool) {
+        let mut state = from;
+        for (i, write) in writes.iter().enumerate() {
+            let next = if i + 1 == writes.len() { to } else { self.fresh_state(from_name, kind) };
+            for y in 0..self.num_symbols {
+                self.rules.push(((state, y), (next, write.unwrap_or(y), Move::from(right))));
+            }
+            state = next;
+        }
+    }
+
+    /// Returns the id of the named state after checking that it can be declared a halting state.
+    fn halting_state(&mut self, name: &str) -> u64 {
+        let id = self.state(name);
+        if self.rules.iter().chain(&self.wildcard_rules).any(|r| r.0.0 == id) {
+            panic!("Halting state `{}` already has rules", name);
+        }
+
+        id
+    }
+
+    fn assert_not_halting(&self, state: u64) {
+        if self.halting_states.contains(state) {
+            panic!("Halting state `{}` can not have rules", self.labels[state as usize]);
+        }
+    }
+
+    /// Returns the id of the named state, allocating it if it has not been mentioned.
+    fn state(&mut self, name: &str) -> u64 {
+        if let Some(&id) = self.names.get(name) {
+            return id;
+        }
+
+        let id = self.labels.len() as u64;
+        self.names.insert(name.to_string(), id);
+        self.labels.push(name.to_string());
+
+        id
+    }
+
+    /// Allocates a new unnamed state, which can never collide with a named one.
+    fn fresh_state(&mut self, from: &str, kind: &str) -> u64 {
+        let id = self.labels.len() as u64;
+        self.labels.push(self.fresh_label(from, kind, id));
+
+        id
+    }
+
+    /// Returns a label for the fresh state `id`, unique since it contains the id,
+    /// and primed if a state was named like it.
+    fn fresh_label(&self, from: &str, kind: &str, id: u64) -> String {
+        let mut label = format!("{}.{}#{}", from, kind, id);
+        while self.names.contains_key(&label) {
+            label.push('\'');
+        }
+
+        label
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::prelude::*;
+
+    #[test]
+    fn test_template() {
+        // copies the symbol at 0 two cells to the right of the block, then returns to 0
+        let mut builder = SmartBuilder::new(3, "start");
+        builder
+            .rule("start", 2, "seek", 2, true)
+            .template("seek", &Template::ScanUntil { symbol: 0, right: true }, "gap")
+            .template("gap", &Template::Move { cells: 2, right: true }, "write")
+            .template("write", &Template::Write { symbols: vec![2, 2], right: false }, "back")
+            .template("back", &Template::ScanUntil { symbol: 0, right: false }, "home")
+            .template("home", &Template::Move { cells: 3, right: false }, "done");
+
+        assert_eq!(builder.state_id("start"), Some(0));
+        assert_eq!(builder.labels().iter().filter(|l| l.contains('#')).count(), 1 + 1 + 1 + 1 + 2);
+        assert_eq!(builder.labels().iter().collect::<HashSet<_>>().len(), builder.labels().len());
+
+        let mut machine = TuringMachine::new(builder.build().0);
+        let mut tape = Tape::new(vec![2, 1, 1]);
+        machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(100));
+
+        assert_eq!(tape.symbols(), [2, 1, 1, 0, 2, 2]);
+        assert_eq!(machine.state(), builder.state_id("done").unwrap());
+        assert_eq!(machine.head_loc(), 0);
+    }
+
+    #[test]
+    fn test_build() {
+        let mut builder = SmartBuilder::new(3, "a");
+        builder
+            .rule("a", 0, "b", 1, true)
+            .rule("b", 0, "a", 1, false)
+            .rule("b", 1, "halt", 1, true)
+            .rule("c", 0, "d", 2, true)
+            .rule("c", 1, "a", 2, true);
+
+        let (trans_fn, warnings) = builder.build();
+        assert_eq!(trans_fn.state_table().len(), 5);
+        assert_eq!(warnings, [
+            BuildWarning::UnreachableState { state: "c".to_string() },
+            BuildWarning::UnreachableState { state: "d".to_string() },
+            BuildWarning::ImplicitHalt { state: "halt".to_string() },
+            BuildWarning::UnhandledSymbol { symbol: 2 },
+            BuildWarning::UnreachableHaltingRule { state: "c".to_string(), symbol: 0 },
+        ]);
+    }
+
+    #[test]
+    fn test_build_machine() {
+        // decides whether a block of 1s has even length
+        let mut builder = SmartBuilder::new(2, "even");
+        builder
+            .accept_state("accept")
+            .reject_state("reject")
+            .rule("even", 1, "odd", 1, true)
+            .rule("odd", 1, "even", 1, true)
+            .rule("even", 0, "accept", 0, true)
+            .rule("odd", 0, "reject", 0, true);
+
+        let (mut machine, warnings) = builder.build_machine();
+        assert!(warnings.is_empty());
+        assert!(machine.halting_states().accept.contains(&builder.state_id("accept").unwrap()));
+
+        for (len, decision) in [(0, Decision::Accept), (3, Decision::Reject), (4, Decision::Accept)] {
+            machine.reset();
+            assert_eq!(machine.decide(&mut Tape::new(vec![1; len])), decision);
+        }
+
+        machine.reset();
+        assert_eq!(machine.decide(&mut Tape::new(vec![1, 2])), Decision::Halt);
+        machine = machine.with_halt_setting(HaltSetting::AfterSteps(2));
+        machine.reset();
+        assert_eq!(machine.decide(&mut Tape::new(vec![1; 10])), Decision::Undecided);
+    }
+
+    #[test]
+    fn test_rule_class() {
+        let mut builder = SmartBuilder::new(4, "a");
+        builder
+            .rule_class("a", &SymbolClass::AnyExcept(vec![0]), "b", WriteAction::Same, false)
+            .rule("a", 2, "c", 0, true)
+            .rule_class("a", &SymbolClass::Any, "c", WriteAction::Symbol(3), true);
+
+        let (trans_fn, _) = builder.build();
+        let (b, c) = (builder.state_id("b").unwrap(), builder.state_id("c").unwrap());
+        assert_eq!(trans_fn.run(0, 0), Some((c, 3, true)));
+        assert_eq!(trans_fn.run(0, 1), Some((b, 1, false)));
+        assert_eq!(trans_fn.run(0, 2), Some((c, 0, true)));
+        assert_eq!(trans_fn.run(0, 3), Some((b, 3, false)));
+        assert_eq!(trans_fn.state_table().len(), 4);
+
+        // a wildcard stay that every symbol overrides allocates no detour state
+        let mut builder = SmartBuilder::new(2, "a");
+        builder
+            .rule_class("a", &SymbolClass::Any, "b", WriteAction::Same, Move::Stay)
+            .rule("a", 0, "b", 1, true)
+            .rule("a", 1, "b", 1, true)
+            .rule("b", 1, "a", 0, false);
+
+        let (trans_fn, warnings) = builder.build();
+        assert_eq!(builder.labels(), ["a", "b"]);
+        assert_eq!(trans_fn.state_table().len(), 3);
+        assert!(warnings.iter().all(|w| matches!(w, BuildWarning::ConflictingRules { .. })));
+
+        // one that wins some symbols shares a single detour
+        let mut builder = SmartBuilder::new(3, "a");
+        builder
+            .rule_class("a", &SymbolClass::Any, "b", WriteAction::Same, Move::Stay)
+            .rule("a", 0, "b", 1, true);
+        let (trans_fn, warnings) = builder.build();
+        assert_eq!(builder.labels(), ["a", "b", "b.stay#2"]);
+        assert_eq!(trans_fn.run(0, 2), Some((2, 2, true)));
+        assert_eq!(trans_fn.run(2, 0), Some((1, 0, false)));
+        assert!(warnings.iter().all(|w| !matches!(w, BuildWarning::UnreachableState { .. } | BuildWarning::UnreachableHaltingRule { .. })));
+    }
+
+    #[test]
+    fn test_conflicting_rules() {
+        let mut builder = SmartBuilder::new(3, "a");
+        builder
+            .rule("a", 1, "b", 1, true)
+            .rule_class("a", &SymbolClass::AnyOf(vec![1, 2]), "b", WriteAction::Same, true)
+            .rule_class("a", &SymbolClass::Any, "a", WriteAction::Symbol(0), false)
+            .rule_class("b", &SymbolClass::Any, "b", WriteAction::Same, true)
+            .rule_class("b", &SymbolClass::Any, "b", WriteAction::Same, true);
+
+        let conflicts = builder.build().1
+            .into_iter()
+            .filter(|w| matches!(w, BuildWarning::ConflictingRules { .. }))
+            .collect::<Vec<_>>();
+
+        // (a, 0) only has one rule, (a, 1) and (a, 2) have two outcomes, and b's wildcards agree
+        assert_eq!(conflicts, [
+            BuildWarning::ConflictingRules {
+                state: "a".to_string(),
+                symbol: 1,
+                outcomes: vec![("b".to_string(), 1, Move::Right), ("a".to_string(), 0, Move::Left)],
+            },
+            BuildWarning::ConflictingRules {
+                state: "a".to_string(),
+                symbol: 2,
+                outcomes: vec![("b".to_string(), 2, Move::Right), ("a".to_string(), 0, Move::Left)],
+            },
+        ]);
+
+        // outcomes are compared before `Move::Stay` is compiled away
+        let mut builder = SmartBuilder::new(2, "a");
+        builder
+            .rule_class("a", &SymbolClass::Any, "b", WriteAction::Symbol(1), Move::Stay)
+            .rule_class("a", &SymbolClass::Any, "b", WriteAction::Symbol(1), Move::Stay)
+            .rule_class("a", &SymbolClass::AnyOf(vec![1]), "b", WriteAction::Symbol(1), Move::Right);
+
+        let conflicts = builder.build().1
+            .into_iter()
+            .filter(|w| matches!(w, BuildWarning::ConflictingRules { .. }))
+            .collect::<Vec<_>>();
+        assert_eq!(conflicts, [BuildWarning::ConflictingRules {
+            state: "a".to_string(),
+            symbol: 1,
+            outcomes: vec![("b".to_string(), 1, Move::Stay), ("b".to_string(), 1, Move::Right)],
+        }]);
+    }
+
+    #[test]
+    fn test_fresh_labels() {
+        // two templates from the same state, and a state named like a fresh one
+        let mut builder = SmartBuilder::new(2, "a");
+        builder
+            .rule("a", 0, "a.move#3", 0, true)
+            .template("a", &Template::Move { cells: 2, right: true }, "b")
+            .template("a", &Template::Write { symbols: vec![1, 1], right: false }, "c");
+
+        let labels = builder.labels();
+        assert_eq!(labels.iter().collect::<HashSet<_>>().len(), labels.len());
+        assert!(labels.contains(&"a.move#3'".to_string()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_halt_state() {
+        SmartBuilder::new(2, "a").halt_state("b").rule("b", 0, "a", 0, true);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_template() {
+        SmartBuilder::new(2, "a").template("a", &Template::Move { cells: 0, right: true }, "b");
+    }
+}
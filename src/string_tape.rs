@@ -0,0 +1,187 @@
+use std::fmt;
+
+use crate::alphabet::Alphabet;
+use crate::machine::Machine;
+use crate::tape::Tape;
+use crate::turing_machine::{Decision, HaltSetting};
+
+/// An error returned when a string contains a character that does not name a symbol of the alphabet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnknownCharError {
+    pub ch: char,
+    /// The position of `ch` in the string, counted in characters.
+    pub position: usize,
+}
+
+impl fmt::Display for UnknownCharError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "character {:?} at position {} is not in the alphabet", self.ch, self.position)
+    }
+}
+
+impl std::error::Error for UnknownCharError {}
+
+/// A `Tape` read from and rendered as a string, with each character standing for the symbol of an `Alphabet`
+/// it names, such as one from `Alphabet::from_chars`. This spares recognizers and string transducers
+/// the bookkeeping of converting between characters and symbols, while machines still only see numbers.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let mut tape = StringTape::new(Alphabet::from_chars("_ab"), "ab_b").unwrap();
+/// assert_eq!(tape.tape().symbols(), [1, 2, 0, 2]);
+/// 
+/// tape.tape_mut().write(-1, 1);
+/// assert_eq!(tape.to_string(), "aab_b");
+/// 
+/// let e = StringTape::new(Alphabet::from_chars("_ab"), "abc").unwrap_err();
+/// assert_eq!(e, UnknownCharError { ch: 'c', position: 2 });
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StringTape {
+    tape: Tape,
+    alphabet: Alphabet,
+}
+
+impl StringTape {
+    /// Constructs a new `StringTape` holding the symbols named by the characters of `input`, starting at location 0.
+    /// 
+    /// # Errors
+    /// Returns the first character of `input` that does not name a symbol of `alphabet`.
+    #[inline]
+    pub fn new(alphabet: Alphabet, input: &str) -> Result<Self, UnknownCharError> {
+        let mut buf = [0; 4];
+        let symbols = input
+            .chars()
+            .enumerate()
+            .map(|(position, ch)| alphabet.symbol(ch.encode_utf8(&mut buf)).ok_or(UnknownCharError { ch, position }))
+            .collect::<Result<Vec<u64>, _>>()?;
+
+        Ok(StringTape { tape: Tape::new(symbols), alphabet })
+    }
+
+    #[inline]
+    pub fn tape(&self) -> &Tape {
+        &self.tape
+    }
+
+    #[inline]
+    pub fn tape_mut(&mut self) -> &mut Tape {
+        &mut self.tape
+    }
+
+    #[inline]
+    pub fn into_tape(self) -> Tape {
+        self.tape
+    }
+
+    #[inline]
+    pub fn alphabet(&self) -> &Alphabet {
+        &self.alphabet
+    }
+}
+
+impl fmt::Display for StringTape {
+    /// Formats the meaningful symbols of the tape, as returned by `Tape::symbols`, by their names.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.tape.symbols().iter().try_for_each(|&symbol| write!(f, "{}", self.alphabet.name(symbol)))
+    }
+}
+
+impl Machine {
+    /// Runs `self` from its initial configuration on the string `input`, read with the alphabet of `self`
+    /// like `StringTape::new`, and returns whether it accepts. A run that does not halt within `budget` steps does not accept,
+    /// like in `language::equivalent_up_to`; see `run_str` to tell such runs apart from rejections.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // accepts strings of a and b with an even number of a
+    /// let mut builder = SmartBuilder::new(3, "even");
+    /// builder
+    ///     .accept_state("yes")
+    ///     .reject_state("no")
+    ///     .rule("even", 1, "odd", 1, true)
+    ///     .rule("even", 2, "even", 2, true)
+    ///     .rule("even", 0, "yes", 0, true)
+    ///     .rule("odd", 1, "even", 1, true)
+    ///     .rule("odd", 2, "odd", 2, true)
+    ///     .rule("odd", 0, "no", 0, true);
+    /// let mut machine = builder.build_bundle().0;
+    /// machine.alphabet = Alphabet::from_chars("_ab");
+    /// 
+    /// assert_eq!(machine.accepts("abba", 100), Ok(true));
+    /// assert_eq!(machine.accepts("bab", 100), Ok(false));
+    /// assert_eq!(machine.accepts("abba", 3), Ok(false));
+    /// assert!(machine.accepts("abc", 100).is_err());
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns the first character of `input` that does not name a symbol of the alphabet of `self`.
+    #[inline]
+    pub fn accepts(&self, input: &str, budget: usize) -> Result<bool, UnknownCharError> {
+        Ok(self.run_str(input, HaltSetting::AfterSteps(budget))?.0 == Decision::Accept)
+    }
+
+    /// Runs `self` from its initial configuration on the string `input`, read with the alphabet of `self`
+    /// like `StringTape::new`, until it halts or `halt_setting` forcibly halts it.
+    /// Returns its decision, as returned by `TuringMachine::decide`, and the tape it left behind as a string.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // replaces every a with a b
+    /// let mut builder = SmartBuilder::new(3, "scan");
+    /// builder
+    ///     .halt_state("done")
+    ///     .rule("scan", 1, "scan", 2, true)
+    ///     .rule("scan", 2, "scan", 2, true)
+    ///     .rule("scan", 0, "done", 0, true);
+    /// let mut machine = builder.build_bundle().0;
+    /// machine.alphabet = Alphabet::from_chars("_ab");
+    /// 
+    /// assert_eq!(machine.run_str("abab", HaltSetting::NoForcedHalt), Ok((Decision::Halt, "bbbb".to_string())));
+    /// assert_eq!(machine.run_str("abab", HaltSetting::AfterSteps(2)), Ok((Decision::Undecided, "bbab".to_string())));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns the first character of `input` that does not name a symbol of the alphabet of `self`.
+    #[inline]
+    pub fn run_str(&self, input: &str, halt_setting: HaltSetting) -> Result<(Decision, String), UnknownCharError> {
+        let mut tape = StringTape::new(self.alphabet.clone(), input)?;
+        let decision = self.turing_machine().with_halt_setting(halt_setting).decide(tape.tape_mut());
+
+        Ok((decision, tape.to_string()))
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_string_tape() {
+        let alphabet = Alphabet::from_chars("_αβ");
+        let tape = StringTape::new(alphabet.clone(), "β_α").unwrap();
+        assert_eq!(tape.tape().symbols(), [2, 0, 1]);
+        assert_eq!(tape.to_string(), "β_α");
+        assert_eq!(StringTape::new(alphabet.clone(), "").unwrap().to_string(), "");
+        assert_eq!(StringTape::new(alphabet.clone(), "__α__").unwrap().to_string(), "α");
+
+        let e = StringTape::new(alphabet, "αβx").unwrap_err();
+        assert_eq!(e, UnknownCharError { ch: 'x', position: 2 });
+        assert_eq!(e.to_string(), "character 'x' at position 2 is not in the alphabet");
+
+        // symbols without a name are rendered as numbers
+        let mut tape = StringTape::new(Alphabet::from_chars("_a"), "a").unwrap();
+        tape.tape_mut().write(1, 12);
+        assert_eq!(tape.to_string(), "a12");
+        assert_eq!(tape.into_tape().symbols(), [1, 12]);
+    }
+}
@@ -1,8 +1,46 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Index, RangeInclusive};
+use std::sync::Arc;
+
+use crate::alphabet::{self, SymbolMapError};
+use crate::rng::Seed;
+
+/// The largest distance from location 0 at which a symbol can be written to a `Tape`.
+/// Beyond it, every cell is blank and writing a nonblank symbol fails with `TapeError::LocationOutOfRange`,
+/// since the dense layout of a tape would need an absurd allocation to reach it.
+/// A tape thus never takes more than 1 GiB; use `Limits` to bound runs more tightly.
+pub const MAX_LOCATION: i64 = 1 << 26;
+
+/// An error returned by fallible `Tape` operations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TapeError {
+    /// The location is farther than `MAX_LOCATION` from location 0.
+    LocationOutOfRange { location: i64 },
+    /// Room for `capacity` symbols could not be allocated.
+    AllocationFailed { capacity: usize },
+}
+
+impl fmt::Display for TapeError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapeError::LocationOutOfRange { location } => write!(f, "tape location {} is out of range", location),
+            TapeError::AllocationFailed { capacity } => write!(f, "failed to allocate a tape with capacity {}", capacity),
+        }
+    }
+}
+
+impl std::error::Error for TapeError {}
+
 /// A tape containing infinite symbols, all initially blank. 
 /// Can be modified by a turing machine.
+/// 
+/// Tapes are copy-on-write: cloning one is O(1), and the symbols are only copied
+/// when one of the clones is first written to. This keeps recording the input of a run cheap.
 #[derive(Clone, Debug, Default)]
 pub struct Tape {
-    raw_symbols: Vec<u64>,
+    raw_symbols: Arc<Vec<u64>>,
 }
 
 impl Tape {
@@ -22,23 +60,15 @@ impl Tape {
     #[inline]
     pub fn new(input: Vec<u64>) -> Self {
         let mut tape = Tape::default();
-        for s in 0..input.len() {
-            tape.write(s as i64, input[s]);
+        for (s, symbol) in input.into_iter().enumerate() {
+            tape.write(s as i64, symbol);
         }
 
         tape
     }
 
-    /// Constructs a new `Tape` with the given input starting at location 0, counting up.
-    /// The internal vector has at least the specified capacity.
-    /// 
-    /// # Panics
-    /// Panics if capacity is less than double input length minus one.
-    /// ```should_panic
-    /// # use turing_machine::tape::Tape;
-    /// // panics because 6 < (2 * 4 - 1)
-    /// let tape1 = Tape::with_capacity(vec![23, 1, 0, 49], 6);
-    /// ```
+    /// Constructs a new `Tape` with the given input starting at location 0, counting up,
+    /// with room for at least `capacity` symbols, or as many as the input needs if that is more.
     /// 
     /// # Examples
     /// ```
@@ -49,17 +79,116 @@ impl Tape {
     /// 
     /// assert!(tape1.raw_symbols().capacity() >= 20);
     /// assert!(tape2.raw_symbols().capacity() < 20);
+    /// assert_eq!(tape1, tape2);
+    /// 
+    /// // a capacity smaller than the input is simply exceeded
+    /// assert_eq!(Tape::with_capacity(vec![23, 1, 0, 49], 1), tape2);
     /// ```
+    /// 
+    /// # Panics
+    /// Panics if the allocation fails or the input does not fit on a tape; see `try_with_capacity`.
     #[inline]
     pub fn with_capacity(input: Vec<u64>, capacity: usize) -> Self {
-        if capacity < input.len() * 2 - 1 { panic!("Capacity must exceed double input len") };
+        Tape::try_with_capacity(input, capacity).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Equivalent to `with_capacity`, but returns an error instead of panicking.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::{Tape, TapeError};
+    /// 
+    /// assert!(Tape::try_with_capacity(vec![1, 2], 100).unwrap().raw_symbols().capacity() >= 100);
+    /// assert_eq!(Tape::try_with_capacity(vec![], usize::MAX), Err(TapeError::AllocationFailed { capacity: usize::MAX }));
+    /// assert_eq!(
+    ///     Tape::try_with_capacity(vec![1; (1 << 26) + 5], 0),
+    ///     Err(TapeError::LocationOutOfRange { location: (1 << 26) + 1 }),
+    /// );
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `TapeError::AllocationFailed` if room for `capacity` symbols cannot be allocated,
+    /// or the error of `try_write` if the input does not fit on a tape.
+    #[inline]
+    pub fn try_with_capacity(input: Vec<u64>, capacity: usize) -> Result<Self, TapeError> {
+        let mut tape = Tape::default();
+        Arc::make_mut(&mut tape.raw_symbols)
+            .try_reserve(capacity)
+            .map_err(|_| TapeError::AllocationFailed { capacity })?;
+        for (s, symbol) in input.into_iter().enumerate() {
+            tape.try_write(s as i64, symbol)?;
+        }
+
+        Ok(tape)
+    }
+
+    /// Constructs a new `Tape` with `len` symbols starting at location 0, counting up,
+    /// each drawn uniformly from `0..alphabet_size` by a generator seeded with `seed`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let tape = Tape::random_seeded(10, 3, Seed(42));
+    /// 
+    /// assert!(tape.symbols().iter().all(|&s| s < 3));
+    /// assert_eq!(tape, Tape::random_seeded(10, 3, Seed(42)));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `alphabet_size` is 0 while `len` is not.
+    #[inline]
+    pub fn random_seeded(len: usize, alphabet_size: u64, seed: Seed) -> Self {
+        let mut rng = seed.rng();
 
+        Tape::new((0..len).map(|_| rng.below(alphabet_size)).collect())
+    }
+
+    /// Constructs a new `Tape` with `len` symbols starting at location 0, counting up,
+    /// each drawn uniformly from `0..alphabet_size`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let tape = Tape::random(10, 3, &mut StdRng::seed_from_u64(42));
+    /// 
+    /// assert!(tape.symbols().iter().all(|&s| s < 3));
+    /// assert!(tape.nonblank_range().map_or(true, |r| *r.start() >= 0 && *r.end() < 10));
+    /// ```
+    #[cfg(feature = "rand")]
+    #[inline]
+    pub fn random(len: usize, alphabet_size: u64, rng: &mut impl rand::Rng) -> Self {
+        Tape::new((0..len).map(|_| rng.gen_range(0..alphabet_size)).collect())
+    }
+
+    /// Constructs a new `Tape` where each location in `range` independently holds a nonblank symbol
+    /// with probability `density`, drawn uniformly from `1..alphabet_size`. All other locations are blank.
+    /// 
+    /// # Panics
+    /// Panics if `density` is not within `0.0..=1.0`, or if `alphabet_size` is less than 2
+    /// while `density` is nonzero.
+    /// 
+    /// # Examples
+    /// ```
+    /// use rand::{rngs::StdRng, SeedableRng};
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let tape = Tape::random_sparse(-50..50, 0.1, 2, &mut StdRng::seed_from_u64(7));
+    /// 
+    /// assert!(tape.symbol(1).iter().all(|n| (-50..50).contains(n)));
+    /// ```
+    #[cfg(feature = "rand")]
+    #[inline]
+    pub fn random_sparse(range: std::ops::Range<i64>, density: f64, alphabet_size: u64, rng: &mut impl rand::Rng) -> Self {
         let mut tape = Tape::default();
-        tape.raw_symbols.reserve(capacity);
-        for s in 0..input.len() {
-            tape.write(s as i64, input[s]);
+        for n in range {
+            if rng.gen_bool(density) {
+                tape.write(n, rng.gen_range(1..alphabet_size));
+            }
         }
-    
+
         tape
     }
 
@@ -79,7 +208,7 @@ impl Tape {
             .enumerate()
             .map(|x| (idx_to_i64(x.0), *x.1))
             .collect::<Vec<(i64, u64)>>();
-        v1.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        v1.sort_unstable_by_key(|a| a.0);
 
         // i64s are no longer needed
         let mut v2 = Vec::with_capacity(v1.capacity());
@@ -91,16 +220,51 @@ impl Tape {
         no_trailing_or_leading_zeros(&v2)
     }
 
+    /// Returns the range of locations spanning all nonzero symbols in `self`, or `None` if `self` is blank.
+    #[inline]
+    pub fn nonblank_range(&self) -> Option<RangeInclusive<i64>> {
+        let mut locations = self.raw_symbols
+            .iter()
+            .enumerate()
+            .filter(|x| *x.1 != 0)
+            .map(|x| idx_to_i64(x.0));
+
+        let first = locations.next()?;
+        let (min, max) = locations.fold((first, first), |acc, n| (acc.0.min(n), acc.1.max(n)));
+
+        Some(min..=max)
+    }
+
+    /// Returns `self` with every symbol `s` replaced by `f(s)`, e.g. to re-encode a tape for a machine
+    /// built over a different symbol convention. `f` must fix the blank symbol and map distinct symbols
+    /// of `self` to distinct symbols.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let tape = Tape::new(vec![1, 2, 0, 2]);
+    /// 
+    /// assert_eq!(tape.map_symbols(|s| if s == 0 { 0 } else { 3 - s }), Ok(Tape::new(vec![2, 1, 0, 1])));
+    /// assert_eq!(tape.map_symbols(|s| s.min(1)), Err(SymbolMapError::NotInjective { a: 1, b: 2, image: 1 }));
+    /// assert_eq!(tape.map_symbols(|s| s + 1), Err(SymbolMapError::MovesBlank { image: 1 }));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns a `SymbolMapError` if `f` maps the blank symbol to another symbol or is not injective on the symbols of `self`.
+    #[inline]
+    pub fn map_symbols(&self, f: impl Fn(u64) -> u64) -> Result<Tape, SymbolMapError> {
+        let images = alphabet::map_symbols(self.raw_symbols.iter().copied(), f)?;
+
+        Ok(Tape {
+            raw_symbols: Arc::new(self.raw_symbols.iter().map(|s| images[s]).collect()),
+        })
+    }
+
     /// Returns the symbol at location n.
     #[inline]
     pub fn symbol_at_n(&self, n: i64) -> u64 {
-        let idx = i64_to_idx(n);
-        if idx >= self.raw_symbols.len() {
-            0
-        }
-        else {
-            self.raw_symbols[i64_to_idx(n)]
-        }
+        self[n]
     }
 
     /// Returns a vector containing all locations on `self` that have the specified symbol.
@@ -116,15 +280,204 @@ impl Tape {
         vec
     }
 
+    /// Returns how many times each symbol occurs in the region returned by `symbols`,
+    /// i.e. from the leftmost to the rightmost nonzero symbol.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let counts = Tape::new(vec![0, 2, 0, 2, 5]).symbol_counts();
+    /// 
+    /// assert_eq!(counts[&0], 1);
+    /// assert_eq!(counts[&2], 2);
+    /// assert_eq!(counts[&5], 1);
+    /// ```
+    #[inline]
+    pub fn symbol_counts(&self) -> HashMap<u64, usize> {
+        let mut counts = HashMap::new();
+        for symbol in self.symbols() {
+            *counts.entry(symbol).or_insert(0) += 1;
+        }
+
+        counts
+    }
+
+    /// Returns the Shannon entropy, in bits per symbol, of the symbol frequencies given by `symbol_counts`.
+    /// A blank tape has an entropy of 0.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// assert_eq!(Tape::new(vec![1, 1, 1]).entropy(), 0.0);
+    /// assert_eq!(Tape::new(vec![1, 2, 1, 2]).entropy(), 1.0);
+    /// ```
+    #[inline]
+    pub fn entropy(&self) -> f64 {
+        let counts = self.symbol_counts();
+        let total = counts.values().sum::<usize>() as f64;
+
+        counts.values()
+            .map(|&c| c as f64 / total)
+            .map(|p| -p * p.log2())
+            .sum::<f64>()
+            .max(0.0)
+    }
+
+    /// Returns the leftmost location at which `pattern` occurs in `self`; see `matches`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let tape = Tape::new(vec![1, 0, 1, 0, 1]);
+    /// 
+    /// assert_eq!(tape.find(&[0, 1]), Some(-1));
+    /// assert_eq!(tape.find(&[1, 1]), None);
+    /// ```
+    #[inline]
+    pub fn find(&self, pattern: &[u64]) -> Option<i64> {
+        self.matches(pattern).first().copied()
+    }
+
+    /// Returns the locations, in ascending order, at which `pattern` occurs in `self`,
+    /// where a location is that of the first symbol of the pattern. Occurrences may overlap.
+    /// Since a tape is blank almost everywhere, only occurrences that overlap the region
+    /// returned by `symbols` are considered, and an empty pattern never matches.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let tape = Tape::new(vec![1, 0, 1, 0, 1]);
+    /// 
+    /// assert_eq!(tape.matches(&[1, 0, 1]), vec![0, 2]);
+    /// // blanks on either side of the meaningful symbols can be matched too
+    /// assert_eq!(tape.matches(&[0, 0, 1]), vec![-2]);
+    /// ```
+    #[inline]
+    pub fn matches(&self, pattern: &[u64]) -> Vec<i64> {
+        let Some(range) = self.nonblank_range() else { return vec![] };
+        if pattern.is_empty() {
+            return vec![];
+        }
+
+        (range.start() - pattern.len() as i64 + 1..=*range.end())
+            .filter(|&n| pattern.iter().zip(n..).all(|(&p, m)| self.symbol_at_n(m) == p))
+            .collect()
+    }
+
+    /// Returns every location at which `self` and `other` hold different symbols,
+    /// along with the symbol of `self` and the symbol of `other` there, sorted by location.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let tape1 = Tape::new(vec![1, 2, 3]);
+    /// let tape2 = Tape::new(vec![1, 5, 3, 4]);
+    /// 
+    /// assert_eq!(tape1.diff(&tape2), vec![(1, 2, 5), (3, 0, 4)]);
+    /// assert!(tape1.diff(&tape1).is_empty());
+    /// ```
+    #[inline]
+    pub fn diff(&self, other: &Tape) -> Vec<(i64, u64, u64)> {
+        let len = self.raw_symbols.len().max(other.raw_symbols.len());
+        let mut diff = (0..len)
+            .map(|idx| {
+                let n = idx_to_i64(idx);
+                (n, self.symbol_at_n(n), other.symbol_at_n(n))
+            })
+            .filter(|x| x.1 != x.2)
+            .collect::<Vec<(i64, u64, u64)>>();
+        diff.sort_unstable_by_key(|x| x.0);
+
+        diff
+    }
+
+    /// Returns a fingerprint of the contents of `self`: a 64-bit FNV-1a hash of the location of
+    /// its first nonblank cell and the symbols of its nonblank region. Equal tapes have equal fingerprints
+    /// however they are stored, and the value is stable across platforms and versions of this crate.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let mut tape = Tape::new(vec![0, 1, 2]);
+    /// tape.write(-10, 0);
+    /// 
+    /// assert_eq!(tape.fingerprint(), Tape::new(vec![0, 1, 2, 0]).fingerprint());
+    /// assert_ne!(tape.fingerprint(), Tape::new(vec![1, 2]).fingerprint());
+    /// ```
+    #[inline]
+    pub fn fingerprint(&self) -> u64 {
+        let mut hash = 0xcbf29ce484222325u64;
+        if let Some(range) = self.nonblank_range() {
+            let start = *range.start() as u64;
+            for byte in std::iter::once(start).chain(self.symbols()).flat_map(|x| x.to_le_bytes()) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+
+        hash
+    }
+
+    /// Writes the specified symbol into `self` at location n.
+    /// If `self` shares its symbols with a clone, they are copied first.
+    /// 
+    /// # Panics
+    /// Panics if a nonblank symbol is written farther than `MAX_LOCATION` from location 0; see `try_write`.
+    #[inline]
+    pub fn write(&mut self, n: i64, symbol: u64) {
+        if let Err(e) = self.try_write(n, symbol) {
+            panic!("{}", e);
+        }
+    }
+
     /// Writes the specified symbol into `self` at location n.
+    /// Writing a blank never allocates, so it always succeeds.
+    /// Growing `self` to reach location n reserves the memory fallibly, so a host that is out of memory is reported as an error.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::{Tape, TapeError};
+    /// 
+    /// let mut tape = Tape::default();
+    /// 
+    /// assert_eq!(tape.try_write(-5, 1), Ok(()));
+    /// assert_eq!(tape.try_write(i64::MAX, 1), Err(TapeError::LocationOutOfRange { location: i64::MAX }));
+    /// assert_eq!(tape.try_write(i64::MIN, 0), Ok(()));
+    /// assert_eq!(tape.symbol_at_n(i64::MIN), 0);
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `TapeError::LocationOutOfRange` if a nonblank symbol is written farther than `MAX_LOCATION` from location 0,
+    /// or `TapeError::AllocationFailed` if `self` cannot grow to reach it.
     #[inline]
-    pub(crate) fn write(&mut self, n: i64, symbol: u64) {
-        let idx = i64_to_idx(n);
+    pub fn try_write(&mut self, n: i64, symbol: u64) -> Result<(), TapeError> {
+        let idx = match checked_i64_to_idx(n) {
+            Some(idx) => idx,
+            None if symbol == 0 => return Ok(()),
+            None => return Err(TapeError::LocationOutOfRange { location: n }),
+        };
+
         if idx >= self.raw_symbols.len() {
-            self.raw_symbols.resize(idx + 1, 0);
+            if symbol == 0 {
+                return Ok(());
+            }
+            let raw_symbols = Arc::make_mut(&mut self.raw_symbols);
+            raw_symbols
+                .try_reserve(idx + 1 - raw_symbols.len())
+                .map_err(|_| TapeError::AllocationFailed { capacity: idx + 1 })?;
+            raw_symbols.resize(idx + 1, 0);
+        }
+        if self.raw_symbols[idx] != symbol {
+            Arc::make_mut(&mut self.raw_symbols)[idx] = symbol;
         }
 
-        self.raw_symbols[idx] = symbol;
+        Ok(())
     }
 }
 
@@ -135,10 +488,46 @@ impl PartialEq for Tape {
     }
 }
 
+impl Index<i64> for Tape {
+    type Output = u64;
+
+    /// Returns a reference to the symbol at the specified location; see `symbol_at_n`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::tape::Tape;
+    /// 
+    /// let tape = Tape::new(vec![4, 5]);
+    /// 
+    /// assert_eq!(tape[1], 5);
+    /// assert_eq!(tape[-100], 0);
+    /// ```
+    #[inline]
+    fn index(&self, n: i64) -> &u64 {
+        checked_i64_to_idx(n).and_then(|idx| self.raw_symbols.get(idx)).unwrap_or(&BLANK)
+    }
+}
+
+static BLANK: u64 = 0;
+
 /// Converts an i64 location to an internal vector index.
+/// 
+/// # Panics
+/// Panics if the location is farther than `MAX_LOCATION` from location 0.
+#[cfg(test)]
 #[inline]
 pub(super) fn i64_to_idx(int: i64) -> usize {
-    ((4 * int.abs() + int.signum() - 1) / 2) as usize
+    checked_i64_to_idx(int).unwrap_or_else(|| panic!("{}", TapeError::LocationOutOfRange { location: int }))
+}
+
+/// Converts an i64 location to an internal vector index, or returns `None` if it is farther than `MAX_LOCATION` from location 0.
+#[inline]
+pub(super) fn checked_i64_to_idx(int: i64) -> Option<usize> {
+    if int.unsigned_abs() > MAX_LOCATION as u64 {
+        return None;
+    }
+
+    usize::try_from((4 * int.abs() + int.signum() - 1) / 2).ok()
 }
 
 /// Converts an internal vector index to an i64 location.
@@ -149,7 +538,7 @@ pub(super) fn idx_to_i64(idx: usize) -> i64 {
 
 /// Returns a modified vector containing no trailing or leading zeros
 #[inline]
-pub(super) fn no_trailing_or_leading_zeros(vec: &Vec<u64>) -> Vec<u64> {
+pub(super) fn no_trailing_or_leading_zeros(vec: &[u64]) -> Vec<u64> {
     if vec.iter().find(|&x| *x != 0).is_none() {
         return vec![];
     }
@@ -160,17 +549,37 @@ pub(super) fn no_trailing_or_leading_zeros(vec: &Vec<u64>) -> Vec<u64> {
 #[allow(unused_imports)]
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use crate::prelude::*;
 
     #[test]
     fn test_new() {
-        assert_eq!(Tape { raw_symbols: vec![2, 0, 3, 0, 5] }, Tape::new(vec![2, 3, 5]));
+        assert_eq!(Tape { raw_symbols: Arc::new(vec![2, 0, 3, 0, 5]) }, Tape::new(vec![2, 3, 5]));
     }
 
     #[test]
-    #[should_panic]
     fn test_with_capacity() {
-        Tape::with_capacity(vec![0, 1], 1);
+        assert_eq!(Tape::with_capacity(vec![0, 1], 1), Tape::new(vec![0, 1]));
+        assert_eq!(Tape::with_capacity(vec![], 0), Tape::default());
+        assert!(Tape::with_capacity(vec![3], 50).raw_symbols().capacity() >= 50);
+        assert!(Tape::try_with_capacity(vec![3], usize::MAX / 2).is_err());
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_random() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let tape = Tape::random(100, 4, &mut StdRng::seed_from_u64(1));
+        assert_eq!(tape, Tape::random(100, 4, &mut StdRng::seed_from_u64(1)));
+        assert!((0..4).all(|s| !tape.symbol(s).is_empty()));
+        assert!(tape.symbol(4).is_empty());
+
+        let sparse = Tape::random_sparse(-1000..1000, 0.25, 3, &mut StdRng::seed_from_u64(2));
+        let nonblank = sparse.symbol(1).len() + sparse.symbol(2).len();
+        assert!(nonblank > 400 && nonblank < 600);
+        assert_eq!(Tape::random_sparse(-10..10, 0.0, 1, &mut StdRng::seed_from_u64(3)), Tape::default());
     }
 
     #[test]
@@ -182,6 +591,16 @@ mod tests {
         assert_eq!(tape.symbols(), vec![946, 0, 0, 0, 0, 0, 0, 3, 34343, 1, 0, 25]);
     }
 
+    #[test]
+    fn test_nonblank_range() {
+        let mut tape = Tape::new(vec![0, 0, 7, 0, 1, 0]);
+        assert_eq!(tape.nonblank_range(), Some(2..=4));
+
+        tape.write(-3, 5);
+        assert_eq!(tape.nonblank_range(), Some(-3..=4));
+        assert_eq!(Tape::new(vec![0, 0]).nonblank_range(), None);
+    }
+
     #[test]
     fn test_symbol_at_n() {
         let mut tape = Tape::new(vec![2, 342,  6, 91]);
@@ -205,6 +624,55 @@ mod tests {
         assert_eq!(tape.symbol(2), vec![-3, -2, 1, 2, 4, 6]);
     }
 
+    #[test]
+    fn test_symbol_counts_and_entropy() {
+        let mut tape = Tape::new(vec![3, 0, 3, 1]);
+        tape.write(-2, 1);
+
+        assert_eq!(tape.symbol_counts(), [(0, 2), (1, 2), (3, 2)].into_iter().collect());
+        assert!((tape.entropy() - 3f64.log2()).abs() < 1e-12);
+        assert!(Tape::default().symbol_counts().is_empty());
+        assert_eq!(Tape::default().entropy(), 0.0);
+    }
+
+    #[test]
+    fn test_index() {
+        let mut tape = Tape::new(vec![8, 0, 6]);
+        tape.write(-4, 2);
+
+        assert_eq!(tape[0], 8);
+        assert_eq!(tape[2], 6);
+        assert_eq!(tape[-4], 2);
+        assert_eq!(tape[-3], 0);
+        assert_eq!(tape[1000], 0);
+    }
+
+    #[test]
+    fn test_matches() {
+        let mut tape = Tape::new(vec![1, 0, 1, 1, 0, 1]);
+        tape.write(-3, 1);
+        tape.write(-1, 1);
+
+        assert_eq!(tape.matches(&[1, 0, 1]), vec![-3, 0, 3]);
+        assert_eq!(tape.find(&[1, 0, 1]), Some(-3));
+        assert_eq!(tape.matches(&[1, 1]), vec![-1, 2]);
+        assert_eq!(tape.matches(&[0]), vec![-2, 1, 4]);
+        assert_eq!(tape.matches(&[1, 0, 0]), vec![5]);
+        assert!(tape.matches(&[]).is_empty());
+        assert!(Tape::default().matches(&[0]).is_empty());
+        assert_eq!(tape.find(&[2]), None);
+    }
+
+    #[test]
+    fn test_diff() {
+        let mut tape1 = Tape::new(vec![0, 4, 4]);
+        tape1.write(-3, 9);
+        let tape2 = Tape::new(vec![0, 4, 0, 0, 0, 0, 1]);
+
+        assert_eq!(tape1.diff(&tape2), vec![(-3, 9, 0), (2, 4, 0), (6, 0, 1)]);
+        assert_eq!(tape2.diff(&tape1), vec![(-3, 0, 9), (2, 0, 4), (6, 1, 0)]);
+    }
+
     #[test]
     fn test_write() {
         let mut tape = Tape::new(vec![0, 22, 3]);
@@ -219,6 +687,35 @@ mod tests {
         assert_eq!(tape.symbol_at_n(32193824), 0);
     }
 
+    #[test]
+    fn test_copy_on_write() {
+        let mut tape = Tape::new(vec![1, 2, 3]);
+        let input = tape.clone();
+        assert!(Arc::ptr_eq(&tape.raw_symbols, &input.raw_symbols));
+
+        // writing the symbol already there does not copy
+        tape.write(1, 2);
+        assert!(Arc::ptr_eq(&tape.raw_symbols, &input.raw_symbols));
+
+        tape.write(1, 5);
+        assert!(!Arc::ptr_eq(&tape.raw_symbols, &input.raw_symbols));
+        assert_eq!((input.symbol_at_n(1), tape.symbol_at_n(1)), (2, 5));
+    }
+
+    #[test]
+    fn test_extreme_locations() {
+        let mut tape = Tape::new(vec![1]);
+        for n in [i64::MAX, i64::MIN, -MAX_LOCATION - 1, MAX_LOCATION + 1] {
+            assert_eq!(tape.symbol_at_n(n), 0);
+            assert_eq!(tape[n], 0);
+            assert_eq!(tape.try_write(n, 2), Err(TapeError::LocationOutOfRange { location: n }));
+        }
+
+        // far blank writes do not allocate
+        tape.write(MAX_LOCATION + 1, 0);
+        assert_eq!(tape.raw_symbols().len(), 1);
+    }
+
     #[test]
     fn test_i64_to_idx() {
         assert_eq!(i64_to_idx(-2), 3);
@@ -0,0 +1,142 @@
+use metrics::{counter, describe_counter, Unit};
+
+use crate::turing_machine::Termination;
+
+/// The total number of steps taken by runs that finished or were stopped.
+pub const STEPS: &str = "turing_machine_steps_total";
+/// The total number of runs that finished, however they ended, except for those that were stopped.
+pub const MACHINES_COMPLETED: &str = "turing_machine_machines_completed_total";
+/// The total number of runs in which the machine halted on its own.
+pub const HALTS: &str = "turing_machine_halts_total";
+/// The total number of runs that were forcibly halted, labeled with a `reason` that is the `Termination::name` of the run.
+pub const FORCED_HALTS: &str = "turing_machine_forced_halts_total";
+/// The total number of runs stopped before they finished, such as by a stop flag, a cancelled `Simulator` job,
+/// or `TuringMachine::run_until_pattern` finding its pattern.
+pub const STOPPED: &str = "turing_machine_stopped_total";
+
+/// Describes the counters to the installed recorder, so that exporters such as Prometheus can show their help text.
+/// 
+/// The counters are updated through the `metrics` facade by the runs of `TuringMachine` that do not record, such as `run`,
+/// `run_with_halt_setting`, `run_with_outcome` and therefore every job of a `Simulator`, by `EnumerationBuilder::simulate`
+/// and therefore `TuringMachine::chaitin_approx`, by `ExperimentState` and `experiment::run`, and by `sandbox::run`.
+/// They are only recorded once a recorder has been installed, e.g. with `metrics::set_global_recorder`.
+#[inline]
+pub fn describe() {
+    describe_counter!(STEPS, Unit::Count, "steps taken by finished runs");
+    describe_counter!(MACHINES_COMPLETED, Unit::Count, "runs that finished");
+    describe_counter!(HALTS, Unit::Count, "runs in which the machine halted on its own");
+    describe_counter!(FORCED_HALTS, Unit::Count, "runs that were forcibly halted, by reason");
+    describe_counter!(STOPPED, Unit::Count, "runs stopped before they finished");
+}
+
+/// Counts a run that took `steps` steps and ended with `termination`.
+#[inline]
+pub(crate) fn record_run(steps: usize, termination: Termination) {
+    counter!(STEPS).increment(steps as u64);
+    if termination == Termination::Stopped {
+        counter!(STOPPED).increment(1);
+        return;
+    }
+
+    counter!(MACHINES_COMPLETED).increment(1);
+    match termination {
+        Termination::Halted => counter!(HALTS).increment(1),
+        forced => counter!(FORCED_HALTS, "reason" => forced.name()).increment(1),
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use metrics::{Counter, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit};
+
+    use crate::prelude::*;
+    use crate::telemetry::*;
+
+    /// A recorder that keeps counters by name and labels, e.g. `turing_machine_forced_halts_total{reason=out_of_steps}`.
+    #[derive(Default)]
+    struct CountingRecorder {
+        counters: Mutex<HashMap<String, Arc<AtomicU64>>>,
+    }
+
+    impl CountingRecorder {
+        fn get(&self, key: &str) -> u64 {
+            self.counters.lock().unwrap().get(key).map_or(0, |counter| counter.load(Ordering::Relaxed))
+        }
+    }
+
+    impl Recorder for CountingRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let labels = key.labels().map(|label| format!("{}={}", label.key(), label.value())).collect::<Vec<String>>();
+            let name = if labels.is_empty() {
+                key.name().to_string()
+            }
+            else {
+                format!("{}{{{}}}", key.name(), labels.join(","))
+            };
+            Counter::from_arc(Arc::clone(self.counters.lock().unwrap().entry(name).or_default()))
+        }
+
+        fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::noop()
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn test_record_run() {
+        let recorder = CountingRecorder::default();
+        metrics::with_local_recorder(&recorder, || {
+            describe();
+            let (machine, _) = crate::examples::busy_beaver_3();
+            TuringMachine::new(machine.transition_fn().clone()).run_with_outcome(&mut Tape::default());
+
+            let bouncer = "[0,0 -> 1,0,R; 1,0 -> 0,0,L]";
+            let policy = crate::sandbox::SandboxPolicy { max_steps: 100, ..Default::default() };
+            crate::sandbox::run(bouncer, &[], &policy);
+            crate::sandbox::run("not a machine", &[], &policy);
+        });
+
+        assert_eq!(recorder.get(STEPS), 114);
+        assert_eq!(recorder.get(MACHINES_COMPLETED), 2);
+        assert_eq!(recorder.get(HALTS), 1);
+        assert_eq!(recorder.get("turing_machine_forced_halts_total{reason=out_of_steps}"), 1);
+        assert_eq!(recorder.get(STOPPED), 0);
+    }
+
+    #[test]
+    fn test_record_other_runs() {
+        let recorder = CountingRecorder::default();
+        let (outcomes, report) = metrics::with_local_recorder(&recorder, || {
+            let (machine, _) = crate::examples::busy_beaver_3();
+            TuringMachine::new(machine.transition_fn().clone()).run(&mut Tape::default());
+
+            // a stopped run is neither completed nor forcibly halted
+            let stop = std::sync::atomic::AtomicBool::new(true);
+            TuringMachine::new(machine.transition_fn().clone()).run_with_stop_flag(&mut Tape::default(), HaltSetting::NoForcedHalt, &stop);
+
+            let outcomes = crate::enumeration::EnumerationBuilder::new(1, 2).simulate(HaltSetting::AfterSteps(10)).collect::<Vec<_>>();
+            let report = TuringMachine::chaitin_approx(1, 2, HaltSetting::AfterSteps(10));
+            (outcomes, report)
+        });
+
+        let simulated_steps = outcomes.iter().map(|(_, outcome)| outcome.steps as u64).sum::<u64>();
+        assert_eq!(recorder.get(STEPS), 14 + 2 * simulated_steps);
+        assert_eq!(recorder.get(STOPPED), 1);
+        assert_eq!(recorder.get(MACHINES_COMPLETED), 1 + 2 * outcomes.len() as u64);
+        assert_eq!(report.total, outcomes.len());
+        let halted = outcomes.iter().filter(|(_, outcome)| outcome.termination == Termination::Halted).count() as u64;
+        assert_eq!(recorder.get(HALTS), 1 + 2 * halted);
+    }
+}
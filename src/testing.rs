@@ -0,0 +1,246 @@
+use crate::recording::Recording;
+use crate::tape::Tape;
+use crate::turing_machine::{HaltSetting, TuringMachine};
+
+/// A description of how a run of a `TuringMachine` is expected to end.
+/// Every field left as `None` is not checked.
+/// The `halt_setting` is used to bound the run so that a faulty machine cannot hang a test;
+/// by default, it is `DEFAULT_STEP_BUDGET` steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpectedOutcome {
+    pub tape: Option<Tape>,
+    pub steps: Option<usize>,
+    pub state: Option<u64>,
+    pub halt_setting: HaltSetting,
+}
+
+/// The number of steps after which the runs of a default `ExpectedOutcome` and of a `TuringMachine`
+/// without an explicit `HaltSetting` are forcibly halted.
+pub const DEFAULT_STEP_BUDGET: usize = 1_000_000;
+
+impl Default for ExpectedOutcome {
+    #[inline]
+    fn default() -> Self {
+        ExpectedOutcome {
+            tape: None,
+            steps: None,
+            state: None,
+            halt_setting: HaltSetting::AfterSteps(DEFAULT_STEP_BUDGET),
+        }
+    }
+}
+
+/// Runs a copy of `machine` on a copy of `input` and asserts that the run ends as described by `expected`.
+/// 
+/// # Panics
+/// Panics with a message listing every mismatch, including a location-by-location
+/// diff of the tapes, if the run does not match `expected`.
+/// ```should_panic
+/// use turing_machine::prelude::*;
+/// 
+/// let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+/// let expected = ExpectedOutcome { state: Some(0), ..Default::default() };
+/// 
+/// // panics because the machine ends in state 1
+/// assert_machine_behavior(&machine, &Tape::default(), &expected);
+/// ```
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 2, true))]));
+/// let expected = ExpectedOutcome {
+///     tape: Some(Tape::new(vec![1, 2])),
+///     steps: Some(2),
+///     state: Some(2),
+///     halt_setting: HaltSetting::AfterSteps(100),
+/// };
+/// 
+/// assert_machine_behavior(&machine, &Tape::default(), &expected);
+/// ```
+#[inline]
+pub fn assert_machine_behavior(machine: &TuringMachine, input: &Tape, expected: &ExpectedOutcome) {
+    let mut machine = machine.clone();
+    let mut tape = input.clone();
+    let recording = machine.run_with_halt_setting_and_record(&mut tape, expected.halt_setting);
+
+    let mismatches = mismatches(&machine, &tape, &recording, expected);
+    if !mismatches.is_empty() {
+        panic!("machine behavior did not match the expected outcome:\n{}", mismatches.join("\n"));
+    }
+}
+
+/// Searches for a smaller input on which `machine` still fails `property`, starting from `failing`,
+/// and returns the smallest one found. Like the shrinking of property-based testing, candidates are tried greedily
+/// until none of them fails: first with runs of cells removed, then with single cells blanked,
+/// then with single cells replaced by smaller symbols. The result is therefore locally minimal,
+/// i.e. no single such change to it makes `property` hold, but not necessarily the smallest failing input overall.
+/// 
+/// `property` is called with the input and the tape after a copy of `machine` has run on it with `run`,
+/// so `machine` should have a `HaltSetting` if it can fail to halt, and returns whether the run was correct.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// 
+/// // meant to append a 1 to a block of 1s, but forgets to when the block contains a 2
+/// let machine = TuringMachine::new(TransitionFn::new(&[((0, 1), (0, 1, true)), ((0, 0), (1, 1, true))]))
+///     .with_halt_setting(HaltSetting::AfterSteps(1000));
+/// let property = |input: &Tape, output: &Tape| output.symbol_counts().get(&1) == Some(&(input.symbol_counts().get(&1).unwrap_or(&0) + 1));
+/// 
+/// let failing = Tape::new(vec![1, 1, 1, 1, 2, 1, 1, 0, 0, 1, 1]);
+/// let shrunk = shrink_input(&machine, &failing, property);
+/// 
+/// assert_eq!(shrunk, Tape::new(vec![2]));
+/// ```
+/// 
+/// # Panics
+/// Panics if `property` holds for `failing`.
+#[inline]
+pub fn shrink_input(machine: &TuringMachine, failing: &Tape, property: impl Fn(&Tape, &Tape) -> bool) -> Tape {
+    let fails = |input: &Tape| {
+        let mut output = input.clone();
+        machine.clone().run(&mut output);
+        !property(input, &output)
+    };
+    assert!(fails(failing), "the property holds for the input to shrink");
+
+    let start = failing.nonblank_range().map_or(0, |range| *range.start());
+    let tape = |cells: &[u64]| {
+        let mut tape = Tape::default();
+        for (n, &symbol) in (start..).zip(cells) {
+            tape.write(n, symbol);
+        }
+        tape
+    };
+
+    let mut cells = failing.nonblank_range().into_iter().flatten().map(|n| failing.symbol_at_n(n)).collect::<Vec<u64>>();
+    'shrink: loop {
+        // remove runs of cells, halving the run length down to single cells
+        let mut len = cells.len();
+        while len > 0 {
+            for i in (0..=cells.len() - len).step_by(len) {
+                let candidate = [&cells[..i], &cells[i + len..]].concat();
+                if fails(&tape(&candidate)) {
+                    cells = candidate;
+                    continue 'shrink;
+                }
+            }
+            len /= 2;
+        }
+
+        // blank single cells, then lower them to every smaller symbol
+        for i in 0..cells.len() {
+            for symbol in 0..cells[i] {
+                let mut candidate = cells.clone();
+                candidate[i] = symbol;
+                if fails(&tape(&candidate)) {
+                    cells = candidate;
+                    continue 'shrink;
+                }
+            }
+        }
+
+        return tape(&cells);
+    }
+}
+
+/// Returns a human-readable description of every way the finished run differs from `expected`.
+fn mismatches(machine: &TuringMachine, tape: &Tape, recording: &Recording, expected: &ExpectedOutcome) -> Vec<String> {
+    let mut mismatches = Vec::default();
+
+    if let Some(steps) = expected.steps {
+        if recording.len() != steps {
+            mismatches.push(format!("  steps: expected {}, found {}", steps, recording.len()));
+        }
+    }
+
+    if let Some(state) = expected.state {
+        if machine.state() != state {
+            mismatches.push(format!("  final state: expected {}, found {}", state, machine.state()));
+        }
+    }
+
+    if let Some(expected_tape) = &expected.tape {
+        let diff = expected_tape.diff(tape);
+        if !diff.is_empty() {
+            mismatches.push(format!("  final tape: expected {:?}, found {:?}", expected_tape.symbols(), tape.symbols()));
+
+            for (n, e, f) in diff {
+                mismatches.push(format!("    at location {}: expected {}, found {}", n, e, f));
+            }
+        }
+    }
+
+    mismatches
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn machine() -> TuringMachine {
+        TuringMachine::new(TransitionFn::new(
+            &[
+                ((0, 0), (1, 1, true)),
+                ((1, 0), (2, 1, false)),
+                ((2, 1), (3, 4, false)),
+            ]
+        ))
+    }
+
+    #[test]
+    fn test_assert_machine_behavior() {
+        let expected = ExpectedOutcome {
+            tape: Some(Tape::new(vec![4, 1])),
+            steps: Some(3),
+            state: Some(3),
+            halt_setting: HaltSetting::AfterSteps(10),
+        };
+
+        assert_machine_behavior(&machine(), &Tape::default(), &expected);
+    }
+
+    #[test]
+    fn test_default_outcome() {
+        // a machine that never halts is forcibly halted instead of hanging the test
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 0, true))]));
+        let expected = ExpectedOutcome { steps: Some(DEFAULT_STEP_BUDGET), ..Default::default() };
+
+        assert_machine_behavior(&machine, &Tape::default(), &expected);
+    }
+
+    #[test]
+    fn test_shrink_input() {
+        // the machine only writes a 3 at location 1 after reading one at location 0, so other inputs containing a 3 fail
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 3), (1, 0, true)), ((1, 0), (2, 3, true))]))
+            .with_halt_setting(HaltSetting::AfterSteps(100));
+        let property = |input: &Tape, output: &Tape| input.symbols().iter().all(|&s| s != 3) || output.symbol_at_n(1) == 3;
+
+        let mut failing = Tape::new(vec![0, 2, 3, 3, 1]);
+        failing.write(-3, 2);
+        let shrunk = shrink_input(&machine, &failing, property);
+        // the 3 must stay at location -3, where the nonblank range starts
+        assert_eq!(shrunk.nonblank_range(), Some(-3..=-3));
+        assert_eq!(shrunk.symbol_at_n(-3), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "the property holds")]
+    fn panic_test_shrink_input() {
+        shrink_input(&machine(), &Tape::default(), |_, _| true);
+    }
+
+    #[test]
+    #[should_panic(expected = "at location 1: expected 2, found 1")]
+    fn panic_test_assert_machine_behavior() {
+        let expected = ExpectedOutcome {
+            tape: Some(Tape::new(vec![4, 2])),
+            ..Default::default()
+        };
+
+        assert_machine_behavior(&machine(), &Tape::default(), &expected);
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+
+use crate::tape::Tape;
+use crate::transition_fn::TransitionFn;
+
+/// A machine over the symbols `{0, 1}` that simulates a machine over more symbols, built by `to_binary`,
+/// along with what is needed to translate between the two.
+/// 
+/// Cell `n` of the original tape is stored in the block of `block_len` cells starting at `n * block_len`,
+/// least significant bit first, so that blank cells are stored as blank blocks.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BinaryEncoding {
+    pub transition_fn: TransitionFn,
+    pub block_len: u32,
+    /// For every original state reachable from state 0, the state in which the binary machine
+    /// starts simulating a step of it with its head on the first cell of a block.
+    pub states: HashMap<u64, u64>,
+}
+
+impl BinaryEncoding {
+    /// Returns the binary tape storing the symbols of `tape`.
+    /// 
+    /// # Panics
+    /// Panics if `tape` holds a symbol that does not fit in `block_len` bits.
+    #[inline]
+    pub fn encode_tape(&self, tape: &Tape) -> Tape {
+        let block_len = self.block_len as i64;
+        let mut encoded = Tape::default();
+        for n in tape.nonblank_range().into_iter().flatten() {
+            let symbol = tape.symbol_at_n(n);
+            assert!(symbol >> self.block_len == 0, "symbol {} does not fit in {} bits", symbol, self.block_len);
+            for i in 0..block_len {
+                encoded.write(n * block_len + i, (symbol >> i) & 1);
+            }
+        }
+
+        encoded
+    }
+
+    /// Returns the tape whose symbols are stored in the binary tape `tape`; the inverse of `encode_tape`.
+    #[inline]
+    pub fn decode_tape(&self, tape: &Tape) -> Tape {
+        let block_len = self.block_len as i64;
+        let mut decoded = Tape::default();
+        for n in tape.nonblank_range().into_iter().flatten() {
+            let block = n.div_euclid(block_len);
+            let bit = tape.symbol_at_n(n) & 1;
+            decoded.write(block, decoded.symbol_at_n(block) | bit << n.rem_euclid(block_len));
+        }
+
+        decoded
+    }
+
+    /// Returns the head location in the binary machine corresponding to `head_loc` in the original machine.
+    #[inline]
+    pub fn encode_head_loc(&self, head_loc: i64) -> i64 {
+        head_loc * self.block_len as i64
+    }
+}
+
+/// What the binary machine built by `to_binary` is doing, which identifies one of its states.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Phase {
+    /// Reading bit `bit` of the current block, having read `read` so far, to simulate `state`.
+    Read { state: u64, bit: u32, read: u64 },
+    /// Writing bit `bit` of `written` while moving left, before moving to the next block and simulating `state`.
+    Write { state: u64, right: bool, bit: u32, written: u64 },
+    /// Moving towards the first cell of the next block, `remaining` cells away, to simulate `state`.
+    Move { state: u64, right: bool, remaining: u32 },
+}
+
+/// Converts a machine over any number of symbols into an equivalent machine over the symbols `{0, 1}`,
+/// by the standard construction that stores each symbol in a block of ⌈log2 k⌉ cells, where k is
+/// one more than the largest symbol `transition_fn` reads or writes.
+/// 
+/// Each step of the original machine takes the binary machine one pass over the block under its head
+/// to read the symbol, one pass back to write the new symbol, and a walk to the first cell
+/// of the next block. Only states reachable from state 0 are simulated. Where the original machine halts,
+/// the binary machine halts on the last cell of the block under the original head.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::transforms::to_binary;
+/// 
+/// // writes 1, 2, 3 and halts
+/// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 2, true)), ((2, 0), (3, 3, true))]);
+/// let binary = to_binary(&trans_fn);
+/// assert_eq!(binary.block_len, 2);
+/// 
+/// let mut tape = binary.encode_tape(&Tape::default());
+/// TuringMachine::new(binary.transition_fn.clone()).run(&mut tape);
+/// 
+/// assert!(tape.symbols().iter().all(|&s| s <= 1));
+/// assert_eq!(binary.decode_tape(&tape), Tape::new(vec![1, 2, 3]));
+/// ```
+#[inline]
+pub fn to_binary(transition_fn: &TransitionFn) -> BinaryEncoding {
+    let num_symbols = transition_fn
+        .state_table()
+        .iter()
+        .map(|((_, read), (_, write, _))| read.max(write) + 1)
+        .max()
+        .unwrap_or(0);
+    let block_len = (u64::BITS - num_symbols.saturating_sub(1).leading_zeros()).max(1);
+
+    // the phase that follows writing bit `bit` of `written` and moving left
+    let after_write = |state: u64, right: bool, bit: u32, written: u64| match bit.checked_sub(1) {
+        Some(bit) => Phase::Write { state, right, bit, written: written & ((1 << (bit + 1)) - 1) },
+        None => after_move(state, right, if right { block_len + 1 } else { block_len - 1 }),
+    };
+
+    let mut ids = HashMap::from([(Phase::Read { state: 0, bit: 0, read: 0 }, 0)]);
+    let mut pending = vec![Phase::Read { state: 0, bit: 0, read: 0 }];
+    let mut rules = Vec::new();
+    while let Some(phase) = pending.pop() {
+        let id = ids[&phase];
+        for symbol in 0..2 {
+            let (write, right, next) = match phase {
+                Phase::Read { state, bit, read } => {
+                    let read = read | symbol << bit;
+                    if bit + 1 < block_len {
+                        (symbol, true, Phase::Read { state, bit: bit + 1, read })
+                    }
+                    else {
+                        let Some((new_state, written, right)) = transition_fn.run(state, read) else { continue };
+                        ((written >> bit) & 1, false, after_write(new_state, right, bit, written))
+                    }
+                }
+                Phase::Write { state, right, bit, written } => ((written >> bit) & 1, false, after_write(state, right, bit, written)),
+                Phase::Move { state, right, remaining } => (symbol, right, after_move(state, right, remaining - 1)),
+            };
+
+            let next_id = ids.len() as u64;
+            let next_id = *ids.entry(next).or_insert_with(|| {
+                pending.push(next);
+                next_id
+            });
+            rules.push(((id, symbol), (next_id, write, right)));
+        }
+    }
+
+    let states = ids
+        .iter()
+        .filter_map(|(phase, &id)| match *phase {
+            Phase::Read { state, bit: 0, .. } => Some((state, id)),
+            _ => None,
+        })
+        .collect();
+
+    BinaryEncoding {
+        transition_fn: TransitionFn::new(&rules),
+        block_len,
+        states,
+    }
+}
+
+/// The phase that follows moving to within `remaining` cells of the first cell of the next block.
+fn after_move(state: u64, right: bool, remaining: u32) -> Phase {
+    match remaining {
+        0 => Phase::Read { state, bit: 0, read: 0 },
+        _ => Phase::Move { state, right, remaining },
+    }
+}
+
+/// Converts a machine into an equivalent machine over the symbols `{0, 1}`, trading states for symbols.
+/// Equivalent to `to_binary`, and named as the counterpart of `reduce_states`.
+#[inline]
+pub fn reduce_symbols(transition_fn: &TransitionFn) -> BinaryEncoding {
+    to_binary(transition_fn)
+}
+
+/// A machine with 2 states that simulates a machine with more states, built by `reduce_states`,
+/// along with what is needed to translate between the two.
+/// 
+/// The cell under the original head is marked with the original state, so a tape has to be encoded
+/// together with the configuration of the original machine, and the simulating machine always starts in state 0.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StateReduction {
+    pub transition_fn: TransitionFn,
+    /// The number of states of the original machine, n.
+    pub num_states: u64,
+    /// The number of symbols of the original machine, m. The simulating machine uses m(4n + 1) symbols.
+    pub num_symbols: u64,
+}
+
+impl StateReduction {
+    /// Returns the tape on which the simulating machine, starting in state 0 at `head_loc`,
+    /// simulates the original machine starting in `state` at `head_loc` on `tape`.
+    /// 
+    /// # Panics
+    /// Panics if `state` is not a state of the original machine or `tape` holds a symbol it does not use.
+    #[inline]
+    pub fn encode_tape(&self, tape: &Tape, state: u64, head_loc: i64) -> Tape {
+        assert!(state < self.num_states, "state {} is not a state of the original machine", state);
+        assert!(
+            tape.nonblank_range().into_iter().flatten().all(|n| tape.symbol_at_n(n) < self.num_symbols),
+            "tape holds a symbol the original machine does not use",
+        );
+
+        let mut encoded = tape.clone();
+        encoded.write(head_loc, self.received(tape.symbol_at_n(head_loc), state + 1, true));
+        encoded
+    }
+
+    /// Returns the tape and state of the original machine once the simulating machine has halted on `tape`
+    /// at `head_loc`, or `None` if the simulating machine is not between steps of the original machine there.
+    #[inline]
+    pub fn decode(&self, tape: &Tape, head_loc: i64) -> Option<(Tape, u64)> {
+        let (symbol, count) = self.decode_received(tape.symbol_at_n(head_loc))?;
+
+        let mut decoded = tape.clone();
+        decoded.write(head_loc, symbol);
+        Some((decoded, count - 1))
+    }
+
+    /// Returns the symbol of a cell holding `symbol` that has received a count of `count` from a neighbour
+    /// on its left, if `from_left`, or on its right.
+    fn received(&self, symbol: u64, count: u64, from_left: bool) -> u64 {
+        let (m, n) = (self.num_symbols, self.num_states);
+        m + 2 * m * n + ((symbol * n + count - 1) * 2 + from_left as u64)
+    }
+
+    /// Returns the symbol and count of a symbol returned by `received`.
+    fn decode_received(&self, symbol: u64) -> Option<(u64, u64)> {
+        let (m, n) = (self.num_symbols, self.num_states);
+        let offset = symbol.checked_sub(m + 2 * m * n).filter(|&offset| offset < 2 * m * n)? / 2;
+        Some((offset / n, offset % n + 1))
+    }
+
+    /// Returns the symbol of a cell that will hold `symbol` once it has sent a count of `remaining` more
+    /// to its neighbour on the right, if `right`, or on the left.
+    fn sending(&self, symbol: u64, remaining: u64, right: bool) -> u64 {
+        let (m, n) = (self.num_symbols, self.num_states);
+        m + ((symbol * n + remaining) * 2 + right as u64)
+    }
+}
+
+/// Converts a machine with n states over m symbols into an equivalent machine with 2 states over m(4n + 1) symbols,
+/// trading symbols for states by Shannon's construction, where n and m are one more than the largest state
+/// and symbol `transition_fn` mentions.
+/// 
+/// The original state is stored on the tape as a count in the cell under the head.
+/// To move, the head bounces between that cell and its neighbour, transferring the new state one unit per bounce,
+/// with the two states telling the neighbour whether to expect more. Where the original machine halts,
+/// the simulating machine halts on the same cell.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::transforms::reduce_states;
+/// 
+/// let (machine, _) = turing_machine::examples::busy_beaver_3();
+/// let reduction = reduce_states(machine.transition_fn());
+/// assert_eq!((reduction.num_states, reduction.num_symbols), (4, 2));
+/// 
+/// let mut simulator = TuringMachine::new(reduction.transition_fn.clone());
+/// let mut tape = reduction.encode_tape(&Tape::default(), 0, 0);
+/// simulator.run(&mut tape);
+/// 
+/// assert!(simulator.transition_fn().state_table().iter().all(|((state, _), _)| *state < 2));
+/// let (decoded, state) = reduction.decode(&tape, simulator.head_loc()).unwrap();
+/// assert_eq!((decoded.symbols(), state), (vec![1; 6], 3));
+/// ```
+#[inline]
+pub fn reduce_states(transition_fn: &TransitionFn) -> StateReduction {
+    let state_table = transition_fn.state_table();
+    let num_states = state_table.iter().map(|((state, _), (new_state, _, _))| state.max(new_state) + 1).max().unwrap_or(1);
+    let num_symbols = state_table.iter().map(|((_, read), (_, write, _))| read.max(write) + 1).max().unwrap_or(1);
+    let reduction = StateReduction { transition_fn: TransitionFn::default(), num_states, num_symbols };
+
+    // state 0 means "done" when arriving at a cell that has received a count, and "moving right" when arriving at a plain cell;
+    // state 1 means "more" and "moving left", respectively
+    let mut rules = Vec::new();
+    for symbol in 0..num_symbols {
+        // first arrival from a sender, which is then left behind
+        for (state, from_left) in [(0, true), (1, false)] {
+            rules.push(((state, symbol), (1, reduction.received(symbol, 1, from_left), !from_left)));
+        }
+
+        for count in 1..=num_states {
+            for from_left in [true, false] {
+                let received = reduction.received(symbol, count, from_left);
+
+                // the sender has more to send
+                if count < num_states {
+                    rules.push(((1, received), (1, reduction.received(symbol, count + 1, from_left), !from_left)));
+                }
+
+                // the transfer is done, so take the next step
+                if let Some((new_state, write, right)) = transition_fn.run(count - 1, symbol) {
+                    rules.push(((0, received), (!right as u64, reduction.sending(write, new_state, right), right)));
+                }
+            }
+        }
+
+        for remaining in 0..num_states {
+            for right in [true, false] {
+                let sending = reduction.sending(symbol, remaining, right);
+                let output = match remaining {
+                    0 => (0, symbol, right),
+                    _ => (1, reduction.sending(symbol, remaining - 1, right), right),
+                };
+                rules.push(((0, sending), output));
+                rules.push(((1, sending), output));
+            }
+        }
+    }
+
+    StateReduction { transition_fn: TransitionFn::new(&rules), ..reduction }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::transforms::*;
+
+    #[test]
+    fn test_to_binary() {
+        for (index, num_symbols) in (0..60).map(|i| (i, 3)).chain((60..100).map(|i| (i, 5))) {
+            let trans_fn = TransitionFn::random(2, num_symbols, Seed(index));
+            let binary = to_binary(&trans_fn);
+            assert_eq!(binary.block_len, if num_symbols == 3 { 2 } else { 3 });
+
+            let input = Tape::random_seeded(6, num_symbols as u64, Seed(index));
+            let mut tape = input.clone();
+            let mut machine = TuringMachine::new(trans_fn);
+            let steps = machine.run_counted(&mut tape, HaltSetting::AfterSteps(30));
+
+            // the binary machine passes through the final configuration of the original machine
+            let mut binary_tape = binary.encode_tape(&input);
+            assert_eq!(binary.decode_tape(&binary_tape), input);
+            let mut binary_machine = TuringMachine::new(binary.transition_fn.clone());
+            let matches = |binary_machine: &TuringMachine, binary_tape: &Tape| {
+                binary.states.get(&machine.state()) == Some(&binary_machine.state())
+                    && binary_machine.head_loc() == binary.encode_head_loc(machine.head_loc())
+                    && binary.decode_tape(binary_tape) == tape
+            };
+            while !matches(&binary_machine, &binary_tape) {
+                assert!(binary_machine.step(&mut binary_tape).is_some(), "machine {} halted early", index);
+            }
+
+            // and halts right after it if the original machine halted
+            if steps < 30 {
+                assert_eq!(binary_machine.run_counted(&mut binary_tape, HaltSetting::AfterSteps(10)), binary.block_len as usize - 1);
+                assert_eq!(binary_machine.head_loc(), binary.encode_head_loc(machine.head_loc()) + binary.block_len as i64 - 1);
+                assert_eq!(binary.decode_tape(&binary_tape), tape);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reduce_states() {
+        for index in 0..100 {
+            let trans_fn = TransitionFn::random(3, 3, Seed(index));
+            let reduction = reduce_states(&trans_fn);
+
+            let input = Tape::random_seeded(6, 3, Seed(index));
+            let mut tape = input.clone();
+            let mut machine = TuringMachine::new(trans_fn);
+            let steps = machine.run_counted(&mut tape, HaltSetting::AfterSteps(30));
+
+            // the simulator passes through the final configuration of the original machine
+            let mut simulator_tape = reduction.encode_tape(&input, 0, 0);
+            let mut simulator = TuringMachine::new(reduction.transition_fn.clone());
+            assert_eq!(simulator.transition_fn().state_table().iter().map(|r| r.0.0).max(), Some(1));
+            while reduction.decode(&simulator_tape, simulator.head_loc()) != Some((tape.clone(), machine.state()))
+                || simulator.head_loc() != machine.head_loc()
+                || simulator.state() != 0
+            {
+                assert!(simulator.step(&mut simulator_tape).is_some(), "machine {} halted early", index);
+            }
+
+            // and halts there if the original machine halted
+            if steps < 30 {
+                assert_eq!(simulator.step(&mut simulator_tape), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_to_binary_two_symbols() {
+        let (machine, _) = crate::examples::busy_beaver_2();
+        let binary = to_binary(machine.transition_fn());
+        assert_eq!(binary.block_len, 1);
+
+        let mut tape = Tape::default();
+        TuringMachine::new(binary.transition_fn).run(&mut tape);
+        assert_eq!(tape.symbols(), [1, 1, 1, 1]);
+    }
+}
@@ -1,12 +1,121 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::hash::{BuildHasherDefault, Hasher};
+use std::io::{self, BufRead, Write};
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::Arc;
+use crate::alphabet::{self, Alphabet, SymbolMapError};
+use crate::enumeration;
+use crate::profile::TransitionProfile;
+use crate::rng::{Seed, SplitMix64};
+use crate::tape::{Tape, TapeError, MAX_LOCATION};
+
+/// A single entry of a state table in the form `((state, symbol), (new state, symbol to write, head movement))`.
+pub type Rule = ((u64, u64), (u64, u64, bool));
+
+/// A head movement. The single-tape model only moves left or right, so `Stay` is compiled away
+/// by tools that accept it, such as `SmartBuilder::rule`; `From<bool>` maps `true` to `Right`.
+/// `Display` and `TryFrom<char>` use the letters `L`, `R` and `S`, which every text format in this crate shares.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::transition_fn::Move;
+/// 
+/// assert_eq!(Move::from(true), Move::Right);
+/// assert_eq!(Move::try_from('S'), Ok(Move::Stay));
+/// assert!(Move::try_from('X').is_err());
+/// assert_eq!(Move::Left.to_string(), "L");
+/// assert_eq!(Move::Left.offset(), -1);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Move {
+    Left,
+    Right,
+    Stay,
+}
+
+/// An alias of `Move`.
+pub type Direction = Move;
+
+impl Move {
+    /// Returns the change in head location caused by `self`.
+    #[inline]
+    pub fn offset(self) -> i64 {
+        match self {
+            Move::Left => -1,
+            Move::Right => 1,
+            Move::Stay => 0,
+        }
+    }
+
+    /// Returns `self` as the `bool` used by the transition model, or `None` for `Stay`.
+    #[inline]
+    pub fn is_right(self) -> Option<bool> {
+        match self {
+            Move::Left => Some(false),
+            Move::Right => Some(true),
+            Move::Stay => None,
+        }
+    }
+}
+
+impl From<bool> for Move {
+    #[inline]
+    fn from(right: bool) -> Self {
+        if right { Move::Right } else { Move::Left }
+    }
+}
+
+impl TryFrom<char> for Move {
+    type Error = ParseMachineError;
+
+    #[inline]
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            'L' => Ok(Move::Left),
+            'R' => Ok(Move::Right),
+            'S' => Ok(Move::Stay),
+            other => Err(ParseMachineError::new(format!("invalid head movement `{}`", other))),
+        }
+    }
+}
+
+impl fmt::Display for Move {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Move::Left => 'L',
+            Move::Right => 'R',
+            Move::Stay => 'S',
+        };
+
+        write!(f, "{}", c)
+    }
+}
+
+/// The symbol used in the tape encoding of a `TransitionFn` to count in unary.
+pub const ENCODING_TALLY: u64 = 1;
+/// The symbol used in the tape encoding of a `TransitionFn` to separate the fields of a rule.
+pub const ENCODING_FIELD_SEP: u64 = 2;
+/// The symbol used in the tape encoding of a `TransitionFn` to end a rule.
+pub const ENCODING_RULE_END: u64 = 3;
 
 /// A representation of a turing machine's transition function.
 /// It takes a state and a symbol and returns a new state, new symbol, 
 /// and whether to move left or right.
-#[derive(Clone, Debug, Default, PartialEq)]
+/// 
+/// The state table is shared behind an `Arc`, so cloning a `TransitionFn`
+/// (or a `TuringMachine` holding one) is O(1) regardless of the table's size.
+/// 
+/// Rules can carry annotations, which document them in `Display`, `explain`, and DOT output
+/// but do not affect execution or `fingerprint`.
+#[derive(Clone, Debug, Default)]
 pub struct TransitionFn {
-    map: HashMap<(u64, u64), (u64, u64, bool), PairingBuildHasher>,
+    map: Arc<StateMap>,
+    annotations: Arc<Annotations>,
+    /// The hottest rules according to `optimize_layout`, hottest first, which `run` checks before `map`.
+    hot: Arc<[Rule]>,
 }
 
 impl TransitionFn {
@@ -23,80 +132,899 @@ impl TransitionFn {
     /// 
     /// let v = vec![((2, 1), (30, 12, false)), ((2, 1), (35, 3, true))];
     /// 
-    /// let trans_fn = TransitionFn::new(&v);
-    /// ```
+    /// let trans_fn = TransitionFn::new(&v);
+    /// ```
+    /// 
+    /// # Examples
+    /// ```
+    /// # use turing_machine::transition_fn::TransitionFn;
+    /// let v = vec![((1, 19), (30, 12, false)), ((22, 79), (35, 3, true))];
+    /// 
+    /// let trans_fn = TransitionFn::new(&v);
+    /// ```
+    #[inline]
+    pub fn new(state_table: &[Rule]) -> Self {
+        TransitionFn::from_rules(state_table.iter().copied()).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Constructs a new `TransitionFn` from the specified rules, taking them by value
+    /// so that any iterator, e.g. one built on the fly, can be passed without collecting it first.
+    /// Equivalent to `new`, but returns an error instead of panicking.
+    /// 
+    /// # Errors
+    /// Returns a `DuplicateRuleError` naming the first key that occurs twice.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::{DuplicateRuleError, TransitionFn};
+    /// 
+    /// // a machine that writes 1 and moves right in every state below 5
+    /// let trans_fn = TransitionFn::from_rules((0..5).map(|state| ((state, 0), (state + 1, 1, true)))).unwrap();
+    /// assert_eq!(trans_fn.run(4, 0), Some((5, 1, true)));
+    /// 
+    /// let duplicated = TransitionFn::from_rules([((2, 1), (30, 12, false)), ((2, 1), (35, 3, true))]);
+    /// assert_eq!(duplicated, Err(DuplicateRuleError { state: 2, symbol: 1 }));
+    /// ```
+    #[inline]
+    pub fn from_rules(rules: impl IntoIterator<Item = Rule>) -> Result<Self, DuplicateRuleError> {
+        let rules = rules.into_iter();
+        let mut map = StateMap::with_capacity_and_hasher(rules.size_hint().0, PairingBuildHasher::default());
+        for ((state, symbol), output) in rules {
+            if map.insert((state, symbol), output).is_some() {
+                return Err(DuplicateRuleError { state, symbol });
+            }
+        }
+
+        Ok(TransitionFn {
+            map: Arc::new(map),
+            annotations: Arc::default(),
+            hot: Arc::default(),
+        })
+    }
+
+    /// Returns `self` with `note` attached to the rule for `(state, symbol)`, replacing any previous annotation.
+    /// In the text format, an annotation follows its rule after a `#`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true))]).with_annotation(0, 0, "mark the start");
+    /// 
+    /// assert_eq!(trans_fn.annotation(0, 0), Some("mark the start"));
+    /// assert_eq!(trans_fn.to_string(), "[0,0 -> 1,1,R # mark the start]");
+    /// assert_eq!(trans_fn.to_string().parse(), Ok(trans_fn));
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if there is no rule for `(state, symbol)`, or if `note` contains `;`, `]`, or a line break,
+    /// which would make the text format ambiguous.
+    #[inline]
+    pub fn with_annotation(mut self, state: u64, symbol: u64, note: &str) -> Self {
+        if !self.map.contains_key(&(state, symbol)) {
+            panic!("There is no rule for state {} and symbol {} to annotate", state, symbol);
+        }
+        if note.contains([';', ']', '\n', '\r']) {
+            panic!("Annotation `{}` contains `;`, `]`, or a line break", note);
+        }
+
+        Arc::make_mut(&mut self.annotations).insert((state, symbol), note.trim().to_string());
+        self
+    }
+
+    /// Returns the annotation of the rule for `(state, symbol)`, if any.
+    #[inline]
+    pub fn annotation(&self, state: u64, symbol: u64) -> Option<&str> {
+        self.annotations.get(&(state, symbol)).map(String::as_str)
+    }
+
+    /// Returns whether `self` and `other` share the same underlying state table,
+    /// i.e. one was cloned from the other.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true))]);
+    /// 
+    /// assert!(trans_fn.shares_table_with(&trans_fn.clone()));
+    /// assert!(!trans_fn.shares_table_with(&TransitionFn::new(&trans_fn.state_table())));
+    /// ```
+    #[inline]
+    pub fn shares_table_with(&self, other: &TransitionFn) -> bool {
+        Arc::ptr_eq(&self.map, &other.map)
+    }
+
+    /// Enumerates all possible turing machines with the specified number of states and symbols.
+    /// This is an extremely expensive function with the output vector having length O(n^(n^2))
+    /// where n is both the number of states and the number of symbols.
+    #[inline]
+    pub fn enumerate(num_states: usize, num_symbols: usize) -> Vec<Self> {
+        let mut fns = Vec::default();
+        let mut keys = Vec::default();
+        let mut values = Vec::default();
+
+        for t in 0..num_states {
+            for y in 0..num_symbols {
+                // this level contains all possible keys
+                keys.push((t as u64, y as u64));
+            }
+        }
+
+        for n in 0..num_states + 1 {
+            for w in 0..num_symbols {
+                for b in 0..2 {
+                    // this level contains all possible values
+                    values.push((n as u64, w as u64, b != 0));
+                }
+            }
+        }
+
+        let value_permutations = permute_with_repetition(&values, keys.len()); 
+        for permutation in value_permutations {
+            // this level contains all possible transition tables
+            let mut state_table = Vec::default();
+
+            for k in 0..keys.len() {
+                state_table.push((keys[k], permutation[k]));
+            }
+
+            fns.push(TransitionFn::new(&state_table));
+        }
+
+        fns
+    }
+
+    /// Returns a machine drawn uniformly from those enumerated by `enumerate`, using a generator seeded with `seed`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::random(3, 2, Seed(7));
+    /// 
+    /// assert_eq!(trans_fn.state_table().len(), 6);
+    /// assert_eq!(trans_fn, TransitionFn::random(3, 2, Seed(7)));
+    /// ```
+    #[inline]
+    pub fn random(num_states: usize, num_symbols: usize, seed: Seed) -> Self {
+        TransitionFn::new(&TransitionFn::random_rules(num_states, num_symbols, &mut seed.rng()))
+    }
+
+    /// Returns a machine drawn uniformly from the canonical machines kept by `EnumerationBuilder::canonical_only`,
+    /// which represent the classes of machines that only differ by mirroring and renaming states.
+    /// Estimates over naive samples from `random` are biased towards classes with many equivalent tables,
+    /// while every class has a single canonical machine unless some of its states can never be entered.
+    /// 
+    /// Tables are drawn from `rng` until one is canonical, which takes about `2 * (num_states - 1)!` tries.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut rng = Seed(7).rng();
+    /// let trans_fn = TransitionFn::random_canonical(4, 2, &mut rng);
+    /// 
+    /// assert!(trans_fn.is_deterministic_complete_for(4, 2));
+    /// assert!(trans_fn.run(0, 0).unwrap().2);
+    /// assert_ne!(TransitionFn::random_canonical(4, 2, &mut rng), trans_fn);
+    /// ```
+    #[inline]
+    pub fn random_canonical(num_states: usize, num_symbols: usize, rng: &mut SplitMix64) -> Self {
+        loop {
+            let rules = TransitionFn::random_rules(num_states, num_symbols, rng);
+            if enumeration::is_canonical(&rules, num_states as u64) {
+                return TransitionFn::new(&rules);
+            }
+        }
+    }
+
+    /// Returns the rules of a machine drawn uniformly from those enumerated by `enumerate`, sorted by key.
+    fn random_rules(num_states: usize, num_symbols: usize, rng: &mut SplitMix64) -> Vec<Rule> {
+        let mut state_table = Vec::with_capacity(num_states * num_symbols);
+        for state in 0..num_states as u64 {
+            for symbol in 0..num_symbols as u64 {
+                let new_state = rng.below(num_states as u64 + 1);
+                let write = rng.below(num_symbols as u64);
+                state_table.push(((state, symbol), (new_state, write, rng.chance(0.5))));
+            }
+        }
+
+        state_table
+    }
+
+    /// Returns the number of machines `enumerate` would return for the specified number of states and symbols,
+    /// without constructing any of them.
+    /// 
+    /// # Panics
+    /// Panics if the number exceeds `u128::MAX`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// assert_eq!(TransitionFn::enumeration_size(2, 2), TransitionFn::enumerate(2, 2).len() as u128);
+    /// assert_eq!(TransitionFn::enumeration_size(5, 2), 24u128.pow(10));
+    /// ```
+    #[inline]
+    pub fn enumeration_size(num_states: usize, num_symbols: usize) -> u128 {
+        u32::try_from(num_states * num_symbols)
+            .ok()
+            .and_then(|num_keys| (((num_states + 1) * num_symbols * 2) as u128).checked_pow(num_keys))
+            .expect("Enumeration size must fit in a u128")
+    }
+
+    /// Equivalent to `enumeration_size`, but for sweeps that index machines with a `usize`.
+    /// 
+    /// # Panics
+    /// Panics if the number exceeds `usize::MAX`.
+    #[inline]
+    pub(crate) fn enumeration_len(num_states: usize, num_symbols: usize) -> usize {
+        usize::try_from(TransitionFn::enumeration_size(num_states, num_symbols))
+            .unwrap_or_else(|_| panic!("The {}-state, {}-symbol machines are too many to index with a usize", num_states, num_symbols))
+    }
+
+    /// Splits the indices of the output of `enumerate` into `num_shards` contiguous ranges
+    /// whose lengths differ by at most 1, for distributing a sweep across workers.
+    /// 
+    /// # Panics
+    /// Panics if `num_shards` is 0 or if the enumeration size exceeds `u128::MAX`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let shards = TransitionFn::enumeration_shards(1, 2, 3);
+    /// 
+    /// assert_eq!(shards, vec![0..22, 22..43, 43..64]);
+    /// ```
+    #[inline]
+    pub fn enumeration_shards(num_states: usize, num_symbols: usize, num_shards: usize) -> Vec<Range<u128>> {
+        if num_shards == 0 {
+            panic!("There must be at least one shard");
+        }
+
+        let size = TransitionFn::enumeration_size(num_states, num_symbols);
+        let (len, rem) = (size / num_shards as u128, size % num_shards as u128);
+
+        let mut start = 0;
+        (0..num_shards as u128)
+            .map(|shard| {
+                let end = start + len + (shard < rem) as u128;
+                let range = start..end;
+                start = end;
+
+                range
+            })
+            .collect()
+    }
+
+    /// Constructs the machine at position `index` of the output of `enumerate` without constructing
+    /// any of the others, by decoding `index` as a mixed-radix number, so that the space of machines
+    /// can be accessed randomly even when it is far too large to enumerate. Returns `None` if `index`
+    /// is not less than `enumeration_size`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let fns = TransitionFn::enumerate(1, 2);
+    /// assert_eq!(TransitionFn::nth(1, 2, 37), Some(fns[37].clone()));
+    /// assert_eq!(TransitionFn::nth(1, 2, 64), None);
+    /// 
+    /// // the last of the 24^10 machines of 5 states and 2 symbols goes to the halting state, writes 1 and moves right everywhere
+    /// let last = TransitionFn::nth(5, 2, 24u128.pow(10) - 1).unwrap();
+    /// assert!((0..5).all(|state| last.run(state, 0) == Some((5, 1, true))));
+    /// ```
+    #[inline]
+    pub fn nth(num_states: usize, num_symbols: usize, index: u128) -> Option<Self> {
+        let num_values = ((num_states + 1) * num_symbols * 2) as u128;
+        let in_range = u32::try_from(num_states * num_symbols)
+            .ok()
+            .and_then(|num_keys| num_values.checked_pow(num_keys))
+            .is_none_or(|size| index < size);
+
+        in_range.then(|| TransitionFn::new(&TransitionFn::enumerated_rules(num_states, num_symbols, index)))
+    }
+
+    /// Equivalent to `nth`, but takes an index that is known to be in range.
+    #[inline]
+    pub(crate) fn enumerated(num_states: usize, num_symbols: usize, index: usize) -> Self {
+        TransitionFn::new(&TransitionFn::enumerated_rules(num_states, num_symbols, index as u128))
+    }
+
+    /// Returns the rules of the machine at position `index` of the output of `enumerate`, sorted by key.
+    pub(crate) fn enumerated_rules(num_states: usize, num_symbols: usize, mut index: u128) -> Vec<Rule> {
+        let num_values = ((num_states + 1) * num_symbols * 2) as u128;
+        let mut state_table = Vec::with_capacity(num_states * num_symbols);
+
+        // the last key varies fastest in `enumerate`
+        for k in (0..num_states * num_symbols).rev() {
+            let v = (index % num_values) as usize;
+            index /= num_values;
+
+            let key = ((k / num_symbols) as u64, (k % num_symbols) as u64);
+            let value = ((v / (num_symbols * 2)) as u64, (v / 2 % num_symbols) as u64, !v.is_multiple_of(2));
+            state_table.push((key, value));
+        }
+        state_table.reverse();
+
+        state_table
+    }
+
+    /// Constructs a `TransitionFn` computing the finite function described by `table`,
+    /// where each entry maps an input bit string to an output bit string.
+    /// 
+    /// Bits are stored on the tape as symbols, `false` as 1 and `true` as 2, so that they can be
+    /// told apart from the blank symbol 0. The machine reads the input starting at location 0,
+    /// erases it, writes the output starting at location 0, and halts in the state one greater
+    /// than its highest non-halting state. On an input not listed in `table`, it halts
+    /// early because no rule matches.
+    /// 
+    /// # Panics
+    /// Panics if `table` lists the same input more than once.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // logical negation of a single bit
+    /// let trans_fn = TransitionFn::from_finite_fn(&[(vec![false], vec![true]), (vec![true], vec![false])]);
+    /// 
+    /// let mut machine = TuringMachine::new(trans_fn);
+    /// let mut tape = Tape::new(vec![1]);
+    /// machine.run(&mut tape);
+    /// 
+    /// assert_eq!(tape, Tape::new(vec![2]));
+    /// ```
+    #[inline]
+    pub fn from_finite_fn(table: &[(Vec<bool>, Vec<bool>)]) -> Self {
+        let bit_symbol = |bit: bool| bit as u64 + 1;
+
+        // the states reading the input form a trie rooted at state 0
+        let mut children = HashMap::new();
+        let mut terminals = Vec::with_capacity(table.len());
+        let mut num_states = 1;
+        for (input, _) in table {
+            let mut state = 0;
+            for &bit in input {
+                state = *children.entry((state, bit)).or_insert_with(|| {
+                    num_states += 1;
+                    num_states - 1
+                });
+            }
+
+            if terminals.contains(&state) {
+                panic!("Each input must be listed only once");
+            }
+            terminals.push(state);
+        }
+
+        let mut state_table = children
+            .iter()
+            .map(|(&(state, bit), &child)| ((state, bit_symbol(bit)), (child, bit_symbol(bit), true)))
+            .collect::<Vec<Rule>>();
+
+        // each terminal erases its input moving left, steps back to 0, then writes its output moving right
+        let halt = num_states + table.iter().map(|(i, o)| i.len() as u64 + 1 + o.len() as u64).sum::<u64>();
+        let mut next = num_states;
+        for ((input, output), &terminal) in table.iter().zip(&terminals) {
+            state_table.push(((terminal, 0), (next, 0, false)));
+
+            let mut chain = Vec::default();
+            chain.extend(std::iter::repeat_n((0, false), input.len()));
+            chain.push((0, true));
+            chain.extend(output.iter().map(|&bit| (bit_symbol(bit), true)));
+
+            for (i, (write, right)) in chain.iter().enumerate() {
+                let state = next + i as u64;
+                let new_state = if i + 1 == chain.len() { halt } else { state + 1 };
+                for symbol in 0..3 {
+                    state_table.push(((state, symbol), (new_state, *write, *right)));
+                }
+            }
+            next += chain.len() as u64;
+        }
+
+        TransitionFn::new(&state_table)
+    }
+
+    /// Returns the state table of `self` in no particular order.
+    #[inline]
+    pub fn state_table(&self) -> Vec<Rule> {
+        self.map.iter().map(|x| (*x.0, *x.1)).collect()
+    }
+
+    /// Returns whether `self` is one of the tables `enumerate` produces for `num_states` and `num_symbols`:
+    /// it has a rule for every state below `num_states` and symbol below `num_symbols`,
+    /// and no rule references a state or symbol out of range, with state `num_states` as the halting state.
+    /// Like the other predicates, this makes a single pass over the rules without building anything.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (1, 1, false)), ((1, 0), (0, 1, false)), ((1, 1), (2, 1, true))]);
+    /// assert!(trans_fn.is_deterministic_complete_for(2, 2));
+    /// assert!(trans_fn.has_halting_rule(2));
+    /// 
+    /// // partial tables and tables with too few states or symbols are not
+    /// assert!(!trans_fn.is_deterministic_complete_for(3, 2));
+    /// assert!(!trans_fn.is_total_for(2, 3));
+    /// assert!(trans_fn.references_out_of_range(1, 2));
+    /// assert!(!TransitionFn::new(&[((0, 0), (1, 1, true))]).is_total_for(2, 2));
+    /// ```
+    #[inline]
+    pub fn is_deterministic_complete_for(&self, num_states: usize, num_symbols: usize) -> bool {
+        self.is_total_for(num_states, num_symbols) && !self.references_out_of_range(num_states, num_symbols)
+    }
+
+    /// Returns whether `self` has a rule for every state below `num_states` and symbol below `num_symbols`,
+    /// regardless of any other rules.
+    #[inline]
+    pub fn is_total_for(&self, num_states: usize, num_symbols: usize) -> bool {
+        let in_range = self.map.keys().filter(|&&(state, symbol)| state < num_states as u64 && symbol < num_symbols as u64).count();
+        num_states.checked_mul(num_symbols) == Some(in_range)
+    }
+
+    /// Returns whether a rule of `self` reads a state or symbol out of range, writes a symbol of at least `num_symbols`,
+    /// or goes to a state above `num_states`, which is the halting state.
+    #[inline]
+    pub fn references_out_of_range(&self, num_states: usize, num_symbols: usize) -> bool {
+        let (num_states, num_symbols) = (num_states as u64, num_symbols as u64);
+        self.map.iter().any(|(&(state, symbol), &(next, write, _))| {
+            state >= num_states || symbol >= num_symbols || next > num_states || write >= num_symbols
+        })
+    }
+
+    /// Returns whether a rule of `self` goes to a state of at least `num_states`,
+    /// i.e. whether `self` can halt by entering the halting state rather than only by missing a rule.
+    #[inline]
+    pub fn has_halting_rule(&self, num_states: usize) -> bool {
+        self.map.values().any(|&(next, _, _)| next >= num_states as u64)
+    }
+
+    /// Returns `self` with every symbol `s` it reads or writes replaced by `f(s)`, keeping annotations,
+    /// so that it runs on tapes re-encoded with `Tape::map_symbols` and the same `f` exactly as `self` runs on the originals.
+    /// `f` must fix the blank symbol and map distinct symbols of `self` to distinct symbols.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // swap the roles of 1 and 2
+    /// let swap = |s| [0, 2, 1][s as usize];
+    /// let trans_fn = TransitionFn::new(&[((0, 1), (0, 2, true)), ((0, 2), (1, 1, true))]);
+    /// let mapped = trans_fn.map_symbols(swap).unwrap();
+    /// 
+    /// assert_eq!(mapped, TransitionFn::new(&[((0, 2), (0, 1, true)), ((0, 1), (1, 2, true))]));
+    /// 
+    /// let mut tape = Tape::new(vec![1, 1, 2]);
+    /// let mut mapped_tape = tape.map_symbols(swap).unwrap();
+    /// TuringMachine::new(trans_fn).run(&mut tape);
+    /// TuringMachine::new(mapped).run(&mut mapped_tape);
+    /// assert_eq!(tape.map_symbols(swap), Ok(mapped_tape));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns a `SymbolMapError` if `f` maps the blank symbol to another symbol or is not injective on the symbols of `self`.
+    #[inline]
+    pub fn map_symbols(&self, f: impl Fn(u64) -> u64) -> Result<Self, SymbolMapError> {
+        let images = alphabet::map_symbols(self.map.iter().flat_map(|((_, y), (_, w, _))| [*y, *w]), f)?;
+
+        let map = self.map
+            .iter()
+            .map(|(&(s, y), &(n, w, r))| ((s, images[&y]), (n, images[&w], r)))
+            .collect();
+        let annotations = self.annotations
+            .iter()
+            .map(|(&(s, y), note)| ((s, images[&y]), note.clone()))
+            .collect();
+
+        Ok(TransitionFn {
+            map: Arc::new(map),
+            annotations: Arc::new(annotations),
+            hot: Arc::default(),
+        })
+    }
+
+    /// Returns a fingerprint of `self`: a 64-bit FNV-1a hash of its state table sorted by key.
+    /// Equal transition functions have equal fingerprints regardless of the order in which
+    /// their rules were given, and the value is stable across platforms and versions of this crate,
+    /// so it can be used to match recordings and other artifacts to the machine that produced them.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let trans_fn1 = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]);
+    /// let trans_fn2 = TransitionFn::new(&[((1, 0), (0, 1, false)), ((0, 0), (1, 1, true))]);
+    /// let trans_fn3 = TransitionFn::new(&[((1, 0), (0, 1, false)), ((0, 0), (1, 1, false))]);
+    /// 
+    /// assert_eq!(trans_fn1.fingerprint(), trans_fn2.fingerprint());
+    /// assert_ne!(trans_fn1.fingerprint(), trans_fn3.fingerprint());
+    /// ```
+    #[inline]
+    pub fn fingerprint(&self) -> u64 {
+        let mut state_table = self.state_table();
+        state_table.sort_unstable_by_key(|x| x.0);
+
+        let mut hash = 0xcbf29ce484222325u64;
+        for ((s, y), (n, w, r)) in state_table {
+            for byte in [s, y, n, w, r as u64].iter().flat_map(|x| x.to_le_bytes()) {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+
+        hash
+    }
+
+    /// Encodes `self` as a sequence of the symbols `1`, `2` and `3`, so that it can be written onto a tape
+    /// and read by another machine.
+    /// 
+    /// Each rule `((s, y), (n, w, r))`, in order of key, is written as its five fields in unary
+    /// (a field `k` is `k` copies of `ENCODING_TALLY`, and a right move is a single tally while a left move is none),
+    /// separated by `ENCODING_FIELD_SEP` and followed by `ENCODING_RULE_END`.
+    /// The encoding never contains the blank symbol, so it occupies a single contiguous block of the tape.
+    /// Since it is written from location 0, it may be at most `MAX_LOCATION + 1` symbols long,
+    /// which bounds the sum of all fields; large states or symbols quickly exceed that.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 1), (2, 0, true))]);
+    /// 
+    /// assert_eq!(trans_fn.encode(), Ok(vec![2, 1, 2, 1, 1, 2, 2, 1, 3]));
+    /// assert_eq!(TransitionFn::decode(&trans_fn.encode().unwrap()), Ok(trans_fn));
+    /// 
+    /// let too_large = TransitionFn::new(&[((0, 0), (1 << 26, 1, true))]);
+    /// assert_eq!(too_large.encode(), Err(TapeError::LocationOutOfRange { location: (1 << 26) + 6 }));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `TapeError::LocationOutOfRange` with the location the last symbol would occupy
+    /// if the encoding is longer than `MAX_LOCATION + 1` symbols.
+    #[inline]
+    pub fn encode(&self) -> Result<Vec<u64>, TapeError> {
+        let mut state_table = self.state_table();
+        state_table.sort_unstable_by_key(|x| x.0);
+
+        // each rule takes its fields, four separators and an end
+        let len = state_table
+            .iter()
+            .flat_map(|((s, y), (n, w, r))| [*s, *y, *n, *w, *r as u64, 5])
+            .fold(0u64, u64::saturating_add);
+        if len > MAX_LOCATION as u64 + 1 {
+            return Err(TapeError::LocationOutOfRange { location: i64::try_from(len - 1).unwrap_or(i64::MAX) });
+        }
+
+        let mut encoding = Vec::with_capacity(len as usize);
+        for ((s, y), (n, w, r)) in state_table {
+            for (i, field) in [s, y, n, w, r as u64].into_iter().enumerate() {
+                if i > 0 {
+                    encoding.push(ENCODING_FIELD_SEP);
+                }
+                encoding.extend(std::iter::repeat_n(ENCODING_TALLY, field as usize));
+            }
+            encoding.push(ENCODING_RULE_END);
+        }
+
+        Ok(encoding)
+    }
+
+    /// Decodes a `TransitionFn` from the format produced by `encode`.
+    /// 
+    /// # Errors
+    /// Returns an error if `symbols` is not a sequence of complete rules, if a rule does not have exactly five fields,
+    /// if a move field is greater than 1, or if two rules share a key.
+    #[inline]
+    pub fn decode(symbols: &[u64]) -> Result<Self, ParseMachineError> {
+        let symbols = match symbols.split_last() {
+            None => return Ok(TransitionFn::default()),
+            Some((&ENCODING_RULE_END, rest)) => rest,
+            Some(_) => return Err(ParseMachineError::new("encoding does not end with a complete rule")),
+        };
+
+        let mut state_table = Vec::new();
+        let mut keys = HashSet::new();
+        for (i, rule) in symbols.split(|&x| x == ENCODING_RULE_END).enumerate() {
+            let fields = rule
+                .split(|&x| x == ENCODING_FIELD_SEP)
+                .map(|field| match field.iter().find(|&&x| x != ENCODING_TALLY) {
+                    Some(x) => Err(ParseMachineError::new(format!("unexpected symbol {} in rule {}", x, i))),
+                    None => Ok(field.len() as u64),
+                })
+                .collect::<Result<Vec<u64>, _>>()?;
+
+            if fields.len() != 5 {
+                return Err(ParseMachineError::new(format!("rule {} has {} fields instead of 5", i, fields.len())));
+            }
+            if fields[4] > 1 {
+                return Err(ParseMachineError::new(format!("rule {} has an invalid head movement", i)));
+            }
+            if !keys.insert((fields[0], fields[1])) {
+                return Err(ParseMachineError::new(format!("duplicate key in rule {}", i)));
+            }
+            state_table.push(((fields[0], fields[1]), (fields[2], fields[3], fields[4] == 1)));
+        }
+
+        Ok(TransitionFn::new(&state_table))
+    }
+
+    /// Returns a tape holding the encoding of `self` starting at location 0; see `encode`.
+    /// 
+    /// # Errors
+    /// Returns an error under the same conditions as `encode`.
+    #[inline]
+    pub fn to_tape(&self) -> Result<Tape, TapeError> {
+        Ok(Tape::new(self.encode()?))
+    }
+
+    /// Decodes a `TransitionFn` from the nonblank region of `tape`; see `decode`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]);
+    /// 
+    /// assert_eq!(TransitionFn::from_tape(&trans_fn.to_tape().unwrap()), Ok(trans_fn));
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns an error under the same conditions as `decode`.
+    #[inline]
+    pub fn from_tape(tape: &Tape) -> Result<Self, ParseMachineError> {
+        Self::decode(&tape.symbols())
+    }
+
+    /// Renders `self` like its `Display` implementation, but with symbols named by `alphabet`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 2, true)), ((1, 2), (0, 1, false))]);
+    /// 
+    /// assert_eq!(trans_fn.render(&Alphabet::new(&["_", "a", "b"])), "[0,_ -> 1,b,R; 1,b -> 0,a,L]");
+    /// ```
+    #[inline]
+    pub fn render(&self, alphabet: &Alphabet) -> String {
+        let mut state_table = self.state_table();
+        state_table.sort_unstable_by_key(|x| x.0);
+
+        let rules = state_table
+            .iter()
+            .map(|((s, y), (n, w, r))| {
+                let note = self.annotation(*s, *y).map(|x| format!(" # {}", x)).unwrap_or_default();
+                format!("{},{} -> {},{},{}{}", s, alphabet.name(*y), n, alphabet.name(*w), Move::from(*r), note)
+            })
+            .collect::<Vec<String>>();
+
+        format!("[{}]", rules.join("; "))
+    }
+
+    /// Describes every rule of `self` in English, one line per rule, grouped by state in ascending order.
+    /// Annotated rules are followed by `# ` and their annotation.
+    /// Equivalent to `explain_with` with the default alphabet and no state labels.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 0, false)), ((1, 0), (2, 1, false))]);
+    /// 
+    /// assert_eq!(trans_fn.explain(), "\
+    /// State 0:
+    ///   In state 0 reading 0: write 1, move right, go to state 1
+    ///   In state 0 reading 1: write 0, move left, go to state 0
+    /// State 1:
+    ///   In state 1 reading 0: write 1, move left, go to state 2 and halt
+    /// ");
+    /// ```
+    #[inline]
+    pub fn explain(&self) -> String {
+        self.explain_with(&Alphabet::default(), &[])
+    }
+
+    /// Equivalent to `explain`, but names symbols with `alphabet` and states with `state_labels`,
+    /// indexed by state, such as `SmartBuilder::labels`. States without a label are named by their number.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut builder = SmartBuilder::new(2, "start");
+    /// builder.halt_state("done").rule("start", 0, "done", 1, true);
+    /// 
+    /// assert_eq!(builder.build().0.explain_with(&Alphabet::new(&["_", "x"]), &builder.labels()), "\
+    /// State start:
+    ///   In state start reading _: write x, move right, go to state done and halt
+    /// ");
+    /// ```
+    #[inline]
+    pub fn explain_with(&self, alphabet: &Alphabet, state_labels: &[String]) -> String {
+        let label = |state: u64| state_labels.get(state as usize).cloned().unwrap_or_else(|| state.to_string());
+        let mut state_table = self.state_table();
+        state_table.sort_unstable_by_key(|x| x.0);
+        // a rule going to a state without any rules is the last one a run takes
+        let ruled_states = state_table.iter().map(|x| x.0 .0).collect::<HashSet<u64>>();
+
+        let mut out = String::new();
+        for (i, ((s, y), (n, w, r))) in state_table.iter().enumerate() {
+            if i == 0 || state_table[i - 1].0 .0 != *s {
+                out.push_str(&format!("State {}:\n", label(*s)));
+            }
+
+            let halts = if ruled_states.contains(n) { "" } else { " and halt" };
+            let direction = if *r { "right" } else { "left" };
+            let note = self.annotation(*s, *y).map(|x| format!(" # {}", x)).unwrap_or_default();
+            out.push_str(&format!(
+                "  In state {} reading {}: write {}, move {}, go to state {}{}{}\n",
+                label(*s), alphabet.name(*y), alphabet.name(*w), direction, label(*n), halts, note,
+            ));
+        }
+
+        out
+    }
+
+    /// Writes `self` as CSV with the header `state,read,next_state,write,move` and one row per rule, sorted by key,
+    /// naming symbols with `alphabet` and states with `state_labels` like `explain_with`.
+    /// If any rule is annotated, a sixth column `note` holds the annotations.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]);
+    /// let mut csv = Vec::new();
+    /// trans_fn.to_csv(&mut csv, &Alphabet::new(&["_", "x"]), &[]).unwrap();
+    /// 
+    /// assert_eq!(String::from_utf8(csv).unwrap(), "state,read,next_state,write,move\n0,_,1,x,R\n1,_,0,x,L\n");
+    /// ```
+    /// 
+    /// # Errors
+    /// Passes on any error from `writer`.
+    #[inline]
+    pub fn to_csv(&self, writer: &mut impl Write, alphabet: &Alphabet, state_labels: &[String]) -> io::Result<()> {
+        let label = |state: u64| state_labels.get(state as usize).cloned().unwrap_or_else(|| state.to_string());
+        let mut state_table = self.state_table();
+        state_table.sort_unstable_by_key(|x| x.0);
+
+        let annotated = !self.annotations.is_empty();
+        writeln!(writer, "state,read,next_state,write,move{}", if annotated { ",note" } else { "" })?;
+        for ((s, y), (n, w, r)) in state_table {
+            let mut fields = vec![label(s), alphabet.name(y), label(n), alphabet.name(w), Move::from(r).to_string()];
+            if annotated {
+                fields.push(self.annotation(s, y).unwrap_or_default().to_string());
+            }
+            writeln!(writer, "{}", fields.iter().map(|x| csv_field(x)).collect::<Vec<String>>().join(","))?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads a `TransitionFn` from CSV with the columns `state,read,next_state,write,move`
+    /// and an optional sixth column holding annotations, as exported by most spreadsheets.
+    /// 
+    /// The first row is skipped if it is a header naming those columns, in any case and with spaces or underscores,
+    /// and the sixth `note` or `annotation`, so header rows are optional; blank rows are ignored. Moves are `L`, `R`, `left`, or `right`, in any case.
+    /// Symbols are looked up in `alphabet`, falling back to numbers. States are numbers unless any of them
+    /// is not, in which case every state is a label, numbered by first appearance so the first row's state is 0.
+    /// Returns the machine along with those labels, indexed by state, or no labels if states are numbers.
     /// 
     /// # Examples
     /// ```
-    /// # use turing_machine::transition_fn::TransitionFn;
-    /// let v = vec![((1, 19), (30, 12, false)), ((22, 79), (35, 3, true))];
+    /// use turing_machine::prelude::*;
     /// 
-    /// let trans_fn = TransitionFn::new(&v);
+    /// let csv = "State,Read,Next State,Write,Move\nstart,_,start,x,right\nstart,x,done,x,L\n";
+    /// let (trans_fn, labels) = TransitionFn::from_csv(csv.as_bytes(), &Alphabet::new(&["_", "x"])).unwrap();
+    /// 
+    /// assert_eq!(labels, ["start", "done"]);
+    /// assert_eq!(trans_fn, TransitionFn::new(&[((0, 0), (0, 1, true)), ((0, 1), (1, 1, false))]));
     /// ```
+    /// 
+    /// # Errors
+    /// Returns an error of kind `InvalidData` naming the offending line if a row has the wrong number of columns,
+    /// an unknown symbol, an invalid move or annotation, or a key already used by an earlier row,
+    /// and passes on any error from `reader`.
     #[inline]
-    pub fn new(state_table: &Vec<((u64, u64), (u64, u64, bool))>) -> Self {
-        TransitionFn {
-            map: HashMap::from_iter(state_table
-                    .iter()
-                    .map(|x| *x)
-                    .scan(HashSet::new(), |state: &mut HashSet<(u64, u64)>, x| {
-                        if state.contains(&x.0) {
-                            panic!()
-                        }
-
-                        state.insert(x.0);
+    pub fn from_csv(reader: impl BufRead, alphabet: &Alphabet) -> io::Result<(Self, Vec<String>)> {
+        let invalid = |line: usize, message: String| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line, message));
 
-                        Some(x)
-                    }
-                )
-            ),
+        let mut rows = Vec::new();
+        let mut seen_first = false;
+        for (i, line) in reader.lines().enumerate() {
+            let fields = split_csv_row(&line?);
+            if fields.iter().all(String::is_empty) {
+                continue;
+            }
+            if fields.len() != 5 && fields.len() != 6 {
+                return Err(invalid(i + 1, format!("expected 5 or 6 columns, found {}", fields.len())));
+            }
+            if !seen_first && is_csv_header(&fields) {
+                seen_first = true;
+                continue;
+            }
+            seen_first = true;
+            rows.push((i + 1, fields));
         }
-    }
 
-    /// Enumerates all possible turing machines with the specified number of states and symbols.
-    /// This is an extremely expensive function with the output vector having length O(n^(n^2))
-    /// where n is both the number of states and the number of symbols.
-    #[inline]
-    pub fn enumerate(num_states: usize, num_symbols: usize) -> Vec<Self> {
-        let mut fns = Vec::default();
-        let mut keys = Vec::default();
-        let mut values = Vec::default();
+        let numeric = rows.iter().all(|(_, f)| f[0].parse::<u64>().is_ok() && f[2].parse::<u64>().is_ok());
+        let mut labels = Vec::new();
+        let mut ids = HashMap::new();
+        let mut state_id = |name: &str| {
+            if numeric {
+                return name.parse::<u64>().unwrap();
+            }
+            *ids.entry(name.to_string()).or_insert_with(|| {
+                labels.push(name.to_string());
+                labels.len() as u64 - 1
+            })
+        };
+        let symbol = |line: usize, name: &str| alphabet
+            .symbol(name)
+            .or_else(|| name.parse().ok())
+            .ok_or_else(|| invalid(line, format!("unknown symbol `{}`", name)));
 
-        for t in 0..num_states {
-            for y in 0..num_symbols {
-                // this level contains all possible keys
-                keys.push((t as u64, y as u64));
+        let mut state_table = Vec::new();
+        let mut notes = Vec::new();
+        let mut keys = HashSet::new();
+        for (line, fields) in &rows {
+            let key = (state_id(&fields[0]), symbol(*line, &fields[1])?);
+            let output = (
+                state_id(&fields[2]),
+                symbol(*line, &fields[3])?,
+                parse_csv_move(&fields[4]).ok_or_else(|| invalid(*line, format!("invalid move `{}`", fields[4])))?,
+            );
+            if !keys.insert(key) {
+                return Err(invalid(*line, "duplicate state and symbol".to_string()));
             }
-        }
+            state_table.push((key, output));
 
-        for n in 0..num_states + 1 {
-            for w in 0..num_symbols {
-                for b in 0..2 {
-                    // this level contains all possible values
-                    values.push((n as u64, w as u64, b != 0));
+            match fields.get(5).filter(|x| !x.is_empty()) {
+                Some(note) if note.contains([';', ']', '\n', '\r']) => {
+                    return Err(invalid(*line, "annotations can not contain `;` or `]`".to_string()));
                 }
+                Some(note) => notes.push((key, note)),
+                None => {}
             }
         }
 
-        let value_permutations = permute_with_repetition(&values, keys.len()); 
-        for f in 0..value_permutations.len() {
-            // this level contains all possible transition tables
-            let mut state_table = Vec::default();
+        let trans_fn = notes
+            .into_iter()
+            .fold(TransitionFn::new(&state_table), |trans_fn, ((state, symbol), note)| trans_fn.with_annotation(state, symbol, note));
 
-            for k in 0..keys.len() {
-                state_table.push((keys[k], value_permutations[f][k]));
-            }
+        Ok((trans_fn, labels))
+    }
 
-            fns.push(TransitionFn::new(&state_table));
+    /// Returns a dense copy of `self` for the fast path of `TuringMachine::run` and friends,
+    /// or `None` if `self` reads or writes a symbol above 2 or its states are too large or too many.
+    #[inline]
+    pub(crate) fn flat_table(&self) -> Option<FlatTable> {
+        let mut num_symbols = 2;
+        let mut rows = 0;
+        for (&(state, symbol), &(new_state, write, _)) in self.map.iter() {
+            if symbol > 2 || write > 2 || state >= FlatTable::MAX_ENTRIES as u64 || new_state > FlatTable::MAX_STATE {
+                return None;
+            }
+            if symbol == 2 || write == 2 {
+                num_symbols = 3;
+            }
+            rows = rows.max(state as usize + 1);
+        }
+        if rows * num_symbols > FlatTable::MAX_ENTRIES {
+            return None;
         }
 
-        fns
-    }
+        let mut entries = vec![0; rows * num_symbols];
+        for (&(state, symbol), &(new_state, write, right)) in self.map.iter() {
+            entries[state as usize * num_symbols + symbol as usize] = (new_state as u32) << 4 | (write as u32) << 2 | (right as u32) << 1 | 1;
+        }
 
-    /// Returns the state table of `self` in no particular order.
-    #[inline]
-    pub fn state_table(&self) -> Vec<((u64, u64), (u64, u64, bool))> {
-        self.map.iter().map(|x| (*x.0, *x.1)).collect()
+        Some(FlatTable { entries, num_symbols })
     }
 
     /// Runs `self` with the specified state and symbol and returns `Some((u64, u64, bool))`
@@ -117,10 +1045,257 @@ impl TransitionFn {
     /// ```
     #[inline]
     pub fn run(&self, state: u64, symbol: u64) -> Option<(u64, u64, bool)> {
+        for &(key, output) in self.hot.iter() {
+            if key == (state, symbol) {
+                return Some(output);
+            }
+        }
+
         self.map.get(&(state, symbol)).copied()
     }
+
+    /// Returns `self` with the rules that fired most often in `profile` moved to the front of its lookup path,
+    /// where `run` finds them with a few comparisons instead of hashing its arguments. At most 4 rules are moved,
+    /// and only those that make up at least a tenth of the profiled steps, so a profile of another machine or of
+    /// a run without dominant rules leaves lookups as they were. The rules themselves, and thus equality,
+    /// `Display` and `fingerprint`, are unchanged.
+    /// 
+    /// Worth it for machines run for billions of steps whose tape or rules use more than 3 symbols,
+    /// since `TuringMachine::run_with_halt_setting` already runs smaller machines on a flat array.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// use turing_machine::profile::profile;
+    /// 
+    /// // scans right over a block of 4s, then halts
+    /// let trans_fn = TransitionFn::new(&[((0, 4), (0, 4, true)), ((0, 0), (1, 0, false)), ((0, 3), (1, 3, false))]);
+    /// let tape = Tape::new(vec![4; 1000]);
+    /// let profile = profile(&TuringMachine::new(trans_fn.clone()), &tape, 10_000);
+    /// 
+    /// let optimized = trans_fn.clone().optimize_layout(&profile);
+    /// 
+    /// assert_eq!(optimized.hot_rules(), [((0, 4), (0, 4, true))]);
+    /// assert_eq!(optimized, trans_fn);
+    /// assert_eq!(optimized.run(0, 0), trans_fn.run(0, 0));
+    /// ```
+    #[inline]
+    pub fn optimize_layout(mut self, profile: &TransitionProfile) -> Self {
+        const MAX_HOT_RULES: usize = 4;
+
+        self.hot = profile
+            .hot_rules()
+            .into_iter()
+            .filter(|&(_, count)| count * 10 >= profile.steps)
+            .filter_map(|(key, _)| self.map.get(&key).map(|&output| (key, output)))
+            .take(MAX_HOT_RULES)
+            .collect();
+        self
+    }
+
+    /// Returns the rules `run` checks first, hottest first; see `optimize_layout`.
+    #[inline]
+    pub fn hot_rules(&self) -> &[Rule] {
+        &self.hot
+    }
+}
+
+impl PartialEq for TransitionFn {
+    /// Compares the rules and annotations of `self` and `other`, regardless of their layout.
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.map == other.map && self.annotations == other.annotations
+    }
+}
+
+impl TryFrom<Vec<Rule>> for TransitionFn {
+    type Error = DuplicateRuleError;
+
+    #[inline]
+    fn try_from(state_table: Vec<Rule>) -> Result<Self, Self::Error> {
+        TransitionFn::from_rules(state_table)
+    }
+}
+
+impl TryFrom<&[Rule]> for TransitionFn {
+    type Error = DuplicateRuleError;
+
+    #[inline]
+    fn try_from(state_table: &[Rule]) -> Result<Self, Self::Error> {
+        TransitionFn::from_rules(state_table.iter().copied())
+    }
+}
+
+impl fmt::Display for TransitionFn {
+    /// Formats `self` as its state table sorted by key, e.g. `[0,0 -> 1,1,R; 1,0 -> 0,1,L]`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(&Alphabet::default()))
+    }
+}
+
+impl FromStr for TransitionFn {
+    type Err = ParseMachineError;
+
+    /// Parses a `TransitionFn` from the format produced by its `Display` implementation,
+    /// in which any rule may be followed by a `#` comment that becomes its annotation.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::transition_fn::TransitionFn;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]);
+    /// 
+    /// assert_eq!(trans_fn.to_string().parse::<TransitionFn>(), Ok(trans_fn));
+    /// ```
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let inner = s.trim()
+            .strip_prefix('[')
+            .and_then(|x| x.strip_suffix(']'))
+            .ok_or_else(|| ParseMachineError::new("state table must be enclosed in brackets"))?;
+
+        let mut state_table = Vec::default();
+        let mut notes = Vec::new();
+        let mut keys = HashSet::new();
+        for rule in inner.split(';').map(str::trim).filter(|x| !x.is_empty()) {
+            let (rule, note) = match rule.split_once('#') {
+                Some((rule, note)) => (rule.trim(), Some(note.trim())),
+                None => (rule, None),
+            };
+            let (key, value) = rule
+                .split_once("->")
+                .ok_or_else(|| ParseMachineError::new(format!("rule `{}` is missing `->`", rule)))?;
+
+            let key = parse_fields(key, 2, rule)?;
+            let value = value.split(',').map(str::trim).collect::<Vec<&str>>();
+            if value.len() != 3 {
+                return Err(ParseMachineError::new(format!("rule `{}` must have 3 output fields", rule)));
+            }
+            let output = parse_fields(&value[..2].join(","), 2, rule)?;
+            let mut chars = value[2].chars();
+            let right = match (chars.next().map(Move::try_from), chars.next()) {
+                (Some(Ok(Move::Stay)), None) => {
+                    return Err(ParseMachineError::new(format!("rule `{}` must move the head left or right", rule)));
+                }
+                (Some(Ok(head_move)), None) => head_move == Move::Right,
+                _ => return Err(ParseMachineError::new(format!("invalid head movement `{}` in rule `{}`", value[2], rule))),
+            };
+
+            if !keys.insert((key[0], key[1])) {
+                return Err(ParseMachineError::new(format!("duplicate key in rule `{}`", rule)));
+            }
+            state_table.push(((key[0], key[1]), (output[0], output[1], right)));
+            if let Some(note) = note {
+                notes.push((key[0], key[1], note));
+            }
+        }
+
+        Ok(notes
+            .into_iter()
+            .fold(TransitionFn::new(&state_table), |trans_fn, (state, symbol, note)| trans_fn.with_annotation(state, symbol, note)))
+    }
+}
+
+/// Splits a CSV row into trimmed fields, honoring double quotes.
+fn split_csv_row(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                fields.last_mut().unwrap().push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            _ => fields.last_mut().unwrap().push(c),
+        }
+    }
+
+    fields.iter().map(|x| x.trim().to_string()).collect()
+}
+
+/// Quotes a CSV field if it would otherwise be read back differently.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"']) || field.trim() != field {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    }
+    else {
+        field.to_string()
+    }
+}
+
+/// Returns whether `fields` name the columns read by `TransitionFn::from_csv`, as in the header written by `to_csv`.
+fn is_csv_header(fields: &[String]) -> bool {
+    const COLUMNS: [&[&str]; 6] = [&["state"], &["read"], &["next_state"], &["write"], &["move"], &["note", "notes", "annotation", "annotations"]];
+
+    fields.iter().zip(COLUMNS).all(|(field, names)| names.contains(&field.to_ascii_lowercase().replace(' ', "_").as_str()))
+}
+
+/// Parses a move column of a CSV row, where the head must move left or right.
+fn parse_csv_move(field: &str) -> Option<bool> {
+    match field.to_ascii_lowercase().as_str() {
+        "l" | "left" => Some(false),
+        "r" | "right" => Some(true),
+        _ => None,
+    }
+}
+
+/// Parses exactly `n` comma-separated `u64`s.
+fn parse_fields(s: &str, n: usize, rule: &str) -> Result<Vec<u64>, ParseMachineError> {
+    let fields = s.split(',')
+        .map(|x| x.trim().parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|e| ParseMachineError::new(format!("invalid number in rule `{}`: {}", rule, e)))?;
+
+    if fields.len() != n {
+        return Err(ParseMachineError::new(format!("rule `{}` has a malformed side", rule)));
+    }
+
+    Ok(fields)
+}
+
+/// An error returned by `TransitionFn::from_rules` when two rules share the key `(state, symbol)`,
+/// which a deterministic transition function cannot have.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DuplicateRuleError {
+    pub state: u64,
+    pub symbol: u64,
+}
+
+impl fmt::Display for DuplicateRuleError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate rule for state {} and symbol {}", self.state, self.symbol)
+    }
+}
+
+impl std::error::Error for DuplicateRuleError {}
+
+/// An error returned when parsing a `TransitionFn` or `TuringMachine` from a string fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseMachineError {
+    message: String,
+}
+
+impl ParseMachineError {
+    #[inline]
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        ParseMachineError { message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseMachineError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to parse machine: {}", self.message)
+    }
 }
 
+impl std::error::Error for ParseMachineError {}
+
 fn permute_with_repetition<T: Clone>(vec: &[T], n: usize) -> Vec<Vec<T>> {
     if n == 0 {
         return vec![vec![]]; // Base case: empty permutation
@@ -165,10 +1340,119 @@ impl Hasher for PairingHasher {
 
 pub(super) type PairingBuildHasher = BuildHasherDefault<PairingHasher>;
 
+type StateMap = HashMap<(u64, u64), (u64, u64, bool), PairingBuildHasher>;
+
+/// A transition function over 2 or 3 symbols laid out as a flat array, along with a run loop
+/// over a dense byte copy of the tape, which avoids hashing on every step.
+#[derive(Clone, Debug)]
+pub(crate) struct FlatTable {
+    /// Indexed by `state * num_symbols + symbol`: 0 if there is no rule,
+    /// or else `new_state << 4 | write << 2 | right << 1 | 1`.
+    entries: Vec<u32>,
+    num_symbols: usize,
+}
+
+impl FlatTable {
+    const MAX_ENTRIES: usize = 1 << 12;
+    const MAX_STATE: u64 = (1 << 28) - 1;
+
+    /// Equivalent to `TransitionFn::run`.
+    #[inline]
+    pub(crate) fn get(&self, state: u64, symbol: u64) -> Option<(u64, u64, bool)> {
+        if symbol as usize >= self.num_symbols || state >= (self.entries.len() / self.num_symbols) as u64 {
+            return None;
+        }
+
+        match self.entries[state as usize * self.num_symbols + symbol as usize] {
+            0 => None,
+            entry => Some(((entry >> 4) as u64, (entry >> 2 & 3) as u64, entry & 2 != 0)),
+        }
+    }
+
+    /// Returns whether running on `tape` for at most `max_steps` steps is worth copying it into and out of a flat buffer,
+    /// which takes time proportional to its length however few steps are taken.
+    #[inline]
+    pub(crate) fn pays_off(tape: &Tape, max_steps: usize) -> bool {
+        max_steps >= tape.raw_symbols().len()
+    }
+
+    /// Runs from `state` and `head_loc` on `tape` for at most `max_steps` steps, until no rule matches,
+    /// or until the head would move farther than `MAX_LOCATION` from location 0, updating all three,
+    /// and returns the number of steps taken. Callers should check `pays_off` first.
+    /// Returns `None` without changing anything if `tape` holds a symbol outside of the table's alphabet.
+    #[inline]
+    pub(crate) fn run(&self, tape: &mut Tape, state: &mut u64, head_loc: &mut i64, max_steps: usize) -> Option<usize> {
+        let range = match tape.nonblank_range() {
+            Some(range) => *range.start().min(head_loc)..=*range.end().max(head_loc),
+            None => *head_loc..=*head_loc,
+        };
+        if range.start().unsigned_abs() > MAX_LOCATION as u64 || range.end().unsigned_abs() > MAX_LOCATION as u64 {
+            return None;
+        }
+        let mut start = *range.start();
+        let mut cells = Vec::with_capacity(range.clone().count().max(16));
+        for n in range {
+            let symbol = tape.symbol_at_n(n);
+            if symbol as usize >= self.num_symbols {
+                return None;
+            }
+            cells.push(symbol as u8);
+        }
+
+        let rows = (self.entries.len() / self.num_symbols) as u64;
+        let mut pos = (*head_loc - start) as usize;
+        let mut current = *state;
+        let mut steps = 0;
+        while steps < max_steps && current < rows {
+            let entry = self.entries[current as usize * self.num_symbols + cells[pos] as usize];
+            if entry == 0 {
+                break;
+            }
+            // leave steps beyond the reach of a tape to the caller, so the buffer never grows past it
+            let next_loc = start + pos as i64 + if entry & 2 != 0 { 1 } else { -1 };
+            if next_loc.unsigned_abs() > MAX_LOCATION as u64 {
+                break;
+            }
+
+            cells[pos] = (entry >> 2 & 3) as u8;
+            current = (entry >> 4) as u64;
+            if entry & 2 != 0 {
+                pos += 1;
+                if pos == cells.len() {
+                    cells.resize((cells.len() * 2).min((MAX_LOCATION - start + 1) as usize), 0);
+                }
+            }
+            else {
+                if pos == 0 {
+                    let grown = cells.len().min((start + MAX_LOCATION) as usize);
+                    cells.splice(0..0, std::iter::repeat_n(0, grown));
+                    pos = grown;
+                    start -= grown as i64;
+                }
+                pos -= 1;
+            }
+            steps += 1;
+        }
+
+        for (n, &symbol) in (start..).zip(&cells) {
+            if tape.symbol_at_n(n) != symbol as u64 {
+                tape.write(n, symbol as u64);
+            }
+        }
+        *state = current;
+        *head_loc = start + pos as i64;
+
+        Some(steps)
+    }
+}
+
+type Annotations = HashMap<(u64, u64), String>;
+
 #[allow(unused_imports)]
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
+    use std::sync::Arc;
 
     use crate::prelude::*;
 
@@ -181,14 +1465,38 @@ mod tests {
         map.insert((1, 2), (3, 2, false));
         map.insert((6, 7), (7, 8, true));
     
-        assert_eq!(trans_fn, TransitionFn { map });
+        assert_eq!(trans_fn, TransitionFn { map: Arc::new(map), annotations: Arc::default(), hot: Arc::default() });
+    }
+
+    #[test]
+    fn test_from_rules_and_try_from() {
+        let v = vec![((1, 2), (3, 2, false)), ((6, 7), (7, 8, true))];
+
+        assert_eq!(TransitionFn::from_rules(v.clone()), Ok(TransitionFn::new(&v)));
+        assert_eq!(TransitionFn::try_from(v.as_slice()), Ok(TransitionFn::new(&v)));
+        assert_eq!(TransitionFn::try_from(v), Ok(TransitionFn::new(&[((6, 7), (7, 8, true)), ((1, 2), (3, 2, false))])));
+        assert_eq!(TransitionFn::from_rules([]), Ok(TransitionFn::default()));
+
+        let duplicated = vec![((0, 0), (1, 1, true)), ((1, 0), (0, 1, true)), ((0, 0), (0, 0, false))];
+        let error = TransitionFn::try_from(duplicated).unwrap_err();
+        assert_eq!(error, DuplicateRuleError { state: 0, symbol: 0 });
+        assert_eq!(error.to_string(), "duplicate rule for state 0 and symbol 0");
+    }
+
+    #[test]
+    fn test_clone_shares_table() {
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]));
+        let clones = vec![machine.clone(); 8];
+
+        assert!(clones.iter().all(|m| m.transition_fn().shares_table_with(machine.transition_fn())));
+        assert_eq!(clones[0], machine);
     }
 
     #[test]
     #[should_panic]
     fn panic_test_new() {
         TransitionFn::new(
-            &vec![
+            &[
                 ((1, 23), (0, 15, true)),
                 ((1, 23), (1, 72, false)),
             ]
@@ -206,7 +1514,7 @@ mod tests {
     #[test]
     fn test_run() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((300, 23), (0, 15, true)),
                 ((1, 23), (1, 72, true)),
                 ((4, 2), (2, 49, true)),
@@ -225,4 +1533,228 @@ mod tests {
         assert_eq!(trans_fn.run(12, 111).unwrap(), (6, 87, true));
         assert_eq!(trans_fn.run(53, 23).unwrap(), (8, 0, false));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_enumeration_shards() {
+        let shards = TransitionFn::enumeration_shards(2, 2, 7);
+        assert_eq!(shards.len(), 7);
+        assert_eq!(shards.first().unwrap().start, 0);
+        assert_eq!(shards.last().unwrap().end, TransitionFn::enumeration_size(2, 2));
+        assert!(shards.windows(2).all(|w| w[0].end == w[1].start));
+        assert!(shards.iter().all(|r| r.end - r.start == 2962 || r.end - r.start == 2963));
+
+        assert_eq!(TransitionFn::enumeration_shards(1, 1, 8).iter().filter(|r| r.is_empty()).count(), 4);
+    }
+
+    #[test]
+    fn test_enumerated() {
+        let fns = TransitionFn::enumerate(2, 2);
+
+        for i in [0, 1, 11, 12, 1000, fns.len() - 1] {
+            assert_eq!(TransitionFn::enumerated(2, 2, i), fns[i]);
+            assert_eq!(TransitionFn::nth(2, 2, i as u128), Some(fns[i].clone()));
+        }
+        assert_eq!(TransitionFn::nth(2, 2, fns.len() as u128), None);
+
+        // 6 states and 4 symbols have 56^24 machines, more than fit in a u128
+        let trans_fn = TransitionFn::nth(6, 4, u128::MAX).unwrap();
+        assert!(trans_fn.is_deterministic_complete_for(6, 4));
+        assert_eq!(TransitionFn::nth(0, 0, 0), Some(TransitionFn::default()));
+    }
+
+    #[test]
+    fn test_optimize_layout() {
+        for seed in 0..50 {
+            let trans_fn = TransitionFn::random(4, 5, Seed(seed));
+            let machine = TuringMachine::new(trans_fn.clone());
+            let optimized = trans_fn.clone().optimize_layout(&crate::profile::profile(&machine, &Tape::default(), 1000));
+            assert!(optimized.hot_rules().len() <= 4);
+            for state in 0..5 {
+                for symbol in 0..6 {
+                    assert_eq!(optimized.run(state, symbol), trans_fn.run(state, symbol));
+                }
+            }
+
+            let (mut tape, mut optimized_tape) = (Tape::default(), Tape::default());
+            TuringMachine::new(trans_fn).run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(1000));
+            TuringMachine::new(optimized).run_with_halt_setting(&mut optimized_tape, HaltSetting::AfterSteps(1000));
+            assert_eq!(tape, optimized_tape);
+        }
+
+        // rules that fire rarely stay in the table
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, true))]);
+        let profile = crate::profile::TransitionProfile { steps: 100, rule_counts: HashMap::from([((0, 0), 95), ((1, 0), 5)]), ..Default::default() };
+        assert_eq!(trans_fn.optimize_layout(&profile).hot_rules(), [((0, 0), (1, 1, true))]);
+    }
+
+    #[test]
+    fn test_random_canonical() {
+        // the 32 canonical machines of 1 state and 2 symbols are drawn about equally often
+        let mut rng = Seed(3).rng();
+        let mut counts = HashMap::new();
+        for _ in 0..32_000 {
+            *counts.entry(TransitionFn::random_canonical(1, 2, &mut rng).fingerprint()).or_insert(0) += 1;
+        }
+        assert_eq!(counts.len(), 32);
+        assert!(counts.values().all(|&n| (800..1200).contains(&n)));
+
+        for _ in 0..100 {
+            let mut rules = TransitionFn::random_canonical(3, 2, &mut rng).state_table();
+            rules.sort_unstable_by_key(|x| x.0);
+            assert!(crate::enumeration::is_canonical(&rules, 3));
+        }
+    }
+
+    #[test]
+    fn test_table_predicates() {
+        assert!(TransitionFn::enumerate(2, 2).iter().all(|x| x.is_deterministic_complete_for(2, 2)));
+        assert_eq!(TransitionFn::enumerate(1, 2).iter().filter(|x| x.has_halting_rule(1)).count(), 64 - 16);
+
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 2, true))]);
+        assert!(trans_fn.is_total_for(1, 2) && !trans_fn.is_deterministic_complete_for(1, 2));
+        assert!(!trans_fn.references_out_of_range(1, 3));
+        assert!(TransitionFn::default().is_deterministic_complete_for(0, 5));
+        assert!(!TransitionFn::default().is_total_for(usize::MAX, usize::MAX));
+    }
+
+    #[test]
+    fn test_from_finite_fn() {
+        let table = [
+            (vec![], vec![true]),
+            (vec![true], vec![false, true]),
+            (vec![true, false], vec![]),
+            (vec![false, false, true], vec![true, true, false]),
+        ];
+        let trans_fn = TransitionFn::from_finite_fn(&table);
+
+        for (input, output) in &table {
+            let mut machine = TuringMachine::new(trans_fn.clone());
+            let mut tape = Tape::new(input.iter().map(|&b| b as u64 + 1).collect());
+            machine.run(&mut tape);
+
+            assert_eq!(tape, Tape::new(output.iter().map(|&b| b as u64 + 1).collect()));
+            assert_eq!(machine.state(), 22);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_from_finite_fn() {
+        TransitionFn::from_finite_fn(&[(vec![true], vec![]), (vec![true], vec![false])]);
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // fixed so that fingerprints stay stable across versions
+        assert_eq!(TransitionFn::default().fingerprint(), 0xcbf29ce484222325);
+        assert_eq!(TransitionFn::new(&[((0, 0), (1, 1, true))]).fingerprint(), 0x4A3243903BB24004);
+
+        let fingerprints = TransitionFn::enumerate(1, 2).iter().map(|x| x.fingerprint()).collect::<std::collections::HashSet<u64>>();
+        assert_eq!(fingerprints.len(), 64);
+    }
+
+    #[test]
+    fn test_display_and_from_str() {
+        let trans_fn = TransitionFn::new(&[((1, 0), (0, 1, false)), ((0, 0), (1, 2, true)), ((0, 2), (3, 0, true))]);
+
+        assert_eq!(trans_fn.to_string(), "[0,0 -> 1,2,R; 0,2 -> 3,0,R; 1,0 -> 0,1,L]");
+        assert_eq!(trans_fn.to_string().parse(), Ok(trans_fn));
+        assert_eq!("[]".parse(), Ok(TransitionFn::default()));
+
+        assert!("0,0 -> 1,1,R".parse::<TransitionFn>().is_err());
+        assert!("[0,0 -> 1,1,S]".parse::<TransitionFn>().is_err());
+        assert!("[0,0 -> 1,1,X]".parse::<TransitionFn>().is_err());
+        assert!("[0,0 -> 1,1,RR]".parse::<TransitionFn>().is_err());
+        assert!("[0,0 -> 1,1]".parse::<TransitionFn>().is_err());
+        assert!("[0,0 -> 1,1,R; 0,0 -> 2,1,L]".parse::<TransitionFn>().is_err());
+    }
+
+    #[test]
+    fn test_encode_and_decode() {
+        let trans_fn = TransitionFn::new(&[((1, 0), (0, 1, false)), ((0, 0), (1, 2, true)), ((0, 2), (3, 0, true))]);
+
+        let encoding = trans_fn.encode().unwrap();
+        assert_eq!(encoding, vec![2, 2, 1, 2, 1, 1, 2, 1, 3, 2, 1, 1, 2, 1, 1, 1, 2, 2, 1, 3, 1, 2, 2, 2, 1, 2, 3]);
+        assert_eq!(TransitionFn::decode(&encoding), Ok(trans_fn.clone()));
+        assert_eq!(TransitionFn::from_tape(&trans_fn.to_tape().unwrap()), Ok(trans_fn));
+
+        // the encoding must fit on a tape, however large the fields
+        let too_large = TransitionFn::new(&[((0, 0), ((1 << 26) + 5, 1, true))]);
+        assert_eq!(too_large.to_tape(), Err(TapeError::LocationOutOfRange { location: (1 << 26) + 11 }));
+        let too_large = TransitionFn::new(&[((0, 0), (u64::MAX, u64::MAX, true))]);
+        assert_eq!(too_large.encode(), Err(TapeError::LocationOutOfRange { location: i64::MAX }));
+        let fits = TransitionFn::new(&[((0, 0), ((1 << 26) - 5, 0, false))]);
+        assert_eq!(fits.encode().map(|x| x.len()), Ok(1 << 26));
+        assert_eq!(TransitionFn::decode(&[]), Ok(TransitionFn::default()));
+
+        assert!(TransitionFn::decode(&[2, 2, 2, 2]).is_err());
+        assert!(TransitionFn::decode(&[2, 2, 2, 3]).is_err());
+        assert!(TransitionFn::decode(&[2, 2, 2, 2, 1, 1, 3]).is_err());
+        assert!(TransitionFn::decode(&[2, 2, 2, 4, 2, 3]).is_err());
+        assert!(TransitionFn::decode(&[2, 2, 2, 2, 3, 2, 2, 1, 2, 1, 3]).is_err());
+    }
+
+    #[test]
+    fn test_explain_with_partial_labels() {
+        let trans_fn = TransitionFn::new(&[((1, 0), (0, 1, true)), ((0, 0), (1, 1, false))]);
+        let explained = trans_fn.explain_with(&Alphabet::default(), &["a".to_string()]);
+
+        assert_eq!(explained.lines().collect::<Vec<_>>(), [
+            "State a:",
+            "  In state a reading 0: write 1, move left, go to state 1",
+            "State 1:",
+            "  In state 1 reading 0: write 1, move right, go to state a",
+        ]);
+        assert_eq!(TransitionFn::default().explain(), "");
+    }
+
+    #[test]
+    fn test_annotations() {
+        let trans_fn = "[0,0 -> 1,1,R # start the block; 0,1 -> 0,1,R;1,0 -> 2,1,L #  done ]"
+            .parse::<TransitionFn>()
+            .unwrap();
+        assert_eq!(trans_fn.annotation(0, 0), Some("start the block"));
+        assert_eq!(trans_fn.annotation(0, 1), None);
+        assert_eq!(trans_fn.annotation(1, 0), Some("done"));
+        assert_eq!(trans_fn.to_string(), "[0,0 -> 1,1,R # start the block; 0,1 -> 0,1,R; 1,0 -> 2,1,L # done]");
+        assert!(trans_fn.explain().contains("go to state 2 and halt # done\n"));
+
+        // annotations do not affect execution
+        let plain = TransitionFn::new(&trans_fn.state_table());
+        assert_ne!(trans_fn, plain);
+        assert_eq!(trans_fn.fingerprint(), plain.fingerprint());
+        assert!(std::panic::catch_unwind(|| plain.clone().with_annotation(5, 5, "missing")).is_err());
+        assert!(std::panic::catch_unwind(|| plain.clone().with_annotation(0, 0, "a; b")).is_err());
+    }
+
+    #[test]
+    fn test_csv_round_trip() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 2, true)), ((1, 2), (0, 1, false)), ((1, 0), (2, 0, true))])
+            .with_annotation(1, 2, "say \"hi\", then turn");
+        let alphabet = Alphabet::new(&["_", "a"]);
+        let labels = ["q0".to_string(), "q1".to_string(), "halt".to_string()];
+
+        let mut csv = Vec::new();
+        trans_fn.to_csv(&mut csv, &alphabet, &labels).unwrap();
+        let text = String::from_utf8(csv).unwrap();
+        assert_eq!(text.lines().nth(3), Some("q1,2,q0,a,L,\"say \"\"hi\"\", then turn\""));
+        assert_eq!(TransitionFn::from_csv(text.as_bytes(), &alphabet).unwrap(), (trans_fn.clone(), labels.to_vec()));
+
+        // numbered states without a header, surrounded by blank rows
+        let csv = "\n0, 0, 1, 2, r\n 1,2,0,1,LEFT \n\n1,0,2,0,R,\n";
+        let (read, labels) = TransitionFn::from_csv(csv.as_bytes(), &Alphabet::default()).unwrap();
+        assert_eq!(read.state_table().len(), 3);
+        assert_eq!(read.to_string(), TransitionFn::new(&trans_fn.state_table()).to_string());
+        assert!(labels.is_empty());
+
+        let error = |csv: &str| TransitionFn::from_csv(csv.as_bytes(), &alphabet).unwrap_err().to_string();
+        assert_eq!(error("0,_,1,a\n"), "line 1: expected 5 or 6 columns, found 4");
+        assert_eq!(error("0,_,1,a,R\n0,b,1,a,R\n"), "line 2: unknown symbol `b`");
+        assert_eq!(error("0,_,1,a,R\n0,_,1,a,S\n"), "line 2: invalid move `S`");
+        assert_eq!(error("0,_,1,a,R\n0,0,1,a,L\n"), "line 2: duplicate state and symbol");
+        // a malformed first row is reported rather than mistaken for a header
+        assert_eq!(error("0,_,1,a,S\n0,a,1,a,R\n"), "line 1: invalid move `S`");
+        assert_eq!(error("State,Read,Next Stat,Write,Move\n"), "line 1: unknown symbol `Read`");
+        assert!(TransitionFn::from_csv("STATE,read,next state,write,move,Notes\n0,_,1,a,R,x\n".as_bytes(), &alphabet).is_ok());
+    }
+}
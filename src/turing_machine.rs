@@ -1,18 +1,55 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::mem;
+use std::ops::{ControlFlow, Deref, RangeInclusive};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
 use std::time::{Duration, Instant};
 
-use crate::prelude::Recording;
-use crate::tape::Tape;
-use crate::transition_fn::TransitionFn;
+use crate::compact_tape::CompactTape;
+use crate::enumeration::EnumerationBuilder;
+use crate::fault::{Fault, FaultModel};
+use crate::recording::{recorded_step, Perturbation, Recording, RecordingConfig, RecordingSummary, Snapshot};
+use crate::tape::{self, Tape};
+use crate::testing::DEFAULT_STEP_BUDGET;
+use crate::transition_fn::{FlatTable, Move, ParseMachineError, TransitionFn};
+
+/// The number of steps between checks of the stop flag of `TuringMachine::run_with_stop_flag`.
+const STOP_CHECK_INTERVAL: usize = 1024;
 
 /// A simulation of a Turing machine, aka an "a-machine", 
 /// a concept invented by Alan Turing in 1936.
 /// This type is inherently mutable as it represents
 /// an actual Turing machine moving around and changing states.
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct TuringMachine {
     transition_fn: TransitionFn,
     state: u64,
     head_loc: i64,
+    halt_setting: HaltSetting,
+    halting_states: HaltingStates,
+    history: StepHistory,
+    limits: Limits,
+    skip_redundant_writes: bool,
+}
+
+impl Default for TuringMachine {
+    /// Returns a machine with an empty transition function whose runs are forcibly halted
+    /// after `DEFAULT_STEP_BUDGET` steps.
+    #[inline]
+    fn default() -> Self {
+        TuringMachine {
+            transition_fn: TransitionFn::default(),
+            state: 0,
+            head_loc: 0,
+            halt_setting: HaltSetting::AfterSteps(DEFAULT_STEP_BUDGET),
+            halting_states: HaltingStates::default(),
+            history: StepHistory::default(),
+            limits: Limits::default(),
+            skip_redundant_writes: false,
+        }
+    }
 }
 
 impl TuringMachine {
@@ -26,6 +63,180 @@ impl TuringMachine {
         }
     }
 
+    /// Sets the `HaltSetting` that `run` and `run_and_record` honor, returning `self`.
+    /// By default, machines are forcibly halted after `DEFAULT_STEP_BUDGET` steps,
+    /// so that a machine that never halts cannot hang the caller; pass `HaltSetting::NoForcedHalt`,
+    /// or call `run_unbounded`, to run a machine until it halts on its own.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // this machine never halts on its own
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (0, 1, true))]);
+    /// let mut machine = TuringMachine::new(trans_fn).with_halt_setting(HaltSetting::AfterSteps(3));
+    /// let mut tape = Tape::default();
+    /// 
+    /// machine.run(&mut tape);
+    /// 
+    /// assert_eq!(tape.symbols(), [1, 1, 1]);
+    /// ```
+    #[inline]
+    pub fn with_halt_setting(mut self, halt_setting: HaltSetting) -> Self {
+        self.halt_setting = halt_setting;
+        self
+    }
+
+    /// Returns the `HaltSetting` that `run` and `run_and_record` honor.
+    #[inline]
+    pub fn halt_setting(&self) -> HaltSetting {
+        self.halt_setting
+    }
+
+    /// Sets the declared halting states that `decide` uses to classify runs, returning `self`.
+    #[inline]
+    pub fn with_halting_states(mut self, halting_states: HaltingStates) -> Self {
+        self.halting_states = halting_states;
+        self
+    }
+
+    /// Returns the declared halting states of `self`.
+    #[inline]
+    pub fn halting_states(&self) -> &HaltingStates {
+        &self.halting_states
+    }
+
+    /// Makes `self` keep its last `capacity` steps in a ring buffer, returning `self`,
+    /// so that they can be inspected with `history` after a run that went wrong, e.g. was forcibly halted,
+    /// without paying for a full `Recording`. A capacity of 0, the default, keeps no history.
+    /// 
+    /// Keeping a history disables the fast path of `run_with_halt_setting`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 2, true))]);
+    /// let mut machine = TuringMachine::new(trans_fn).with_history(3);
+    /// machine.run_with_halt_setting(&mut Tape::default(), HaltSetting::AfterSteps(1000));
+    /// 
+    /// let history = machine.history();
+    /// assert_eq!(history.iter().map(|e| e.step_num).collect::<Vec<_>>(), [998, 999, 1000]);
+    /// assert_eq!(history[2].head_loc, 999);
+    /// ```
+    #[inline]
+    pub fn with_history(mut self, capacity: usize) -> Self {
+        self.history = StepHistory {
+            capacity,
+            // grows as steps are taken, so that a generous capacity costs nothing for short runs
+            events: VecDeque::new(),
+            step_num: 0,
+        };
+        self
+    }
+
+    /// Sets the `Limits` on the space a run may use, returning `self`. By default, runs are not limited.
+    /// 
+    /// Every run of `self`, including those that produce a `Recording`, stops before taking a step that would exceed the limits,
+    /// so that untrusted or randomly generated machines cannot exhaust the memory of the host.
+    /// `run_with_outcome` and `run_with_stop_flag` report such runs as `Termination::OutOfSpace`, unless `runs_off_forever` proves
+    /// that the machine never halts; after any other run, `next_step_exceeds_limits` tells whether it stopped for that reason.
+    /// The steps held by a `Recording` are not counted; see `RecordingConfig::max_memory_bytes` to bound those.
+    /// Limiting a run disables the fast path of `run_with_halt_setting`. A `Cursor`, which has no limits, is not limited.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // this machine fills the tape with ones to the right, stepping back after every second one
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 1, true)), ((1, 0), (0, 1, false))]);
+    /// let limits = Limits { max_tape_cells: Some(100), ..Default::default() };
+    /// let mut machine = TuringMachine::new(trans_fn).with_limits(limits);
+    /// let mut tape = Tape::default();
+    /// 
+    /// let outcome = machine.run_with_outcome(&mut tape);
+    /// 
+    /// assert_eq!(outcome.termination, Termination::OutOfSpace);
+    /// assert_eq!(tape.nonblank_range(), Some(0..=49));
+    /// ```
+    #[inline]
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Returns the `Limits` on the space a run of `self` may use.
+    #[inline]
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Sets whether `step` skips writing to the tape when a rule writes the symbol it read, returning `self`.
+    /// Such writes leave the tape unchanged, so skipping them, and checking the `Limits` before them,
+    /// speeds up machines that mostly scan. By default, every write is performed.
+    /// `run_with_outcome` counts the skipped writes in `RunOutcome::skipped_writes`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // this machine scans right over ones and halts on the first blank
+    /// let trans_fn = TransitionFn::new(&[((0, 1), (0, 1, true)), ((0, 0), (1, 1, true))]);
+    /// let mut machine = TuringMachine::new(trans_fn).with_write_skipping(true);
+    /// let mut tape = Tape::new(vec![1; 100]);
+    /// 
+    /// let outcome = machine.run_with_outcome(&mut tape);
+    /// 
+    /// assert_eq!((outcome.steps, outcome.skipped_writes), (101, 100));
+    /// assert_eq!(tape.symbols(), [1; 101]);
+    /// ```
+    #[inline]
+    pub fn with_write_skipping(mut self, skip_redundant_writes: bool) -> Self {
+        self.skip_redundant_writes = skip_redundant_writes;
+        self
+    }
+
+    /// Returns whether `self` skips writes of the symbol that was read; see `with_write_skipping`.
+    #[inline]
+    pub fn skips_redundant_writes(&self) -> bool {
+        self.skip_redundant_writes
+    }
+
+    /// Returns whether taking the next step on `tape` would exceed the `Limits` of `self`,
+    /// or write a nonblank symbol farther than `MAX_LOCATION` from location 0, which no tape can hold.
+    /// Always returns `false` if `self` has halted.
+    #[inline]
+    pub fn next_step_exceeds_limits(&self, tape: &Tape) -> bool {
+        let idx = tape::checked_i64_to_idx(self.head_loc);
+        if idx.is_some() && self.limits == Limits::default() {
+            return false;
+        }
+        let read = tape.symbol_at_n(self.head_loc);
+        let Some((_, symbol, _)) = self.transition_fn.run(self.state, read) else { return false };
+        if self.skip_redundant_writes && symbol == read {
+            return false;
+        }
+
+        let len = tape.raw_symbols().len();
+        let cells = match idx {
+            Some(idx) if symbol != 0 => len.max(idx + 1),
+            None if symbol != 0 => return true,
+            _ => len,
+        };
+        let events = (self.history.events.len() + 1).min(self.history.capacity);
+        let bytes = cells * mem::size_of::<u64>() + events * mem::size_of::<StepEvent>();
+
+        self.limits.max_tape_cells.is_some_and(|max| cells > max) || self.limits.max_memory_bytes.is_some_and(|max| bytes > max)
+    }
+
+    /// Returns the last steps taken since `self` was constructed or `reset`, oldest first,
+    /// up to the capacity set by `with_history`. `run_and_record`, `run_with_halt_setting_and_record` and
+    /// `run_with_faults_and_record`, whose `Recording` already holds every step, do not contribute.
+    #[inline]
+    pub fn history(&self) -> Vec<StepEvent> {
+        self.history.events.iter().copied().collect()
+    }
+
     /// Returns the transition function of `self`.
     #[inline]
     pub fn transition_fn(&self) -> &TransitionFn {
@@ -44,199 +255,1832 @@ impl TuringMachine {
         self.head_loc
     }
 
-    /// Resets the state and head location of `self` to their initial values of 0.
+    /// Resets the state and head location of `self` to their initial values of 0 and clears its history.
     #[inline]
     pub fn reset(&mut self) {
         self.state = 0;
         self.head_loc = 0;
+        self.history.events.clear();
+        self.history.step_num = 0;
+    }
+
+    /// Sets the current state of `self`, e.g. to resume a run from the middle.
+    #[inline]
+    pub fn set_state(&mut self, state: u64) {
+        self.state = state;
+    }
+
+    /// Sets the current head location of `self`, e.g. to resume a run from the middle.
+    #[inline]
+    pub fn set_head_loc(&mut self, head_loc: i64) {
+        self.head_loc = head_loc;
+    }
+
+    /// Places `self` in the state and at the head location of `configuration`, such as a `Snapshot` of a recording,
+    /// and clears its history, which then counts steps from the step number of `configuration`.
+    /// Returns a copy of the tape of `configuration` to continue the run on.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let (mut machine, recording) = turing_machine::examples::busy_beaver_3();
+    /// 
+    /// let mut tape = machine.load_configuration(&recording.seek(10).unwrap());
+    /// machine.run(&mut tape);
+    /// 
+    /// let end = recording.seek(14).unwrap();
+    /// assert_eq!((machine.state(), machine.head_loc(), tape), (end.state, end.head_loc, end.tape));
+    /// ```
+    #[inline]
+    pub fn load_configuration(&mut self, configuration: &Snapshot) -> Tape {
+        self.state = configuration.state;
+        self.head_loc = configuration.head_loc;
+        self.history.events.clear();
+        self.history.step_num = configuration.step_num;
+
+        configuration.tape.clone()
+    }
+
+    /// Runs `self` on the specified tape with `run`, then classifies where it stopped
+    /// using its declared `HaltingStates`.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // accepts tapes starting with a 1
+    /// let mut builder = SmartBuilder::new(2, "start");
+    /// builder
+    ///     .accept_state("yes")
+    ///     .reject_state("no")
+    ///     .rule("start", 1, "yes", 1, true)
+    ///     .rule("start", 0, "no", 0, true);
+    /// let mut machine = builder.build_machine().0;
+    /// 
+    /// assert_eq!(machine.decide(&mut Tape::new(vec![1, 0, 1])), Decision::Accept);
+    /// machine.reset();
+    /// assert_eq!(machine.decide(&mut Tape::new(vec![0, 1])), Decision::Reject);
+    /// ```
+    #[inline]
+    pub fn decide(&mut self, tape: &mut Tape) -> Decision {
+        self.run(tape);
+
+        if self.halting_states.accept.contains(&self.state) {
+            Decision::Accept
+        }
+        else if self.halting_states.reject.contains(&self.state) {
+            Decision::Reject
+        }
+        else if self.transition_fn.run(self.state, tape.symbol_at_n(self.head_loc)).is_none() {
+            Decision::Halt
+        }
+        else {
+            Decision::Undecided
+        }
+    }
+
+    /// Returns whether `self` provably never halts on the specified tape because its head is past every nonblank cell
+    /// and, reading only blanks from now on, cycles through states that all move it further out.
+    /// This is the decider behind `Termination::NonHalting`; `false` means nothing was proven.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // alternates between two states while running off to the right
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 0, true))]);
+    /// let mut machine = TuringMachine::new(trans_fn);
+    /// let mut tape = Tape::default();
+    /// machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(10));
+    /// 
+    /// assert!(machine.runs_off_forever(&tape));
+    /// ```
+    #[inline]
+    pub fn runs_off_forever(&self, tape: &Tape) -> bool {
+        runs_off_forever(&self.transition_fn, self.state, self.head_loc, tape)
+    }
+
+    /// Returns a `Cursor` that borrows the transition function of `self` and starts from its current state and head location.
+    /// Any number of cursors can run at once on different tapes without cloning the machine.
+    #[inline]
+    pub fn cursor(&self) -> Cursor<'_> {
+        Cursor {
+            transition_fn: &self.transition_fn,
+            state: self.state,
+            head_loc: self.head_loc,
+        }
+    }
+
+    /// Returns the next `k` steps `self` would take on the specified tape, or fewer if it halts first,
+    /// without changing `self` or the tape. Step numbers count from the current configuration.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 1, false))]));
+    /// let tape = Tape::default();
+    /// 
+    /// let events = machine.peek(&tape, 5);
+    /// 
+    /// assert_eq!(events.len(), 2);
+    /// assert_eq!(events[1], StepEvent { step_num: 2, state: 1, head_loc: 1, symbol: 0, output: (2, 1, false) });
+    /// assert_eq!(machine.state(), 0);
+    /// ```
+    #[inline]
+    pub fn peek(&self, tape: &Tape, k: usize) -> Vec<StepEvent> {
+        let mut cursor = self.cursor();
+        let mut tape = tape.clone();
+
+        // a huge `k` is a bound rather than a size, since the machine may halt long before
+        let mut events = Vec::new();
+        for step_num in 1..=k {
+            let (state, head_loc) = (cursor.state(), cursor.head_loc());
+            let symbol = tape.symbol_at_n(head_loc);
+            match cursor.step(&mut tape) {
+                Some(output) => events.push(StepEvent { step_num, state, head_loc, symbol, output }),
+                None => break,
+            }
+        }
+
+        events
+    }
+
+    /// Performs a single step of `self` on the specified tape.
+    /// Returns the applied output of the transition function, or `None` if no rule matched,
+    /// in which case `self` has halted, or if the tape could not be written to, e.g. farther than `MAX_LOCATION`
+    /// from location 0, in which case `self` ran out of space. Either way, nothing is changed.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 5, false))]));
+    /// let mut tape = Tape::default();
+    /// 
+    /// assert_eq!(machine.step(&mut tape), Some((1, 5, false)));
+    /// assert_eq!(machine.head_loc(), -1);
+    /// assert_eq!(machine.step(&mut tape), None);
+    /// ```
+    #[inline]
+    pub fn step(&mut self, tape: &mut Tape) -> Option<(u64, u64, bool)> {
+        self.step_skipping(tape).map(|(output, _)| output)
+    }
+
+    /// Equivalent to `step`, but takes no step and returns `None` if it would exceed the `Limits` of `self`.
+    fn step_within_limits(&mut self, tape: &mut Tape) -> Option<(u64, u64, bool)> {
+        if self.next_step_exceeds_limits(tape) {
+            return None;
+        }
+
+        self.step(tape)
+    }
+
+    /// Equivalent to `step`, but also returns whether the write was skipped; see `with_write_skipping`.
+    fn step_skipping(&mut self, tape: &mut Tape) -> Option<((u64, u64, bool), bool)> {
+        let symbol = tape.symbol_at_n(self.head_loc);
+        let output = self.transition_fn.run(self.state, symbol)?;
+        let skipped = self.skip_redundant_writes && output.1 == symbol;
+        if !skipped {
+            tape.try_write(self.head_loc, output.1).ok()?;
+        }
+        if self.history.capacity > 0 {
+            self.history.push(StepEvent { step_num: 0, state: self.state, head_loc: self.head_loc, symbol, output });
+        }
+        self.state = output.0;
+        self.head_loc += Move::from(output.2).offset();
+
+        Some((output, skipped))
     }
 
     /// Runs `self`, changing its state and moving its head while writing to the specified tape.
+    /// `self` is forcibly halted according to its own `HaltSetting`.
     #[inline]
     pub fn run(&mut self, tape: &mut Tape) {
+        self.run_with_halt_setting(tape, self.halt_setting);
+    }
+
+    /// Runs `self`, changing its state and moving its head while writing to the specified tape.
+    /// Ignores the `HaltSetting` of `self`, so this never returns if `self` never halts.
+    #[inline]
+    pub fn run_unbounded(&mut self, tape: &mut Tape) {
+        let steps = self.run_flat(tape, usize::MAX) + self.run_counted(tape, HaltSetting::NoForcedHalt);
+        record_run(steps, || self.termination_after(tape, HaltSetting::NoForcedHalt));
+    }
+
+    /// Runs `self`, changing its state and moving its head while writing to the specified tape.
+    /// `self` is forcibly halted according to its own `HaltSetting`.
+    /// Returns a `Recording` of the process that contains all steps and can be played back.
+    #[inline]
+    pub fn run_and_record(&mut self, tape: &mut Tape) -> Recording {
+        self.run_with_halt_setting_and_record(tape, self.halt_setting)
+    }
+
+    /// Equivalent to `run_unbounded`, but returns a `Recording` of the process.
+    #[inline]
+    fn run_unbounded_and_record(&mut self, tape: &mut Tape) -> Recording {
+        let input = tape.clone();
+        let init_state = self.state;
+        let init_head_loc = self.head_loc;
+        let mut steps = Vec::default();
+        let mut overwritten = Vec::default();
+
         let mut symbol;
         loop {
+            if self.next_step_exceeds_limits(tape) {
+                break;
+            }
+
             symbol = tape.symbol_at_n(self.head_loc);
             if let Some(output) = self.transition_fn.run(self.state, symbol) {
+                if tape.try_write(self.head_loc, output.1).is_err() {
+                    break;
+                }
                 self.state = output.0;
-                tape.write(self.head_loc, output.1);
-                self.head_loc += output.2 as i64 * 2 - 1;
+                self.head_loc += Move::from(output.2).offset();
+
+                steps.push(recorded_step(output));
+                overwritten.push(symbol);
             }
             else {
                 break;
             }
         }
+
+        let mut recording = Recording {
+            input_fingerprint: input.fingerprint(),
+            input,
+            init_state,
+            init_head_loc,
+            steps,
+            overwritten,
+            machine_fingerprint: self.transition_fn.fingerprint(),
+            ..Default::default()
+        };
+        recording.summarize(tape);
+
+        recording
     }
 
     /// Runs `self`, changing its state and moving its head while writing to the specified tape.
+    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
+    /// 
+    /// Unless the run is limited by a real duration, machines whose rules and tape only use the symbols 0 to 2
+    /// are run on a flat array instead of the state table, which is several times faster,
+    /// as long as the step budget is large enough to pay for copying the tape into the array.
+    #[inline]
+    pub fn run_with_halt_setting(&mut self, tape: &mut Tape, halt_setting: HaltSetting) {
+        if halt_setting == HaltSetting::NoForcedHalt {
+            self.run_unbounded(tape);
+            return;
+        }
+        let (mut steps, mut budget) = (0, halt_setting);
+        if let Some(max_steps) = halt_setting.max_steps() {
+            // the flat path stops early only at the edge of the tape, leaving the rest of the run to the loop below
+            steps = self.run_flat(tape, max_steps);
+            budget = HaltSetting::AfterSteps(max_steps - steps);
+        }
+
+        steps += self.run_counted(tape, budget);
+        record_run(steps, || self.termination_after(tape, halt_setting));
+    }
+
+    /// Runs for at most `max_steps` steps with `TransitionFn::flat_table` if `self` and `tape` use at most 3 symbols,
+    /// which is several times faster than looking up every step in the state table
+    /// once `max_steps` is large enough to pay for copying the tape.
+    /// Returns the number of steps taken, which is 0 if the fast path was not taken.
+    fn run_flat(&mut self, tape: &mut Tape, max_steps: usize) -> usize {
+        if self.history.capacity > 0 || self.limits != Limits::default() || !FlatTable::pays_off(tape, max_steps) {
+            return 0;
+        }
+
+        self.transition_fn
+            .flat_table()
+            .and_then(|table| table.run(tape, &mut self.state, &mut self.head_loc, max_steps))
+            .unwrap_or(0)
+    }
+
+    /// Equivalent to `run_with_halt_setting_and_record`, but also embeds the periodic tape snapshots
+    /// requested by `config` in the recording so that `Recording::seek` is fast on long runs,
+    /// or records only those snapshots if `config` asks for a sampled recording.
+    #[inline]
+    pub fn run_and_record_with_config(&mut self, tape: &mut Tape, halt_setting: HaltSetting, config: &RecordingConfig) -> Recording {
+        let mut recording = Recording {
+            input: if config.detach_input { Tape::default() } else { tape.clone() },
+            init_state: self.state,
+            init_head_loc: self.head_loc,
+            machine_fingerprint: self.transition_fn.fingerprint(),
+            snapshot_every: config.snapshot_every,
+            input_detached: config.detach_input,
+            input_fingerprint: tape.fingerprint(),
+            // the symbols the steps overwrite would reveal the input
+            overwritten_unknown: config.detach_input,
+            max_memory_bytes: config.max_memory_bytes,
+            ..Default::default()
+        };
+        if config.sample_rate != 0 {
+            self.run_and_sample(tape, halt_setting, config.sample_rate, &mut recording);
+            return recording;
+        }
+
+        let mut written = self.head_loc..=self.head_loc;
+        let mut halt_check = HaltCheck::new(halt_setting);
+        while !halt_check.should_halt() {
+            let (symbol, head_loc) = (tape.symbol_at_n(self.head_loc), self.head_loc);
+            if let Some(output) = self.step_within_limits(tape) {
+                recording.push_step(recorded_step(output), symbol);
+            }
+            else {
+                break;
+            }
+            written = *written.start().min(&head_loc)..=*written.end().max(&head_loc);
+
+            if config.snapshot_every != 0 && recording.len().is_multiple_of(config.snapshot_every) {
+                recording.push_snapshot(self.snapshot(tape, config.detach_input.then_some(&written), recording.len()));
+            }
+        }
+        recording.summarize(tape);
+
+        recording
+    }
+
+    /// Runs `self` like `run_with_halt_setting`, adding a snapshot to `recording` every `sample_rate` steps
+    /// and after the last step, and counting every step in its summary instead of recording it.
+    fn run_and_sample(&mut self, tape: &mut Tape, halt_setting: HaltSetting, sample_rate: usize, recording: &mut Recording) {
+        recording.sample_rate = sample_rate;
+        let mut summary = RecordingSummary { head_range: self.head_loc..=self.head_loc, ..Default::default() };
+        let detached = recording.input_detached;
+        let mut written = self.head_loc..=self.head_loc;
+
+        let mut halt_check = HaltCheck::new(halt_setting);
+        while !halt_check.should_halt() {
+            let (state, head_loc) = (self.state, self.head_loc);
+            if self.step_within_limits(tape).is_none() {
+                break;
+            }
+            written = *written.start().min(&head_loc)..=*written.end().max(&head_loc);
+            summary.count_step(state, self.head_loc);
+            summary.steps += 1;
+
+            if summary.steps.is_multiple_of(sample_rate) {
+                recording.push_snapshot(self.snapshot(tape, detached.then_some(&written), summary.steps));
+            }
+        }
+        if !summary.steps.is_multiple_of(sample_rate) {
+            recording.push_snapshot(self.snapshot(tape, detached.then_some(&written), summary.steps));
+        }
+
+        summary.nonblank = tape.symbols().iter().filter(|&&s| s != 0).count();
+        recording.summary = summary;
+    }
+
+    /// Returns a snapshot of the current configuration of `self` with `tape` after `step_num` steps.
+    /// If the input is detached, the snapshot only shows the `written` cells, like those `Recording::rebuild_snapshots`
+    /// replays from a blank tape; the head moves at most one cell per step, so those cells are contiguous.
+    fn snapshot(&self, tape: &Tape, written: Option<&RangeInclusive<i64>>, step_num: usize) -> Snapshot {
+        let tape = match written {
+            Some(written) => {
+                let mut blank = Tape::default();
+                for n in written.clone() {
+                    blank.write(n, tape.symbol_at_n(n));
+                }
+                blank
+            }
+            None => tape.clone(),
+        };
+
+        Snapshot { step_num, state: self.state, head_loc: self.head_loc, tape }
+    }
+
+    /// Runs `self`, changing its state and moving its head while writing to the specified tape.
+    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
     /// Returns a `Recording` of the process that contains all steps and can be played back.
     #[inline]
-    pub fn run_and_record(&mut self, tape: &mut Tape) -> Recording {
+    pub fn run_with_halt_setting_and_record(&mut self, tape: &mut Tape, halt_setting: HaltSetting) -> Recording {
         let input = tape.clone();
-        let init_state = self.state.clone();
-        let init_head_loc = self.head_loc.clone();
+        let init_state = self.state;
+        let init_head_loc = self.head_loc;
         let mut steps = Vec::default();
+        let mut overwritten = Vec::default();
+
+        if halt_setting == HaltSetting::NoForcedHalt {
+            return self.run_unbounded_and_record(tape);
+        }
+
+        let mut halt_check = HaltCheck::new(halt_setting);
 
         let mut symbol;
         loop {
+            if halt_check.should_halt() || self.next_step_exceeds_limits(tape) {
+                break;
+            }
+
             symbol = tape.symbol_at_n(self.head_loc);
             if let Some(output) = self.transition_fn.run(self.state, symbol) {
+                if tape.try_write(self.head_loc, output.1).is_err() {
+                    break;
+                }
                 self.state = output.0;
-                tape.write(self.head_loc, output.1);
-                self.head_loc += output.2 as i64 * 2 - 1;
+                self.head_loc += Move::from(output.2).offset();
 
-                steps.push(output);
+                steps.push(recorded_step(output));
+                overwritten.push(symbol);
             }
             else {
                 break;
             }
         }
 
-        Recording {
+        let mut recording = Recording {
+            input_fingerprint: input.fingerprint(),
             input,
             init_state,
             init_head_loc,
             steps,
+            overwritten,
+            machine_fingerprint: self.transition_fn.fingerprint(),
+            ..Default::default()
+        };
+        recording.summarize(tape);
+
+        recording
+    }
+
+    /// Runs `self`, changing its state and moving its head while writing to the specified tape.
+    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
+    /// After each step, `hook` is called with the tape and a `StepEvent` describing the step,
+    /// and may freely modify the tape before the next step.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (0, 1, true)), ((0, 1), (1, 1, true))]);
+    /// let mut machine = TuringMachine::new(trans_fn);
+    /// let mut tape = Tape::default();
+    /// 
+    /// // an adversarial environment that places a 1 in front of the head after the second step
+    /// machine.run_with_hook(&mut tape, HaltSetting::AfterSteps(10), |tape, event| {
+    ///     if event.step_num == 2 {
+    ///         tape.write(2, 1);
+    ///     }
+    /// });
+    /// 
+    /// assert_eq!(tape.symbols(), [1, 1, 1]);
+    /// assert_eq!(machine.state(), 1);
+    /// ```
+    #[inline]
+    pub fn run_with_hook(&mut self, tape: &mut Tape, halt_setting: HaltSetting, mut hook: impl FnMut(&mut Tape, &StepEvent)) {
+        let mut halt_check = HaltCheck::new(halt_setting);
+        let mut step_num = 0;
+        while !halt_check.should_halt() {
+            let (state, head_loc, symbol) = (self.state, self.head_loc, tape.symbol_at_n(self.head_loc));
+            let Some(output) = self.step_within_limits(tape) else { break };
+            step_num += 1;
+
+            hook(tape, &StepEvent { step_num, state, head_loc, symbol, output });
+        }
+    }
+
+    /// Runs `self` like `run`, but paced in real time to at most `steps_per_second` steps per second,
+    /// for live demos and dashboards. After each step, `observer` is called with the tape and a
+    /// `StepEvent` describing the step, and may stop the run early by returning `ControlFlow::Break`.
+    /// Returns the number of steps taken.
+    /// 
+    /// Steps are scheduled against the start of the run rather than the previous step,
+    /// so time spent in `observer` does not slow the run down unless it exceeds the interval.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::ops::ControlFlow;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let (mut machine, _) = turing_machine::examples::busy_beaver_2();
+    /// machine.reset();
+    /// 
+    /// let steps = machine.run_throttled(&mut Tape::default(), 1000.0, |tape, event| {
+    ///     println!("step {}: {:?}", event.step_num, tape.symbols());
+    ///     ControlFlow::Continue(())
+    /// });
+    /// 
+    /// assert_eq!(steps, 6);
+    /// ```
+    /// 
+    /// # Panics
+    /// Panics if `steps_per_second` is not positive.
+    #[inline]
+    pub fn run_throttled(&mut self, tape: &mut Tape, steps_per_second: f64, mut observer: impl FnMut(&Tape, &StepEvent) -> ControlFlow<()>) -> usize {
+        assert!(steps_per_second > 0.0, "steps_per_second must be positive");
+        let interval = Duration::from_secs_f64(1.0 / steps_per_second);
+
+        let start = Instant::now();
+        let mut halt_check = HaltCheck::new(self.halt_setting);
+        let mut step_num = 0;
+        while !halt_check.should_halt() {
+            if let Some(wait) = interval.mul_f64(step_num as f64).checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+
+            let (state, head_loc, symbol) = (self.state, self.head_loc, tape.symbol_at_n(self.head_loc));
+            let Some(output) = self.step_within_limits(tape) else { break };
+            step_num += 1;
+
+            if observer(tape, &StepEvent { step_num, state, head_loc, symbol, output }).is_break() {
+                break;
+            }
+        }
+
+        step_num
+    }
+
+    /// Equivalent to `run_with_hook`, but returns a `Recording` of the process in which every
+    /// change made by `hook` is logged as a `Perturbation`, so that playback reproduces it.
+    /// `hook` writes through a `HookTape`, which logs each change as it is made.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+    /// let recording = machine.run_with_hook_and_record(&mut Tape::default(), HaltSetting::AfterSteps(3), |tape, event| {
+    ///     if event.step_num == 2 {
+    ///         tape.write(-1, 7);
+    ///     }
+    /// });
+    /// 
+    /// assert_eq!(recording.perturbations(), [Perturbation { step_num: 2, location: -1, old: 0, new: 7 }]);
+    /// assert_eq!(recording.seek(3).unwrap().tape.symbols(), [7, 1, 1, 1]);
+    /// ```
+    #[inline]
+    pub fn run_with_hook_and_record(&mut self, tape: &mut Tape, halt_setting: HaltSetting, mut hook: impl FnMut(&mut HookTape<'_>, &StepEvent)) -> Recording {
+        let mut recording = Recording {
+            input: tape.clone(),
+            init_state: self.state,
+            init_head_loc: self.head_loc,
+            machine_fingerprint: self.transition_fn.fingerprint(),
+            input_fingerprint: tape.fingerprint(),
+            ..Default::default()
+        };
+
+        self.run_with_hook(tape, halt_setting, |tape, event| {
+            recording.steps.push(recorded_step(event.output));
+            recording.overwritten.push(event.symbol);
+
+            let mut tape = HookTape { tape, step_num: event.step_num, perturbations: &mut recording.perturbations };
+            hook(&mut tape, event);
+        });
+        recording.summarize(tape);
+
+        recording
+    }
+
+    /// Runs `self`, changing its state and moving its head while writing to the specified tape,
+    /// while injecting the faults described by `fault_model`.
+    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
+    /// Returns a `Recording` of the process in which every injected fault is logged, and every
+    /// change to the tape not made by the machine is logged as a `Perturbation` for playback.
+    /// 
+    /// If a misread produces a symbol for which there is no rule, `self` halts, and the misread is logged
+    /// with the number of the step it prevented.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (0, 1, true))]);
+    /// let fault_model = FaultModel { num_symbols: 2, stuck_cells: vec![2], ..Default::default() };
+    /// 
+    /// let mut machine = TuringMachine::new(trans_fn);
+    /// let mut tape = Tape::default();
+    /// let recording = machine.run_with_faults_and_record(&mut tape, HaltSetting::AfterSteps(4), &fault_model);
+    /// 
+    /// assert_eq!(tape.symbols(), [1, 1, 0, 1]);
+    /// assert_eq!(recording.faults(), [Fault::StuckWrite { step_num: 3, location: 2, intended: 1 }]);
+    /// ```
+    #[inline]
+    pub fn run_with_faults_and_record(&mut self, tape: &mut Tape, halt_setting: HaltSetting, fault_model: &FaultModel) -> Recording {
+        let mut recording = Recording {
+            input: tape.clone(),
+            init_state: self.state,
+            init_head_loc: self.head_loc,
+            machine_fingerprint: self.transition_fn.fingerprint(),
+            input_fingerprint: tape.fingerprint(),
+            seed: Some(fault_model.seed),
+            ..Default::default()
+        };
+
+        let mut rng = fault_model.seed.rng();
+        let can_corrupt = fault_model.num_symbols >= 2;
+
+        let mut halt_check = HaltCheck::new(halt_setting);
+        let mut step_num = 0;
+        while !halt_check.should_halt() && !self.next_step_exceeds_limits(tape) {
+            let location = self.head_loc;
+            let actual = tape.symbol_at_n(location);
+            let mut symbol = actual;
+            let misread = can_corrupt && rng.chance(fault_model.misread_probability);
+            if misread {
+                symbol = fault_model.other_symbol(&mut rng, actual).expect("a fault model that can corrupt has another symbol");
+            }
+
+            let Some(output) = self.transition_fn.run(self.state, symbol) else {
+                // the fault halted the machine, so it is logged with the step that was not taken
+                if misread {
+                    recording.faults.push(Fault::Misread { step_num: step_num + 1, location, actual, read: symbol });
+                }
+                break;
+            };
+            // a misread may pick a rule that writes where `next_step_exceeds_limits` did not look
+            if tape.try_write(location, output.1).is_err() {
+                break;
+            }
+            step_num += 1;
+            if misread {
+                recording.faults.push(Fault::Misread { step_num, location, actual, read: symbol });
+            }
+
+            self.state = output.0;
+            self.head_loc += Move::from(output.2).offset();
+            recording.steps.push(recorded_step(output));
+            recording.overwritten.push(actual);
+
+            if fault_model.stuck_cells.contains(&location) && output.1 != actual {
+                tape.write(location, actual);
+                recording.faults.push(Fault::StuckWrite { step_num, location, intended: output.1 });
+                recording.perturbations.push(Perturbation { step_num, location, old: output.1, new: actual });
+            }
+            else if can_corrupt && rng.chance(fault_model.flip_probability) {
+                let old = tape.symbol_at_n(location);
+                let new = fault_model.other_symbol(&mut rng, old).expect("a fault model that can corrupt has another symbol");
+                if tape.try_write(location, new).is_ok() {
+                    recording.faults.push(Fault::Flip { step_num, location, old, new });
+                    recording.perturbations.push(Perturbation { step_num, location, old, new });
+                }
+            }
+        }
+        recording.summarize(tape);
+
+        recording
+    }
+
+    /// Runs `self`, changing its state and moving its head while writing to the specified tape,
+    /// until `pattern` appears on the tape, `self` halts, or the `HaltSetting` forcibly halts it.
+    /// Returns the location of the first symbol of the pattern if it appeared, as in `Tape::find`.
+    /// 
+    /// The tape is scanned once up front; afterwards, only the occurrences that include
+    /// the most recently written cell are checked, so each step costs O(pattern length²).
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // writes 1, 2, 3, 1, 2, 3, ... forever
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (2, 2, true)), ((2, 0), (0, 3, true))]);
+    /// let mut machine = TuringMachine::new(trans_fn);
+    /// let mut tape = Tape::default();
+    /// 
+    /// let found = machine.run_until_pattern(&mut tape, &[3, 1], HaltSetting::AfterSteps(100));
+    /// 
+    /// assert_eq!(found, Some(2));
+    /// assert_eq!(tape.symbols(), [1, 2, 3, 1]);
+    /// ```
+    #[inline]
+    pub fn run_until_pattern(&mut self, tape: &mut Tape, pattern: &[u64], halt_setting: HaltSetting) -> Option<i64> {
+        if let Some(n) = tape.find(pattern) {
+            return Some(n);
+        }
+
+        let mut halt_check = HaltCheck::new(halt_setting);
+        let mut steps = 0;
+        while !halt_check.should_halt() {
+            let written_at = self.head_loc;
+            if self.step_within_limits(tape).is_none() {
+                break;
+            }
+            steps += 1;
+
+            let len = pattern.len() as i64;
+            let found = (written_at - len + 1..=written_at)
+                .find(|&n| pattern.iter().zip(n..).all(|(&p, m)| tape.symbol_at_n(m) == p));
+            if found.is_some() {
+                record_run(steps, || Termination::Stopped);
+                return found;
+            }
+        }
+        record_run(steps, || self.termination_after(tape, halt_setting));
+
+        None
+    }
+
+    /// Runs `self` like `run`, and returns a `RunOutcome` summarizing the run.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let (mut machine, _) = turing_machine::examples::busy_beaver_3();
+    /// machine.reset();
+    /// 
+    /// let outcome = machine.run_with_outcome(&mut Tape::default());
+    /// 
+    /// assert_eq!((outcome.termination, outcome.steps, outcome.nonblank), (Termination::Halted, 14, 6));
+    /// assert!(outcome.to_string().starts_with("halted after 14 steps in "));
+    /// assert!(outcome.to_string().ends_with("; 6 ones on tape; head range -1..=4"));
+    /// ```
+    #[inline]
+    pub fn run_with_outcome(&mut self, tape: &mut Tape) -> RunOutcome {
+        self.run_with_stop_flag(tape, self.halt_setting, &AtomicBool::new(false))
+    }
+
+    /// Runs `self` like `run_with_outcome`, but with the specified `HaltSetting`, and stops cleanly once `stop` is set,
+    /// which is checked every 1024 steps. A stopped run is reported as `Termination::Stopped` with everything
+    /// it did so far, and `self` and `tape` are left in a consistent configuration from which the run can be resumed.
+    /// Set `stop` from another thread, or from a signal handler to interrupt long runs with Ctrl-C.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// // this machine never halts, nor can it be proven not to
+    /// let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 0, false)), ((0, 1), (0, 1, true)), ((1, 1), (1, 0, false))]);
+    /// let mut machine = TuringMachine::new(trans_fn);
+    /// let mut tape = Tape::default();
+    /// 
+    /// let stop = AtomicBool::new(false);
+    /// let outcome = thread::scope(|scope| {
+    ///     scope.spawn(|| {
+    ///         thread::sleep(Duration::from_millis(10));
+    ///         stop.store(true, Ordering::Relaxed);
+    ///     });
+    ///     machine.run_with_stop_flag(&mut tape, HaltSetting::NoForcedHalt, &stop)
+    /// });
+    /// 
+    /// assert_eq!(outcome.termination, Termination::Stopped);
+    /// assert!(outcome.steps > 0);
+    /// ```
+    #[inline]
+    pub fn run_with_stop_flag(&mut self, tape: &mut Tape, halt_setting: HaltSetting, stop: &AtomicBool) -> RunOutcome {
+        let start = Instant::now();
+        let mut halt_check = HaltCheck::new(halt_setting);
+        let mut steps = 0usize;
+        let mut skipped_writes = 0;
+        let (mut min_head_loc, mut max_head_loc) = (self.head_loc, self.head_loc);
+        let mut out_of_space = false;
+        let mut stopped = false;
+        while !halt_check.should_halt() {
+            if self.next_step_exceeds_limits(tape) {
+                out_of_space = true;
+                break;
+            }
+            if steps.is_multiple_of(STOP_CHECK_INTERVAL) && stop.load(Ordering::Relaxed) {
+                stopped = true;
+                break;
+            }
+            // a step that matched a rule but could not write ran out of space
+            let Some((_, skipped)) = self.step_skipping(tape) else {
+                out_of_space = true;
+                break;
+            };
+            steps += 1;
+            skipped_writes += usize::from(skipped);
+            min_head_loc = min_head_loc.min(self.head_loc);
+            max_head_loc = max_head_loc.max(self.head_loc);
+        }
+        let elapsed = start.elapsed();
+
+        let termination = if self.transition_fn.run(self.state, tape.symbol_at_n(self.head_loc)).is_none() {
+            Termination::Halted
+        }
+        else if stopped {
+            Termination::Stopped
+        }
+        else if self.runs_off_forever(tape) {
+            Termination::NonHalting
+        }
+        else if out_of_space {
+            Termination::OutOfSpace
+        }
+        else {
+            Termination::forced_by(halt_setting)
+        };
+        let symbols = tape.symbols();
+        record_run(steps, || termination);
+
+        RunOutcome {
+            termination,
+            steps,
+            elapsed,
+            nonblank: symbols.iter().filter(|&&s| s != 0).count(),
+            binary: symbols.iter().all(|&s| s <= 1),
+            head_range: min_head_loc..=max_head_loc,
+            skipped_writes,
+        }
+    }
+
+    /// Equivalent to `run_with_stop_flag`, but returns a `Recording` of the steps taken until the run halted,
+    /// was forcibly halted, or was stopped, so that an interrupted run can still be played back and saved.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::sync::atomic::AtomicBool;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let (mut machine, recording) = turing_machine::examples::busy_beaver_2();
+    /// machine.reset();
+    /// 
+    /// // a flag that is set before the run stops it before the first step
+    /// let stopped = machine.run_with_stop_flag_and_record(&mut Tape::default(), HaltSetting::NoForcedHalt, &AtomicBool::new(true));
+    /// assert!(stopped.is_empty());
+    /// 
+    /// let finished = machine.run_with_stop_flag_and_record(&mut Tape::default(), HaltSetting::NoForcedHalt, &AtomicBool::new(false));
+    /// assert_eq!(finished.steps(), recording.steps());
+    /// ```
+    #[inline]
+    pub fn run_with_stop_flag_and_record(&mut self, tape: &mut Tape, halt_setting: HaltSetting, stop: &AtomicBool) -> Recording {
+        let mut recording = Recording {
+            input: tape.clone(),
+            init_state: self.state,
+            init_head_loc: self.head_loc,
+            machine_fingerprint: self.transition_fn.fingerprint(),
+            input_fingerprint: tape.fingerprint(),
+            ..Default::default()
+        };
+
+        let mut halt_check = HaltCheck::new(halt_setting);
+        while !halt_check.should_halt() {
+            if recording.steps.len().is_multiple_of(STOP_CHECK_INTERVAL) && stop.load(Ordering::Relaxed) {
+                break;
+            }
+            let symbol = tape.symbol_at_n(self.head_loc);
+            let Some(output) = self.step_within_limits(tape) else { break };
+            recording.steps.push(recorded_step(output));
+            recording.overwritten.push(symbol);
+        }
+        recording.summarize(tape);
+
+        recording
+    }
+
+    /// Approximates the fraction of machines with the specified number of states and symbols
+    /// that halt on a blank tape, by running every machine from `TransitionFn::enumerate` with the specified `HaltSetting`.
+    /// A machine has halted if it has reached the state `num_states`.
+    /// Returns a `ChaitinReport` with the fractions of halted and undecided machines, where the rest were proven never to halt,
+    /// along with the parameters and duration of the sweep.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let report = TuringMachine::chaitin_approx(2, 2, HaltSetting::AfterSteps(50));
+    /// 
+    /// assert_eq!((report.total, report.budget), (20_736, HaltSetting::AfterSteps(50)));
+    /// assert!(report.to_string().starts_with("2 states and 2 symbols within 50 steps: "));
+    /// ```
+    #[inline]
+    pub fn chaitin_approx(num_states: usize, num_symbols: usize, halt_setting: HaltSetting) -> ChaitinReport {
+        TuringMachine::chaitin_approx_with(num_states, num_symbols, halt_setting, |_, _, _| {})
+    }
+
+    /// Equivalent to `chaitin_approx`, but also calls `callback` for every machine with its index
+    /// in the output of `TransitionFn::enumerate`, its `Termination`, and the number of steps it took.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut undecided = Vec::new();
+    /// let report = TuringMachine::chaitin_approx_with(2, 2, HaltSetting::AfterSteps(50), |index, termination, _| {
+    ///     if termination.is_undecided() {
+    ///         undecided.push(index);
+    ///     }
+    /// });
+    /// 
+    /// // the undecided set can be followed up with a stronger budget
+    /// let trans_fns = TransitionFn::enumerate(2, 2);
+    /// assert_eq!(undecided.len() as f64 / trans_fns.len() as f64, report.undecided);
+    /// ```
+    #[inline]
+    pub fn chaitin_approx_with(num_states: usize, num_symbols: usize, halt_setting: HaltSetting, mut callback: impl FnMut(usize, Termination, usize)) -> ChaitinReport {
+        let start = Instant::now();
+        let counts = TuringMachine::termination_counts_with(num_states, num_symbols, halt_setting, &mut callback);
+
+        ChaitinReport {
+            halted: counts.halted as f64 / counts.total() as f64,
+            undecided: counts.undecided() as f64 / counts.total() as f64,
+            total: counts.total(),
+            budget: halt_setting,
+            num_states,
+            num_symbols,
+            duration: start.elapsed(),
+        }
+    }
+
+    /// Runs every machine from `TransitionFn::enumerate` like `chaitin_approx`, and counts how each run ended.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let counts = TuringMachine::termination_counts(2, 2, HaltSetting::AfterSteps(50));
+    /// 
+    /// assert_eq!(counts.total(), TransitionFn::enumerate(2, 2).len());
+    /// assert!(counts.non_halting > 0);
+    /// assert_eq!(counts.out_of_time, 0);
+    /// assert!(counts.to_string().contains(", out of time: 0, "));
+    /// ```
+    #[inline]
+    pub fn termination_counts(num_states: usize, num_symbols: usize, halt_setting: HaltSetting) -> TerminationCounts {
+        TuringMachine::termination_counts_with(num_states, num_symbols, halt_setting, |_, _, _| {})
+    }
+
+    fn termination_counts_with(num_states: usize, num_symbols: usize, halt_setting: HaltSetting, mut callback: impl FnMut(usize, Termination, usize)) -> TerminationCounts {
+        let mut counts = TerminationCounts::default();
+        for (_, outcome) in EnumerationBuilder::new(num_states, num_symbols).simulate(halt_setting) {
+            counts.add(outcome.termination);
+            callback(outcome.index as usize, outcome.termination, outcome.steps);
+        }
+
+        counts
+    }
+
+    /// Returns how a run of `self` with `halt_setting` that was not stopped ended, now that it left `tape` behind,
+    /// like `run_with_outcome` reports it.
+    pub(crate) fn termination_after(&self, tape: &Tape, halt_setting: HaltSetting) -> Termination {
+        if self.transition_fn.run(self.state, tape.symbol_at_n(self.head_loc)).is_none() {
+            Termination::Halted
+        }
+        else if self.runs_off_forever(tape) {
+            Termination::NonHalting
+        }
+        else if self.next_step_exceeds_limits(tape) {
+            Termination::OutOfSpace
+        }
+        else {
+            Termination::forced_by(halt_setting)
+        }
+    }
+
+    /// Equivalent to `run_with_halt_setting` without the fast path, but returns the number of steps taken
+    /// and is not counted in the `telemetry` counters.
+    #[inline]
+    pub(crate) fn run_counted(&mut self, tape: &mut Tape, halt_setting: HaltSetting) -> usize {
+        let mut halt_check = HaltCheck::new(halt_setting);
+        let mut steps = 0;
+        while !halt_check.should_halt() && self.step_within_limits(tape).is_some() {
+            steps += 1;
+        }
+
+        steps
+    }
+}
+
+impl fmt::Display for TuringMachine {
+    /// Formats `self` as its state, head location, and transition function, e.g. `0 0 [0,0 -> 1,1,R]`,
+    /// followed by a `key=value` section for every setting that is not the default, e.g. `halt_setting=steps:5`.
+    /// The keys are `halt_setting`, `accept`, `reject` and `halt` for the `HaltingStates`, `history`,
+    /// `max_tape_cells`, `max_memory_bytes` and `skip_redundant_writes`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.state, self.head_loc, self.transition_fn)?;
+
+        if self.halt_setting != HaltSetting::AfterSteps(DEFAULT_STEP_BUDGET) {
+            write!(f, " halt_setting={}", self.halt_setting.token())?;
+        }
+        for (key, states) in [("accept", &self.halting_states.accept), ("reject", &self.halting_states.reject), ("halt", &self.halting_states.halt)] {
+            if !states.is_empty() {
+                let mut states = states.iter().collect::<Vec<&u64>>();
+                states.sort_unstable();
+                write!(f, " {}={}", key, states.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(","))?;
+            }
+        }
+        if self.history != StepHistory::default() {
+            write!(f, " history={}@{}", self.history.capacity, self.history.step_num)?;
+            // the events are numbered consecutively up to `step_num`, so their numbers are not written
+            let events = self.history.events
+                .iter()
+                .map(|e| format!("{},{},{}->{},{},{}", e.state, e.head_loc, e.symbol, e.output.0, e.output.1, Move::from(e.output.2)))
+                .collect::<Vec<String>>();
+            if !events.is_empty() {
+                write!(f, ":{}", events.join("/"))?;
+            }
+        }
+        if let Some(max_tape_cells) = self.limits.max_tape_cells {
+            write!(f, " max_tape_cells={}", max_tape_cells)?;
+        }
+        if let Some(max_memory_bytes) = self.limits.max_memory_bytes {
+            write!(f, " max_memory_bytes={}", max_memory_bytes)?;
+        }
+        if self.skip_redundant_writes {
+            write!(f, " skip_redundant_writes=true")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for TuringMachine {
+    type Err = ParseMachineError;
+
+    /// Parses a `TuringMachine` from the format produced by its `Display` implementation.
+    /// Settings without a section keep their default.
+    /// 
+    /// # Examples
+    /// ```
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+    /// machine.run(&mut Tape::default());
+    /// 
+    /// let logged = machine.to_string();
+    /// assert_eq!(logged, "1 1 [0,0 -> 1,1,R]");
+    /// assert_eq!(logged.parse(), Ok(machine.clone()));
+    /// 
+    /// let machine = machine.with_halt_setting(HaltSetting::AfterSteps(5));
+    /// assert_eq!(machine.to_string(), "1 1 [0,0 -> 1,1,R] halt_setting=steps:5");
+    /// assert_eq!(machine.to_string().parse(), Ok(machine));
+    /// ```
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.trim().splitn(3, ' ');
+        let state = parts.next()
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(|| ParseMachineError::new("missing or invalid state"))?;
+        let head_loc = parts.next()
+            .and_then(|x| x.parse().ok())
+            .ok_or_else(|| ParseMachineError::new("missing or invalid head location"))?;
+        let rest = parts.next().ok_or_else(|| ParseMachineError::new("missing state table"))?;
+        // no section contains a bracket, so the state table ends at the last one
+        let (table, sections) = rest.split_at(rest.rfind(']').map_or(rest.len(), |i| i + 1));
+
+        let mut machine = TuringMachine {
+            transition_fn: table.parse()?,
+            state,
+            head_loc,
+            ..Default::default()
+        };
+        for section in sections.split_whitespace() {
+            let invalid = || ParseMachineError::new(format!("invalid section `{}`", section));
+            let (key, value) = section.split_once('=').ok_or_else(invalid)?;
+            let number = || value.parse::<usize>().map_err(|_| invalid());
+            let states = || value.split(',').map(|x| x.parse::<u64>().map_err(|_| invalid())).collect::<Result<HashSet<u64>, _>>();
+
+            match key {
+                "halt_setting" => machine.halt_setting = HaltSetting::from_token(value).ok_or_else(invalid)?,
+                "accept" => machine.halting_states.accept = states()?,
+                "reject" => machine.halting_states.reject = states()?,
+                "halt" => machine.halting_states.halt = states()?,
+                "history" => machine.history = StepHistory::from_token(value).ok_or_else(invalid)?,
+                "max_tape_cells" => machine.limits.max_tape_cells = Some(number()?),
+                "max_memory_bytes" => machine.limits.max_memory_bytes = Some(number()?),
+                "skip_redundant_writes" => machine.skip_redundant_writes = value.parse().map_err(|_| invalid())?,
+                _ => return Err(ParseMachineError::new(format!("unknown section `{}`", section))),
+            }
+        }
+
+        Ok(machine)
+    }
+}
+
+/// An execution position over a borrowed `TransitionFn`: the state and head location of a run.
+/// Since the transition function is only borrowed, a single machine definition can be run
+/// concurrently on many tapes, e.g. with one cursor per thread.
+/// 
+/// # Examples
+/// ```
+/// use std::thread;
+/// use turing_machine::prelude::*;
+/// 
+/// let trans_fn = TransitionFn::new(&[((0, 1), (0, 2, true))]);
+/// 
+/// let steps = thread::scope(|scope| {
+///     let workers = (0..4)
+///         .map(|len| {
+///             let trans_fn = &trans_fn;
+///             scope.spawn(move || Cursor::new(trans_fn).run_with_halt_setting(&mut Tape::new(vec![1; len]), HaltSetting::NoForcedHalt))
+///         })
+///         .collect::<Vec<_>>();
+/// 
+///     workers.into_iter().map(|w| w.join().unwrap()).collect::<Vec<usize>>()
+/// });
+/// 
+/// assert_eq!(steps, vec![0, 1, 2, 3]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Cursor<'a> {
+    transition_fn: &'a TransitionFn,
+    state: u64,
+    head_loc: i64,
+}
+
+impl<'a> Cursor<'a> {
+    /// Constructs a new `Cursor` over the specified transition function,
+    /// starting in state 0 at location 0.
+    #[inline]
+    pub fn new(transition_fn: &'a TransitionFn) -> Self {
+        Cursor {
+            transition_fn,
+            state: 0,
+            head_loc: 0,
+        }
+    }
+
+    /// Returns the transition function `self` runs.
+    #[inline]
+    pub fn transition_fn(&self) -> &'a TransitionFn {
+        self.transition_fn
+    }
+
+    /// Returns the current state of `self`.
+    #[inline]
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Returns the current head location of `self`.
+    #[inline]
+    pub fn head_loc(&self) -> i64 {
+        self.head_loc
+    }
+
+    /// Performs a single step on the specified tape, like `TuringMachine::step`.
+    #[inline]
+    pub fn step(&mut self, tape: &mut Tape) -> Option<(u64, u64, bool)> {
+        let output = self.transition_fn.run(self.state, tape.symbol_at_n(self.head_loc))?;
+        tape.try_write(self.head_loc, output.1).ok()?;
+        self.state = output.0;
+        self.head_loc += Move::from(output.2).offset();
+
+        Some(output)
+    }
+
+    /// Equivalent to `run_with_halt_setting`, but runs on a `CompactTape`.
+    #[inline]
+    pub fn run_compact(&mut self, tape: &mut CompactTape, halt_setting: HaltSetting) -> usize {
+        let mut halt_check = HaltCheck::new(halt_setting);
+
+        let mut steps = 0;
+        while !halt_check.should_halt() {
+            if let Some(output) = self.transition_fn.run(self.state, tape.symbol_at_n(self.head_loc)) {
+                if tape.try_write(self.head_loc, output.1).is_err() {
+                    break;
+                }
+                self.state = output.0;
+                self.head_loc += Move::from(output.2).offset();
+                steps += 1;
+            }
+            else {
+                break;
+            }
+        }
+
+        steps
+    }
+
+    /// Runs on the specified tape until no rule matches, the next write is farther than `MAX_LOCATION` from location 0,
+    /// or `halt_setting` forcibly halts the run. Returns the number of steps taken.
+    #[inline]
+    pub fn run_with_halt_setting(&mut self, tape: &mut Tape, halt_setting: HaltSetting) -> usize {
+        let max_steps = match halt_setting {
+            HaltSetting::NoForcedHalt => Some(usize::MAX),
+            _ => halt_setting.max_steps(),
+        };
+        let mut halt_setting = halt_setting;
+        let mut steps = 0;
+        if let Some(max_steps) = max_steps.filter(|&max_steps| FlatTable::pays_off(tape, max_steps)) {
+            steps = self.transition_fn
+                .flat_table()
+                .and_then(|table| table.run(tape, &mut self.state, &mut self.head_loc, max_steps))
+                .unwrap_or(0);
+            if halt_setting != HaltSetting::NoForcedHalt {
+                halt_setting = HaltSetting::AfterSteps(max_steps - steps);
+            }
+        }
+
+        let mut halt_check = HaltCheck::new(halt_setting);
+        while !halt_check.should_halt() && self.step(tape).is_some() {
+            steps += 1;
+        }
+
+        steps
+    }
+}
+
+/// A parameter type that describes when a Turing machine should be forcibly halted.
+/// The `NoForcedHalt` variant simply states that the machine should not be forcibly halted.
+/// The `AfterSteps(usize)` variant states that it should be halted after `usize` number of steps;
+/// i.e., the machine has written to the tape `usize` number of times.
+/// The `AfterDuration(Duration)` variant states the machine should be halted after a `Duration` has elapsed.
+/// The `AfterVirtualDuration` variant states the machine should be halted after the `duration` it would take
+/// to run at `steps_per_second`; unlike `AfterDuration`, it is deterministic, so runs bounded by it are reproducible
+/// regardless of the speed or load of the host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HaltSetting {
+    #[default]
+    NoForcedHalt,
+    AfterSteps(usize),
+    AfterDuration(Duration),
+    AfterVirtualDuration { duration: Duration, steps_per_second: u64 },
+}
+
+impl HaltSetting {
+    /// Returns the number of steps after which `self` forcibly halts a run, if that is deterministic.
+    /// 
+    /// # Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use turing_machine::prelude::*;
+    /// 
+    /// let halt_setting = HaltSetting::AfterVirtualDuration { duration: Duration::from_millis(1500), steps_per_second: 1000 };
+    /// 
+    /// assert_eq!(halt_setting.max_steps(), Some(1500));
+    /// assert_eq!(HaltSetting::AfterDuration(Duration::from_millis(1500)).max_steps(), None);
+    /// ```
+    #[inline]
+    pub fn max_steps(self) -> Option<usize> {
+        match self {
+            HaltSetting::NoForcedHalt | HaltSetting::AfterDuration(_) => None,
+            HaltSetting::AfterSteps(max_steps) => Some(max_steps),
+            HaltSetting::AfterVirtualDuration { duration, steps_per_second } => {
+                let max_steps = duration.as_nanos() * steps_per_second as u128 / 1_000_000_000;
+                Some(usize::try_from(max_steps).unwrap_or(usize::MAX))
+            }
+        }
+    }
+
+    /// Returns `self` as a single token without spaces, e.g. `steps:5`, as written by `ExperimentState::save`
+    /// and `TuringMachine`'s `Display` implementation.
+    pub(crate) fn token(self) -> String {
+        match self {
+            HaltSetting::NoForcedHalt => "none".to_string(),
+            HaltSetting::AfterSteps(steps) => format!("steps:{}", steps),
+            HaltSetting::AfterDuration(duration) => format!("nanos:{}", duration.as_nanos()),
+            HaltSetting::AfterVirtualDuration { duration, steps_per_second } => format!("virtual:{}:{}", duration.as_nanos(), steps_per_second),
+        }
+    }
+
+    /// Parses a token written by `token`, or returns `None` if it is malformed.
+    pub(crate) fn from_token(token: &str) -> Option<Self> {
+        match token.split_once(':') {
+            None if token == "none" => Some(HaltSetting::NoForcedHalt),
+            Some(("steps", n)) => n.parse().ok().map(HaltSetting::AfterSteps),
+            Some(("nanos", n)) => n.parse().ok().map(|n| HaltSetting::AfterDuration(Duration::from_nanos(n))),
+            Some(("virtual", rest)) => {
+                let (nanos, steps_per_second) = rest.split_once(':')?;
+                Some(HaltSetting::AfterVirtualDuration {
+                    duration: Duration::from_nanos(nanos.parse().ok()?),
+                    steps_per_second: steps_per_second.parse().ok()?,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Bounds on the space a run of a `TuringMachine` may use, set with `TuringMachine::with_limits`. `None` means unbounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Limits {
+    /// The largest number of locations the tape may hold. A tape holds a range of locations around location 0
+    /// that grows on both sides to reach its farthest nonblank symbol, so a limit of 2n cells lets a run
+    /// write nonblank symbols at locations -n through n - 1.
+    pub max_tape_cells: Option<usize>,
+    /// The largest number of bytes the tape's cells and the machine's history may take up.
+    /// The allocation behind the tape may have spare capacity beyond that, up to as much again.
+    pub max_memory_bytes: Option<usize>,
+}
+
+/// The states in which a machine is declared to halt, as opposed to halting merely because no rule matches.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HaltingStates {
+    pub accept: HashSet<u64>,
+    pub reject: HashSet<u64>,
+    /// States that halt without accepting or rejecting.
+    pub halt: HashSet<u64>,
+}
+
+impl HaltingStates {
+    /// Returns whether `state` is declared as any kind of halting state.
+    #[inline]
+    pub fn contains(&self, state: u64) -> bool {
+        self.accept.contains(&state) || self.reject.contains(&state) || self.halt.contains(&state)
+    }
+}
+
+/// The result of `TuringMachine::decide`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Decision {
+    /// The machine stopped in a declared accepting state.
+    Accept,
+    /// The machine stopped in a declared rejecting state.
+    Reject,
+    /// The machine halted in any other state.
+    Halt,
+    /// The machine was forcibly halted.
+    Undecided,
+}
+
+/// How a machine's run ended in a sweep such as `TuringMachine::chaitin_approx_with`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Termination {
+    /// The machine reached the halting state.
+    Halted,
+    /// The machine was forcibly halted by `HaltSetting::AfterSteps`.
+    OutOfSteps,
+    /// The machine was forcibly halted by `HaltSetting::AfterDuration` or `HaltSetting::AfterVirtualDuration`.
+    OutOfTime,
+    /// The machine was forcibly halted, but `TuringMachine::runs_off_forever` proved it would never halt.
+    NonHalting,
+    /// The machine was forcibly halted before its next step would have exceeded its `Limits`.
+    OutOfSpace,
+    /// The run was interrupted by its stop flag; see `TuringMachine::run_with_stop_flag`.
+    Stopped,
+}
+
+impl Termination {
+    /// Returns whether the run was forcibly halted without its fate being decided.
+    #[inline]
+    pub fn is_undecided(self) -> bool {
+        matches!(self, Termination::OutOfSteps | Termination::OutOfTime | Termination::OutOfSpace | Termination::Stopped)
+    }
+
+    /// Returns the name of `self` in snake case, e.g. `out_of_steps`, as used in logs and metrics.
+    #[inline]
+    pub fn name(self) -> &'static str {
+        match self {
+            Termination::Halted => "halted",
+            Termination::OutOfSteps => "out_of_steps",
+            Termination::OutOfTime => "out_of_time",
+            Termination::NonHalting => "non_halting",
+            Termination::OutOfSpace => "out_of_space",
+            Termination::Stopped => "stopped",
+        }
+    }
+
+    /// Returns the undecided termination of a run forcibly halted by `halt_setting`.
+    #[inline]
+    pub(crate) fn forced_by(halt_setting: HaltSetting) -> Self {
+        match halt_setting {
+            HaltSetting::AfterDuration(_) | HaltSetting::AfterVirtualDuration { .. } => Termination::OutOfTime,
+            _ => Termination::OutOfSteps,
+        }
+    }
+}
+
+/// A summary of a run returned by `TuringMachine::run_with_outcome`, whose `Display` implementation
+/// formats it for humans, e.g. `halted after 4,098 steps in 1.2ms; 13 ones on tape; head range -5..=17`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub termination: Termination,
+    pub steps: usize,
+    pub elapsed: Duration,
+    /// The number of nonblank cells on the tape after the run.
+    pub nonblank: usize,
+    /// Whether the tape only held the symbols 0 and 1 after the run, in which case `nonblank` counts ones.
+    pub binary: bool,
+    /// The leftmost and rightmost locations of the head during the run.
+    pub head_range: RangeInclusive<i64>,
+    /// The number of writes skipped because they wrote the symbol that was read; see `TuringMachine::with_write_skipping`.
+    pub skipped_writes: usize,
+}
+
+impl fmt::Display for RunOutcome {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let termination = match self.termination {
+            Termination::Halted => "halted",
+            Termination::OutOfSteps => "ran out of steps",
+            Termination::OutOfTime => "ran out of time",
+            Termination::NonHalting => "proven non-halting",
+            Termination::OutOfSpace => "ran out of space",
+            Termination::Stopped => "was stopped",
+        };
+        let steps = group_digits(self.steps);
+        let step_noun = if self.steps == 1 { "step" } else { "steps" };
+        let cells = match (self.binary, self.nonblank) {
+            (true, 1) => "one",
+            (true, _) => "ones",
+            (false, 1) => "nonblank cell",
+            (false, _) => "nonblank cells",
+        };
+
+        write!(
+            f,
+            "{} after {} {} in {:.1?}; {} {} on tape; head range {}..={}",
+            termination, steps, step_noun, self.elapsed, self.nonblank, cells, self.head_range.start(), self.head_range.end(),
+        )
+    }
+}
+
+/// The number of runs that ended with each `Termination`, as returned by `TuringMachine::termination_counts`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TerminationCounts {
+    pub halted: usize,
+    pub out_of_steps: usize,
+    pub out_of_time: usize,
+    pub non_halting: usize,
+    pub out_of_space: usize,
+    pub stopped: usize,
+}
+
+impl TerminationCounts {
+    /// Counts one more run that ended with `termination`.
+    #[inline]
+    pub fn add(&mut self, termination: Termination) {
+        match termination {
+            Termination::Halted => self.halted += 1,
+            Termination::OutOfSteps => self.out_of_steps += 1,
+            Termination::OutOfTime => self.out_of_time += 1,
+            Termination::NonHalting => self.non_halting += 1,
+            Termination::OutOfSpace => self.out_of_space += 1,
+            Termination::Stopped => self.stopped += 1,
+        }
+    }
+
+    /// Returns the number of runs that were forcibly halted without their fate being decided.
+    #[inline]
+    pub fn undecided(&self) -> usize {
+        self.out_of_steps + self.out_of_time + self.out_of_space + self.stopped
+    }
+
+    /// Returns the number of runs counted.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.halted + self.undecided() + self.non_halting
+    }
+}
+
+impl fmt::Display for TerminationCounts {
+    /// Formats `self` as e.g. `halted: 3, out of steps: 1, out of time: 0, non-halting: 2, out of space: 0, stopped: 0`.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "halted: {}, out of steps: {}, out of time: {}, non-halting: {}, out of space: {}, stopped: {}",
+            self.halted, self.out_of_steps, self.out_of_time, self.non_halting, self.out_of_space, self.stopped
+        )
+    }
+}
+
+/// The result of `TuringMachine::chaitin_approx`, along with everything needed to interpret and compare it,
+/// whose `Display` implementation formats it for humans, e.g.
+/// `2 states and 2 symbols within 50 steps: 47.18% halted, 4.83% undecided of 20,736 machines in 25.3ms`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChaitinReport {
+    /// The fraction of machines that halted.
+    pub halted: f64,
+    /// The fraction of machines that were forcibly halted without being proven never to halt.
+    pub undecided: f64,
+    /// The number of machines run.
+    pub total: usize,
+    /// The `HaltSetting` each machine was run with.
+    pub budget: HaltSetting,
+    pub num_states: usize,
+    pub num_symbols: usize,
+    /// How long the sweep took.
+    pub duration: Duration,
+}
+
+impl ChaitinReport {
+    /// Returns the fractions of halted and undecided machines.
+    #[inline]
+    pub fn ratios(&self) -> (f64, f64) {
+        (self.halted, self.undecided)
+    }
+}
+
+impl fmt::Display for ChaitinReport {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let budget = match self.budget {
+            HaltSetting::NoForcedHalt => "without a budget".to_string(),
+            HaltSetting::AfterSteps(steps) => format!("within {} steps", group_digits(steps)),
+            HaltSetting::AfterDuration(duration) => format!("within {:?}", duration),
+            HaltSetting::AfterVirtualDuration { duration, steps_per_second } => format!("within {:?} at {} steps per second", duration, group_digits(steps_per_second as usize)),
+        };
+
+        write!(
+            f,
+            "{} states and {} symbols {}: {:.2}% halted, {:.2}% undecided of {} machines in {:.1?}",
+            self.num_states, self.num_symbols, budget, self.halted * 100.0, self.undecided * 100.0, group_digits(self.total), self.duration,
+        )
+    }
+}
+
+/// Formats `n` with commas between groups of three digits, e.g. `4,098`.
+fn group_digits(n: usize) -> String {
+    let mut digits = n.to_string();
+    let len = digits.len();
+    for i in (1..len).rev().filter(|i| (len - i).is_multiple_of(3)) {
+        digits.insert(i, ',');
+    }
+
+    digits
+}
+
+/// A description of a single step taken by a `TuringMachine`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepEvent {
+    /// The number of steps taken so far, including this one.
+    pub step_num: usize,
+    /// The state before the step.
+    pub state: u64,
+    /// The head location before the step.
+    pub head_loc: i64,
+    /// The symbol read at `head_loc`.
+    pub symbol: u64,
+    /// The output of the transition function: new state, symbol written, and head movement.
+    pub output: (u64, u64, bool),
+}
+
+/// The tape handed to the hook of `TuringMachine::run_with_hook_and_record`, which reads like a `Tape`
+/// but logs every change it writes as a `Perturbation` of the recording.
+#[derive(Debug)]
+pub struct HookTape<'a> {
+    tape: &'a mut Tape,
+    step_num: usize,
+    perturbations: &'a mut Vec<Perturbation>,
+}
+
+impl HookTape<'_> {
+    /// Writes the specified symbol at location n like `Tape::write`, logging the change unless the cell already held it.
+    /// 
+    /// # Panics
+    /// Panics if a nonblank symbol is written farther than `tape::MAX_LOCATION` from location 0.
+    #[inline]
+    pub fn write(&mut self, n: i64, symbol: u64) {
+        let old = self.tape.symbol_at_n(n);
+        self.tape.write(n, symbol);
+        if old != symbol {
+            self.perturbations.push(Perturbation { step_num: self.step_num, location: n, old, new: symbol });
+        }
+    }
+}
+
+impl Deref for HookTape<'_> {
+    type Target = Tape;
+
+    #[inline]
+    fn deref(&self) -> &Tape {
+        self.tape
+    }
+}
+
+/// The ring buffer behind `TuringMachine::with_history`.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct StepHistory {
+    capacity: usize,
+    events: VecDeque<StepEvent>,
+    /// The number of steps taken since the machine was constructed or reset.
+    step_num: usize,
+}
+
+impl StepHistory {
+    /// Appends `event`, numbering it, and evicts the oldest event if full.
+    fn push(&mut self, mut event: StepEvent) {
+        self.step_num += 1;
+        event.step_num = self.step_num;
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Parses the value of the `history` section written by `TuringMachine`'s `Display` implementation,
+    /// `capacity@step_num` optionally followed by `:` and the events separated by `/`, or returns `None` if it is malformed.
+    fn from_token(token: &str) -> Option<Self> {
+        let (head, events) = match token.split_once(':') {
+            Some((head, events)) => (head, events.split('/').collect()),
+            None => (token, Vec::new()),
+        };
+        let (capacity, step_num) = head.split_once('@')?;
+        let mut history = StepHistory { capacity: capacity.parse().ok()?, events: VecDeque::new(), step_num: step_num.parse().ok()? };
+        if events.len() > history.capacity || events.len() > history.step_num {
+            return None;
+        }
+
+        let first = history.step_num + 1 - events.len();
+        for (step_num, event) in (first..).zip(events) {
+            let (input, output) = event.split_once("->")?;
+            let input = input.split(',').collect::<Vec<&str>>();
+            let output = output.split(',').collect::<Vec<&str>>();
+            let ([state, head_loc, symbol], [new_state, write, head_move]) = (&input[..], &output[..]) else { return None };
+            let mut chars = head_move.chars();
+            let right = match (chars.next().map(Move::try_from), chars.next()) {
+                (Some(Ok(Move::Left)), None) => false,
+                (Some(Ok(Move::Right)), None) => true,
+                _ => return None,
+            };
+
+            history.events.push_back(StepEvent {
+                step_num,
+                state: state.parse().ok()?,
+                head_loc: head_loc.parse().ok()?,
+                symbol: symbol.parse().ok()?,
+                output: (new_state.parse().ok()?, write.parse().ok()?, right),
+            });
+        }
+
+        Some(history)
+    }
+}
+
+/// The decider behind `TuringMachine::runs_off_forever`, for a machine in `state` at `head_loc`.
+pub(crate) fn runs_off_forever(transition_fn: &TransitionFn, state: u64, head_loc: i64, tape: &Tape) -> bool {
+    // the direction the head must keep moving in to never see a nonblank cell again
+    let mut right = match tape.nonblank_range() {
+        None => None,
+        Some(range) if head_loc > *range.end() => Some(true),
+        Some(range) if head_loc < *range.start() => Some(false),
+        Some(_) => return false,
+    };
+
+    let mut seen = HashSet::new();
+    let mut state = state;
+    while seen.insert(state) {
+        let Some((next_state, _, moves_right)) = transition_fn.run(state, 0) else { return false };
+        if *right.get_or_insert(moves_right) != moves_right {
+            return false;
         }
+        state = next_state;
     }
 
-    /// Runs `self`, changing its state and moving its head while writing to the specified tape.
-    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
-    #[inline]
-    pub fn run_with_halt_setting(&mut self, tape: &mut Tape, halt_setting: HaltSetting) {
-        if halt_setting == HaltSetting::NoForcedHalt {
-            self.run(tape);
-            return;
-        }
+    true
+}
 
-        let start = Instant::now();
-        let mut step_num = 0;
+/// Counts a run that took `steps` steps and ended with `termination` in the `telemetry` counters,
+/// computing `termination` only if the `metrics` feature is enabled.
+#[inline]
+pub(crate) fn record_run(_steps: usize, _termination: impl FnOnce() -> Termination) {
+    #[cfg(feature = "metrics")]
+    crate::telemetry::record_run(_steps, _termination());
+}
 
-        let mut symbol;
-        loop {
-            if let HaltSetting::AfterSteps(max_steps) = halt_setting {
-                if step_num >= max_steps {
-                    break;
-                }
-                step_num += 1;
-            }
-            else if let HaltSetting::AfterDuration(max_duration) = halt_setting {
-                if start.elapsed() >= max_duration {
-                    break;
-                }
-            } 
+/// Tracks whether a `HaltSetting` has forcibly halted a run.
+pub(crate) struct HaltCheck {
+    halt_setting: HaltSetting,
+    start: Instant,
+    step_num: usize,
+}
 
-            symbol = tape.symbol_at_n(self.head_loc);
-            if let Some(output) = self.transition_fn.run(self.state, symbol) {
-                self.state = output.0;
-                tape.write(self.head_loc, output.1);
-                self.head_loc += output.2 as i64 * 2 - 1;
-            }
-            else {
-                break;
-            }
+impl HaltCheck {
+    #[inline]
+    pub(crate) fn new(halt_setting: HaltSetting) -> Self {
+        HaltCheck {
+            halt_setting: match halt_setting {
+                HaltSetting::AfterVirtualDuration { .. } => HaltSetting::AfterSteps(halt_setting.max_steps().unwrap_or(usize::MAX)),
+                _ => halt_setting,
+            },
+            start: Instant::now(),
+            step_num: 0,
         }
     }
 
-    /// Runs `self`, changing its state and moving its head while writing to the specified tape.
-    /// Takes in a `HaltSetting` that describes when the machine should be forcibly halted.
-    /// Returns a `Recording` of the process that contains all steps and can be played back.
+    /// Returns whether the run should be forcibly halted before taking its next step.
     #[inline]
-    pub fn run_with_halt_setting_and_record(&mut self, tape: &mut Tape, halt_setting: HaltSetting) -> Recording {
-        let input = tape.clone();
-        let init_state = self.state.clone();
-        let init_head_loc = self.head_loc.clone();
-        let mut steps = Vec::default();
+    pub(crate) fn should_halt(&mut self) -> bool {
+        match self.halt_setting {
+            HaltSetting::NoForcedHalt => false,
+            HaltSetting::AfterSteps(max_steps) => {
+                if self.step_num >= max_steps {
+                    return true;
+                }
+                self.step_num += 1;
 
-        if halt_setting == HaltSetting::NoForcedHalt {
-            return self.run_and_record(tape);
+                false
+            }
+            HaltSetting::AfterDuration(max_duration) => self.start.elapsed() >= max_duration,
+            HaltSetting::AfterVirtualDuration { .. } => unreachable!("converted to a step budget by HaltCheck::new"),
         }
+    }
+}
 
-        let start = Instant::now();
-        let mut step_num = 0;
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
 
-        let mut symbol;
-        loop {
-            if let HaltSetting::AfterSteps(max_steps) = halt_setting {
-                if step_num >= max_steps {
-                    break;
-                }
-                step_num += 1;
-            }
-            else if let HaltSetting::AfterDuration(max_duration) = halt_setting {
-                if start.elapsed() >= max_duration {
-                    break;
-                }
-            } 
+    use crate::prelude::*;
+    use crate::transition_fn::FlatTable;
 
-            symbol = tape.symbol_at_n(self.head_loc);
-            if let Some(output) = self.transition_fn.run(self.state, symbol) {
-                self.state = output.0;
-                tape.write(self.head_loc, output.1);
-                self.head_loc += output.2 as i64 * 2 - 1;
+    #[test]
+    fn test_run_throttled() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (0, 1, true))]);
+        let mut machine = TuringMachine::new(trans_fn).with_halt_setting(HaltSetting::AfterSteps(100));
 
-                steps.push(output);
+        let start = std::time::Instant::now();
+        let mut seen = Vec::new();
+        let steps = machine.run_throttled(&mut Tape::default(), 200.0, |tape, event| {
+            seen.push((event.step_num, tape.symbols().len()));
+            if event.step_num == 11 {
+                std::ops::ControlFlow::Break(())
             }
             else {
-                break;
+                std::ops::ControlFlow::Continue(())
             }
-        }
+        });
 
-        Recording {
-            input,
-            init_state,
-            init_head_loc,
-            steps,
-        }
+        assert_eq!(steps, 11);
+        assert_eq!(seen, (1..=11).map(|n| (n, n)).collect::<Vec<_>>());
+        assert!(start.elapsed() >= Duration::from_millis(50));
     }
 
-    #[inline]
-    pub fn chaitin_approx(num_states: usize, num_symbols: usize, halt_setting: HaltSetting) -> (f64, f64) {
-        let trans_fns = TransitionFn::enumerate(num_states, num_symbols);
-        let mut halted = 0;
-        let mut undecided = 0;
-
-        for t in 0..trans_fns.len() {
-            let mut tm = TuringMachine::new(trans_fns[t].clone());
+    #[test]
+    fn test_flat_table_fast_path() {
+        for i in 0..200 {
+            let num_symbols = 2 + i % 3;
+            let trans_fn = TransitionFn::random(4, num_symbols, Seed(i as u64));
+            let input = Tape::random_seeded(i % 7, num_symbols as u64, Seed(1000 + i as u64));
+            let halt_setting = HaltSetting::AfterSteps(i * 3);
 
-            tm.run_with_halt_setting(&mut Tape::default(), halt_setting);
-            
-            if tm.state == num_states as u64 {
-                halted += 1;
-            }
-            else {
-                undecided += 1;
+            // step by step through the state table
+            let mut expected = TuringMachine::new(trans_fn.clone());
+            let mut expected_tape = input.clone();
+            let mut expected_steps = 0;
+            while expected_steps < i * 3 && expected.step(&mut expected_tape).is_some() {
+                expected_steps += 1;
             }
+
+            let mut machine = TuringMachine::new(trans_fn.clone());
+            let mut tape = input.clone();
+            machine.run_with_halt_setting(&mut tape, halt_setting);
+            assert_eq!((machine.state(), machine.head_loc(), &tape), (expected.state(), expected.head_loc(), &expected_tape));
+
+            let mut tape = input.clone();
+            let mut cursor = Cursor::new(&trans_fn);
+            assert_eq!(cursor.run_with_halt_setting(&mut tape, halt_setting), expected_steps);
+            assert_eq!((cursor.state(), cursor.head_loc(), &tape), (expected.state(), expected.head_loc(), &expected_tape));
         }
 
-        (halted as f64 / trans_fns.len() as f64, undecided as f64 / trans_fns.len() as f64)
+        // a short run on a long tape is not worth copying the tape for
+        let tape = Tape::new(vec![1; 100]);
+        assert!(!FlatTable::pays_off(&tape, tape.raw_symbols().len() - 1));
+        assert!(FlatTable::pays_off(&tape, tape.raw_symbols().len()));
+
+        // states that the flat table can not hold fall back to the state table
+        let trans_fn = TransitionFn::new(&[((1 << 30, 0), (0, 1, true)), ((0, 0), (1 << 30, 1, true))]);
+        assert!(trans_fn.flat_table().is_none());
+        let mut machine = TuringMachine::new(trans_fn);
+        let mut tape = Tape::default();
+        machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(5));
+        assert_eq!((tape.symbols(), machine.state()), (vec![1, 1, 1, 1, 1], 1 << 30));
     }
-}
 
-/// A parameter type that describes when a Turing machine should be forcibly halted.
-/// The `NoForcedHalt` variant simply states that the machine should not be forcibly halted.
-/// The `AfterSteps(usize)` variant states that it should be halted after `usize` number of steps;
-/// i.e., the machine has written to the tape `usize` number of times.
-/// The `AfterDuration(Duration)` variant states the machine should be halted after a `Duration` has elapsed.
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
-pub enum HaltSetting {
-    #[default]
-    NoForcedHalt,
-    AfterSteps(usize),
-    AfterDuration(Duration),
-}
+    #[test]
+    fn test_peek() {
+        let (mut machine, _) = crate::examples::busy_beaver_2();
+        machine.reset();
+        let mut tape = Tape::default();
 
-#[allow(unused_imports)]
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
+        let events = machine.peek(&tape, 3);
+        assert_eq!(events.len(), 3);
+        assert_eq!(tape, Tape::default());
+        assert!(machine.peek(&tape, 0).is_empty());
+        // peeking as far as possible only allocates for the steps taken
+        assert_eq!(machine.peek(&tape, usize::MAX).len(), 6);
 
-    use crate::prelude::*;
+        for event in events {
+            assert_eq!((machine.state(), machine.head_loc()), (event.state, event.head_loc));
+            assert_eq!(machine.step(&mut tape), Some(event.output));
+        }
+    }
+
+    #[test]
+    fn test_cursor() {
+        let machine = TuringMachine::new(TransitionFn::new(&[
+            ((0, 0), (1, 1, true)),
+            ((0, 1), (1, 1, false)),
+            ((1, 0), (0, 1, false)),
+            ((1, 1), (2, 1, true)),
+        ]));
+
+        let mut cursor = machine.cursor();
+        let mut tape = Tape::default();
+        assert_eq!(cursor.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(4)), 4);
+        assert_eq!(cursor.run_with_halt_setting(&mut tape, HaltSetting::NoForcedHalt), 2);
+        assert_eq!((tape.symbols(), tape.nonblank_range()), (vec![1, 1, 1, 1], Some(-2..=1)));
+        assert_eq!((cursor.state(), cursor.head_loc()), (2, 0));
+
+        // the machine itself is untouched
+        assert_eq!((machine.state(), machine.head_loc()), (0, 0));
+        assert_eq!(Cursor::new(machine.transition_fn()), machine.cursor());
+    }
 
     #[test]
     fn test_reset() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((0, 0), (1, 312, true)),
                 ((1, 0), (2, 0, false)),
                 ((2, 0), (7, 4, true)),
@@ -267,7 +2111,7 @@ mod tests {
     #[test]
     fn test_run() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((0, 0), (10, 10, false)),
                 ((10, 0), (9, 9, false)),
                 ((9, 0), (8, 8, false)),
@@ -289,10 +2133,51 @@ mod tests {
         assert_eq!(tape.symbols(), [1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
     }
 
+    #[test]
+    fn test_run_unbounded() {
+        let trans_fn = TransitionFn::new(
+            &[
+                ((0, 0), (1, 1, true)),
+                ((1, 0), (2, 2, true)),
+                ((2, 0), (3, 3, true)),
+            ]
+        );
+
+        let mut machine = TuringMachine::new(trans_fn).with_halt_setting(HaltSetting::AfterSteps(1));
+        let mut tape = Tape::default();
+
+        machine.run(&mut tape);
+        assert_eq!(tape.symbols(), [1]);
+
+        machine.reset();
+        tape = Tape::default();
+        machine.run_unbounded(&mut tape);
+        assert_eq!(tape.symbols(), [1, 2, 3]);
+
+        machine.reset();
+        let record = machine.run_and_record(&mut Tape::default());
+        assert_eq!(record.steps, [(1, 1, Move::Right)]);
+    }
+
+    #[test]
+    fn test_default_halt_setting() {
+        // this machine never halts on its own
+        let trans_fn = TransitionFn::new(&[((0, 0), (0, 0, true))]);
+        let mut machine = TuringMachine::new(trans_fn);
+        assert_eq!(machine.halt_setting(), HaltSetting::AfterSteps(DEFAULT_STEP_BUDGET));
+
+        machine.run(&mut Tape::default());
+        assert_eq!(machine.head_loc(), DEFAULT_STEP_BUDGET as i64);
+
+        let unbounded = machine.clone().with_halt_setting(HaltSetting::NoForcedHalt);
+        assert!(unbounded.to_string().ends_with(" halt_setting=none"));
+        assert_eq!(unbounded.to_string().parse(), Ok(unbounded));
+    }
+
     #[test]
     fn test_run_and_record() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((0, 0), (1, 1, true)),
                 ((1, 0), (1, 4, false)),
                 ((1, 1), (2, 1, false)),
@@ -308,13 +2193,13 @@ mod tests {
 
         assert_eq!(tape2.symbols(), vec![3, 1, 4, 1, 5, 9]);
         assert_eq!(record.input, tape);
-        assert_eq!(record.steps, [(1, 1, true), (1, 4, false), (2, 1, false), (3, 3, true)]);
+        assert_eq!(record.steps, [(1, 1, Move::Right), (1, 4, Move::Left), (2, 1, Move::Left), (3, 3, Move::Right)]);
     }
 
     #[test]
     fn test_run_with_halt_setting() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((0, 0), (1, 1, true)),
                 ((1, 0), (2, 2, true)),
                 ((2, 0), (0, 3, true)),
@@ -334,17 +2219,31 @@ mod tests {
         machine.reset();
         tape = Tape::default();
         machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(0));
-        assert_eq!(tape.symbols(), []);
+        assert!(tape.symbols().is_empty());
 
         machine.reset();
         tape = Tape::default();
         machine.run_with_halt_setting(&mut tape, HaltSetting::AfterDuration(Duration::from_micros(1000)));
+
+        // a virtual duration is a step budget, also on the fast path and with a cursor
+        let halt_setting = HaltSetting::AfterVirtualDuration { duration: Duration::from_millis(7), steps_per_second: 1000 };
+        machine.reset();
+        tape = Tape::default();
+        machine.run_with_halt_setting(&mut tape, halt_setting);
+        assert_eq!(tape.symbols(), [1, 2, 3, 1, 2, 3, 1]);
+        assert_eq!(machine.cursor().run_with_halt_setting(&mut Tape::default(), halt_setting), 7);
+
+        // never halts, and can't be proven not to
+        let bouncer = TransitionFn::new(&[((0, 0), (1, 0, true)), ((1, 0), (0, 0, false))]);
+        let mut machine = TuringMachine::new(bouncer).with_halt_setting(halt_setting);
+        let outcome = machine.run_with_outcome(&mut Tape::default());
+        assert_eq!((outcome.termination, outcome.steps), (Termination::OutOfTime, 7));
     }
 
     #[test]
     fn test_run_with_halt_setting_and_record() {
         let trans_fn = TransitionFn::new(
-            &vec![
+            &[
                 ((0, 0), (1, 1, true)),
                 ((1, 0), (0, 1, false)),
                 ((0, 1), (1, 2, true)),
@@ -364,6 +2263,448 @@ mod tests {
 
         assert_eq!(tape2.symbols(), vec![3, 2]);
         assert_eq!(record.input, tape);
-        assert_eq!(record.steps, [(1, 1, true), (0, 1, false), (1, 2, true), (0, 2, false), (1, 3, true)]);
+        assert_eq!(record.steps, [(1, 1, Move::Right), (0, 1, Move::Left), (1, 2, Move::Right), (0, 2, Move::Left), (1, 3, Move::Right)]);
+    }
+
+    #[test]
+    fn test_run_with_hook_and_record() {
+        let trans_fn = TransitionFn::new(
+            &[
+                ((0, 0), (0, 1, true)),
+                ((0, 2), (1, 3, false)),
+            ]
+        );
+
+        let mut machine = TuringMachine::new(trans_fn);
+        let mut tape = Tape::default();
+        let mut events = Vec::default();
+
+        let record = machine.run_with_hook_and_record(&mut tape, HaltSetting::AfterSteps(10), |tape, event| {
+            events.push(*event);
+            if event.step_num == 3 {
+                tape.write(3, 2);
+                tape.write(-1, 5);
+                // writing what a cell already holds is not a change
+                tape.write(0, tape.symbol_at_n(0));
+            }
+        });
+
+        assert_eq!(tape.symbols(), [5, 1, 1, 1, 3]);
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[3], StepEvent { step_num: 4, state: 0, head_loc: 3, symbol: 2, output: (1, 3, false) });
+        assert_eq!(record.perturbations, [
+            Perturbation { step_num: 3, location: 3, old: 0, new: 2 },
+            Perturbation { step_num: 3, location: -1, old: 0, new: 5 },
+        ]);
+
+        let last = record.frames(Duration::ZERO).last().unwrap().1;
+        assert_eq!(last.window, [0, 0, 5, 1, 1, 1, 3, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_run_with_faults_and_record() {
+        let trans_fn = TransitionFn::new(
+            &[
+                ((0, 0), (1, 1, true)),
+                ((1, 0), (0, 0, true)),
+                ((0, 1), (1, 0, true)),
+                ((1, 1), (0, 1, true)),
+            ]
+        );
+        let fault_model = FaultModel {
+            num_symbols: 2,
+            flip_probability: 0.3,
+            misread_probability: 0.2,
+            stuck_cells: vec![5, 6],
+            seed: Seed(1234),
+        };
+
+        let mut machine = TuringMachine::new(trans_fn.clone());
+        let mut tape = Tape::default();
+        let record = machine.run_with_faults_and_record(&mut tape, HaltSetting::AfterSteps(200), &fault_model);
+
+        assert_eq!(record.steps.len(), 200);
+        assert_eq!(record.seed(), Some(Seed(1234)));
+        assert_eq!(FaultModel { flip_probability: 0.3, misread_probability: 0.2, stuck_cells: vec![5, 6], ..FaultModel::new(2, Seed(1234)) }, fault_model);
+        assert!(record.faults().iter().any(|f| matches!(f, Fault::Misread { .. })));
+        assert!(record.faults().iter().any(|f| matches!(f, Fault::Flip { .. })));
+        assert!(record.faults().windows(2).all(|w| w[0].step_num() <= w[1].step_num()));
+
+        // playback reproduces the faulty tape
+        let last = record.frames(Duration::ZERO).last().unwrap().1;
+        assert_eq!(last.window, (195..=205).map(|n| tape.symbol_at_n(n)).collect::<Vec<u64>>());
+
+        // the same seed reproduces the same faults
+        let mut machine = TuringMachine::new(trans_fn);
+        let mut tape2 = Tape::default();
+        assert_eq!(machine.run_with_faults_and_record(&mut tape2, HaltSetting::AfterSteps(200), &fault_model), record);
+        assert_eq!(tape2, tape);
+
+        // a symbol beyond the alphabet is flipped to one within it
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 7), (1, 7, true))]));
+        let mut tape = Tape::new(vec![7]);
+        let fault_model = FaultModel { flip_probability: 1.0, ..FaultModel::new(2, Seed(1)) };
+        let record = machine.run_with_faults_and_record(&mut tape, HaltSetting::AfterSteps(1), &fault_model);
+        assert!(matches!(record.faults(), [Fault::Flip { old: 7, new: 0 | 1, .. }]));
+
+        // a misread that finds no rule halts the machine, and is still logged
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+        let mut tape = Tape::default();
+        let fault_model = FaultModel { misread_probability: 1.0, ..FaultModel::new(2, Seed(1)) };
+        let record = machine.run_with_faults_and_record(&mut tape, HaltSetting::AfterSteps(10), &fault_model);
+        assert!(record.is_empty());
+        assert_eq!(record.faults(), [Fault::Misread { step_num: 1, location: 0, actual: 0, read: 1 }]);
+        assert_eq!(tape, Tape::default());
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_test_fault_model_without_symbols() {
+        FaultModel::new(0, Seed(1));
+    }
+
+    #[test]
+    fn test_run_until_pattern() {
+        let trans_fn = TransitionFn::new(
+            &[
+                ((0, 0), (1, 1, false)),
+                ((1, 0), (2, 0, false)),
+                ((2, 0), (0, 2, false)),
+            ]
+        );
+
+        let mut machine = TuringMachine::new(trans_fn.clone());
+        let mut tape = Tape::default();
+        assert_eq!(machine.run_until_pattern(&mut tape, &[2, 0, 1, 2], HaltSetting::AfterSteps(100)), Some(-5));
+        assert_eq!(machine.head_loc(), -6);
+
+        // already present
+        let mut machine = TuringMachine::new(trans_fn.clone());
+        assert_eq!(machine.run_until_pattern(&mut tape, &[0, 1], HaltSetting::AfterSteps(100)), Some(-4));
+        assert_eq!(machine.head_loc(), 0);
+
+        // forcibly halted first
+        let mut machine = TuringMachine::new(trans_fn);
+        assert_eq!(machine.run_until_pattern(&mut Tape::default(), &[2, 2], HaltSetting::AfterSteps(100)), None);
+        assert_eq!(machine.head_loc(), -100);
+    }
+
+    #[test]
+    fn test_chaitin_approx_with() {
+        let mut counts = TerminationCounts::default();
+        let mut max_halting_steps = 0;
+        let report = TuringMachine::chaitin_approx_with(2, 2, HaltSetting::AfterSteps(30), |_, termination, steps| {
+            counts.add(termination);
+            match termination {
+                Termination::Halted => max_halting_steps = max_halting_steps.max(steps),
+                _ => assert_eq!(steps, 30),
+            }
+        });
+
+        assert_eq!(report.ratios(), TuringMachine::chaitin_approx(2, 2, HaltSetting::AfterSteps(30)).ratios());
+        assert_eq!(counts, TuringMachine::termination_counts(2, 2, HaltSetting::AfterSteps(30)));
+        assert_eq!((counts.total(), report.total), (20736, 20736));
+        assert_eq!(counts.halted as f64 / 20736.0, report.halted);
+        assert_eq!(counts.undecided() as f64 / 20736.0, report.undecided);
+        assert_eq!((report.num_states, report.num_symbols, report.budget), (2, 2, HaltSetting::AfterSteps(30)));
+
+        let report = ChaitinReport { halted: 0.25, undecided: 0.125, duration: Duration::from_millis(12), ..report };
+        assert_eq!(report.to_string(), "2 states and 2 symbols within 30 steps: 25.00% halted, 12.50% undecided of 20,736 machines in 12.0ms");
+        assert_eq!(counts.out_of_time, 0);
+        assert_eq!(max_halting_steps, 6);
+    }
+
+    #[test]
+    fn test_runs_off_forever() {
+        // runs off to the left after writing a 1
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, false)), ((1, 0), (2, 0, false)), ((2, 0), (1, 0, false))]);
+        let mut machine = TuringMachine::new(trans_fn);
+        let mut tape = Tape::default();
+        assert!(machine.runs_off_forever(&tape));
+        machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(3));
+        assert_eq!(machine.head_loc(), -3);
+        assert!(machine.runs_off_forever(&tape));
+
+        // a nonblank cell lies ahead
+        tape.write(-5, 1);
+        assert!(!machine.runs_off_forever(&tape));
+
+        // the head is between nonblank cells
+        let mut between = Tape::new(vec![1]);
+        between.write(-4, 1);
+        assert!(!machine.runs_off_forever(&between));
+
+        // turns around
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, false))]));
+        assert!(!machine.runs_off_forever(&Tape::default()));
+
+        // halts on a blank
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+        assert!(!machine.runs_off_forever(&Tape::default()));
+    }
+
+    #[test]
+    fn test_run_with_outcome() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 2, true)), ((1, 0), (1, 0, false)), ((1, 2), (2, 2, false)), ((2, 0), (2, 0, false))]);
+        let mut machine = TuringMachine::new(trans_fn).with_halt_setting(HaltSetting::AfterSteps(1234));
+        let mut outcome = machine.run_with_outcome(&mut Tape::default());
+
+        assert_eq!(outcome.termination, Termination::NonHalting);
+        assert_eq!(outcome.head_range, -1232..=1);
+        outcome.elapsed = Duration::from_micros(1250);
+        assert_eq!(outcome.to_string(), "proven non-halting after 1,234 steps in 1.2ms; 1 nonblank cell on tape; head range -1232..=1");
+
+        outcome.termination = Termination::OutOfSteps;
+        outcome.steps = 1;
+        outcome.binary = true;
+        outcome.nonblank = 0;
+        assert_eq!(outcome.to_string(), "ran out of steps after 1 step in 1.2ms; 0 ones on tape; head range -1232..=1");
+    }
+
+    #[test]
+    fn test_with_history() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 2, true))]);
+
+        let mut machine = TuringMachine::new(trans_fn.clone()).with_history(2);
+        machine.run_with_halt_setting(&mut Tape::default(), HaltSetting::AfterSteps(5));
+        assert_eq!(machine.history(), [
+            StepEvent { step_num: 4, state: 1, head_loc: 3, symbol: 0, output: (0, 2, true) },
+            StepEvent { step_num: 5, state: 0, head_loc: 4, symbol: 0, output: (1, 1, true) },
+        ]);
+
+        // stepping continues the numbering
+        machine.step(&mut Tape::default());
+        assert_eq!(machine.history()[1].step_num, 6);
+
+        machine.reset();
+        assert!(machine.history().is_empty());
+
+        // the capacity only bounds the history, even against limits
+        let limits = Limits { max_tape_cells: None, max_memory_bytes: Some(1000) };
+        let mut machine = TuringMachine::new(trans_fn.clone()).with_history(usize::MAX).with_limits(limits);
+        machine.run_with_halt_setting(&mut Tape::default(), HaltSetting::AfterSteps(5));
+        assert_eq!(machine.history().len(), 5);
+
+        // no history by default
+        let mut machine = TuringMachine::new(trans_fn);
+        machine.run_with_halt_setting(&mut Tape::default(), HaltSetting::AfterSteps(5));
+        assert!(machine.history().is_empty());
+    }
+
+    #[test]
+    fn test_with_limits() {
+        // fills the tape with ones to the right, stepping back after every second one
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 1, true)), ((1, 0), (0, 1, false))]);
+
+        // 100 cells hold locations -50 through 49, so 50 ones fit to the right of location 0
+        let limits = Limits { max_tape_cells: Some(100), max_memory_bytes: None };
+        let mut machine = TuringMachine::new(trans_fn.clone()).with_limits(limits).with_halt_setting(HaltSetting::AfterSteps(1000));
+        let mut tape = Tape::default();
+        let outcome = machine.run_with_outcome(&mut tape);
+        assert_eq!(outcome.termination, Termination::OutOfSpace);
+        assert!(outcome.termination.is_undecided());
+        assert!(outcome.to_string().starts_with("ran out of space after "));
+        assert_eq!(tape.symbols(), vec![1; 50]);
+
+        // the history counts towards the memory
+        let history_bytes = 10 * std::mem::size_of::<StepEvent>();
+        let limits = Limits { max_tape_cells: None, max_memory_bytes: Some(800 + history_bytes) };
+        let mut machine = TuringMachine::new(trans_fn).with_limits(limits).with_history(10);
+        let mut tape = Tape::default();
+        machine.run_unbounded(&mut tape);
+        assert_eq!(tape.symbols(), vec![1; 50]);
+        assert!(machine.next_step_exceeds_limits(&tape));
+
+        // a proof of non-halting takes precedence, as with other forced halts
+        let trans_fn = TransitionFn::new(&[((0, 0), (0, 1, true))]);
+        let mut machine = TuringMachine::new(trans_fn).with_limits(limits);
+        assert_eq!(machine.run_with_outcome(&mut Tape::default()).termination, Termination::NonHalting);
+
+        // writing blanks never allocates
+        let trans_fn = TransitionFn::new(&[((0, 0), (0, 0, false))]);
+        let mut machine = TuringMachine::new(trans_fn).with_limits(limits).with_halt_setting(HaltSetting::AfterSteps(1000));
+        assert_eq!(machine.run_with_outcome(&mut Tape::default()).termination, Termination::NonHalting);
+
+        let (machine, _) = crate::examples::busy_beaver_3();
+        let mut machine = machine.with_limits(limits);
+        machine.reset();
+        assert_eq!(machine.run_with_outcome(&mut Tape::default()).termination, Termination::Halted);
+
+        // every other run stops at the limits too, including those that record
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((0, 1), (0, 1, true)), ((1, 0), (0, 1, false))]);
+        let limits = Limits { max_tape_cells: Some(100), max_memory_bytes: None };
+        let machine = TuringMachine::new(trans_fn).with_limits(limits).with_halt_setting(HaltSetting::AfterSteps(1000));
+        let runs: [fn(&mut TuringMachine, &mut Tape); 9] = [
+            |m, t| m.run_with_hook(t, HaltSetting::AfterSteps(1000), |_, _| {}),
+            |m, t| { m.run_throttled(t, 1e9, |_, _| std::ops::ControlFlow::Continue(())); },
+            |m, t| { m.run_until_pattern(t, &[2], HaltSetting::AfterSteps(1000)); },
+            |m, t| { m.run_counted(t, HaltSetting::AfterSteps(1000)); },
+            |m, t| { m.run_and_record(t); },
+            |m, t| { m.run_with_hook_and_record(t, HaltSetting::AfterSteps(1000), |_, _| {}); },
+            |m, t| { m.run_with_faults_and_record(t, HaltSetting::AfterSteps(1000), &FaultModel::default()); },
+            |m, t| { m.run_with_stop_flag_and_record(t, HaltSetting::NoForcedHalt, &AtomicBool::new(false)); },
+            |m, t| { m.run_and_record_with_config(t, HaltSetting::AfterSteps(1000), &RecordingConfig { sample_rate: 7, ..Default::default() }); },
+        ];
+        for run in runs {
+            let mut machine = machine.clone();
+            let mut tape = Tape::default();
+            run(&mut machine, &mut tape);
+            assert_eq!(tape.symbols(), vec![1; 50]);
+            assert!(machine.next_step_exceeds_limits(&tape));
+        }
+        let mut machine = machine.clone();
+        let mut tape = Tape::default();
+        machine.run_unbounded_and_record(&mut tape);
+        assert_eq!(tape.symbols(), vec![1; 50]);
+    }
+
+    #[test]
+    fn test_run_past_max_location() {
+        // writes three blanks, then a one, moving right; started near the edge, only the one lies out of range
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 0, true)), ((1, 0), (2, 0, true)), ((2, 0), (3, 0, true)), ((3, 0), (4, 1, true))]);
+        let mut machine = TuringMachine::new(trans_fn).with_halt_setting(HaltSetting::AfterSteps(100_000_000));
+        machine.set_head_loc(MAX_LOCATION - 2);
+
+        let mut stuck = machine.clone();
+        let mut tape = Tape::default();
+        stuck.run(&mut tape);
+        assert_eq!((stuck.state(), stuck.head_loc()), (3, MAX_LOCATION + 1));
+        assert!(stuck.next_step_exceeds_limits(&tape));
+        assert_eq!(stuck.step(&mut tape), None);
+        assert_eq!((stuck.state(), stuck.head_loc()), (3, MAX_LOCATION + 1));
+        assert_eq!(stuck.cursor().step(&mut tape), None);
+
+        let outcome = machine.clone().run_with_outcome(&mut Tape::default());
+        assert_eq!((outcome.termination, outcome.steps), (Termination::OutOfSpace, 3));
+
+        let runs: [fn(&mut TuringMachine, &mut Tape); 11] = [
+            |m, t| m.run_unbounded(t),
+            |m, t| m.run_with_hook(t, HaltSetting::NoForcedHalt, |_, _| {}),
+            |m, t| { m.run_throttled(t, 1e9, |_, _| std::ops::ControlFlow::Continue(())); },
+            |m, t| { m.run_until_pattern(t, &[2], HaltSetting::NoForcedHalt); },
+            |m, t| { m.run_and_record(t); },
+            |m, t| { m.run_unbounded_and_record(t); },
+            |m, t| { m.run_with_hook_and_record(t, HaltSetting::NoForcedHalt, |_, _| {}); },
+            |m, t| { m.run_with_faults_and_record(t, HaltSetting::NoForcedHalt, &FaultModel::default()); },
+            |m, t| { m.run_with_stop_flag_and_record(t, HaltSetting::NoForcedHalt, &AtomicBool::new(false)); },
+            |m, t| { m.run_and_record_with_config(t, HaltSetting::NoForcedHalt, &RecordingConfig { sample_rate: 7, ..Default::default() }); },
+            |m, t| {
+                let mut cursor = m.cursor();
+                assert_eq!(cursor.run_with_halt_setting(t, HaltSetting::NoForcedHalt), 3);
+                let (state, head_loc) = (cursor.state(), cursor.head_loc());
+                m.set_state(state);
+                m.set_head_loc(head_loc);
+            },
+        ];
+        for run in runs {
+            let mut machine = machine.clone();
+            let mut tape = Tape::default();
+            run(&mut machine, &mut tape);
+            assert_eq!((machine.state(), machine.head_loc()), (3, MAX_LOCATION + 1));
+            assert!(tape.nonblank_range().is_none());
+        }
+
+        let mut cursor = machine.cursor();
+        assert_eq!(cursor.run_compact(&mut CompactTape::default(), HaltSetting::NoForcedHalt), 3);
+        assert_eq!(cursor.head_loc(), MAX_LOCATION + 1);
+    }
+
+    #[test]
+    fn test_load_configuration() {
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 1, true))]);
+        let mut machine = TuringMachine::new(trans_fn).with_history(2);
+        machine.set_state(1);
+        machine.set_head_loc(-3);
+        machine.run_with_halt_setting(&mut Tape::default(), HaltSetting::AfterSteps(1));
+        assert_eq!((machine.state(), machine.head_loc()), (0, -2));
+
+        let configuration = Snapshot { step_num: 40, state: 1, head_loc: 5, tape: Tape::new(vec![2]) };
+        let mut tape = machine.load_configuration(&configuration);
+        machine.run_with_halt_setting(&mut tape, HaltSetting::AfterSteps(3));
+        assert_eq!(machine.history().iter().map(|e| (e.step_num, e.state)).collect::<Vec<_>>(), [(42, 0), (43, 1)]);
+        assert_eq!((tape.symbol_at_n(0), tape.symbol_at_n(7)), (2, 1));
+    }
+
+    #[test]
+    fn test_run_with_stop_flag() {
+        let (mut machine, _) = crate::examples::busy_beaver_3();
+        machine.reset();
+        let mut tape = Tape::default();
+
+        let outcome = machine.run_with_stop_flag(&mut tape, HaltSetting::NoForcedHalt, &AtomicBool::new(true));
+        assert_eq!((outcome.termination, outcome.steps), (Termination::Stopped, 0));
+        assert!(outcome.termination.is_undecided());
+        assert!(outcome.to_string().starts_with("was stopped after 0 steps"));
+
+        // a stopped run picks up where it left off
+        let outcome = machine.run_with_stop_flag(&mut tape, HaltSetting::NoForcedHalt, &AtomicBool::new(false));
+        assert_eq!((outcome.termination, outcome.steps, outcome.nonblank), (Termination::Halted, 14, 6));
+
+        let mut counts = TerminationCounts::default();
+        counts.add(Termination::Stopped);
+        assert_eq!((counts.undecided(), counts.total()), (1, 1));
+    }
+
+    #[test]
+    fn test_write_skipping() {
+        // skipping redundant writes never changes the outcome of a run
+        for seed in 0..200 {
+            let trans_fn = TransitionFn::random(3, 3, Seed(seed));
+            let run = |skip| {
+                let mut machine = TuringMachine::new(trans_fn.clone()).with_write_skipping(skip).with_halt_setting(HaltSetting::AfterSteps(500));
+                let mut tape = Tape::new(vec![1, 2, 0, 2]);
+                let outcome = machine.run_with_outcome(&mut tape);
+                ((outcome.termination, outcome.steps, outcome.head_range, machine.state(), tape.symbols()), outcome.skipped_writes)
+            };
+            let (skipping, writing) = (run(true), run(false));
+            assert_eq!(skipping.0, writing.0, "{}", trans_fn);
+            assert_eq!(writing.1, 0);
+        }
+
+        // a scan over a tape already beyond the limits is not stopped, since it never touches the tape
+        let trans_fn = TransitionFn::new(&[((0, 1), (0, 1, true))]);
+        let limits = Limits { max_tape_cells: Some(10), max_memory_bytes: None };
+        let mut machine = TuringMachine::new(trans_fn).with_limits(limits).with_write_skipping(true);
+        let outcome = machine.run_with_outcome(&mut Tape::new(vec![1; 20]));
+        assert_eq!((outcome.termination, outcome.steps, outcome.skipped_writes), (Termination::Halted, 20, 20));
+        assert!(machine.skips_redundant_writes());
+    }
+
+    #[test]
+    fn test_display_and_from_str() {
+        let mut machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, false)), ((1, 0), (2, 3, false))]));
+        machine.run(&mut Tape::default());
+
+        assert_eq!(machine.to_string(), "2 -2 [0,0 -> 1,1,L; 1,0 -> 2,3,L]");
+        assert_eq!(machine.to_string().parse(), Ok(machine));
+        assert!("x 0 []".parse::<TuringMachine>().is_err());
+        assert!("0 0".parse::<TuringMachine>().is_err());
+
+        // every setting round-trips, including a history that has recorded steps
+        let halting_states = HaltingStates { accept: HashSet::from([2, 5]), reject: HashSet::from([3]), halt: HashSet::from([4]) };
+        let limits = Limits { max_tape_cells: Some(1000), max_memory_bytes: Some(1 << 20) };
+        let trans_fn = TransitionFn::new(&[((0, 0), (1, 1, false)), ((1, 0), (0, 3, true)), ((0, 3), (2, 3, true))]);
+        let mut machine = TuringMachine::new(trans_fn)
+            .with_halt_setting(HaltSetting::AfterVirtualDuration { duration: Duration::from_millis(7), steps_per_second: 1000 })
+            .with_halting_states(halting_states)
+            .with_history(2)
+            .with_limits(limits)
+            .with_write_skipping(true);
+        machine.run(&mut Tape::default());
+        assert_eq!(machine.history().len(), 2);
+
+        let logged = machine.to_string();
+        assert_eq!(
+            logged,
+            "0 0 [0,0 -> 1,1,L; 0,3 -> 2,3,R; 1,0 -> 0,3,R] halt_setting=virtual:7000000:1000 accept=2,5 reject=3 halt=4 \
+            history=2@2:0,0,0->1,1,L/1,-1,0->0,3,R max_tape_cells=1000 max_memory_bytes=1048576 skip_redundant_writes=true",
+        );
+        assert_eq!(logged.parse(), Ok(machine.clone()));
+
+        let mut settings = [HaltSetting::NoForcedHalt, HaltSetting::AfterSteps(5), HaltSetting::AfterDuration(Duration::from_nanos(12))].into_iter();
+        assert!(settings.all(|h| HaltSetting::from_token(&h.token()) == Some(h)));
+        assert!("0 0 [] history=1@0:0,0,0->1,1,R".parse::<TuringMachine>().is_err());
+        assert!("0 0 [] skip_redundant_writes".parse::<TuringMachine>().is_err());
+        assert!("0 0 [] color=red".parse::<TuringMachine>().is_err());
     }
 }
\ No newline at end of file
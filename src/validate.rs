@@ -0,0 +1,148 @@
+use crate::tape::Tape;
+use crate::transition_fn::TransitionFn;
+use crate::turing_machine::{HaltSetting, TuringMachine};
+
+/// Describes the simulator being validated.
+/// `simulate` runs the given transition function from state 0 and head location 0 on the given tape,
+/// honoring the given `HaltSetting`, and returns the number of steps it performed.
+#[derive(Clone, Copy, Debug)]
+pub struct SimulatorConfig {
+    pub halt_setting: HaltSetting,
+    pub simulate: fn(&TransitionFn, &mut Tape, HaltSetting) -> usize,
+}
+
+impl Default for SimulatorConfig {
+    /// The crate's own simulator with a budget of 1000 steps, comfortably above every known champion.
+    #[inline]
+    fn default() -> Self {
+        SimulatorConfig {
+            halt_setting: HaltSetting::AfterSteps(1000),
+            simulate: default_simulate,
+        }
+    }
+}
+
+/// The result of running a single busy beaver champion.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BusyBeaverCheck {
+    pub name: &'static str,
+    pub expected_steps: usize,
+    pub expected_ones: usize,
+    pub steps: usize,
+    pub ones: usize,
+}
+
+impl BusyBeaverCheck {
+    /// Returns whether the simulator reproduced both published values.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.steps == self.expected_steps && self.ones == self.expected_ones
+    }
+}
+
+/// The results of `against_known_busy_beavers`, one check per champion.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub checks: Vec<BusyBeaverCheck>,
+}
+
+impl ValidationReport {
+    /// Returns whether every check passed.
+    #[inline]
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|c| c.passed())
+    }
+
+    /// Returns the checks that did not pass.
+    #[inline]
+    pub fn failures(&self) -> Vec<&BusyBeaverCheck> {
+        self.checks.iter().filter(|c| !c.passed()).collect()
+    }
+}
+
+/// Runs the 2-symbol busy beaver champions for 2 to 4 states with the specified simulator
+/// and compares their step counts and number of ones written against the published values.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::validate::*;
+/// 
+/// let report = against_known_busy_beavers(&SimulatorConfig::default());
+/// 
+/// assert!(report.passed());
+/// ```
+#[inline]
+pub fn against_known_busy_beavers(config: &SimulatorConfig) -> ValidationReport {
+    let checks = known_busy_beavers()
+        .into_iter()
+        .map(|(name, trans_fn, expected_steps, expected_ones)| {
+            let mut tape = Tape::default();
+            let steps = (config.simulate)(&trans_fn, &mut tape, config.halt_setting);
+
+            BusyBeaverCheck {
+                name,
+                expected_steps,
+                expected_ones,
+                steps,
+                ones: tape.symbol(1).len(),
+            }
+        })
+        .collect();
+
+    ValidationReport { checks }
+}
+
+/// Returns the champions as `(name, transition function, steps, ones)`.
+/// The halting state of an n-state champion is state n.
+fn known_busy_beavers() -> Vec<(&'static str, TransitionFn, usize, usize)> {
+    vec![
+        ("BB-2", TransitionFn::new(&[
+            ((0, 0), (1, 1, true)), ((0, 1), (1, 1, false)),
+            ((1, 0), (0, 1, false)), ((1, 1), (2, 1, true)),
+        ]), 6, 4),
+        ("BB-3 (sigma)", TransitionFn::new(&[
+            ((0, 0), (1, 1, true)), ((0, 1), (3, 1, true)),
+            ((1, 0), (2, 0, true)), ((1, 1), (1, 1, true)),
+            ((2, 0), (2, 1, false)), ((2, 1), (0, 1, false)),
+        ]), 14, 6),
+        ("BB-3 (shift)", TransitionFn::new(&[
+            ((0, 0), (1, 1, true)), ((0, 1), (3, 1, true)),
+            ((1, 0), (1, 1, false)), ((1, 1), (2, 0, true)),
+            ((2, 0), (2, 1, false)), ((2, 1), (0, 1, false)),
+        ]), 21, 5),
+        ("BB-4", TransitionFn::new(&[
+            ((0, 0), (1, 1, true)), ((0, 1), (1, 1, false)),
+            ((1, 0), (0, 1, false)), ((1, 1), (2, 0, false)),
+            ((2, 0), (4, 1, true)), ((2, 1), (3, 1, false)),
+            ((3, 0), (3, 1, true)), ((3, 1), (0, 0, true)),
+        ]), 107, 13),
+    ]
+}
+
+/// Simulates with `TuringMachine::run_with_halt_setting_and_record`.
+fn default_simulate(trans_fn: &TransitionFn, tape: &mut Tape, halt_setting: HaltSetting) -> usize {
+    TuringMachine::new(trans_fn.clone())
+        .run_with_halt_setting_and_record(tape, halt_setting)
+        .steps
+        .len()
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    #[test]
+    fn test_against_known_busy_beavers() {
+        let report = against_known_busy_beavers(&SimulatorConfig::default());
+        assert_eq!(report.checks.len(), 4);
+        assert!(report.passed());
+
+        // a simulator that gives up too early must be caught
+        let report = against_known_busy_beavers(&SimulatorConfig {
+            halt_setting: HaltSetting::AfterSteps(20),
+            ..Default::default()
+        });
+        assert_eq!(report.failures().iter().map(|c| c.name).collect::<Vec<_>>(), ["BB-3 (shift)", "BB-4"]);
+    }
+}
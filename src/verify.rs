@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+
+use crate::recording::{recorded_step, Recording, Snapshot};
+use crate::tape::Tape;
+use crate::transition_fn::{Rule, TransitionFn};
+use crate::turing_machine::TuringMachine;
+
+/// Runs `machine` from its current configuration on a copy of `input` for up to `budget` steps,
+/// once with `TuringMachine::step` and once with a naive reference engine that looks rules up by a linear scan
+/// and stores the tape in a `HashMap`, and asserts that both produce the same trace and the same final tape.
+/// If the machine can use the flat fast path of `TuringMachine::run_with_halt_setting`, it also asserts
+/// that the fast path reaches the same configuration in the same number of steps. Returns the number of steps taken.
+/// 
+/// This is a differential test: any faster engine added to this crate should agree with the reference engine.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::verify::cross_check;
+/// 
+/// let machine = TuringMachine::new(TransitionFn::new(&[
+///     ((0, 0), (1, 1, true)),
+///     ((0, 1), (1, 1, false)),
+///     ((1, 0), (0, 1, false)),
+///     ((1, 1), (2, 1, true)),
+/// ]));
+/// 
+/// // the 2-state busy beaver halts after 6 steps
+/// assert_eq!(cross_check(&machine, &Tape::new(vec![]), 100), 6);
+/// ```
+/// 
+/// # Panics
+/// Panics if the two engines diverge, describing the first step at which they do.
+#[inline]
+pub fn cross_check(machine: &TuringMachine, input: &Tape, budget: usize) -> usize {
+    let mut machine = machine.clone();
+    let init = (machine.state(), machine.head_loc());
+    let mut tape = input.clone();
+    let mut reference = ReferenceEngine::new(&machine, input);
+
+    let mut steps = budget;
+    for step_num in 0..budget {
+        let output = machine.step(&mut tape);
+        let expected = reference.step();
+
+        if output != expected {
+            panic!("engines diverged at step {}: expected output {:?}, got {:?}", step_num, expected, output);
+        }
+        if output.is_none() {
+            steps = step_num;
+            break;
+        }
+        if (machine.state(), machine.head_loc()) != (reference.state, reference.head_loc) {
+            panic!(
+                "engines diverged after step {}: expected state {} at {}, got state {} at {}",
+                step_num, reference.state, reference.head_loc, machine.state(), machine.head_loc(),
+            );
+        }
+    }
+
+    reference.assert_tape_eq(&tape);
+    cross_check_flat(machine.transition_fn(), input, init, steps, (machine.state(), machine.head_loc(), &tape));
+
+    steps
+}
+
+/// Runs the flat fast path of `transition_fn`, if it has one, from the state and head location `init`
+/// on a copy of `input` for at most `steps` steps, and asserts that it reaches the `expected` state, head location and tape
+/// after exactly `steps` steps.
+fn cross_check_flat(transition_fn: &TransitionFn, input: &Tape, init: (u64, i64), steps: usize, expected: (u64, i64, &Tape)) {
+    let Some(table) = transition_fn.flat_table() else { return };
+    let (mut state, mut head_loc) = init;
+    let mut tape = input.clone();
+    let Some(flat_steps) = table.run(&mut tape, &mut state, &mut head_loc, steps) else { return };
+
+    if (flat_steps, state, head_loc, &tape) != (steps, expected.0, expected.1, expected.2) {
+        panic!(
+            "flat engine diverged: expected state {} at {} after {} steps, got state {} at {} after {}",
+            expected.0, expected.1, steps, state, head_loc, flat_steps,
+        );
+    }
+}
+
+/// A configuration of a machine checked by `check_invariant`: its state, head location, and tape after `step_num` steps.
+pub type Configuration = Snapshot;
+
+/// A violation of an invariant found by `check_invariant`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CounterExample {
+    /// The first configuration that violated the invariant.
+    pub violation: Configuration,
+    /// The run from the initial configuration up to `violation`, which can be played back or inspected.
+    pub trace: Box<Recording>,
+}
+
+/// Runs `machine` from its current configuration on a copy of `input` for up to `budget` steps and checks
+/// `invariant` on every configuration reached, including the initial one. Returns the first configuration
+/// that violates it along with the recorded trace leading there, which turns the simulator into a
+/// bounded model checker: `Ok(())` means no violation occurs within `budget` steps or before halting.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::verify::check_invariant;
+/// 
+/// let (mut machine, _) = turing_machine::examples::busy_beaver_2();
+/// machine.reset();
+/// 
+/// // the head of BB-2 stays within 2 cells of the origin...
+/// assert_eq!(check_invariant(&machine, &Tape::default(), 100, |c| c.head_loc.abs() <= 2), Ok(()));
+/// 
+/// // ...but not within 1
+/// let counter_example = check_invariant(&machine, &Tape::default(), 100, |c| c.head_loc.abs() <= 1).unwrap_err();
+/// assert_eq!(counter_example.violation.step_num, 4);
+/// assert_eq!(counter_example.violation.head_loc, -2);
+/// assert_eq!(counter_example.trace.len(), 4);
+/// ```
+#[inline]
+pub fn check_invariant(machine: &TuringMachine, input: &Tape, budget: usize, invariant: impl Fn(&Configuration) -> bool) -> Result<(), CounterExample> {
+    let mut machine = machine.clone();
+    let mut configuration = Configuration {
+        step_num: 0,
+        state: machine.state(),
+        head_loc: machine.head_loc(),
+        tape: input.clone(),
+    };
+    let mut trace = Recording {
+        input: input.clone(),
+        init_state: machine.state(),
+        init_head_loc: machine.head_loc(),
+        machine_fingerprint: machine.transition_fn().fingerprint(),
+        input_fingerprint: input.fingerprint(),
+        ..Default::default()
+    };
+
+    loop {
+        if !invariant(&configuration) {
+            trace.summarize(&configuration.tape);
+            return Err(CounterExample { violation: configuration, trace: Box::new(trace) });
+        }
+        if configuration.step_num == budget {
+            return Ok(());
+        }
+
+        let symbol = configuration.tape.symbol_at_n(configuration.head_loc);
+        match machine.step(&mut configuration.tape) {
+            Some(output) => {
+                trace.steps.push(recorded_step(output));
+                trace.overwritten.push(symbol);
+            }
+            None => return Ok(()),
+        }
+        configuration.step_num += 1;
+        configuration.state = machine.state();
+        configuration.head_loc = machine.head_loc();
+    }
+}
+
+/// The first step at which the machines run by `cosimulate` differed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// The configuration of the first machine before the step, after `step_num` agreeing steps.
+    pub a: Configuration,
+    /// The configuration of the second machine before the step, after `step_num` agreeing steps.
+    pub b: Configuration,
+    /// The output of the step for the first machine: new state, symbol written, and head movement, or `None` if it halted.
+    pub output_a: Option<(u64, u64, bool)>,
+    /// The output of the step for the second machine, or `None` if it halted.
+    pub output_b: Option<(u64, u64, bool)>,
+}
+
+/// Runs `a` on `tape_a` and `b` on `tape_b` in lock-step for up to `budget` steps, stopping at the first step
+/// where they change to different states, write different symbols, or move in different directions,
+/// or where only one of them halts. Returns the number of steps taken if they never diverged.
+/// 
+/// Both machines are left in the configuration before the diverging step, which is also reported,
+/// so that they can be inspected further. This is meant for checking an optimized variant of a machine against
+/// the original, whose rules may differ as long as its runs do not.
+/// 
+/// # Examples
+/// ```
+/// use turing_machine::prelude::*;
+/// use turing_machine::verify::cosimulate;
+/// 
+/// let (mut original, _) = turing_machine::examples::busy_beaver_2();
+/// original.reset();
+/// // a variant with an unreachable extra rule agrees with the original...
+/// let mut variant = TuringMachine::new(TransitionFn::from_rules(
+///     original.transition_fn().state_table().into_iter().chain([((5, 0), (0, 0, true))]),
+/// ).unwrap());
+/// assert_eq!(cosimulate(&mut original, &mut variant, &mut Tape::default(), &mut Tape::default(), 100), Ok(6));
+/// 
+/// // ...but a variant that moves the wrong way does not
+/// original.reset();
+/// let mut variant = TuringMachine::new(TransitionFn::new(&[
+///     ((0, 0), (1, 1, true)), ((0, 1), (1, 1, true)),
+///     ((1, 0), (0, 1, false)), ((1, 1), (2, 1, true)),
+/// ]));
+/// let divergence = cosimulate(&mut original, &mut variant, &mut Tape::default(), &mut Tape::default(), 100).unwrap_err();
+/// assert_eq!(divergence.a.step_num, 2);
+/// assert_eq!((divergence.output_a, divergence.output_b), (Some((1, 1, false)), Some((1, 1, true))));
+/// assert_eq!(variant.head_loc(), divergence.b.head_loc);
+/// ```
+#[inline]
+pub fn cosimulate(a: &mut TuringMachine, b: &mut TuringMachine, tape_a: &mut Tape, tape_b: &mut Tape, budget: usize) -> Result<usize, Divergence> {
+    for step_num in 0..budget {
+        let output_a = a.transition_fn().run(a.state(), tape_a.symbol_at_n(a.head_loc()));
+        let output_b = b.transition_fn().run(b.state(), tape_b.symbol_at_n(b.head_loc()));
+        if output_a != output_b {
+            let configuration = |machine: &TuringMachine, tape: &Tape| Configuration {
+                step_num,
+                state: machine.state(),
+                head_loc: machine.head_loc(),
+                tape: tape.clone(),
+            };
+
+            return Err(Divergence { a: configuration(a, tape_a), b: configuration(b, tape_b), output_a, output_b });
+        }
+        if output_a.is_none() {
+            return Ok(step_num);
+        }
+
+        a.step(tape_a);
+        b.step(tape_b);
+    }
+
+    Ok(budget)
+}
+
+/// A deliberately simple simulator used as the source of truth by `cross_check`.
+struct ReferenceEngine {
+    state_table: Vec<Rule>,
+    state: u64,
+    head_loc: i64,
+    tape: HashMap<i64, u64>,
+}
+
+impl ReferenceEngine {
+    fn new(machine: &TuringMachine, input: &Tape) -> Self {
+        let tape = input.nonblank_range()
+            .into_iter()
+            .flatten()
+            .map(|n| (n, input.symbol_at_n(n)))
+            .collect();
+
+        ReferenceEngine {
+            state_table: machine.transition_fn().state_table(),
+            state: machine.state(),
+            head_loc: machine.head_loc(),
+            tape,
+        }
+    }
+
+    fn step(&mut self) -> Option<(u64, u64, bool)> {
+        let symbol = self.tape.get(&self.head_loc).copied().unwrap_or(0);
+        let (_, output) = *self.state_table.iter().find(|(key, _)| *key == (self.state, symbol))?;
+
+        self.tape.insert(self.head_loc, output.1);
+        self.state = output.0;
+        self.head_loc += if output.2 { 1 } else { -1 };
+
+        Some(output)
+    }
+
+    fn assert_tape_eq(&self, tape: &Tape) {
+        let locations = self.tape.keys().copied().chain(tape.nonblank_range().into_iter().flatten());
+        for n in locations {
+            let expected = self.tape.get(&n).copied().unwrap_or(0);
+            if tape.symbol_at_n(n) != expected {
+                panic!("engines produced different tapes: expected {} at {}, got {}", expected, n, tape.symbol_at_n(n));
+            }
+        }
+    }
+}
+
+#[allow(unused_imports)]
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+    use crate::verify::*;
+
+    #[test]
+    fn test_cross_check() {
+        for index in 0..TransitionFn::enumeration_size(2, 2) as usize {
+            let machine = TuringMachine::new(TransitionFn::enumerated(2, 2, index));
+            cross_check(&machine, &Tape::new(vec![1, 0, 1]), 50);
+        }
+
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+        assert_eq!(cross_check(&machine, &Tape::new(vec![]), 20), 20);
+
+        // 3-symbol machines also take the flat path, which has to grow its buffer in both directions
+        for seed in 0..20 {
+            let machine = TuringMachine::new(TransitionFn::random(4, 3, Seed(seed)));
+            cross_check(&machine, &Tape::new(vec![2, 1, 0, 2]), 200);
+        }
+    }
+
+    #[test]
+    fn test_cosimulate() {
+        let original = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true)), ((1, 0), (0, 2, true))]));
+
+        // different tapes make the same machine diverge once it reads them
+        let mut tape_b = Tape::new(vec![0, 0, 0, 1]);
+        let divergence = cosimulate(&mut original.clone(), &mut original.clone(), &mut Tape::default(), &mut tape_b, 10).unwrap_err();
+        assert_eq!((divergence.a.step_num, divergence.a.head_loc), (3, 3));
+        assert_eq!((divergence.output_a, divergence.output_b), (Some((0, 2, true)), None));
+        assert_eq!(divergence.b.tape.symbols(), [1, 2, 1, 1]);
+
+        // identical runs stop at the budget
+        assert_eq!(cosimulate(&mut original.clone(), &mut original.clone(), &mut Tape::default(), &mut Tape::default(), 10), Ok(10));
+
+        // a variant that halts early
+        let mut variant = TuringMachine::new(TransitionFn::new(&[((0, 0), (1, 1, true))]));
+        let divergence = cosimulate(&mut original.clone(), &mut variant, &mut Tape::default(), &mut Tape::default(), 10).unwrap_err();
+        assert_eq!((divergence.a.step_num, divergence.output_b), (1, None));
+    }
+
+    #[test]
+    fn test_check_invariant() {
+        let machine = TuringMachine::new(TransitionFn::new(&[((0, 0), (0, 1, true))]));
+
+        // the tape only ever holds as many ones as steps taken
+        let invariant = |c: &Configuration| c.tape.symbol_counts().get(&1).copied().unwrap_or(0) == c.step_num;
+        assert_eq!(check_invariant(&machine, &Tape::default(), 50, invariant), Ok(()));
+
+        let counter_example = check_invariant(&machine, &Tape::default(), 50, |c| c.head_loc < 10).unwrap_err();
+        assert_eq!(counter_example.violation.step_num, 10);
+        assert_eq!(counter_example.trace.seek(10).unwrap(), counter_example.violation);
+
+        // the initial configuration is checked too
+        assert!(check_invariant(&machine, &Tape::default(), 0, |_| false).is_err());
+    }
+}